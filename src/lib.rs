@@ -10,13 +10,21 @@ pub use link::Link;
 
 pub use data_bucket_codegen::{SizeMeasure, VariableSizeMeasure};
 pub use page::{
-    get_index_page_size_from_data_length, map_data_pages_to_general, parse_data_page,
-    parse_data_pages_batch, parse_general_header_by_index, parse_page, parse_pages_batch,
-    persist_page, persist_pages_batch, seek_by_link, seek_to_page_start, update_at, DataPage,
-    GeneralHeader, GeneralPage, IndexPage, IndexPageUtility, IndexValue, Interval, PageType,
-    SpaceInfoPage, TableOfContentsPage, UnsizedIndexPage, UnsizedIndexPageUtility, DATA_VERSION,
+    bulk_load_index_pages, bulk_load_multimap_index_pages, bulk_load_multimap_unsized_index_pages,
+    bulk_load_unsized_index_pages, find_links_with_filters, get_index_page_size_from_data_length,
+    map_data_pages_to_general, parse_bloom_filters, parse_data_page, parse_data_pages_batch,
+    parse_general_header_by_index, parse_page, parse_pages_batch, parse_table_of_contents_chain,
+    persist_bloom_filter, persist_page, persist_pages_batch, persist_table_of_contents_chain,
+    seek_by_link, seek_to_page_start, update_at, vacuum_index_pages, vacuum_unsized_index_pages,
+    BloomFilter, BloomFilterConfig, BloomFilterPage, BloomKey, BulkLoadConfig, BulkLoadedIndex,
+    ColumnSchema, DataPage, FilterKeys, FilteredIndexPage, GeneralHeader, GeneralPage, IndexColumn,
+    IndexPage, IndexPageUtility, IndexSchema, IndexValue, Interval, PageType, PrefixIndexPage,
+    PrefixIndexPageUtility, SchemaChange, SchemaVersion, SortOrder, SpaceInfoPage,
+    TableOfContentsPage, UnsizedIndexPage, UnsizedIndexPageUtility, VacuumReport, DATA_VERSION,
     GENERAL_HEADER_SIZE, INNER_PAGE_SIZE, PAGE_SIZE,
 };
 pub use persistence::{PersistableIndex, PersistableTable};
 pub use space::Id as SpaceId;
-pub use util::{align, align8, align_vec, Persistable, SizeMeasurable, VariableSizeMeasurable};
+pub use util::{
+    align, align8, align_vec, Desc, Persistable, SizeMeasurable, VariableSizeMeasurable,
+};
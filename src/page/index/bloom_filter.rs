@@ -0,0 +1,842 @@
+//! Bloom filters for index nodes.
+//!
+//! Negative lookups on cold indexes require reading of index pages from disk
+//! just to find out that key is absent. [`BloomFilter`] of the node answers
+//! "key is definitely not in node" without reading node's values. Filter is
+//! persisted as a sidecar page with [`PageType::IndexBloomFilter`] type.
+//!
+//! Filter can not remove keys, so removes are only counted, and filter is
+//! rebuilt from node's values when too many keys were removed or inserted.
+//!
+//! Filter page is [`BloomFilterPage`] which holds [`PageId`] of it's node, so
+//! filters are found by scan of the file's filter pages and are not required
+//! to be placed near their nodes.
+//!
+//! [`PageType::IndexBloomFilter`]: crate::PageType::IndexBloomFilter
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hasher;
+
+use eyre::bail;
+use indexset::cdc::change::ChangeEvent;
+use indexset::core::pair::Pair;
+use rkyv::api::high::HighDeserializer;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::page::PageId;
+use crate::{
+    parse_general_header_by_index, parse_page, persist_page, space, GeneralHeader, GeneralPage,
+    IndexPage, Link, PageType, Persistable, SizeMeasurable, TableOfContentsPage, UnsizedIndexPage,
+    VariableSizeMeasurable, INNER_PAGE_SIZE, PAGE_SIZE,
+};
+
+/// `FNV-1a` offset basis. Standard library's hashers are not guaranteed to
+/// be same between releases, so persisted filters use own hasher.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+/// Seed of the second hash which is used for double hashing.
+const SECOND_HASH_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Configuration of the node's [`BloomFilter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomFilterConfig {
+    /// Desired probability of false positive answer of
+    /// [`BloomFilter::may_contain`]. Should be in `(0.0, 1.0)` range.
+    pub false_positive_rate: f64,
+}
+
+impl Default for BloomFilterConfig {
+    fn default() -> Self {
+        Self {
+            false_positive_rate: 0.01,
+        }
+    }
+}
+
+impl BloomFilterConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if !(self.false_positive_rate > 0.0 && self.false_positive_rate < 1.0) {
+            bail!(
+                "False positive rate should be in (0.0, 1.0) range, got {}",
+                self.false_positive_rate
+            )
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+/// Key of the [`BloomFilter`]. Filters are persisted, so keys are hashed by
+/// their canonical little-endian encoding instead of [`std::hash::Hash`], which
+/// depends on platform's endianness and pointer width.
+pub trait BloomKey {
+    fn write_canonical<H: Hasher>(&self, state: &mut H);
+}
+
+macro_rules! bloom_key_le {
+    ($($ty:ty),*) => {
+        $(
+            impl BloomKey for $ty {
+                fn write_canonical<H: Hasher>(&self, state: &mut H) {
+                    state.write(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+bloom_key_le!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl BloomKey for bool {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        state.write(&[*self as u8])
+    }
+}
+
+impl BloomKey for char {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        state.write(&(*self as u32).to_le_bytes())
+    }
+}
+
+impl BloomKey for str {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        state.write(&(self.len() as u64).to_le_bytes());
+        state.write(self.as_bytes())
+    }
+}
+
+impl BloomKey for String {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        self.as_str().write_canonical(state)
+    }
+}
+
+impl BloomKey for Uuid {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        state.write(self.as_bytes())
+    }
+}
+
+impl<T: BloomKey + ?Sized> BloomKey for &T {
+    fn write_canonical<H: Hasher>(&self, state: &mut H) {
+        (**self).write_canonical(state)
+    }
+}
+
+/// Bloom filter of the index node's keys.
+#[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize)]
+pub struct BloomFilter {
+    /// Filter's bits.
+    pub bits: Vec<u64>,
+    /// Count of hash functions.
+    pub hashes_count: u32,
+    /// Count of keys filter was sized for.
+    pub expected_items: u32,
+    /// Count of keys inserted in filter.
+    pub items: u32,
+    /// Count of keys removed from node after filter was built. They are
+    /// still reported by filter.
+    pub removed: u32,
+    /// Desired false positive rate which is used on filter's rebuild.
+    pub false_positive_rate: f64,
+}
+
+impl BloomFilter {
+    /// Creates empty filter sized for `expected_items` keys.
+    pub fn new(expected_items: usize, config: BloomFilterConfig) -> eyre::Result<Self> {
+        config.validate()?;
+        let n = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits_count = (-n * config.false_positive_rate.ln() / (ln2 * ln2)).ceil();
+        let words = (bits_count as usize).div_ceil(u64::BITS as usize).max(1);
+        let hashes_count = ((words * u64::BITS as usize) as f64 / n * ln2).round();
+        Ok(Self {
+            bits: vec![0; words],
+            hashes_count: (hashes_count as u32).max(1),
+            expected_items: expected_items.max(1) as u32,
+            items: 0,
+            removed: 0,
+            false_positive_rate: config.false_positive_rate,
+        })
+    }
+
+    /// Creates filter that contains all `keys`.
+    pub fn from_keys<'a, T: BloomKey + 'a>(
+        keys: impl ExactSizeIterator<Item = &'a T>,
+        expected_items: usize,
+        config: BloomFilterConfig,
+    ) -> eyre::Result<Self> {
+        let mut filter = Self::new(expected_items.max(keys.len()), config)?;
+        for key in keys {
+            filter.insert(key)
+        }
+        Ok(filter)
+    }
+
+    pub fn config(&self) -> BloomFilterConfig {
+        BloomFilterConfig {
+            false_positive_rate: self.false_positive_rate,
+        }
+    }
+
+    fn bit_positions<T: BloomKey + ?Sized>(&self, key: &T) -> impl Iterator<Item = usize> {
+        let mut first = FnvHasher(FNV_OFFSET_BASIS);
+        key.write_canonical(&mut first);
+        let mut second = FnvHasher(FNV_OFFSET_BASIS ^ SECOND_HASH_SEED);
+        key.write_canonical(&mut second);
+        let (h1, h2) = (first.finish(), second.finish() | 1);
+        let bits_count = (self.bits.len() * u64::BITS as usize) as u64;
+        (0..self.hashes_count as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits_count) as usize)
+    }
+
+    pub fn insert<T: BloomKey + ?Sized>(&mut self, key: &T) {
+        let positions = self.bit_positions(key).collect::<Vec<_>>();
+        for pos in positions {
+            self.bits[pos / u64::BITS as usize] |= 1 << (pos % u64::BITS as usize);
+        }
+        self.items += 1;
+    }
+
+    /// Returns `false` if `key` is definitely not in the node.
+    pub fn may_contain<T: BloomKey + ?Sized>(&self, key: &T) -> bool {
+        self.bit_positions(key)
+            .all(|pos| self.bits[pos / u64::BITS as usize] & (1 << (pos % u64::BITS as usize)) != 0)
+    }
+
+    /// Updates filter with node's [`ChangeEvent`]. Inserted keys are added to
+    /// filter, removed keys are only counted.
+    pub fn apply_change_event<T: BloomKey>(&mut self, event: &ChangeEvent<Pair<T, Link>>) {
+        match event {
+            ChangeEvent::InsertAt { value, .. } => self.insert(&value.key),
+            ChangeEvent::RemoveAt { .. } => self.removed += 1,
+            ChangeEvent::SplitNode { .. }
+            | ChangeEvent::CreateNode { .. }
+            | ChangeEvent::RemoveNode { .. } => {}
+        }
+    }
+
+    /// Checks if filter should be rebuilt: more keys were inserted than
+    /// filter was sized for or a quarter of keys was removed.
+    pub fn needs_rebuild(&self) -> bool {
+        self.items > self.expected_items || self.removed > self.expected_items / 4
+    }
+}
+
+impl Persistable for BloomFilter {
+    fn as_bytes(&self) -> impl AsRef<[u8]> + Send {
+        rkyv::to_bytes::<rkyv::rancor::Error>(self).unwrap()
+    }
+
+    fn from_bytes(bytes: &[u8], _version: u32) -> Self {
+        let archived = unsafe { rkyv::access_unchecked::<<Self as Archive>::Archived>(bytes) };
+        rkyv::deserialize::<_, rkyv::rancor::Error>(archived).expect("data should be valid")
+    }
+}
+
+/// [`PageType::IndexBloomFilter`] page's content: filter of the index node
+/// which is placed at `node_id` page.
+#[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize)]
+pub struct BloomFilterPage {
+    pub node_id: PageId,
+    pub filter: BloomFilter,
+}
+
+impl Persistable for BloomFilterPage {
+    fn as_bytes(&self) -> impl AsRef<[u8]> + Send {
+        rkyv::to_bytes::<rkyv::rancor::Error>(self).unwrap()
+    }
+
+    fn from_bytes(bytes: &[u8], _version: u32) -> Self {
+        let archived = unsafe { rkyv::access_unchecked::<<Self as Archive>::Archived>(bytes) };
+        rkyv::deserialize::<_, rkyv::rancor::Error>(archived).expect("data should be valid")
+    }
+}
+
+impl BloomFilterPage {
+    /// Checks that `bytes` are valid archived [`BloomFilterPage`], so they can
+    /// be safely read with [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8]) -> eyre::Result<()> {
+        // Archived bytes are validated only if they are aligned.
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(bytes);
+        rkyv::access::<<Self as Archive>::Archived, rkyv::rancor::Error>(&aligned)?;
+        Ok(())
+    }
+}
+
+/// Writes `filter` of the node placed at `node_id` page to the `page_id`
+/// page. Returns error if filter does not fit in one page.
+pub async fn persist_bloom_filter(
+    file: &mut File,
+    filter: BloomFilter,
+    node_id: PageId,
+    page_id: PageId,
+    space_id: space::Id,
+) -> eyre::Result<()> {
+    let mut page = GeneralPage {
+        header: GeneralHeader::new(page_id, PageType::IndexBloomFilter, space_id),
+        inner: BloomFilterPage { node_id, filter },
+    };
+    if page.inner.as_bytes().as_ref().len() > INNER_PAGE_SIZE {
+        bail!(
+            "Bloom filter of the node {} is bigger than {}",
+            node_id,
+            INNER_PAGE_SIZE
+        )
+    }
+    persist_page(&mut page, file).await
+}
+
+/// Reads all [`PageType::IndexBloomFilter`] pages of the index file and
+/// returns their filters by [`PageId`] of the node. Returns error if some
+/// filter page is damaged or node has several filters.
+pub async fn parse_bloom_filters(file: &mut File) -> eyre::Result<HashMap<PageId, BloomFilter>> {
+    let page_count = file.metadata().await?.len().div_ceil(PAGE_SIZE as u64) as u32;
+    let mut filters = HashMap::new();
+    for index in 0..page_count {
+        let header = parse_general_header_by_index(file, index).await?;
+        if header.page_type != PageType::IndexBloomFilter {
+            continue;
+        }
+        if header.data_length as usize > INNER_PAGE_SIZE {
+            bail!(
+                "Bloom filter page {} has length {} which is bigger than {}",
+                index,
+                header.data_length,
+                INNER_PAGE_SIZE
+            )
+        }
+        let mut buffer = vec![0u8; header.data_length as usize];
+        file.read_exact(&mut buffer).await?;
+        BloomFilterPage::check_bytes(&buffer)?;
+        let page = BloomFilterPage::from_bytes(&buffer, header.data_version);
+        if filters.insert(page.node_id, page.filter).is_some() {
+            bail!("Node {} has several bloom filters", page.node_id)
+        }
+    }
+
+    Ok(filters)
+}
+
+/// Returns [`Link`]'s of the `key`'s values. Node that should contain `key` is
+/// found in `toc`, and it's page is read only if node has no filter in
+/// `filters` or it's filter may contain `key`.
+pub async fn find_links_with_filters<Page, T>(
+    file: &mut File,
+    toc: &TableOfContentsPage<T>,
+    filters: &HashMap<PageId, BloomFilter>,
+    key: &T,
+) -> eyre::Result<Vec<Link>>
+where
+    Page: Archive + Persistable + FilterKeys<T>,
+    <Page as Archive>::Archived: Deserialize<Page, HighDeserializer<rkyv::rancor::Error>>,
+    T: BloomKey + Debug + Ord + Eq,
+{
+    let Some(node_id) = toc.find(key) else {
+        return Ok(vec![]);
+    };
+    if filters
+        .get(&node_id)
+        .is_some_and(|filter| !filter.may_contain(key))
+    {
+        return Ok(vec![]);
+    }
+    let page = parse_page::<Page, { INNER_PAGE_SIZE as u32 }>(file, node_id.0).await?;
+    Ok(page.inner.find_links(key))
+}
+
+/// Index node with it's [`BloomFilter`]. Filter is updated on
+/// `apply_change_event` and rebuilt on split, merge and when
+/// [`BloomFilter::needs_rebuild`].
+#[derive(Clone, Debug)]
+pub struct FilteredIndexPage<Page> {
+    pub page: Page,
+    pub filter: BloomFilter,
+}
+
+/// Node's keys that are used to build [`BloomFilter`].
+pub trait FilterKeys<T> {
+    fn filter_keys(&self) -> Vec<&T>;
+
+    /// Count of keys that node can hold, filter is sized for it.
+    fn expected_items(&self) -> usize;
+
+    /// Returns [`Link`]'s of the node's values with `key`.
+    fn find_links(&self, key: &T) -> Vec<Link>
+    where
+        T: Eq;
+}
+
+impl<T> FilterKeys<T> for IndexPage<T>
+where
+    T: Default + SizeMeasurable,
+{
+    fn filter_keys(&self) -> Vec<&T> {
+        self.slots[..self.current_length as usize]
+            .iter()
+            .map(|pos| &self.index_values[*pos as usize].key)
+            .collect()
+    }
+
+    fn expected_items(&self) -> usize {
+        self.size as usize
+    }
+
+    fn find_links(&self, key: &T) -> Vec<Link>
+    where
+        T: Eq,
+    {
+        self.slots[..self.current_length as usize]
+            .iter()
+            .map(|pos| &self.index_values[*pos as usize])
+            .filter(|v| &v.key == key)
+            .map(|v| v.link)
+            .collect()
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> FilterKeys<T> for UnsizedIndexPage<T, DATA_LENGTH>
+where
+    T: Default + SizeMeasurable + VariableSizeMeasurable,
+{
+    fn filter_keys(&self) -> Vec<&T> {
+        self.index_values.iter().map(|v| &v.key).collect()
+    }
+
+    fn expected_items(&self) -> usize {
+        // Unsized node's capacity depends on keys' lengths, so filter is
+        // sized for twice more keys than node has now.
+        self.index_values.len() * 2
+    }
+
+    fn find_links(&self, key: &T) -> Vec<Link>
+    where
+        T: Eq,
+    {
+        self.index_values
+            .iter()
+            .filter(|v| &v.key == key)
+            .map(|v| v.link)
+            .collect()
+    }
+}
+
+impl<Page> FilteredIndexPage<Page> {
+    pub fn new<T: BloomKey>(page: Page, config: BloomFilterConfig) -> eyre::Result<Self>
+    where
+        Page: FilterKeys<T>,
+    {
+        let keys = page.filter_keys();
+        let filter = BloomFilter::from_keys(keys.into_iter(), page.expected_items(), config)?;
+        Ok(Self { page, filter })
+    }
+
+    /// Rebuilds filter from node's keys.
+    pub fn rebuild_filter<T: BloomKey>(&mut self) -> eyre::Result<()>
+    where
+        Page: FilterKeys<T>,
+    {
+        let keys = self.page.filter_keys();
+        self.filter = BloomFilter::from_keys(
+            keys.into_iter(),
+            self.page.expected_items(),
+            self.filter.config(),
+        )?;
+        Ok(())
+    }
+
+    /// Returns `false` if `key` is definitely not in the node, so node's
+    /// values can be not read.
+    pub fn may_contain<T: BloomKey + ?Sized>(&self, key: &T) -> bool {
+        self.filter.may_contain(key)
+    }
+}
+
+impl<T> FilteredIndexPage<IndexPage<T>>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Clone
+        + BloomKey
+        + SizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        > + Ord
+        + Send
+        + Sync,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>
+        + Ord
+        + Eq
+        + PartialEq
+        + PartialOrd
+        + Debug,
+{
+    pub fn apply_change_event(&mut self, event: ChangeEvent<Pair<T, Link>>) -> eyre::Result<()> {
+        // Filter is updated only after page, so failed insert is not added.
+        self.page.apply_change_event(event.clone())?;
+        self.filter.apply_change_event(&event);
+        if self.filter.needs_rebuild() {
+            self.rebuild_filter()?;
+        }
+        Ok(())
+    }
+
+    pub fn split(&mut self, index: usize) -> eyre::Result<Self> {
+        let page = self.page.split(index);
+        self.rebuild_filter()?;
+        Self::new(page, self.filter.config())
+    }
+
+    pub fn merge(&mut self, right: Self) -> eyre::Result<()> {
+        self.page.merge(right.page)?;
+        self.rebuild_filter()
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> FilteredIndexPage<UnsizedIndexPage<T, DATA_LENGTH>>
+where
+    T: Archive
+        + Ord
+        + Eq
+        + Clone
+        + Default
+        + BloomKey
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    pub fn apply_change_event(&mut self, event: ChangeEvent<Pair<T, Link>>) -> eyre::Result<()> {
+        // Filter is updated only after page, so failed insert is not added.
+        self.page.apply_change_event(event.clone())?;
+        self.filter.apply_change_event(&event);
+        if self.filter.needs_rebuild() {
+            self.rebuild_filter()?;
+        }
+        Ok(())
+    }
+
+    pub fn split(&mut self, index: usize) -> eyre::Result<Self> {
+        let page = self.page.split(index);
+        self.rebuild_filter()?;
+        Self::new(page, self.filter.config())
+    }
+
+    pub fn merge(&mut self, right: Self) -> eyre::Result<()> {
+        self.page.merge(right.page)?;
+        self.rebuild_filter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexset::cdc::change::ChangeEvent;
+    use indexset::core::pair::Pair;
+
+    use std::collections::HashMap;
+    use std::hash::Hasher;
+
+    use tokio::fs::File;
+
+    use super::{
+        find_links_with_filters, parse_bloom_filters, persist_bloom_filter, BloomFilter,
+        BloomFilterConfig, BloomKey, FilteredIndexPage, FnvHasher, FNV_OFFSET_BASIS,
+    };
+    use crate::{
+        get_index_page_size_from_data_length, persist_page, GeneralHeader, GeneralPage, IndexPage,
+        IndexValue, Link, PageType, Persistable, TableOfContentsPage, UnsizedIndexPage,
+        INNER_PAGE_SIZE,
+    };
+
+    fn email(i: u32) -> String {
+        format!("user{i}@example.com")
+    }
+
+    #[test]
+    fn false_positive_rate() {
+        let keys = (0..1000).map(email).collect::<Vec<_>>();
+        let filter = BloomFilter::from_keys(keys.iter(), 1000, Default::default()).unwrap();
+        assert!(keys.iter().all(|k| filter.may_contain(k)));
+
+        let false_positives = (1000..11000)
+            .filter(|i| filter.may_contain(&email(*i)))
+            .count();
+        // Expected rate is 0.01, so 100 false positives of 10000.
+        assert!(false_positives < 200, "{false_positives}");
+
+        assert!(BloomFilter::new(
+            10,
+            BloomFilterConfig {
+                false_positive_rate: 1.0
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn to_bytes_and_back() {
+        let keys = (0..100).map(email).collect::<Vec<_>>();
+        let filter = BloomFilter::from_keys(keys.iter(), 100, Default::default()).unwrap();
+        let bytes = filter.as_bytes();
+        let parsed = BloomFilter::from_bytes(bytes.as_ref(), 0);
+        assert_eq!(parsed, filter);
+        assert!(parsed.may_contain(&email(10)));
+    }
+
+    #[test]
+    fn filtered_sized_page() {
+        let size = get_index_page_size_from_data_length::<u64>(INNER_PAGE_SIZE);
+        let node = (0..10u64)
+            .map(|i| IndexValue {
+                key: i * 2,
+                link: Link::default(),
+            })
+            .collect::<Vec<_>>();
+        let mut page =
+            FilteredIndexPage::new(IndexPage::from_node(&node, size), Default::default()).unwrap();
+        assert!(page.may_contain(&4u64));
+
+        let event = ChangeEvent::InsertAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: 18,
+                value: Link::default(),
+            },
+            value: Pair {
+                key: 21,
+                value: Link::default(),
+            },
+            index: 10,
+        };
+        page.apply_change_event(event).unwrap();
+        assert!(page.may_contain(&21u64));
+
+        let right = page.split(5).unwrap();
+        assert!(right.may_contain(&21u64));
+        assert!(page.may_contain(&0u64));
+        assert_eq!(page.filter.items, 5);
+        assert_eq!(right.filter.items, 6);
+
+        page.merge(right).unwrap();
+        assert_eq!(page.filter.items, 11);
+        assert!(page.may_contain(&21u64));
+    }
+
+    #[test]
+    fn filtered_unsized_page_rebuild() {
+        let node = (0..8)
+            .map(|i| IndexValue {
+                key: email(i),
+                link: Link::default(),
+            })
+            .collect::<Vec<_>>();
+        let mut page = FilteredIndexPage::new(
+            UnsizedIndexPage::<_, 1024>::from_node(&node),
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(page.filter.expected_items, 16);
+
+        for i in 0..5 {
+            let event = ChangeEvent::RemoveAt {
+                event_id: 0.into(),
+                max_value: Pair {
+                    key: email(7),
+                    value: Link::default(),
+                },
+                value: Pair {
+                    key: email(i),
+                    value: Link::default(),
+                },
+                index: 0,
+            };
+            page.apply_change_event(event).unwrap();
+        }
+        // Filter is rebuilt after a quarter of expected keys was removed.
+        assert_eq!(page.filter.removed, 0);
+        assert_eq!(page.filter.items, 3);
+        assert!(page.may_contain(&email(7)));
+    }
+
+    #[test]
+    fn canonical_hash() {
+        let mut hasher = FnvHasher(FNV_OFFSET_BASIS);
+        b'a'.write_canonical(&mut hasher);
+        // `FNV-1a` of "a".
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let mut hasher = FnvHasher(FNV_OFFSET_BASIS);
+        0x0102u16.write_canonical(&mut hasher);
+        let mut expected = FnvHasher(FNV_OFFSET_BASIS);
+        expected.write(&[2, 1]);
+        assert_eq!(hasher.finish(), expected.finish());
+    }
+
+    #[test]
+    fn filtered_page_failed_insert() {
+        let node = vec![IndexValue {
+            key: "a".to_string(),
+            link: Link::default(),
+        }];
+        let mut page = FilteredIndexPage::new(
+            UnsizedIndexPage::<_, 128>::from_node(&node),
+            Default::default(),
+        )
+        .unwrap();
+        let key = "b".repeat(128);
+        let event = ChangeEvent::InsertAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: "a".to_string(),
+                value: Link::default(),
+            },
+            value: Pair {
+                key: key.clone(),
+                value: Link::default(),
+            },
+            index: 1,
+        };
+        assert!(page.apply_change_event(event).is_err());
+        assert_eq!(page.filter.items, 1);
+        assert!(!page.may_contain(&key));
+    }
+
+    #[test]
+    fn filtered_page_merge_overfull() {
+        let size = 8;
+        let page = |keys: std::ops::Range<u64>| {
+            let node = keys
+                .map(|key| IndexValue {
+                    key,
+                    link: Link::default(),
+                })
+                .collect::<Vec<_>>();
+            FilteredIndexPage::new(IndexPage::from_node(&node, size), Default::default()).unwrap()
+        };
+        let mut left = page(0..5);
+        let right = page(5..9);
+        let filter = left.filter.clone();
+
+        assert!(left.merge(right).is_err());
+        assert_eq!(left.page.current_length, 5);
+        assert_eq!(left.filter, filter);
+        assert!(!left.may_contain(&8u64));
+    }
+
+    #[tokio::test]
+    async fn persisted_filters_lookup() {
+        let size = get_index_page_size_from_data_length::<u64>(INNER_PAGE_SIZE);
+        let link = |key: u64| Link {
+            page_id: 1.into(),
+            offset: key as u32 * 8,
+            length: 8,
+        };
+        let node = |keys: std::ops::Range<u64>| {
+            let node = keys
+                .map(|key| IndexValue {
+                    key: key * 2,
+                    link: link(key * 2),
+                })
+                .collect::<Vec<_>>();
+            FilteredIndexPage::new(IndexPage::from_node(&node, size), Default::default()).unwrap()
+        };
+        let left = node(0..10);
+        let right = node(10..20);
+
+        let filename = "tests/data/bloom_filter_lookup.wt";
+        let mut file = File::create(filename).await.unwrap();
+        for (page_id, node) in [(1, &left), (2, &right)] {
+            let mut page = GeneralPage {
+                header: GeneralHeader::new(page_id.into(), PageType::Index, 0.into()),
+                inner: node.page.clone(),
+            };
+            persist_page(&mut page, &mut file).await.unwrap();
+        }
+        persist_bloom_filter(&mut file, left.filter.clone(), 1.into(), 3.into(), 0.into())
+            .await
+            .unwrap();
+        persist_bloom_filter(
+            &mut file,
+            right.filter.clone(),
+            2.into(),
+            4.into(),
+            0.into(),
+        )
+        .await
+        .unwrap();
+        // Node 6 is not written, so lookup fails if it's page is read.
+        let missing = BloomFilter::from_keys([1000u64].iter(), 1, Default::default()).unwrap();
+        persist_bloom_filter(&mut file, missing.clone(), 6.into(), 5.into(), 0.into())
+            .await
+            .unwrap();
+        let mut toc = TableOfContentsPage::default();
+        toc.insert(18u64, 1.into());
+        toc.insert(38, 2.into());
+        toc.insert(1000, 6.into());
+
+        let mut file = File::open(filename).await.unwrap();
+        let filters = parse_bloom_filters(&mut file).await.unwrap();
+        assert_eq!(
+            filters,
+            HashMap::from([
+                (1.into(), left.filter),
+                (2.into(), right.filter),
+                (6.into(), missing)
+            ])
+        );
+
+        let find = |key: u64| {
+            let (toc, filters) = (&toc, &filters);
+            async move {
+                let mut file = File::open(filename).await.unwrap();
+                find_links_with_filters::<IndexPage<u64>, _>(&mut file, toc, filters, &key).await
+            }
+        };
+        assert_eq!(find(4).await.unwrap(), vec![link(4)]);
+        assert_eq!(find(36).await.unwrap(), vec![link(36)]);
+        assert!(find(5).await.unwrap().is_empty());
+        assert!(find(2000).await.unwrap().is_empty());
+        assert!(find(999).await.unwrap().is_empty());
+
+        let mut file = File::open(filename).await.unwrap();
+        assert!(find_links_with_filters::<IndexPage<u64>, _>(
+            &mut file,
+            &toc,
+            &HashMap::new(),
+            &999
+        )
+        .await
+        .is_err());
+    }
+}
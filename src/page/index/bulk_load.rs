@@ -0,0 +1,522 @@
+//! Bulk loading of index pages from already sorted values.
+//!
+//! Instead of replaying inserts through in-memory tree and applying CDC events
+//! one by one, values are packed into pages sequentially. Every page's
+//! `node_id` is its last (max) value, so [`TableOfContentsPage`] is filled in
+//! the same pass.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use eyre::bail;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::page::PageId;
+use crate::{
+    get_index_page_size_from_data_length, space, GeneralHeader, GeneralPage, IndexPage, IndexValue,
+    Link, PageType, SizeMeasurable, TableOfContentsPage, UnsizedIndexPage, UnsizedIndexPageUtility,
+    VariableSizeMeasurable, INNER_PAGE_SIZE,
+};
+
+/// Configuration of index bulk loading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BulkLoadConfig {
+    /// Part of the page's capacity that will be filled with values. Should be
+    /// in `(0.0, 1.0]` range. Values less than `1.0` leave space for further
+    /// inserts without splits.
+    pub fill_factor: f64,
+    /// [`space::Id`] that will be set in pages headers.
+    pub space_id: space::Id,
+    /// [`PageId`] of the first index page. Next pages get sequential ids.
+    pub first_page_id: PageId,
+}
+
+impl Default for BulkLoadConfig {
+    fn default() -> Self {
+        Self {
+            fill_factor: 1.0,
+            space_id: 0.into(),
+            first_page_id: 1.into(),
+        }
+    }
+}
+
+impl BulkLoadConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if !(self.fill_factor > 0.0 && self.fill_factor <= 1.0) {
+            bail!(
+                "Fill factor should be in (0.0, 1.0] range, got {}",
+                self.fill_factor
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Result of index bulk loading: packed index pages with headers and
+/// [`TableOfContentsPage`] that points to them.
+#[derive(Debug)]
+pub struct BulkLoadedIndex<Page, T: Ord + Eq> {
+    pub pages: Vec<GeneralPage<Page>>,
+    pub toc: TableOfContentsPage<T>,
+}
+
+/// Accumulates nodes and builds [`GeneralPage`]'s with sequential
+/// [`PageId`]'s and [`TableOfContentsPage`] for them.
+struct PagesAccumulator<Page, T: Ord + Eq> {
+    page_type: PageType,
+    config: BulkLoadConfig,
+    pages: Vec<GeneralPage<Page>>,
+    toc: TableOfContentsPage<T>,
+}
+
+impl<Page, T> PagesAccumulator<Page, T>
+where
+    T: Debug + Ord + Eq + Clone + SizeMeasurable,
+{
+    fn new(page_type: PageType, config: BulkLoadConfig) -> Self {
+        Self {
+            page_type,
+            config,
+            pages: vec![],
+            toc: TableOfContentsPage::default(),
+        }
+    }
+
+    fn push(&mut self, node_id: T, page: Page) {
+        let header = if let Some(previous) = self.pages.last_mut() {
+            previous.header.follow()
+        } else {
+            GeneralHeader::new(
+                self.config.first_page_id,
+                self.page_type,
+                self.config.space_id,
+            )
+        };
+        self.toc.insert(node_id, header.page_id);
+        self.pages.push(GeneralPage {
+            header,
+            inner: page,
+        });
+    }
+
+    fn finish(self) -> BulkLoadedIndex<Page, T> {
+        BulkLoadedIndex {
+            pages: self.pages,
+            toc: self.toc,
+        }
+    }
+}
+
+/// Checks order of the incoming values, so every page's last value can be
+/// used as it's `node_id`.
+enum OrderCheck<T> {
+    /// Keys should be strictly ascending.
+    Unique { previous: Option<T> },
+    /// Keys should be ascending, but values with same key can go in any order,
+    /// as `indexset` doesn't order them by [`Link`]. `(key, link)` pairs should
+    /// still be unique, as they identify nodes in [`TableOfContentsPage`].
+    Multi {
+        previous: Option<T>,
+        links: HashSet<Link>,
+    },
+}
+
+impl<T: Ord + Clone + Debug> OrderCheck<T> {
+    fn unique() -> Self {
+        Self::Unique { previous: None }
+    }
+
+    fn multi() -> Self {
+        Self::Multi {
+            previous: None,
+            links: HashSet::new(),
+        }
+    }
+
+    fn check(&mut self, value: &IndexValue<T>) -> eyre::Result<()> {
+        match self {
+            Self::Unique { previous } => {
+                if let Some(previous) = previous.as_ref() {
+                    if previous >= &value.key {
+                        bail!(
+                            "Values should be sorted in ascending order without duplicates, but {:?} goes after {:?}",
+                            value.key,
+                            previous
+                        )
+                    }
+                }
+                *previous = Some(value.key.clone());
+            }
+            Self::Multi { previous, links } => {
+                match previous.as_ref() {
+                    Some(previous) if previous > &value.key => bail!(
+                        "Values should be sorted by key in ascending order, but {:?} goes after {:?}",
+                        value.key,
+                        previous
+                    ),
+                    Some(previous) if previous == &value.key => {}
+                    _ => links.clear(),
+                }
+                if !links.insert(value.link) {
+                    bail!("Value {:?} is duplicated", value)
+                }
+                *previous = Some(value.key.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds [`IndexPage`]'s from sorted `(key, link)` pairs. Each page holds
+/// `fill_factor` part of [`get_index_page_size_from_data_length`] values.
+pub fn bulk_load_index_pages<T>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+) -> eyre::Result<BulkLoadedIndex<IndexPage<T>, T>>
+where
+    T: Debug + Default + Ord + Eq + Clone + SizeMeasurable,
+{
+    load_index_pages(values, config, OrderCheck::unique(), |v| v.key.clone())
+}
+
+/// Builds [`IndexPage`]'s of the multimap (non-unique) index from `(key, link)`
+/// pairs sorted by key. Values with same key can go in any order.
+/// [`TableOfContentsPage`] is keyed by `(key, link)` of page's last value, so
+/// nodes are identified even if one key spans several pages.
+pub fn bulk_load_multimap_index_pages<T>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+) -> eyre::Result<BulkLoadedIndex<IndexPage<T>, (T, Link)>>
+where
+    T: Debug + Default + Ord + Eq + Clone + SizeMeasurable,
+{
+    load_index_pages(values, config, OrderCheck::multi(), |v| {
+        (v.key.clone(), v.link)
+    })
+}
+
+fn load_index_pages<T, K>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+    mut order: OrderCheck<T>,
+    node_key: impl Fn(&IndexValue<T>) -> K,
+) -> eyre::Result<BulkLoadedIndex<IndexPage<T>, K>>
+where
+    T: Debug + Default + Ord + Eq + Clone + SizeMeasurable,
+    K: Debug + Ord + Eq + Clone + SizeMeasurable,
+{
+    config.validate()?;
+    let size = get_index_page_size_from_data_length::<T>(INNER_PAGE_SIZE);
+    let values_per_page = ((size as f64 * config.fill_factor) as usize).max(1);
+
+    let mut acc = PagesAccumulator::new(PageType::Index, config);
+    let mut node: Vec<IndexValue<T>> = Vec::with_capacity(values_per_page);
+    for (key, link) in values {
+        let value = IndexValue { key, link };
+        order.check(&value)?;
+        node.push(value);
+        if node.len() == values_per_page {
+            let node_id = node_key(node.last().expect("node is not empty"));
+            acc.push(node_id, IndexPage::from_node(&node, size));
+            node.clear();
+        }
+    }
+    if let Some(last) = node.last() {
+        let node_id = node_key(last);
+        acc.push(node_id, IndexPage::from_node(&node, size));
+    }
+
+    Ok(acc.finish())
+}
+
+/// Builds [`UnsizedIndexPage`]'s from sorted `(key, link)` pairs. Values are
+/// added to the page while page's persisted size stays under `fill_factor`
+/// part of `DATA_LENGTH`.
+pub fn bulk_load_unsized_index_pages<T, const DATA_LENGTH: u32>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+) -> eyre::Result<BulkLoadedIndex<UnsizedIndexPage<T, DATA_LENGTH>, T>>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Eq
+        + Clone
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    load_unsized_index_pages(values, config, OrderCheck::unique(), |v| v.key.clone())
+}
+
+/// Builds [`UnsizedIndexPage`]'s of the multimap (non-unique) index from
+/// `(key, link)` pairs sorted by key. Like
+/// [`bulk_load_multimap_index_pages`], [`TableOfContentsPage`] is keyed by
+/// `(key, link)`.
+pub fn bulk_load_multimap_unsized_index_pages<T, const DATA_LENGTH: u32>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+) -> eyre::Result<BulkLoadedIndex<UnsizedIndexPage<T, DATA_LENGTH>, (T, Link)>>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Eq
+        + Clone
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    load_unsized_index_pages(values, config, OrderCheck::multi(), |v| {
+        (v.key.clone(), v.link)
+    })
+}
+
+fn load_unsized_index_pages<T, K, const DATA_LENGTH: u32>(
+    values: impl IntoIterator<Item = (T, Link)>,
+    config: BulkLoadConfig,
+    mut order: OrderCheck<T>,
+    node_key: impl Fn(&IndexValue<T>) -> K,
+) -> eyre::Result<BulkLoadedIndex<UnsizedIndexPage<T, DATA_LENGTH>, K>>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Eq
+        + Clone
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+    K: Debug + Ord + Eq + Clone + SizeMeasurable,
+{
+    config.validate()?;
+    let budget = ((DATA_LENGTH as f64 * config.fill_factor) as usize).max(1);
+
+    let mut acc = PagesAccumulator::new(PageType::IndexUnsized, config);
+    let mut node: Vec<IndexValue<T>> = vec![];
+    let mut values_len = 0;
+    for (key, link) in values {
+        let value = IndexValue { key, link };
+        order.check(&value)?;
+        let value_len = value.aligned_size();
+        // Value becomes new `node_id`, so it is counted twice: in utility part
+        // and in values part.
+        let required = |count: usize, len: usize| {
+            UnsizedIndexPageUtility::<T>::persisted_size(count, value_len) + len + value_len
+        };
+
+        if !node.is_empty() && required(node.len() + 1, values_len) > budget {
+            let node_id = node_key(node.last().expect("node is not empty"));
+            acc.push(node_id, UnsizedIndexPage::from_node(&node));
+            node.clear();
+            values_len = 0;
+        }
+        if required(node.len() + 1, values_len) > DATA_LENGTH as usize {
+            bail!(
+                "Value {:?} with length {} can not fit in page with length {}",
+                value.key,
+                value_len,
+                DATA_LENGTH
+            )
+        }
+        values_len += value_len;
+        node.push(value);
+    }
+    if let Some(last) = node.last() {
+        let node_id = node_key(last);
+        acc.push(node_id, UnsizedIndexPage::from_node(&node));
+    }
+
+    Ok(acc.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bulk_load_index_pages, bulk_load_multimap_index_pages,
+        bulk_load_multimap_unsized_index_pages, bulk_load_unsized_index_pages, BulkLoadConfig,
+    };
+    use crate::{
+        get_index_page_size_from_data_length, Link, PageType, Persistable, INNER_PAGE_SIZE,
+    };
+
+    fn link(i: u32) -> Link {
+        Link {
+            page_id: 1.into(),
+            offset: i * 24,
+            length: 24,
+        }
+    }
+
+    #[test]
+    fn test_bulk_load_sized() {
+        let size = get_index_page_size_from_data_length::<u64>(INNER_PAGE_SIZE);
+        let count = size * 2 + 10;
+        let index = bulk_load_index_pages(
+            (0..count as u64).map(|i| (i, link(i as u32))),
+            BulkLoadConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(index.pages.len(), 3);
+        assert_eq!(index.pages[0].header.page_id, 1.into());
+        assert_eq!(index.pages[0].header.next_id, 2.into());
+        assert_eq!(index.pages[2].header.previous_id, 2.into());
+        assert_eq!(index.pages[2].header.page_type, PageType::Index);
+        assert_eq!(index.pages[0].inner.current_length as usize, size);
+        assert_eq!(index.pages[0].inner.node_id.key, size as u64 - 1);
+        assert_eq!(index.pages[2].inner.current_length, 10);
+        assert_eq!(index.pages[2].inner.node_id.key, count as u64 - 1);
+
+        assert_eq!(index.toc.get(&(size as u64 - 1)), Some(1.into()));
+        assert_eq!(index.toc.get(&(count as u64 - 1)), Some(3.into()));
+        assert_eq!(index.toc.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_bulk_load_fill_factor() {
+        let size = get_index_page_size_from_data_length::<u64>(INNER_PAGE_SIZE);
+        let half = size / 2;
+        let index = bulk_load_index_pages(
+            (0..(half * 2) as u64).map(|i| (i, link(i as u32))),
+            BulkLoadConfig {
+                fill_factor: 0.5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(index.pages.len(), 2);
+        assert_eq!(index.pages[0].inner.current_length as usize, half);
+        assert_eq!(index.pages[0].inner.size as usize, size);
+    }
+
+    #[test]
+    fn test_bulk_load_unsorted() {
+        let res = bulk_load_index_pages(
+            vec![(2u64, link(0)), (1, link(1))],
+            BulkLoadConfig::default(),
+        );
+        assert!(res.is_err());
+        let res = bulk_load_index_pages(
+            vec![(1u64, link(0)), (1, link(1))],
+            BulkLoadConfig::default(),
+        );
+        assert!(res.is_err());
+        let res = bulk_load_index_pages(
+            vec![(1u64, link(0))],
+            BulkLoadConfig {
+                fill_factor: 0.0,
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_bulk_load_unsized() {
+        let index = bulk_load_unsized_index_pages::<_, 1024>(
+            (0..100u32).map(|i| (format!("{i:03}___________________{i:03}"), link(i))),
+            BulkLoadConfig::default(),
+        )
+        .unwrap();
+
+        assert!(index.pages.len() > 1);
+        let mut count = 0;
+        for page in &index.pages {
+            assert_eq!(page.header.page_type, PageType::IndexUnsized);
+            assert_eq!(page.inner.node_id, *page.inner.index_values.last().unwrap());
+            assert_eq!(
+                index.toc.get(&page.inner.node_id.key),
+                Some(page.header.page_id)
+            );
+            let bytes = page.inner.as_bytes();
+            assert_eq!(bytes.as_ref().len(), 1024);
+            count += page.inner.index_values.len();
+        }
+        assert_eq!(count, 100);
+    }
+
+    #[test]
+    fn test_bulk_load_unsized_too_long() {
+        let res = bulk_load_unsized_index_pages::<_, 128>(
+            vec![("a".repeat(256), link(0))],
+            BulkLoadConfig::default(),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_bulk_load_multimap() {
+        let size = get_index_page_size_from_data_length::<u64>(INNER_PAGE_SIZE);
+        // All values of the second page have same key, so it's values are
+        // spread over two pages. Values with same key are not ordered by link.
+        let values = (0..size as u32)
+            .map(|i| (i as u64, link(i)))
+            .chain((0..size as u32 * 2).rev().map(|i| (size as u64, link(i))))
+            .collect::<Vec<_>>();
+        let index = bulk_load_multimap_index_pages(values, BulkLoadConfig::default()).unwrap();
+
+        assert_eq!(index.pages.len(), 3);
+        assert_eq!(index.pages[1].inner.node_id.key, size as u64);
+        assert_eq!(index.pages[1].inner.node_id.link, link(size as u32));
+        assert_eq!(index.toc.get(&(size as u64, link(0))), Some(3.into()));
+        let mut pages = index.toc.get_pages_for_key(&(size as u64));
+        pages.sort();
+        assert_eq!(pages, vec![2.into(), 3.into()]);
+        assert_eq!(index.toc.get_pages_for_key(&1), vec![1.into()]);
+        assert_eq!(index.toc.get_pages_for_key(&(size as u64 + 1)), vec![]);
+
+        let res = bulk_load_multimap_index_pages(
+            vec![(1u64, link(1)), (1, link(0)), (1, link(1))],
+            BulkLoadConfig::default(),
+        );
+        assert!(res.is_err());
+        let res = bulk_load_multimap_index_pages(
+            vec![(2u64, link(1)), (1, link(0))],
+            BulkLoadConfig::default(),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_bulk_load_multimap_unsized() {
+        let index = bulk_load_multimap_unsized_index_pages::<_, 1024>(
+            (0..100u32).map(|i| (format!("{:03}___________________", i / 50), link(i))),
+            BulkLoadConfig::default(),
+        )
+        .unwrap();
+
+        assert!(index.pages.len() > 2);
+        let pages = index
+            .toc
+            .get_pages_for_key(&"000___________________".to_string());
+        let count = index
+            .pages
+            .iter()
+            .filter(|p| pages.contains(&p.header.page_id))
+            .flat_map(|p| p.inner.index_values.iter())
+            .filter(|v| v.key == "000___________________")
+            .count();
+        assert_eq!(count, 50);
+    }
+}
@@ -1,8 +1,12 @@
 use std::fmt::Debug;
 use std::io::SeekFrom;
 
+use eyre::bail;
 use indexset::core::multipair::MultiPair;
 use indexset::core::pair::Pair;
+use rkyv::api::high::HighValidator;
+use rkyv::bytecheck::CheckBytes;
+use rkyv::util::AlignedVec;
 use rkyv::{Archive, Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
@@ -12,17 +16,33 @@ use crate::{
     GENERAL_HEADER_SIZE,
 };
 
+mod bloom_filter;
+mod bulk_load;
 mod page;
 mod page_cdc_impl;
 mod page_for_unsized;
 mod page_for_unsized_cdc_impl;
+mod page_with_prefix;
+mod table_of_contents_chain;
 mod table_of_contents_page;
+mod vacuum;
 
 use crate::page::PageId;
 
+pub use bloom_filter::{
+    find_links_with_filters, parse_bloom_filters, persist_bloom_filter, BloomFilter,
+    BloomFilterConfig, BloomFilterPage, BloomKey, FilterKeys, FilteredIndexPage,
+};
+pub use bulk_load::{
+    bulk_load_index_pages, bulk_load_multimap_index_pages, bulk_load_multimap_unsized_index_pages,
+    bulk_load_unsized_index_pages, BulkLoadConfig, BulkLoadedIndex,
+};
 pub use page::{get_index_page_size_from_data_length, IndexPage};
 pub use page_for_unsized::{UnsizedIndexPage, UnsizedIndexPageUtility};
+pub use page_with_prefix::{PrefixIndexPage, PrefixIndexPageUtility};
+pub use table_of_contents_chain::{parse_table_of_contents_chain, persist_table_of_contents_chain};
 pub use table_of_contents_page::TableOfContentsPage;
+pub use vacuum::{vacuum_index_pages, vacuum_unsized_index_pages, VacuumReport};
 
 pub trait IndexPageUtility<T> {
     type Utility: Persistable + Send + Sync;
@@ -117,3 +137,36 @@ where
         }
     }
 }
+
+/// Checks that `len` bytes of the page from `offset` are valid archived `T`.
+/// Pages persisted by parts are checked part by part with it.
+pub(crate) fn check_archived_part<T>(bytes: &[u8], offset: usize, len: usize) -> eyre::Result<()>
+where
+    T: Archive,
+    <T as Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>,
+{
+    let Some(part) = offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+    else {
+        bail!(
+            "Part from {} with length {} is out of page with length {}",
+            offset,
+            len,
+            bytes.len()
+        )
+    };
+    // Archived bytes are validated only if they are aligned.
+    let mut aligned = AlignedVec::<16>::new();
+    aligned.extend_from_slice(part);
+    rkyv::access::<<T as Archive>::Archived, rkyv::rancor::Error>(&aligned)?;
+    Ok(())
+}
+
+/// Reads size field of the page persisted by parts. Sizes are checked before
+/// they are used to find next parts.
+pub(crate) fn read_size_part(bytes: &[u8], offset: usize) -> eyre::Result<u16> {
+    let len = u16::default().aligned_size();
+    check_archived_part::<u16>(bytes, offset, len)?;
+    Ok(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]))
+}
@@ -6,7 +6,10 @@ use std::io::SeekFrom;
 use std::mem;
 
 use data_bucket_codegen::Persistable;
+use eyre::bail;
 use indexset::core::pair::Pair;
+use rkyv::api::high::HighValidator;
+use rkyv::bytecheck::CheckBytes;
 use rkyv::de::Pool;
 use rkyv::rancor::Strategy;
 use rkyv::ser::allocator::ArenaHandle;
@@ -17,7 +20,7 @@ use rkyv::{Archive, Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
-use crate::page::index::IndexPageUtility;
+use crate::page::index::{check_archived_part, read_size_part, IndexPageUtility};
 use crate::page::{IndexValue, PageId};
 use crate::{
     align, align8, seek_to_page_start, Link, Persistable, SizeMeasurable, GENERAL_HEADER_SIZE,
@@ -295,6 +298,82 @@ impl<T: Default + SizeMeasurable> IndexPage<T> {
 
         page
     }
+
+    /// Returns part of the page's slots that are occupied by values.
+    pub fn fill_factor(&self) -> f64 {
+        self.current_length as f64 / self.size as f64
+    }
+
+    /// Checks if values of both pages can be placed in one page.
+    pub fn can_merge(&self, right: &IndexPage<T>) -> bool {
+        self.current_length as usize + right.current_length as usize <= self.size as usize
+    }
+
+    /// Moves all values of the `right` page to this page. `right` page's
+    /// values should be greater than this page's values, so `node_id` becomes
+    /// equal to `right`'s `node_id`. Values are packed, so all removed slots
+    /// are reclaimed. Returns error and leaves pages unchanged if values
+    /// don't fit in one page.
+    pub fn merge(&mut self, right: IndexPage<T>) -> eyre::Result<()>
+    where
+        T: Clone + Ord,
+    {
+        if !self.can_merge(&right) {
+            bail!(
+                "Pages with {} and {} values can not be merged in page of size {}",
+                self.current_length,
+                right.current_length,
+                self.size
+            )
+        }
+        let mut node = self.get_node();
+        node.extend(right.get_node());
+        if node.is_empty() {
+            self.node_id = right.node_id;
+            return Ok(());
+        }
+        *self = IndexPage::from_node(&node, self.size as usize);
+        Ok(())
+    }
+
+    /// Redistributes values between this page and `right` page, so both pages
+    /// have same amount of values. `right` page's values should be greater
+    /// than this page's values. `right`'s `node_id` is not changed.
+    pub fn redistribute(&mut self, right: &mut IndexPage<T>)
+    where
+        T: Clone + Ord,
+    {
+        let mut node = self.get_node();
+        node.extend(right.get_node());
+        if node.len() < 2 {
+            return;
+        }
+        let right_node = node.split_off(node.len() / 2);
+        *self = IndexPage::from_node(&node, self.size as usize);
+        *right = IndexPage::from_node(&right_node, right.size as usize);
+    }
+}
+
+impl<T> IndexPage<T>
+where
+    T: Archive + Default + SizeMeasurable,
+    <T as Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>,
+{
+    /// Checks that `bytes` are valid [`IndexPage`] persisted by parts, so they
+    /// can be safely read with [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8]) -> eyre::Result<()> {
+        let size = read_size_part(bytes, 0)? as usize;
+        let mut offset = Self::size_size();
+        check_archived_part::<IndexValue<T>>(bytes, offset, Self::node_id_size())?;
+        offset += Self::node_id_size();
+        check_archived_part::<u16>(bytes, offset, Self::current_index_size())?;
+        offset += Self::current_index_size();
+        check_archived_part::<u16>(bytes, offset, Self::current_length_size())?;
+        offset += Self::current_length_size();
+        check_archived_part::<Vec<u16>>(bytes, offset, Self::slots_size(size))?;
+        offset += Self::slots_size(size);
+        check_archived_part::<Vec<IndexValue<T>>>(bytes, offset, Self::index_values_size(size))
+    }
 }
 
 #[cfg(test)]
@@ -364,6 +443,16 @@ mod tests {
         assert_eq!(new_page.index_values, page.index_values);
     }
 
+    #[test]
+    fn test_check_bytes() {
+        let page = page_with_keys(0..5, 8);
+        let bytes = page.as_bytes();
+        assert!(IndexPage::<u64>::check_bytes(bytes.as_ref()).is_ok());
+        let len = bytes.as_ref().len();
+        assert!(IndexPage::<u64>::check_bytes(&bytes.as_ref()[..len - 1]).is_err());
+        assert!(IndexPage::<u64>::check_bytes(&[]).is_err());
+    }
+
     #[test]
     fn test_split() {
         let mut page = IndexPage::<u64>::new(
@@ -409,4 +498,59 @@ mod tests {
         assert_eq!(split.index_values[2].key, 6);
         assert_eq!(split.index_values[3].key, 7);
     }
+
+    fn page_with_keys(keys: impl Iterator<Item = u64>, size: usize) -> IndexPage<u64> {
+        let node = keys
+            .map(|key| IndexValue {
+                key,
+                link: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        IndexPage::from_node(&node, size)
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut left = page_with_keys(0..3, 8);
+        let right = page_with_keys(3..6, 8);
+        assert!(left.can_merge(&right));
+
+        left.merge(right).unwrap();
+        assert_eq!(left.current_length, 6);
+        assert_eq!(left.current_index, 6);
+        assert_eq!(left.node_id.key, 5);
+        assert_eq!(
+            left.get_node()
+                .into_iter()
+                .map(|p| p.key)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+        assert!(!left.can_merge(&page_with_keys(6..9, 8)));
+    }
+
+    #[test]
+    fn test_merge_overfull() {
+        let mut left = page_with_keys(0..5, 8);
+        let right = page_with_keys(5..9, 8);
+        assert!(!left.can_merge(&right));
+
+        assert!(left.merge(right).is_err());
+        assert_eq!(left.current_length, 5);
+        assert_eq!(left.node_id.key, 4);
+    }
+
+    #[test]
+    fn test_redistribute() {
+        let mut left = page_with_keys(0..1, 8);
+        let mut right = page_with_keys(1..8, 8);
+
+        left.redistribute(&mut right);
+        assert_eq!(left.current_length, 4);
+        assert_eq!(left.node_id.key, 3);
+        assert_eq!(right.current_length, 4);
+        assert_eq!(right.node_id.key, 7);
+        assert_eq!(right.index_values[0].key, 4);
+        assert_eq!(left.fill_factor(), 0.5);
+    }
 }
@@ -2,6 +2,7 @@ use std::fmt::Debug;
 
 use eyre::bail;
 use indexset::cdc::change::ChangeEvent;
+use indexset::core::multipair::MultiPair;
 use indexset::core::pair::Pair;
 use rkyv::de::Pool;
 use rkyv::rancor::Strategy;
@@ -73,6 +74,52 @@ where
         }
     }
 
+    /// Applies [`ChangeEvent`] of the multimap (non-unique) index. Values with
+    /// same key have no defined order (`indexset` orders them by a random
+    /// discriminator), so `node_id` is always just the last value of the page.
+    pub fn apply_multi_change_event(
+        &mut self,
+        event: ChangeEvent<MultiPair<T, Link>>,
+    ) -> eyre::Result<()> {
+        match event {
+            ChangeEvent::InsertAt {
+                event_id: _,
+                max_value: _,
+                value,
+                index,
+            } => {
+                let value: IndexValue<T> = value.into();
+                if index == self.current_length as usize {
+                    self.node_id = value.clone();
+                }
+                self.apply_insert_at(
+                    index,
+                    Pair {
+                        key: value.key,
+                        value: value.link,
+                    },
+                )
+            }
+            ChangeEvent::RemoveAt {
+                event_id: _,
+                max_value: _,
+                value: _,
+                index,
+            } => {
+                if index != 0 && index == self.current_length as usize - 1 {
+                    let previous_value_pos = self.slots[index - 1];
+                    self.node_id = self.index_values[previous_value_pos as usize].clone();
+                }
+                self.apply_remove_at(index)
+            }
+            ChangeEvent::SplitNode { .. }
+            | ChangeEvent::CreateNode { .. }
+            | ChangeEvent::RemoveNode { .. } => {
+                bail!("Events of `SplitNode`, `CreateNode` or `RemoveNode` can not be applied")
+            }
+        }
+    }
+
     fn apply_insert_at(&mut self, index: usize, value: Pair<T, Link>) -> eyre::Result<()> {
         // For insert we first add slot entry for our new index value
         self.slots.insert(index, self.current_index);
@@ -119,6 +166,8 @@ where
 mod test {
     use crate::{IndexPage, IndexValue, Link};
     use indexset::cdc::change::ChangeEvent;
+    use indexset::concurrent::multimap::BTreeMultiMap;
+    use indexset::core::multipair::MultiPair;
     use indexset::core::pair::Pair;
 
     #[test]
@@ -326,4 +375,104 @@ mod test {
         );
         assert_eq!(page.index_values[1], IndexValue::default())
     }
+
+    fn multi_link(offset: u32) -> Link {
+        Link {
+            page_id: 1.into(),
+            offset,
+            length: 8,
+        }
+    }
+
+    #[test]
+    fn test_multi_change_event() {
+        let mut page = IndexPage::new(
+            IndexValue {
+                key: 1,
+                link: multi_link(0),
+            },
+            10,
+        );
+        for (index, offset) in [0, 8, 16].into_iter().enumerate() {
+            let event = ChangeEvent::InsertAt {
+                event_id: 0.into(),
+                max_value: MultiPair::new(1, multi_link(0)),
+                value: MultiPair::new(1, multi_link(offset)),
+                index,
+            };
+            page.apply_multi_change_event(event).unwrap();
+        }
+        assert_eq!(page.node_id.link, multi_link(16));
+
+        let event = ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: MultiPair::new(1, multi_link(16)),
+            value: MultiPair::new(1, multi_link(16)),
+            index: 2,
+        };
+        page.apply_multi_change_event(event).unwrap();
+        assert_eq!(page.node_id.link, multi_link(8));
+        assert_eq!(page.current_length, 2);
+
+        let event = ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: MultiPair::new(1, multi_link(8)),
+            value: MultiPair::new(1, multi_link(0)),
+            index: 0,
+        };
+        page.apply_multi_change_event(event).unwrap();
+        assert_eq!(page.node_id.link, multi_link(8));
+        assert_eq!(page.get_node()[0].value, multi_link(8));
+    }
+
+    #[test]
+    fn test_multi_change_event_from_multimap() {
+        let map = BTreeMultiMap::<u64, Link>::with_maximum_node_size(64);
+        let mut page: Option<IndexPage<u64>> = None;
+        let mut apply = |events: Vec<ChangeEvent<MultiPair<u64, Link>>>| {
+            for event in events {
+                if let ChangeEvent::CreateNode { max_value, .. } = event {
+                    let mut new_page = IndexPage::new(max_value.clone().into(), 64);
+                    let event = ChangeEvent::InsertAt {
+                        event_id: 0.into(),
+                        max_value: max_value.clone(),
+                        value: max_value,
+                        index: 0,
+                    };
+                    new_page.apply_multi_change_event(event).unwrap();
+                    page = Some(new_page);
+                } else {
+                    let page = page.as_mut().expect("node should be created");
+                    page.apply_multi_change_event(event).unwrap();
+                }
+            }
+        };
+        // Values with same key are ordered by random discriminator in `indexset`.
+        for i in 0..32 {
+            let (_, events) = map.insert_cdc(i as u64 % 3, multi_link(i * 8));
+            apply(events);
+        }
+        let (last_key, last_link) = map.iter().last().map(|(k, l)| (*k, *l)).unwrap();
+        let (_, events) = map.remove_cdc(&last_key, &last_link);
+        apply(events);
+        let (_, events) = map.remove_cdc(&0, &multi_link(0));
+        apply(events);
+
+        let page = page.unwrap();
+        let expected = map.iter().map(|(k, l)| (*k, *l)).collect::<Vec<_>>();
+        let node = page
+            .get_node()
+            .into_iter()
+            .map(|p| (p.key, p.value))
+            .collect::<Vec<_>>();
+        assert_eq!(node, expected);
+        let (key, link) = expected.last().unwrap();
+        assert_eq!(
+            page.node_id,
+            IndexValue {
+                key: *key,
+                link: *link
+            }
+        );
+    }
 }
@@ -2,7 +2,10 @@ use std::fmt::Debug;
 use std::io::SeekFrom;
 
 use data_bucket_codegen::Persistable;
+use eyre::bail;
 use indexset::core::pair::Pair;
+use rkyv::api::high::HighValidator;
+use rkyv::bytecheck::CheckBytes;
 use rkyv::de::Pool;
 use rkyv::rancor::Strategy;
 use rkyv::ser::allocator::ArenaHandle;
@@ -13,7 +16,7 @@ use rkyv::{Archive, Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
-use crate::page::index::IndexPageUtility;
+use crate::page::index::{check_archived_part, read_size_part, IndexPageUtility};
 use crate::page::PageId;
 use crate::{align8, VariableSizeMeasurable};
 use crate::{seek_to_page_start, IndexValue, SizeMeasurable, GENERAL_HEADER_SIZE};
@@ -162,12 +165,22 @@ where
         }
     }
 
+    /// Sets `node_id` to the page's last value and repacks values. `node_id`
+    /// of the empty page is not changed.
     pub fn rebuild(&mut self)
     where
         T: Clone,
     {
-        self.node_id = self.index_values.last().unwrap().clone();
-        self.node_id_size = self.node_id.aligned_size() as u16;
+        if let Some(node_id) = self.index_values.last() {
+            self.node_id = node_id.clone();
+            self.node_id_size = self.node_id.aligned_size() as u16;
+        }
+        self.compact();
+    }
+
+    /// Repacks values, so space of removed values is reclaimed. Unlike
+    /// [`UnsizedIndexPage::rebuild`], `node_id` is not changed.
+    pub fn compact(&mut self) {
         self.last_value_offset = 0;
         self.removed_len = 0;
         let mut slots = vec![];
@@ -263,6 +276,175 @@ where
         let values = node.iter().map(|v| v.clone().into()).collect::<Vec<_>>();
         Self::new_with_values(values)
     }
+
+    /// Returns length of the persisted utility and values of a page that
+    /// contains provided values.
+    fn persisted_len(values: &[IndexValue<T>]) -> usize {
+        let values_len = values.iter().map(|v| v.aligned_size()).sum::<usize>();
+        let node_id_len = values.last().map(|v| v.aligned_size()).unwrap_or_default();
+        UnsizedIndexPageUtility::<T>::persisted_size(values.len(), node_id_len) + values_len
+    }
+
+    /// Returns length of the page's utility and values that are not removed.
+    pub fn live_bytes(&self) -> usize {
+        let values_len = self.slots.iter().map(|(_, l)| *l as usize).sum::<usize>();
+        UnsizedIndexPageUtility::<T>::persisted_size(
+            self.slots_size as usize,
+            self.node_id_size as usize,
+        ) + values_len
+    }
+
+    /// Returns length of the removed values that still occupy page's space.
+    /// This space is reclaimed by [`UnsizedIndexPage::compact`].
+    pub fn dead_bytes(&self) -> usize {
+        let values_len = self.slots.iter().map(|(_, l)| *l as usize).sum::<usize>();
+        self.last_value_offset as usize - values_len
+    }
+
+    /// Returns length of the space between page's utility and values, which
+    /// can be used for new values without compaction.
+    pub fn free_bytes(&self) -> usize {
+        (DATA_LENGTH as usize).saturating_sub(self.live_bytes() + self.dead_bytes())
+    }
+
+    /// Returns part of the page's `DATA_LENGTH` that is occupied by removed
+    /// values.
+    pub fn fragmentation(&self) -> f64 {
+        self.dead_bytes() as f64 / DATA_LENGTH as f64
+    }
+
+    /// Returns part of the page's `DATA_LENGTH` that is occupied by utility
+    /// and values.
+    pub fn fill_factor(&self) -> f64 {
+        Self::persisted_len(&self.index_values) as f64 / DATA_LENGTH as f64
+    }
+
+    /// Checks if values of both pages can be placed in one page.
+    pub fn can_merge(&self, right: &UnsizedIndexPage<T, DATA_LENGTH>) -> bool {
+        let values_len = self
+            .index_values
+            .iter()
+            .chain(right.index_values.iter())
+            .map(|v| v.aligned_size())
+            .sum::<usize>();
+        let node_id = right.index_values.last().or(self.index_values.last());
+        let node_id_len = node_id.map(|v| v.aligned_size()).unwrap_or_default();
+        let count = self.index_values.len() + right.index_values.len();
+        UnsizedIndexPageUtility::<T>::persisted_size(count, node_id_len) + values_len
+            <= DATA_LENGTH as usize
+    }
+
+    /// Moves all values of the `right` page to this page. `right` page's
+    /// values should be greater than this page's values, so `node_id` becomes
+    /// equal to `right`'s `node_id`. Page is rebuilt, so removed values' space
+    /// is reclaimed. Returns error and leaves pages unchanged if values don't
+    /// fit in one page.
+    pub fn merge(&mut self, right: UnsizedIndexPage<T, DATA_LENGTH>) -> eyre::Result<()> {
+        if !self.can_merge(&right) {
+            bail!(
+                "Pages with {} and {} values can not be merged in page of length {}",
+                self.index_values.len(),
+                right.index_values.len(),
+                DATA_LENGTH
+            )
+        }
+        if right.index_values.is_empty() && self.index_values.is_empty() {
+            self.node_id = right.node_id;
+            self.node_id_size = right.node_id_size;
+            return Ok(());
+        }
+        self.index_values.extend(right.index_values);
+        self.rebuild();
+        Ok(())
+    }
+
+    /// Redistributes values between this page and `right` page, so both pages
+    /// have nearly same persisted length. `right` page's values should be
+    /// greater than this page's values. `right`'s `node_id` is not changed.
+    /// Empty pages are left unchanged. Returns error and leaves pages
+    /// unchanged if values can't be split between two pages.
+    pub fn redistribute(
+        &mut self,
+        right: &mut UnsizedIndexPage<T, DATA_LENGTH>,
+    ) -> eyre::Result<()> {
+        if self.index_values.is_empty() && right.index_values.is_empty() {
+            return Ok(());
+        }
+        let left_len = self.index_values.len();
+        let mut values = std::mem::take(&mut self.index_values);
+        values.append(&mut right.index_values);
+        if values.len() < 2 {
+            self.index_values = values;
+            self.rebuild();
+            right.compact();
+            return Ok(());
+        }
+
+        let mut split_index = None;
+        let mut best_diff = usize::MAX;
+        for index in 1..values.len() {
+            let left_len = Self::persisted_len(&values[..index]);
+            let right_len = Self::persisted_len(&values[index..]);
+            if left_len > DATA_LENGTH as usize || right_len > DATA_LENGTH as usize {
+                continue;
+            }
+            let diff = left_len.abs_diff(right_len);
+            if diff < best_diff {
+                best_diff = diff;
+                split_index = Some(index);
+            }
+        }
+        let Some(split_index) = split_index else {
+            right.index_values = values.split_off(left_len);
+            self.index_values = values;
+            bail!("Values of the pages can not be split between two pages")
+        };
+        right.index_values = values.split_off(split_index);
+        right.rebuild();
+        self.index_values = values;
+        self.rebuild();
+        Ok(())
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> UnsizedIndexPage<T, DATA_LENGTH>
+where
+    T: Archive + Default + SizeMeasurable + VariableSizeMeasurable,
+    <T as Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>,
+{
+    /// Checks that `bytes` are valid [`UnsizedIndexPage`] persisted by parts,
+    /// so they can be safely read with [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8]) -> eyre::Result<()> {
+        type Utility<T> = UnsizedIndexPageUtility<T>;
+
+        let slots_size = read_size_part(bytes, 0)? as usize;
+        let mut offset = Utility::<T>::slots_size_size();
+        let node_id_size = read_size_part(bytes, offset)? as usize;
+        offset += Utility::<T>::node_id_size_size();
+        check_archived_part::<IndexValue<T>>(bytes, offset, node_id_size)?;
+        offset += node_id_size;
+        check_archived_part::<u32>(bytes, offset, Utility::<T>::last_value_offset_size())?;
+        offset += Utility::<T>::last_value_offset_size();
+        check_archived_part::<u32>(bytes, offset, Utility::<T>::removed_len_size())?;
+        offset += Utility::<T>::removed_len_size();
+        let slots_len = Utility::<T>::slots_size(slots_size);
+        check_archived_part::<Vec<(u32, u16)>>(bytes, offset, slots_len)?;
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(&bytes[offset..offset + slots_len]);
+        let slots = rkyv::from_bytes::<Vec<(u32, u16)>, rkyv::rancor::Error>(&aligned)?;
+
+        for (value_offset, len) in slots {
+            let Some(value_offset) = bytes.len().checked_sub(value_offset as usize) else {
+                bail!(
+                    "Value offset {} is out of page with length {}",
+                    value_offset,
+                    bytes.len()
+                )
+            };
+            check_archived_part::<IndexValue<T>>(bytes, value_offset, len as usize)?;
+        }
+        Ok(())
+    }
 }
 
 impl<T, const DATA_LENGTH: u32> Persistable for UnsizedIndexPage<T, DATA_LENGTH>
@@ -349,7 +531,7 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{IndexValue, Link, Persistable, UnsizedIndexPage};
+    use crate::{IndexValue, Link, Persistable, SizeMeasurable, UnsizedIndexPage};
 
     #[test]
     fn to_bytes_and_back() {
@@ -364,6 +546,29 @@ mod test {
         assert_eq!(page_back, page)
     }
 
+    #[test]
+    fn check_bytes() {
+        let page = UnsizedIndexPage::<_, 1024>::new(IndexValue {
+            key: "Someone from somewhere".to_string(),
+            link: Default::default(),
+        })
+        .unwrap();
+        let bytes = page.as_bytes();
+        assert!(UnsizedIndexPage::<String, 1024>::check_bytes(bytes.as_ref()).is_ok());
+
+        // Value's offset points out of page.
+        let mut broken = bytes.as_ref().to_vec();
+        let slots_offset = 4 + page.node_id_size as usize + 8;
+        broken[slots_offset..slots_offset + 4].copy_from_slice(&2000u32.to_le_bytes());
+        assert!(UnsizedIndexPage::<String, 1024>::check_bytes(&broken).is_err());
+
+        // Value's string is not valid UTF-8.
+        let mut broken = bytes.as_ref().to_vec();
+        let (offset, _) = page.slots[0];
+        broken[1024 - offset as usize] = 0xff;
+        assert!(UnsizedIndexPage::<String, 1024>::check_bytes(&broken).is_err());
+    }
+
     #[test]
     fn split() {
         let mut values = vec![];
@@ -392,4 +597,111 @@ mod test {
         assert_eq!(split.last_value_offset, offset as u32);
         assert_eq!(split.last_value_offset, page.slots.last().unwrap().0)
     }
+
+    #[test]
+    fn space_accounting() {
+        let mut page = page_with_keys(0..10);
+        let value_len = page.index_values[0].aligned_size();
+        assert_eq!(page.dead_bytes(), 0);
+        assert_eq!(page.live_bytes() + page.free_bytes(), 1024);
+
+        let live = page.live_bytes();
+        page.slots.remove(0);
+        page.index_values.remove(0);
+        page.slots_size -= 1;
+        assert_eq!(page.dead_bytes(), value_len);
+        assert_eq!(
+            page.live_bytes() + page.dead_bytes() + page.free_bytes(),
+            1024
+        );
+        assert!(page.live_bytes() < live);
+        assert!(page.fragmentation() > 0.0);
+
+        let node_id = page.node_id.clone();
+        page.compact();
+        assert_eq!(page.dead_bytes(), 0);
+        assert_eq!(page.node_id, node_id);
+        assert_eq!(page.live_bytes() + page.free_bytes(), 1024);
+    }
+
+    fn page_with_keys(keys: std::ops::Range<u32>) -> UnsizedIndexPage<String, 1024> {
+        let values = keys
+            .map(|i| IndexValue {
+                key: format!("{i:03}___________________{i:03}"),
+                link: Default::default(),
+            })
+            .collect();
+        UnsizedIndexPage::new_with_values(values)
+    }
+
+    #[test]
+    fn merge() {
+        let mut left = page_with_keys(0..5);
+        let right = page_with_keys(5..10);
+        assert!(left.can_merge(&right));
+
+        left.merge(right).unwrap();
+        assert_eq!(left.slots_size, 10);
+        assert_eq!(left.node_id, *left.index_values.last().unwrap());
+        assert_eq!(left.removed_len, 0);
+        let offset = left.slots.iter().map(|(_, l)| *l).sum::<u16>();
+        assert_eq!(left.last_value_offset, offset as u32);
+        assert!(left.as_bytes().as_ref().len() == 1024);
+
+        assert!(!left.can_merge(&page_with_keys(10..40)));
+        let slots_size = left.slots_size;
+        assert!(left.merge(page_with_keys(10..40)).is_err());
+        assert_eq!(left.slots_size, slots_size);
+    }
+
+    #[test]
+    fn redistribute() {
+        let mut left = page_with_keys(0..2);
+        let mut right = page_with_keys(2..20);
+        let right_node_id = right.node_id.clone();
+
+        left.redistribute(&mut right).unwrap();
+        assert_eq!(left.slots_size, 10);
+        assert_eq!(right.slots_size, 10);
+        assert_eq!(right.node_id, right_node_id);
+        assert_eq!(left.node_id, left.index_values[9]);
+        assert_eq!(left.fill_factor(), right.fill_factor());
+    }
+
+    #[test]
+    fn redistribute_empty() {
+        let mut left = page_with_keys(0..1);
+        left.index_values.clear();
+        let mut right = page_with_keys(1..2);
+        right.index_values.clear();
+        left.redistribute(&mut right).unwrap();
+        assert!(left.index_values.is_empty());
+        assert!(right.index_values.is_empty());
+    }
+
+    #[test]
+    fn redistribute_single_value() {
+        let mut left = page_with_keys(0..1);
+        let mut right = page_with_keys(1..3);
+        right.index_values.clear();
+        left.redistribute(&mut right).unwrap();
+        assert_eq!(left.slots_size, 1);
+        assert!(right.index_values.is_empty());
+        assert!(right.slots.is_empty());
+        assert_eq!(right.slots_size, 0);
+        assert_eq!(right.last_value_offset, 0);
+        let bytes = right.as_bytes();
+        UnsizedIndexPage::<String, 1024>::check_bytes(bytes.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn rebuild_empty() {
+        let mut page = page_with_keys(0..3);
+        let node_id = page.node_id.clone();
+        page.index_values.clear();
+        page.rebuild();
+        assert_eq!(page.node_id, node_id);
+        assert_eq!(page.slots_size, 0);
+        assert_eq!(page.last_value_offset, 0);
+    }
 }
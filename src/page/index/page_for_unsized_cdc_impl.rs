@@ -1,5 +1,6 @@
 use eyre::bail;
 use indexset::cdc::change::ChangeEvent;
+use indexset::core::multipair::MultiPair;
 use indexset::core::pair::Pair;
 use rkyv::de::Pool;
 use rkyv::rancor::Strategy;
@@ -14,6 +15,10 @@ use crate::{
     VariableSizeMeasurable,
 };
 
+/// Part of the page's `DATA_LENGTH` occupied by removed values after which
+/// page is compacted on insert.
+const COMPACTION_THRESHOLD: f64 = 0.25;
+
 impl<T, const DATA_LENGTH: u32> UnsizedIndexPage<T, DATA_LENGTH>
 where
     T: Archive
@@ -36,16 +41,9 @@ where
                 value,
                 index,
             } => {
-                if value.key > self.node_id.key {
-                    self.node_id = value.clone().into();
-                    self.node_id_size = value.aligned_size() as u16;
-                }
-                if index == self.slots_size as usize {
-                    self.node_id = value.clone().into();
-                    self.node_id_size = value.aligned_size() as u16;
-                }
-                self.apply_insert_at(index, value)?;
-                Ok(())
+                let node_id = (value.key > self.node_id.key || index == self.slots_size as usize)
+                    .then(|| IndexValue::from(value.clone()));
+                self.apply_insert_at(index, value, node_id)
             }
             ChangeEvent::RemoveAt {
                 event_id: _,
@@ -75,15 +73,95 @@ where
         }
     }
 
-    fn apply_insert_at(&mut self, index: usize, value: Pair<T, Link>) -> eyre::Result<()> {
+    /// Applies [`ChangeEvent`] of the multimap (non-unique) index. Values with
+    /// same key have no defined order (`indexset` orders them by a random
+    /// discriminator), so `node_id` is always just the last value of the page.
+    pub fn apply_multi_change_event(
+        &mut self,
+        event: ChangeEvent<MultiPair<T, Link>>,
+    ) -> eyre::Result<()> {
+        match event {
+            ChangeEvent::InsertAt {
+                event_id: _,
+                max_value: _,
+                value,
+                index,
+            } => {
+                let value: IndexValue<T> = value.into();
+                let node_id = (index == self.slots_size as usize).then(|| value.clone());
+                self.apply_insert_at(
+                    index,
+                    Pair {
+                        key: value.key,
+                        value: value.link,
+                    },
+                    node_id,
+                )
+            }
+            ChangeEvent::RemoveAt {
+                event_id: _,
+                max_value: _,
+                value: _,
+                index,
+            } => {
+                if index != 0 && index == self.slots_size as usize - 1 {
+                    let new_node_id = self
+                        .index_values
+                        .get(index - 1)
+                        .expect("should be available");
+                    self.node_id = new_node_id.clone();
+                    self.node_id_size = new_node_id.aligned_size() as u16;
+                }
+                self.apply_remove_at(index)
+            }
+            ChangeEvent::SplitNode { .. }
+            | ChangeEvent::CreateNode { .. }
+            | ChangeEvent::RemoveNode { .. } => {
+                bail!("Events of `SplitNode`, `CreateNode` or `RemoveNode` can not be applied")
+            }
+        }
+    }
+
+    /// Inserts value at `index` and sets `node_id` if it's provided. Page is
+    /// not changed if value doesn't fit in it.
+    fn apply_insert_at(
+        &mut self,
+        index: usize,
+        value: Pair<T, Link>,
+        node_id: Option<IndexValue<T>>,
+    ) -> eyre::Result<()> {
+        let node_id_size = node_id
+            .as_ref()
+            .map(|v| v.aligned_size() as u16)
+            .unwrap_or(self.node_id_size);
         // For insert we first add slot entry for our new index value
         let index_value = IndexValue {
             key: value.key.clone(),
             link: value.value,
         };
         let bytes = rkyv::to_bytes(&index_value)?;
-        let value_offset = self.last_value_offset;
         let len = bytes.len();
+        let required = |page: &Self| {
+            UnsizedIndexPageUtility::<T>::persisted_size(
+                page.slots_size as usize + 1,
+                node_id_size as usize,
+            ) + page.last_value_offset as usize
+                + len
+        };
+        if self.dead_bytes() != 0
+            && (required(self) > DATA_LENGTH as usize
+                || self.fragmentation() > COMPACTION_THRESHOLD)
+        {
+            self.compact();
+        }
+        if required(self) > DATA_LENGTH as usize {
+            bail!(
+                "Value with length {} can not fit in page with {} free bytes",
+                len,
+                self.free_bytes()
+            )
+        }
+        let value_offset = self.last_value_offset;
         self.last_value_offset += bytes.len() as u32;
         if index == self.index_values.len() {
             self.index_values.push(index_value.clone());
@@ -94,6 +172,10 @@ where
         self.slots
             .insert(index, (value_offset + len as u32, len as u16));
         self.slots_size += 1;
+        if let Some(node_id) = node_id {
+            self.node_id = node_id;
+            self.node_id_size = node_id_size;
+        }
 
         Ok(())
     }
@@ -115,8 +197,10 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{IndexValue, Link, UnsizedIndexPage};
+    use crate::{IndexValue, Link, SizeMeasurable, UnsizedIndexPage};
     use indexset::cdc::change::ChangeEvent;
+    use indexset::concurrent::multimap::BTreeMultiMap;
+    use indexset::core::multipair::MultiPair;
     use indexset::core::pair::Pair;
 
     #[test]
@@ -216,4 +300,158 @@ mod test {
 
         assert_eq!(page.node_id.key, "Something".to_string());
     }
+
+    fn insert_event(key: String, index: usize) -> ChangeEvent<Pair<String, Link>> {
+        ChangeEvent::InsertAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: key.clone(),
+                value: Link::default(),
+            },
+            value: Pair {
+                key,
+                value: Link::default(),
+            },
+            index,
+        }
+    }
+
+    fn remove_event(key: String, index: usize) -> ChangeEvent<Pair<String, Link>> {
+        ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: "zzz".to_string(),
+                value: Link::default(),
+            },
+            value: Pair {
+                key,
+                value: Link::default(),
+            },
+            index,
+        }
+    }
+
+    #[test]
+    fn test_insert_at_compacts_page() {
+        let key = |i: usize| format!("{i:03}_______________________");
+        let mut page = UnsizedIndexPage::<_, 1024>::new(IndexValue {
+            key: key(0),
+            link: Default::default(),
+        })
+        .unwrap();
+        let mut i = 1;
+        while page.free_bytes() > 128 {
+            page.apply_change_event(insert_event(key(i), i)).unwrap();
+            i += 1;
+        }
+        // Removes values from start, so `node_id` is not changed.
+        for _ in 0..4 {
+            let value = page.index_values[0].key.clone();
+            page.apply_change_event(remove_event(value, 0)).unwrap();
+        }
+        let dead = page.dead_bytes();
+        assert!(dead > 0);
+        while page.dead_bytes() != 0 {
+            page.apply_change_event(insert_event(key(i), page.index_values.len()))
+                .unwrap();
+            i += 1;
+        }
+        assert!(page.free_bytes() < dead);
+        let offset = page.slots.iter().map(|(_, l)| *l as u32).sum::<u32>();
+        assert_eq!(page.last_value_offset, offset);
+        assert_eq!(page.removed_len, 0);
+        assert_eq!(page.node_id, *page.index_values.last().unwrap());
+    }
+
+    #[test]
+    fn test_insert_at_full_page() {
+        let mut page = UnsizedIndexPage::<_, 128>::new(IndexValue {
+            key: "a".to_string(),
+            link: Default::default(),
+        })
+        .unwrap();
+        let node_id = page.node_id.clone();
+        let res = page.apply_change_event(insert_event("b".repeat(128), 1));
+        assert!(res.is_err());
+        assert_eq!(page.node_id, node_id);
+        assert_eq!(page.node_id_size, node_id.aligned_size() as u16);
+        assert_eq!(page.index_values, vec![node_id]);
+    }
+
+    #[test]
+    fn test_multi_change_event() {
+        let link = |offset| Link {
+            page_id: 1.into(),
+            offset,
+            length: 8,
+        };
+        let mut page = UnsizedIndexPage::<_, 1024>::new(IndexValue {
+            key: "Something".to_string(),
+            link: link(0),
+        })
+        .unwrap();
+        for (index, offset) in [8, 16].into_iter().enumerate() {
+            let event = ChangeEvent::InsertAt {
+                event_id: 0.into(),
+                max_value: MultiPair::new("Something".to_string(), link(0)),
+                value: MultiPair::new("Something".to_string(), link(offset)),
+                index: index + 1,
+            };
+            page.apply_multi_change_event(event).unwrap();
+        }
+        assert_eq!(page.node_id.link, link(16));
+
+        let event = ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: MultiPair::new("Something".to_string(), link(16)),
+            value: MultiPair::new("Something".to_string(), link(16)),
+            index: 2,
+        };
+        page.apply_multi_change_event(event).unwrap();
+        assert_eq!(page.node_id.link, link(8));
+        assert_eq!(page.slots_size, 2);
+    }
+
+    #[test]
+    fn test_multi_change_event_from_multimap() {
+        let link = |offset| Link {
+            page_id: 1.into(),
+            offset,
+            length: 8,
+        };
+        let map = BTreeMultiMap::<String, Link>::with_maximum_node_size(64);
+        let mut page: Option<UnsizedIndexPage<String, 4096>> = None;
+        let mut apply = |events: Vec<ChangeEvent<MultiPair<String, Link>>>| {
+            for event in events {
+                if let ChangeEvent::CreateNode { max_value, .. } = event {
+                    page = Some(UnsizedIndexPage::new(max_value.into()).unwrap());
+                } else {
+                    let page = page.as_mut().expect("node should be created");
+                    page.apply_multi_change_event(event).unwrap();
+                }
+            }
+        };
+        // Values with same key are ordered by random discriminator in `indexset`.
+        for i in 0..32 {
+            let (_, events) = map.insert_cdc(format!("Something{}", i % 3), link(i * 8));
+            apply(events);
+        }
+        let (last_key, last_link) = map.iter().last().map(|(k, l)| (k.clone(), *l)).unwrap();
+        let (_, events) = map.remove_cdc(&last_key, &last_link);
+        apply(events);
+        let (_, events) = map.remove_cdc(&"Something0".to_string(), &link(0));
+        apply(events);
+
+        let page = page.unwrap();
+        let expected = map.iter().map(|(k, l)| (k.clone(), *l)).collect::<Vec<_>>();
+        let node = page
+            .get_node()
+            .into_iter()
+            .map(|p| (p.key, p.value))
+            .collect::<Vec<_>>();
+        assert_eq!(node, expected);
+        let (key, link) = expected.last().unwrap().clone();
+        assert_eq!(page.node_id, IndexValue { key, link });
+        assert_eq!(page.node_id_size, page.node_id.aligned_size() as u16);
+    }
 }
@@ -0,0 +1,685 @@
+//! Prefix compressed index page for [`String`] keys.
+//!
+//! Keys of one index page often share long prefix (tenant ids, URL paths and
+//! etc.), so storing it in every [`IndexValue`] wastes page's space. This page
+//! stores common prefix of all page's keys once and values hold only keys'
+//! suffixes. As all keys share the prefix, suffixes are ordered in the same
+//! way as full keys, so binary search works over suffixes.
+//!
+//! Page is persisted like [`UnsizedIndexPage`]: [`PrefixIndexPageUtility`]
+//! with it's length is placed at the page's start and values are placed at
+//! the page's end, so page's utility can be updated without values.
+//!
+//! [`UnsizedIndexPage`]: crate::UnsizedIndexPage
+
+use std::cmp::Ordering;
+use std::io::SeekFrom;
+
+use eyre::bail;
+use indexset::cdc::change::ChangeEvent;
+use indexset::core::pair::Pair;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::page::index::{check_archived_part, read_size_part, IndexPageUtility};
+use crate::page::PageId;
+use crate::{
+    align, seek_to_page_start, IndexValue, Link, Persistable, SizeMeasurable, GENERAL_HEADER_SIZE,
+};
+
+/// Offset of the archived [`PrefixIndexPageUtility`] in the page. It's
+/// preceded by it's length as archived `u16`.
+const UTILITY_OFFSET: usize = 4;
+/// Length of the archived [`PrefixIndexPageUtility`] without `prefix`,
+/// `node_id`'s key and `slots` which are placed before it.
+const UTILITY_ROOT_SIZE: usize = 36;
+/// Length of the archived `(u32, u16)` slot.
+const SLOT_SIZE: usize = 8;
+/// Length of the [`String`] that is archived inline.
+const INLINE_STRING_LENGTH: usize = 8;
+
+#[derive(Archive, Clone, Deserialize, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct PrefixIndexPage<const DATA_LENGTH: u32> {
+    /// Common prefix of all page's keys.
+    pub prefix: String,
+    /// Max value of the page with full key.
+    pub node_id: IndexValue<String>,
+    /// Page's values with keys' suffixes.
+    pub index_values: Vec<IndexValue<String>>,
+}
+
+/// Part of the persisted [`PrefixIndexPage`] that is placed at the page's
+/// start.
+#[derive(Archive, Clone, Deserialize, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct PrefixIndexPageUtility {
+    pub prefix: String,
+    pub node_id: IndexValue<String>,
+    /// Offsets of the values from the page's end and their lengths, in
+    /// values' order.
+    pub slots: Vec<(u32, u16)>,
+}
+
+impl PrefixIndexPageUtility {
+    /// Returns length of the persisted utility with it's length.
+    pub fn persisted_size(&self) -> usize {
+        let out_of_line = |s: &str| {
+            if s.len() <= INLINE_STRING_LENGTH {
+                0
+            } else {
+                s.len()
+            }
+        };
+        let mut len = out_of_line(&self.prefix) + out_of_line(&self.node_id.key);
+        if !self.slots.is_empty() {
+            len = align(len) + self.slots.len() * SLOT_SIZE;
+        }
+        UTILITY_OFFSET + align(len) + UTILITY_ROOT_SIZE
+    }
+}
+
+impl Persistable for PrefixIndexPageUtility {
+    fn as_bytes(&self) -> impl AsRef<[u8]> + Send {
+        let utility_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(self).unwrap();
+        let size_bytes =
+            rkyv::to_bytes::<rkyv::rancor::Error>(&(utility_bytes.len() as u16)).unwrap();
+        let mut bytes = vec![0u8; UTILITY_OFFSET];
+        bytes[..size_bytes.len()].copy_from_slice(&size_bytes);
+        bytes.extend_from_slice(&utility_bytes);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8], _version: u32) -> Self {
+        let size_length = u16::default().aligned_size();
+        let archived =
+            unsafe { rkyv::access_unchecked::<<u16 as Archive>::Archived>(&bytes[..size_length]) };
+        let size =
+            rkyv::deserialize::<u16, rkyv::rancor::Error>(archived).expect("data should be valid");
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(&bytes[UTILITY_OFFSET..UTILITY_OFFSET + size as usize]);
+        let archived = unsafe { rkyv::access_unchecked::<<Self as Archive>::Archived>(&aligned) };
+        rkyv::deserialize::<_, rkyv::rancor::Error>(archived).expect("data should be valid")
+    }
+}
+
+impl<const DATA_LENGTH: u32> IndexPageUtility<String> for PrefixIndexPage<DATA_LENGTH> {
+    type Utility = PrefixIndexPageUtility;
+
+    async fn parse_index_page_utility(
+        file: &mut File,
+        page_id: PageId,
+    ) -> eyre::Result<Self::Utility> {
+        seek_to_page_start(file, page_id.0).await?;
+        file.seek(SeekFrom::Current(GENERAL_HEADER_SIZE as i64))
+            .await?;
+        let mut bytes = vec![0u8; UTILITY_OFFSET];
+        file.read_exact(&mut bytes).await?;
+        let size = read_size_part(&bytes, 0)? as usize;
+        if UTILITY_OFFSET + size > DATA_LENGTH as usize {
+            bail!(
+                "Utility with length {} is out of page with length {}",
+                size,
+                DATA_LENGTH
+            )
+        }
+        bytes.resize(UTILITY_OFFSET + size, 0);
+        file.read_exact(&mut bytes[UTILITY_OFFSET..]).await?;
+        check_archived_part::<PrefixIndexPageUtility>(&bytes, UTILITY_OFFSET, size)?;
+
+        Ok(PrefixIndexPageUtility::from_bytes(&bytes, 0))
+    }
+}
+
+/// Returns length in bytes of the common prefix of `left` and `right`. Length
+/// is always at `char` boundary.
+fn common_prefix_len(left: &str, right: &str) -> usize {
+    left.char_indices()
+        .zip(right.chars())
+        .find(|((_, l), r)| l != r)
+        .map(|((i, _), _)| i)
+        .unwrap_or(left.len().min(right.len()))
+}
+
+impl<const DATA_LENGTH: u32> PrefixIndexPage<DATA_LENGTH> {
+    pub fn new(node_id: IndexValue<String>) -> Self {
+        Self {
+            prefix: node_id.key.clone(),
+            index_values: vec![IndexValue {
+                key: String::new(),
+                link: node_id.link,
+            }],
+            node_id,
+        }
+    }
+
+    /// Creates page from values sorted in ascending order.
+    pub fn from_node(node: &[impl Into<IndexValue<String>> + Clone]) -> Self {
+        let values = node.iter().map(|v| v.clone().into()).collect::<Vec<_>>();
+        let node_id = values.last().expect("Node should be not empty").clone();
+        let mut page = Self {
+            prefix: String::new(),
+            node_id,
+            index_values: values,
+        };
+        page.extend_prefix();
+        page
+    }
+
+    pub fn get_node(&self) -> Vec<Pair<String, Link>> {
+        self.index_values
+            .iter()
+            .map(|v| Pair {
+                key: self.full_key(&v.key),
+                value: v.link,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index_values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index_values.is_empty()
+    }
+
+    /// Returns value at `index` with full key.
+    pub fn get(&self, index: usize) -> Option<IndexValue<String>> {
+        self.index_values.get(index).map(|v| IndexValue {
+            key: self.full_key(&v.key),
+            link: v.link,
+        })
+    }
+
+    fn full_key(&self, suffix: &str) -> String {
+        let mut key = String::with_capacity(self.prefix.len() + suffix.len());
+        key.push_str(&self.prefix);
+        key.push_str(suffix);
+        key
+    }
+
+    /// Searches for the first value with `key` like [`slice::binary_search`]
+    /// does.
+    pub fn binary_search(&self, key: &str) -> Result<usize, usize> {
+        let Some(suffix) = key.strip_prefix(self.prefix.as_str()) else {
+            return match key.cmp(self.prefix.as_str()) {
+                Ordering::Less | Ordering::Equal => Err(0),
+                Ordering::Greater => Err(self.index_values.len()),
+            };
+        };
+        let index = self
+            .index_values
+            .partition_point(|v| v.key.as_str() < suffix);
+        match self.index_values.get(index) {
+            Some(v) if v.key == suffix => Ok(index),
+            _ => Err(index),
+        }
+    }
+
+    /// Returns page's utility, values are placed one after another from the
+    /// page's end.
+    pub fn utility(&self) -> PrefixIndexPageUtility {
+        let mut offset = 0;
+        let slots = self
+            .index_values
+            .iter()
+            .map(|v| {
+                let len = v.aligned_size();
+                offset += len as u32;
+                (offset, len as u16)
+            })
+            .collect();
+        PrefixIndexPageUtility {
+            prefix: self.prefix.clone(),
+            node_id: self.node_id.clone(),
+            slots,
+        }
+    }
+
+    /// Returns length of the page's utility and values, which is exact length
+    /// of the used part of the persisted page.
+    pub fn persisted_size(&self) -> usize {
+        self.utility().persisted_size()
+            + self
+                .index_values
+                .iter()
+                .map(|v| v.aligned_size())
+                .sum::<usize>()
+    }
+
+    /// Shortens page's prefix, so `key` starts with it.
+    fn shrink_prefix(&mut self, key: &str) {
+        let len = common_prefix_len(&self.prefix, key);
+        if len == self.prefix.len() {
+            return;
+        }
+        let removed = self.prefix.split_off(len);
+        for value in self.index_values.iter_mut() {
+            value.key.insert_str(0, &removed);
+        }
+    }
+
+    /// Extends page's prefix to the common prefix of the first and the last
+    /// keys, which is common for all keys as they are sorted.
+    fn extend_prefix(&mut self) {
+        let (Some(first), Some(last)) = (self.index_values.first(), self.index_values.last())
+        else {
+            return;
+        };
+        let len = common_prefix_len(&first.key, &last.key);
+        if len == 0 {
+            return;
+        }
+        let added = first.key[..len].to_string();
+        for value in self.index_values.iter_mut() {
+            value.key.drain(..len);
+        }
+        self.prefix.push_str(&added);
+    }
+
+    pub fn split(&mut self, index: usize) -> PrefixIndexPage<DATA_LENGTH> {
+        let mut new_page = Self {
+            prefix: self.prefix.clone(),
+            node_id: self.node_id.clone(),
+            index_values: self.index_values.split_off(index),
+        };
+        new_page.extend_prefix();
+        if let Some(last) = self.get(self.index_values.len().wrapping_sub(1)) {
+            self.node_id = last;
+        }
+        self.extend_prefix();
+
+        new_page
+    }
+
+    pub fn apply_change_event(
+        &mut self,
+        event: ChangeEvent<Pair<String, Link>>,
+    ) -> eyre::Result<()> {
+        match event {
+            ChangeEvent::InsertAt {
+                event_id: _,
+                max_value: _,
+                value,
+                index,
+            } => {
+                let is_node_id = value.key > self.node_id.key || index == self.index_values.len();
+                self.apply_insert_at(index, value.clone())?;
+                if is_node_id {
+                    self.node_id = value.into();
+                }
+                Ok(())
+            }
+            ChangeEvent::RemoveAt {
+                event_id: _,
+                max_value,
+                value,
+                index,
+            } => {
+                // Same as for `UnsizedIndexPage`, index is checked to be
+                // non-zero, because for non-unique indexes this is possible.
+                if value == max_value && index != 0 && index == self.index_values.len() - 1 {
+                    self.node_id = self.get(index - 1).expect("should be available");
+                }
+                self.apply_remove_at(index)
+            }
+            ChangeEvent::SplitNode { .. }
+            | ChangeEvent::CreateNode { .. }
+            | ChangeEvent::RemoveNode { .. } => {
+                bail!("Events of `SplitNode`, `CreateNode` or `RemoveNode` can not be applied")
+            }
+        }
+    }
+
+    fn apply_insert_at(&mut self, index: usize, value: Pair<String, Link>) -> eyre::Result<()> {
+        if self.index_values.is_empty() {
+            self.prefix.clear();
+        }
+        self.shrink_prefix(&value.key);
+        let suffix = value.key[self.prefix.len()..].to_string();
+        self.index_values.insert(
+            index,
+            IndexValue {
+                key: suffix,
+                link: value.value,
+            },
+        );
+        if index == 0 || index == self.index_values.len() - 1 {
+            self.extend_prefix();
+        }
+        if self.persisted_size() > DATA_LENGTH as usize {
+            self.index_values.remove(index);
+            self.extend_prefix();
+            bail!(
+                "Value with key {:?} can not fit in page with length {}",
+                value.key,
+                DATA_LENGTH
+            )
+        }
+
+        Ok(())
+    }
+
+    fn apply_remove_at(&mut self, index: usize) -> eyre::Result<()> {
+        if index >= self.index_values.len() {
+            bail!(
+                "Index {} is out of page with {} values",
+                index,
+                self.index_values.len()
+            )
+        }
+        self.index_values.remove(index);
+        if index == 0 || index == self.index_values.len() {
+            self.extend_prefix();
+        }
+
+        Ok(())
+    }
+}
+
+impl<const DATA_LENGTH: u32> PrefixIndexPage<DATA_LENGTH> {
+    /// Checks that `bytes` are valid [`PrefixIndexPage`] persisted by parts,
+    /// so they can be safely read with [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8]) -> eyre::Result<()> {
+        let size = read_size_part(bytes, 0)? as usize;
+        check_archived_part::<PrefixIndexPageUtility>(bytes, UTILITY_OFFSET, size)?;
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(&bytes[UTILITY_OFFSET..UTILITY_OFFSET + size]);
+        let utility = rkyv::from_bytes::<PrefixIndexPageUtility, rkyv::rancor::Error>(&aligned)?;
+
+        for (value_offset, len) in utility.slots {
+            let Some(value_offset) = bytes.len().checked_sub(value_offset as usize) else {
+                bail!(
+                    "Value offset {} is out of page with length {}",
+                    value_offset,
+                    bytes.len()
+                )
+            };
+            check_archived_part::<IndexValue<String>>(bytes, value_offset, len as usize)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const DATA_LENGTH: u32> Persistable for PrefixIndexPage<DATA_LENGTH> {
+    fn as_bytes(&self) -> impl AsRef<[u8]> + Send {
+        let data_length = DATA_LENGTH as usize;
+        let utility = self.utility();
+        let utility_bytes = utility.as_bytes();
+        let utility_bytes = utility_bytes.as_ref();
+        let mut bytes = vec![0u8; data_length];
+        bytes[..utility_bytes.len()].copy_from_slice(utility_bytes);
+
+        for ((offset, len), value) in utility.slots.iter().zip(self.index_values.iter()) {
+            let offset = data_length - *offset as usize;
+            let value_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(value).unwrap();
+            bytes[offset..offset + *len as usize].copy_from_slice(&value_bytes);
+        }
+
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8], version: u32) -> Self {
+        let utility = PrefixIndexPageUtility::from_bytes(bytes, version);
+        let mut index_values = Vec::with_capacity(utility.slots.len());
+        for (offset, len) in &utility.slots {
+            let offset = bytes.len() - *offset as usize;
+            let mut aligned = AlignedVec::<16>::new();
+            aligned.extend_from_slice(&bytes[offset..offset + *len as usize]);
+            let archived = unsafe {
+                rkyv::access_unchecked::<<IndexValue<String> as Archive>::Archived>(&aligned)
+            };
+            let value = rkyv::deserialize::<_, rkyv::rancor::Error>(archived)
+                .expect("data should be valid");
+            index_values.push(value)
+        }
+
+        Self {
+            prefix: utility.prefix,
+            node_id: utility.node_id,
+            index_values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexset::cdc::change::ChangeEvent;
+    use indexset::core::pair::Pair;
+
+    use tokio::fs::File;
+
+    use crate::page::index::IndexPageUtility;
+    use crate::{
+        persist_page, GeneralHeader, GeneralPage, IndexValue, Link, PageType, Persistable,
+        PrefixIndexPage, PrefixIndexPageUtility,
+    };
+
+    fn key(i: u32) -> String {
+        format!("tenant/00000001/users/{i:04}")
+    }
+
+    fn page(keys: impl Iterator<Item = u32>) -> PrefixIndexPage<1024> {
+        let node = keys
+            .map(|i| IndexValue {
+                key: key(i),
+                link: Link {
+                    page_id: 1.into(),
+                    offset: i,
+                    length: 1,
+                },
+            })
+            .collect::<Vec<_>>();
+        PrefixIndexPage::from_node(&node)
+    }
+
+    fn insert_event(key: String, index: usize) -> ChangeEvent<Pair<String, Link>> {
+        ChangeEvent::InsertAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: key.clone(),
+                value: Link::default(),
+            },
+            value: Pair {
+                key,
+                value: Link::default(),
+            },
+            index,
+        }
+    }
+
+    #[test]
+    fn from_node() {
+        let page = page((10..20).map(|i| i * 10));
+        assert_eq!(page.prefix, "tenant/00000001/users/01");
+        assert_eq!(page.index_values[0].key, "00");
+        assert_eq!(page.node_id.key, key(190));
+        assert_eq!(page.get(3).unwrap().key, key(130));
+        assert_eq!(page.get_node().len(), 10);
+        assert_eq!(page.get_node()[9].key, key(190));
+    }
+
+    #[test]
+    fn binary_search() {
+        let page = page((10..20).map(|i| i * 10));
+        assert_eq!(page.binary_search(&key(100)), Ok(0));
+        assert_eq!(page.binary_search(&key(150)), Ok(5));
+        assert_eq!(page.binary_search(&key(155)), Err(6));
+        assert_eq!(page.binary_search(&key(5)), Err(0));
+        assert_eq!(page.binary_search("tenant"), Err(0));
+        assert_eq!(page.binary_search("tenant/00000002"), Err(10));
+    }
+
+    #[test]
+    fn apply_change_event() {
+        let mut page = page((10..20).map(|i| i * 10));
+
+        page.apply_change_event(insert_event(key(200), 10)).unwrap();
+        assert_eq!(page.node_id.key, key(200));
+        assert_eq!(page.prefix, "tenant/00000001/users/0");
+        assert_eq!(page.index_values[10].key, "200");
+
+        page.apply_change_event(insert_event("other".to_string(), 0))
+            .unwrap();
+        assert_eq!(page.prefix, "");
+        assert_eq!(page.get(1).unwrap().key, key(100));
+        assert_eq!(page.binary_search(&key(200)), Ok(11));
+
+        let event = ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: key(200),
+                value: Link::default(),
+            },
+            value: Pair {
+                key: "other".to_string(),
+                value: Link::default(),
+            },
+            index: 0,
+        };
+        page.apply_change_event(event).unwrap();
+        assert_eq!(page.prefix, "tenant/00000001/users/0");
+        assert_eq!(page.get(0).unwrap().key, key(100));
+
+        let event = ChangeEvent::RemoveAt {
+            event_id: 0.into(),
+            max_value: Pair {
+                key: key(200),
+                value: Link::default(),
+            },
+            value: Pair {
+                key: key(200),
+                value: Link::default(),
+            },
+            index: 10,
+        };
+        page.apply_change_event(event).unwrap();
+        assert_eq!(page.node_id.key, key(190));
+        assert_eq!(page.prefix, "tenant/00000001/users/01");
+    }
+
+    #[test]
+    fn split() {
+        let mut page = page(0..20);
+        let right = page.split(10);
+        assert_eq!(page.node_id.key, key(9));
+        assert_eq!(page.prefix, "tenant/00000001/users/000");
+        assert_eq!(right.node_id.key, key(19));
+        assert_eq!(right.prefix, "tenant/00000001/users/001");
+        assert_eq!(right.get(0).unwrap().key, key(10));
+    }
+
+    #[test]
+    fn to_bytes_and_back() {
+        let page = page(0..20);
+        let bytes = page.as_bytes();
+        assert_eq!(bytes.as_ref().len(), 1024);
+        PrefixIndexPage::<1024>::check_bytes(bytes.as_ref()).unwrap();
+        let parsed = PrefixIndexPage::<1024>::from_bytes(bytes.as_ref(), 0);
+        assert_eq!(parsed, page);
+    }
+
+    #[test]
+    fn utility_persisted_size() {
+        for prefix_len in 0..20 {
+            for key_len in 0..20 {
+                for slots_count in 0..3 {
+                    let utility = PrefixIndexPageUtility {
+                        prefix: "a".repeat(prefix_len),
+                        node_id: IndexValue {
+                            key: "b".repeat(key_len),
+                            link: Link::default(),
+                        },
+                        slots: vec![(8, 8); slots_count],
+                    };
+                    assert_eq!(
+                        utility.as_bytes().as_ref().len(),
+                        utility.persisted_size(),
+                        "{utility:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn persisted_size() {
+        let page = page(0..20);
+        let used = page.utility().as_bytes().as_ref().len()
+            + page
+                .index_values
+                .iter()
+                .map(|v| rkyv::to_bytes::<rkyv::rancor::Error>(v).unwrap().len())
+                .sum::<usize>();
+        assert_eq!(page.persisted_size(), used);
+    }
+
+    #[test]
+    fn check_bytes() {
+        let page = page(0..20);
+        let bytes = page.as_bytes().as_ref().to_vec();
+
+        let mut broken = bytes.clone();
+        broken[0] = 0xff;
+        broken[1] = 0xff;
+        assert!(PrefixIndexPage::<1024>::check_bytes(&broken).is_err());
+
+        let mut broken = bytes.clone();
+        let value_offset = 1024 - page.utility().slots[0].0 as usize;
+        // Inline key's bytes are not valid UTF-8.
+        broken[value_offset..value_offset + 2].copy_from_slice(&[0xc0, 0x00]);
+        assert!(PrefixIndexPage::<1024>::check_bytes(&broken).is_err());
+
+        assert!(PrefixIndexPage::<1024>::check_bytes(&bytes[..512]).is_err());
+    }
+
+    #[tokio::test]
+    async fn persist_utility() {
+        let page = page(0..20);
+        let filename = "tests/data/prefix_index_page_utility.wt";
+        let mut file = File::create(filename).await.unwrap();
+        let mut general_page = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::IndexPrefixCompressed, 0.into()),
+            inner: page.clone(),
+        };
+        persist_page(&mut general_page, &mut file).await.unwrap();
+
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .open(filename)
+            .await
+            .unwrap();
+        let mut utility = PrefixIndexPage::<1024>::parse_index_page_utility(&mut file, 0.into())
+            .await
+            .unwrap();
+        assert_eq!(utility, page.utility());
+
+        utility.node_id.key = key(99);
+        PrefixIndexPage::<1024>::persist_index_page_utility(&mut file, 0.into(), utility.clone())
+            .await
+            .unwrap();
+        let parsed = PrefixIndexPage::<1024>::parse_index_page_utility(&mut file, 0.into())
+            .await
+            .unwrap();
+        assert_eq!(parsed, utility);
+    }
+
+    #[test]
+    fn full_page() {
+        let mut page = page(0..1);
+        let mut i = 1;
+        while page.persisted_size() < 1024 - 64 {
+            page.apply_change_event(insert_event(key(i), i as usize))
+                .unwrap();
+            i += 1;
+        }
+        let before = page.clone();
+        let res = page.apply_change_event(insert_event("x".repeat(64), i as usize));
+        assert!(res.is_err());
+        assert_eq!(page, before);
+        assert!(page.persisted_size() <= 1024);
+        assert_eq!(page.as_bytes().as_ref().len(), 1024);
+    }
+}
@@ -0,0 +1,185 @@
+//! [`TableOfContentsPage`] that spans linked chain of
+//! [`PageType::IndexTableOfContents`] pages.
+//!
+//! Big indexes (or indexes with long keys) have table of contents that can
+//! not fit in one page. In memory table of contents is still one
+//! [`TableOfContentsPage`], but on persist it is split into pages which are
+//! linked using [`GeneralHeader::next_id`]. Chain's pages are not required to
+//! be placed one after another, so new pages can be added at the file's end.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use eyre::bail;
+use rkyv::api::high::HighDeserializer;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+
+use crate::page::PageId;
+use crate::{
+    parse_page, persist_page, space, GeneralHeader, GeneralPage, PageType, Persistable,
+    SizeMeasurable, TableOfContentsPage, INNER_PAGE_SIZE,
+};
+
+/// Splits `toc` into pages that fit in [`INNER_PAGE_SIZE`] and writes them as
+/// a chain. First page is written at `first_page_id`, next pages use ids from
+/// `page_ids`. Returns [`PageId`]'s of all chain's pages.
+pub async fn persist_table_of_contents_chain<T>(
+    file: &mut File,
+    toc: TableOfContentsPage<T>,
+    space_id: space::Id,
+    first_page_id: PageId,
+    page_ids: impl IntoIterator<Item = PageId>,
+) -> eyre::Result<Vec<PageId>>
+where
+    T: Archive
+        + Debug
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + Send
+        + Sync
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>> + Ord,
+{
+    let parts = toc.split_by_size(INNER_PAGE_SIZE)?;
+    let mut page_ids = page_ids.into_iter();
+    let mut headers = vec![GeneralHeader::new(
+        first_page_id,
+        PageType::IndexTableOfContents,
+        space_id,
+    )];
+    for _ in 1..parts.len() {
+        let Some(page_id) = page_ids.next() else {
+            bail!(
+                "Not enough page ids for table of contents chain of {} pages",
+                parts.len()
+            )
+        };
+        let header = headers
+            .last_mut()
+            .expect("first header is added before")
+            .follow_with_page_id(page_id);
+        headers.push(header);
+    }
+
+    let mut ids = Vec::with_capacity(parts.len());
+    for (header, inner) in headers.into_iter().zip(parts) {
+        ids.push(header.page_id);
+        let mut page = GeneralPage { header, inner };
+        if page.inner.as_bytes().as_ref().len() > INNER_PAGE_SIZE {
+            bail!(
+                "Table of contents page {} is bigger than {}",
+                page.header.page_id,
+                INNER_PAGE_SIZE
+            )
+        }
+        persist_page(&mut page, file).await?;
+    }
+
+    Ok(ids)
+}
+
+/// Reads all pages of the table of contents chain that starts at
+/// `first_page_id` and merges them into one [`TableOfContentsPage`].
+pub async fn parse_table_of_contents_chain<T>(
+    file: &mut File,
+    first_page_id: PageId,
+) -> eyre::Result<TableOfContentsPage<T>>
+where
+    T: Archive
+        + Debug
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>> + Ord,
+{
+    let mut toc = TableOfContentsPage::default();
+    let mut visited = HashSet::new();
+    let mut page_id = first_page_id;
+    loop {
+        if !visited.insert(page_id) {
+            bail!("Table of contents chain has cycle at page {}", page_id)
+        }
+        let page =
+            parse_page::<TableOfContentsPage<T>, { INNER_PAGE_SIZE as u32 }>(file, page_id.0)
+                .await?;
+        if page.header.page_type != PageType::IndexTableOfContents {
+            bail!(
+                "Page {} has {} type, but table of contents page was expected",
+                page_id,
+                page.header.page_type
+            )
+        }
+        toc.append(page.inner);
+        if page.header.next_id == PageId::default() {
+            break;
+        }
+        page_id = page.header.next_id;
+    }
+
+    Ok(toc)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_table_of_contents_chain, persist_table_of_contents_chain};
+    use crate::{parse_general_header_by_index, TableOfContentsPage};
+
+    #[tokio::test]
+    async fn test_toc_chain() {
+        let mut toc = TableOfContentsPage::<String>::default();
+        for i in 0..1000u32 {
+            toc.insert(format!("some long key of the index {i:04}"), (i + 2).into());
+        }
+        let filename = "tests/data/toc_chain.wt";
+        let mut file = tokio::fs::File::create(filename).await.unwrap();
+        let ids = persist_table_of_contents_chain(
+            &mut file,
+            toc.clone(),
+            0.into(),
+            1.into(),
+            (1002..).map(|i: u32| i.into()),
+        )
+        .await
+        .unwrap();
+        assert!(ids.len() > 1);
+        assert_eq!(ids[0], 1.into());
+        assert_eq!(ids[1], 1002.into());
+
+        let mut file = tokio::fs::File::open(filename).await.unwrap();
+        let header = parse_general_header_by_index(&mut file, 1).await.unwrap();
+        assert_eq!(header.next_id, 1002.into());
+        let parsed = parse_table_of_contents_chain::<String>(&mut file, 1.into())
+            .await
+            .unwrap();
+        assert_eq!(parsed.estimated_size(), toc.estimated_size());
+        assert_eq!(
+            parsed.iter().collect::<Vec<_>>(),
+            toc.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_toc_chain_not_enough_ids() {
+        let mut toc = TableOfContentsPage::<String>::default();
+        for i in 0..1000u32 {
+            toc.insert(format!("some long key of the index {i:04}"), i.into());
+        }
+        let mut file = tokio::fs::File::create("tests/data/toc_chain_err.wt")
+            .await
+            .unwrap();
+        let res = persist_table_of_contents_chain(&mut file, toc, 0.into(), 1.into(), []).await;
+        assert!(res.is_err());
+    }
+}
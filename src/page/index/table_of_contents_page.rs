@@ -1,9 +1,13 @@
+use eyre::bail;
+use rkyv::api::high::HighValidator;
+use rkyv::bytecheck::CheckBytes;
+use rkyv::util::AlignedVec;
 use rkyv::{Archive, Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 use crate::page::PageId;
-use crate::{align, Persistable, SizeMeasurable};
+use crate::{align, Link, Persistable, SizeMeasurable};
 
 #[derive(Archive, Clone, Deserialize, Debug, Serialize)]
 pub struct TableOfContentsPage<T: Ord + Eq> {
@@ -78,6 +82,24 @@ where
     }
 }
 
+impl<T> TableOfContentsPage<T>
+where
+    T: Archive + Ord + Eq,
+    <T as Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>,
+{
+    /// Checks that `bytes` are valid archived [`TableOfContentsPage`], so they
+    /// can be safely read with [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8]) -> eyre::Result<()> {
+        // Archived bytes are validated only if they are aligned.
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(bytes);
+        rkyv::access::<<TableOfContentsPagePersisted<T> as Archive>::Archived, rkyv::rancor::Error>(
+            &aligned,
+        )?;
+        Ok(())
+    }
+}
+
 impl<T> TableOfContentsPage<T>
 where
     T: Debug + Ord + Eq,
@@ -150,6 +172,87 @@ where
     pub fn iter(&self) -> impl Iterator<Item = (&T, &PageId)> {
         self.records.iter()
     }
+
+    /// Returns [`PageId`] of the node that should contain `val`. It is the
+    /// node with the smallest `node_id` that is not less than `val`.
+    pub fn find(&self, val: &T) -> Option<PageId> {
+        self.records.range(val..).next().map(|(_, id)| *id)
+    }
+
+    /// Splits table of contents into pages which `estimated_size` is not
+    /// greater than `max_size`. Records are split in keys order, empty pages
+    /// are placed in the first page.
+    pub fn split_by_size(self, max_size: usize) -> eyre::Result<Vec<Self>>
+    where
+        T: SizeMeasurable + Clone,
+    {
+        let mut current = Self::default();
+        for id in self.empty_pages {
+            current.estimated_size += id.aligned_size();
+            current.empty_pages.push(id);
+        }
+        if current.estimated_size > max_size {
+            bail!(
+                "Empty pages of table of contents can not fit in page with length {}",
+                max_size
+            )
+        }
+
+        let mut pages = vec![];
+        for (key, page_id) in self.records {
+            let record_size = (key.clone(), page_id).aligned_size();
+            if current.estimated_size + record_size > max_size {
+                if current.records.is_empty() {
+                    bail!(
+                        "Record {:?} can not fit in page with length {}",
+                        key,
+                        max_size
+                    )
+                }
+                pages.push(std::mem::take(&mut current));
+            }
+            current.insert(key, page_id);
+        }
+        pages.push(current);
+
+        Ok(pages)
+    }
+
+    /// Moves all records and empty pages of `other` to this table of contents.
+    pub fn append(&mut self, other: Self)
+    where
+        T: SizeMeasurable + Clone,
+    {
+        for (key, page_id) in other.records {
+            self.insert(key, page_id)
+        }
+        for id in other.empty_pages {
+            self.estimated_size += id.aligned_size();
+            self.empty_pages.push(id);
+        }
+    }
+}
+
+impl<T> TableOfContentsPage<(T, Link)>
+where
+    T: Debug + Ord + Eq + Clone,
+{
+    /// Returns [`PageId`]'s of all nodes of the multimap index that can
+    /// contain values with `key`. Nodes are identified by `(key, link)`, so
+    /// these are all nodes which `node_id` has same key and the first node
+    /// after them, as it can start with values with `key` too. Values with same
+    /// key are not ordered by [`Link`], so returned pages are not sorted in
+    /// the order of nodes.
+    pub fn get_pages_for_key(&self, key: &T) -> Vec<PageId> {
+        let mut pages = vec![];
+        for ((node_key, _), page_id) in self.records.range((key.clone(), Link::default())..) {
+            pages.push(*page_id);
+            if node_key != key {
+                break;
+            }
+        }
+        pages
+    }
 }
 
 impl<T> IntoIterator for TableOfContentsPage<T>
@@ -185,4 +288,45 @@ mod test {
         );
         assert_eq!(toc_page.as_bytes().as_ref().len(), toc_page.estimated_size);
     }
+
+    #[test]
+    fn test_find() {
+        let mut toc = TableOfContentsPage::<u64>::default();
+        toc.insert(10, 1.into());
+        toc.insert(20, 2.into());
+        assert_eq!(toc.find(&5), Some(1.into()));
+        assert_eq!(toc.find(&10), Some(1.into()));
+        assert_eq!(toc.find(&11), Some(2.into()));
+        assert_eq!(toc.find(&21), None);
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let mut toc = TableOfContentsPage::<u64>::default();
+        for i in 0..100 {
+            toc.insert(i, (i as u32).into());
+        }
+        toc.remove(&0);
+
+        let pages = toc.split_by_size(256).unwrap();
+        assert!(pages.len() > 1);
+        for page in &pages {
+            assert!(page.estimated_size <= 256);
+            assert_eq!(page.as_bytes().as_ref().len(), page.estimated_size);
+        }
+        assert_eq!(pages[0].empty_pages, vec![0.into()]);
+        assert_eq!(
+            pages[1].records.keys().next(),
+            Some(&(pages[0].records.len() as u64 + 1))
+        );
+
+        let mut pages = pages.into_iter();
+        let mut toc = pages.next().unwrap();
+        for page in pages {
+            toc.append(page)
+        }
+        assert_eq!(toc.records.len(), 99);
+        assert_eq!(toc.as_bytes().as_ref().len(), toc.estimated_size);
+        assert_eq!(toc.pop_empty_page(), Some(0.into()));
+    }
 }
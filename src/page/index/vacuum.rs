@@ -0,0 +1,430 @@
+//! Offline vacuum of index pages.
+//!
+//! After heavy deletes index pages can become nearly empty, but they are still
+//! referenced from [`TableOfContentsPage`]. Vacuum pass walks pages in
+//! [`TableOfContentsPage`] order and coalesces neighbour pages when one of
+//! them is filled less than threshold. If values of both pages fit in one
+//! page, left page is merged into right one, otherwise values are
+//! redistributed between them. Merged page keeps right page's
+//! [`PageId`] and `node_id`, so only left page's record is removed from
+//! [`TableOfContentsPage`] and it's [`PageId`] is added to empty pages.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+use eyre::bail;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::page::PageId;
+use crate::{
+    GeneralPage, IndexPage, SizeMeasurable, TableOfContentsPage, UnsizedIndexPage,
+    VariableSizeMeasurable,
+};
+
+/// Summary of the vacuum pass.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VacuumReport {
+    /// Count of merges of neighbour pages.
+    pub merged: usize,
+    /// Count of values redistributions between neighbour pages.
+    pub redistributed: usize,
+    /// [`PageId`]'s of pages that were removed from the index. They are also
+    /// added to [`TableOfContentsPage`]'s empty pages.
+    pub freed_pages: Vec<PageId>,
+}
+
+/// Common operations of both index page kinds that are used by vacuum pass.
+trait VacuumNode<T>: Clone {
+    fn node_key(&self) -> &T;
+    fn is_empty(&self) -> bool;
+    fn fill_factor(&self) -> f64;
+    fn can_merge(&self, right: &Self) -> bool;
+    fn merge(&mut self, right: Self) -> eyre::Result<()>;
+    fn redistribute(&mut self, right: &mut Self) -> eyre::Result<()>;
+}
+
+impl<T> VacuumNode<T> for IndexPage<T>
+where
+    T: Default + Clone + Ord + SizeMeasurable,
+{
+    fn node_key(&self) -> &T {
+        &self.node_id.key
+    }
+
+    fn is_empty(&self) -> bool {
+        self.current_length == 0
+    }
+
+    fn fill_factor(&self) -> f64 {
+        IndexPage::fill_factor(self)
+    }
+
+    fn can_merge(&self, right: &Self) -> bool {
+        IndexPage::can_merge(self, right)
+    }
+
+    fn merge(&mut self, right: Self) -> eyre::Result<()> {
+        IndexPage::merge(self, right)
+    }
+
+    fn redistribute(&mut self, right: &mut Self) -> eyre::Result<()> {
+        IndexPage::redistribute(self, right);
+        Ok(())
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> VacuumNode<T> for UnsizedIndexPage<T, DATA_LENGTH>
+where
+    T: Archive
+        + Default
+        + Clone
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    fn node_key(&self) -> &T {
+        &self.node_id.key
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index_values.is_empty()
+    }
+
+    fn fill_factor(&self) -> f64 {
+        UnsizedIndexPage::fill_factor(self)
+    }
+
+    fn can_merge(&self, right: &Self) -> bool {
+        UnsizedIndexPage::can_merge(self, right)
+    }
+
+    fn merge(&mut self, right: Self) -> eyre::Result<()> {
+        UnsizedIndexPage::merge(self, right)
+    }
+
+    fn redistribute(&mut self, right: &mut Self) -> eyre::Result<()> {
+        UnsizedIndexPage::redistribute(self, right)
+    }
+}
+
+/// Coalesces [`IndexPage`]'s which are filled less than `threshold` with
+/// their neighbours. `pages` should contain all pages referenced from `toc`.
+/// Freed pages are removed from `pages`, left pages are sorted in `node_id`
+/// order and their headers are relinked. `pages` and `toc` are left unchanged
+/// on error.
+pub fn vacuum_index_pages<T>(
+    pages: &mut Vec<GeneralPage<IndexPage<T>>>,
+    toc: &mut TableOfContentsPage<T>,
+    threshold: f64,
+) -> eyre::Result<VacuumReport>
+where
+    T: Debug + Default + Clone + Ord + SizeMeasurable,
+{
+    vacuum(pages, toc, threshold)
+}
+
+/// Coalesces [`UnsizedIndexPage`]'s which are filled less than `threshold`
+/// with their neighbours. `pages` should contain all pages referenced from
+/// `toc`. Freed pages are removed from `pages`, left pages are sorted in
+/// `node_id` order and their headers are relinked. `pages` and `toc` are left
+/// unchanged on error.
+pub fn vacuum_unsized_index_pages<T, const DATA_LENGTH: u32>(
+    pages: &mut Vec<GeneralPage<UnsizedIndexPage<T, DATA_LENGTH>>>,
+    toc: &mut TableOfContentsPage<T>,
+    threshold: f64,
+) -> eyre::Result<VacuumReport>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Clone
+        + Ord
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    vacuum(pages, toc, threshold)
+}
+
+fn vacuum<Page, T>(
+    pages: &mut Vec<GeneralPage<Page>>,
+    toc: &mut TableOfContentsPage<T>,
+    threshold: f64,
+) -> eyre::Result<VacuumReport>
+where
+    Page: VacuumNode<T>,
+    T: Debug + Clone + Ord + SizeMeasurable,
+{
+    if !(0.0..=1.0).contains(&threshold) {
+        bail!("Threshold should be in [0.0, 1.0] range, got {}", threshold)
+    }
+    let records = toc
+        .iter()
+        .map(|(key, page_id)| (key.clone(), *page_id))
+        .collect::<Vec<_>>();
+    let mut ids = pages
+        .iter()
+        .map(|p| p.header.page_id)
+        .collect::<HashSet<_>>();
+    for (key, page_id) in &records {
+        if !ids.remove(page_id) {
+            bail!("Page {} of node {:?} is not found", page_id, key)
+        }
+    }
+    if let Some(page_id) = ids.iter().next() {
+        bail!("Page {} is not referenced from table of contents", page_id)
+    }
+    if pages.len() != records.len() {
+        bail!("Some pages have same page id")
+    }
+    // Pass works on copies of pages and `toc`, so they are left untouched if
+    // some pages can't be coalesced.
+    let by_id = pages
+        .iter()
+        .map(|p| (p.header.page_id, p))
+        .collect::<HashMap<_, _>>();
+    let ordered = records
+        .into_iter()
+        .map(|(key, page_id)| (key, by_id[&page_id].clone()))
+        .collect::<Vec<_>>();
+    let mut new_toc = toc.clone();
+
+    let mut report = VacuumReport::default();
+    let mut result: Vec<(T, GeneralPage<Page>)> = Vec::with_capacity(ordered.len());
+    for (key, mut page) in ordered {
+        if page.inner.is_empty() {
+            report.freed_pages.push(new_toc.remove(&key));
+            continue;
+        }
+        let Some((left_key, left)) = result.last_mut() else {
+            result.push((key, page));
+            continue;
+        };
+        if left.inner.fill_factor() >= threshold && page.inner.fill_factor() >= threshold {
+            result.push((key, page));
+            continue;
+        }
+
+        if left.inner.can_merge(&page.inner) {
+            let (left_key, left) = result.pop().expect("checked before");
+            let mut inner = left.inner;
+            inner.merge(page.inner)?;
+            page.inner = inner;
+            report.freed_pages.push(new_toc.remove(&left_key));
+            report.merged += 1;
+        } else {
+            left.inner.redistribute(&mut page.inner)?;
+            let new_key = left.inner.node_key().clone();
+            if new_key != *left_key {
+                let page_id = new_toc.remove_without_record(left_key);
+                new_toc.insert(new_key.clone(), page_id);
+                *left_key = new_key;
+            }
+            report.redistributed += 1;
+        }
+        result.push((key, page));
+    }
+
+    *toc = new_toc;
+    pages.clear();
+    let mut previous_id = PageId::default();
+    let ids = result
+        .iter()
+        .map(|(_, p)| p.header.page_id)
+        .collect::<Vec<_>>();
+    for (i, (_, mut page)) in result.into_iter().enumerate() {
+        page.header.previous_id = previous_id;
+        page.header.next_id = ids.get(i + 1).copied().unwrap_or_default();
+        previous_id = page.header.page_id;
+        pages.push(page);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{vacuum_index_pages, vacuum_unsized_index_pages};
+    use crate::{
+        bulk_load_index_pages, bulk_load_unsized_index_pages, BulkLoadConfig, Link, PageType,
+    };
+
+    fn link(i: u32) -> Link {
+        Link {
+            page_id: 1.into(),
+            offset: i * 24,
+            length: 24,
+        }
+    }
+
+    #[test]
+    fn test_vacuum_sized() {
+        let mut index = bulk_load_index_pages(
+            (0..8000u64).map(|i| (i, link(i as u32))),
+            Default::default(),
+        )
+        .unwrap();
+        let pages_count = index.pages.len();
+        assert!(pages_count > 4);
+        // Leave only each 100th value.
+        for page in index.pages.iter_mut() {
+            let node = page
+                .inner
+                .get_node()
+                .into_iter()
+                .filter(|p| p.key % 100 == 0 || p.key == page.inner.node_id.key)
+                .collect::<Vec<_>>();
+            page.inner = crate::IndexPage::from_node(&node, page.inner.size as usize);
+        }
+
+        let report = vacuum_index_pages(&mut index.pages, &mut index.toc, 0.5).unwrap();
+        assert_eq!(index.pages.len(), 1);
+        assert_eq!(report.merged, pages_count - 1);
+        assert_eq!(report.freed_pages.len(), pages_count - 1);
+        assert_eq!(index.toc.iter().count(), 1);
+        let page = &index.pages[0];
+        assert_eq!(page.inner.node_id.key, 7999);
+        assert_eq!(index.toc.get(&7999), Some(page.header.page_id));
+        assert_eq!(page.header.next_id, 0.into());
+        assert_eq!(page.header.previous_id, 0.into());
+
+        let empty = index.toc.pop_empty_page().unwrap();
+        assert!(report.freed_pages.contains(&empty));
+    }
+
+    #[test]
+    fn test_vacuum_redistribute() {
+        let size = crate::get_index_page_size_from_data_length::<u64>(crate::INNER_PAGE_SIZE);
+        let mut index = bulk_load_index_pages(
+            (0..(size + 10) as u64).map(|i| (i, link(i as u32))),
+            BulkLoadConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(index.pages.len(), 2);
+
+        let report = vacuum_index_pages(&mut index.pages, &mut index.toc, 0.5).unwrap();
+        assert_eq!(report.merged, 0);
+        assert_eq!(report.redistributed, 1);
+        let left = &index.pages[0];
+        let right = &index.pages[1];
+        assert_eq!(left.inner.current_length as usize, (size + 10) / 2);
+        assert_eq!(
+            index.toc.get(&left.inner.node_id.key),
+            Some(left.header.page_id)
+        );
+        assert_eq!(
+            index.toc.get(&(size as u64 + 9)),
+            Some(right.header.page_id)
+        );
+        assert_eq!(index.toc.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_vacuum_unsized() {
+        let mut index = bulk_load_unsized_index_pages::<_, 1024>(
+            (0..100u32).map(|i| (format!("{i:03}___________________{i:03}"), link(i))),
+            BulkLoadConfig {
+                fill_factor: 0.3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let pages_count = index.pages.len();
+
+        let report = vacuum_unsized_index_pages(&mut index.pages, &mut index.toc, 0.5).unwrap();
+        assert!(report.merged > 0);
+        assert!(index.pages.len() < pages_count);
+        let mut count = 0;
+        for page in &index.pages {
+            assert_eq!(page.header.page_type, PageType::IndexUnsized);
+            assert_eq!(page.inner.node_id, *page.inner.index_values.last().unwrap());
+            assert_eq!(
+                index.toc.get(&page.inner.node_id.key),
+                Some(page.header.page_id)
+            );
+            count += page.inner.index_values.len();
+        }
+        assert_eq!(count, 100);
+        assert_eq!(index.toc.iter().count(), index.pages.len());
+    }
+
+    #[test]
+    fn test_vacuum_invalid() {
+        let mut index =
+            bulk_load_index_pages((0..10u64).map(|i| (i, link(i as u32))), Default::default())
+                .unwrap();
+        let mut pages = vec![];
+        assert!(vacuum_index_pages(&mut pages, &mut index.toc, 0.5).is_err());
+        assert!(vacuum_index_pages(&mut index.pages, &mut index.toc, 1.5).is_err());
+
+        // Pages are left untouched if they don't match table of contents.
+        let mut extra = index.pages[0].clone();
+        extra.header.page_id = 100.into();
+        index.pages.push(extra);
+        let err = vacuum_index_pages(&mut index.pages, &mut index.toc, 0.5).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Page 100 is not referenced from table of contents"
+        );
+        assert_eq!(index.pages.len(), 2);
+
+        let mut pages = vec![index.pages[1].clone()];
+        assert!(vacuum_index_pages(&mut pages, &mut index.toc, 0.5).is_err());
+        assert_eq!(pages.len(), 1);
+    }
+
+    #[test]
+    fn test_vacuum_failed_redistribute() {
+        let mut index = bulk_load_unsized_index_pages::<_, 1024>(
+            (0..100u32).map(|i| (format!("{i:03}___________________{i:03}"), link(i))),
+            BulkLoadConfig {
+                fill_factor: 0.3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // First page is overfilled, so it's values can't be split with the
+        // second page's values.
+        let first = &mut index.pages[0].inner;
+        first.index_values = first
+            .index_values
+            .iter()
+            .flat_map(|v| std::iter::repeat_n(v.clone(), 8))
+            .collect();
+        first.rebuild();
+        let pages = index.pages.clone();
+        let records = index
+            .toc
+            .iter()
+            .map(|(k, p)| (k.clone(), *p))
+            .collect::<Vec<_>>();
+
+        let err = vacuum_unsized_index_pages(&mut index.pages, &mut index.toc, 0.5).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Values of the pages can not be split between two pages"
+        );
+        assert_eq!(index.pages, pages);
+        assert_eq!(
+            index
+                .toc
+                .iter()
+                .map(|(k, p)| (k.clone(), *p))
+                .collect::<Vec<_>>(),
+            records
+        );
+        assert!(index.toc.pop_empty_page().is_none());
+    }
+}
@@ -2,6 +2,7 @@ mod data;
 mod header;
 mod index;
 //mod iterators;
+mod schema;
 mod space_info;
 mod ty;
 mod util;
@@ -15,11 +16,20 @@ use crate::{align, SizeMeasurable};
 pub use data::DataPage;
 pub use header::{GeneralHeader, DATA_VERSION};
 pub use index::{
-    get_index_page_size_from_data_length, IndexPage, IndexPageUtility, IndexValue,
-    TableOfContentsPage, UnsizedIndexPage, UnsizedIndexPageUtility,
+    bulk_load_index_pages, bulk_load_multimap_index_pages, bulk_load_multimap_unsized_index_pages,
+    bulk_load_unsized_index_pages, find_links_with_filters, get_index_page_size_from_data_length,
+    parse_bloom_filters, parse_table_of_contents_chain, persist_bloom_filter,
+    persist_table_of_contents_chain, vacuum_index_pages, vacuum_unsized_index_pages, BloomFilter,
+    BloomFilterConfig, BloomFilterPage, BloomKey, BulkLoadConfig, BulkLoadedIndex, FilterKeys,
+    FilteredIndexPage, IndexPage, IndexPageUtility, IndexValue, PrefixIndexPage,
+    PrefixIndexPageUtility, TableOfContentsPage, UnsizedIndexPage, UnsizedIndexPageUtility,
+    VacuumReport,
 };
 //pub use iterators::{DataIterator, LinksIterator};
-pub use space_info::{Interval, SpaceInfoPage};
+pub use schema::SchemaChange;
+pub use space_info::{
+    ColumnSchema, IndexColumn, IndexSchema, Interval, SchemaVersion, SortOrder, SpaceInfoPage,
+};
 pub use ty::PageType;
 pub use util::{
     map_data_pages_to_general, parse_data_page, parse_data_pages_batch,
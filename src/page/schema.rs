@@ -0,0 +1,339 @@
+//! Row schema evolution of the [`SpaceInfoPage`].
+//!
+//! Every schema change increases [`SpaceInfoPage::schema_version`] and moves
+//! replaced columns to [`SpaceInfoPage::schema_history`]. Data pages are not
+//! tagged one by one: schema version remembers last data page that existed
+//! when it was replaced, so page belongs to the oldest version whose
+//! `last_page_id` is not less than page's id, or to the current version.
+//!
+//! So schema change closes the last data page: rows of the new version are
+//! written to the new pages only, and every page holds rows of one version.
+//! Empty links of closed pages are moved from
+//! [`SpaceInfoPage::empty_links_list`] to [`SchemaVersion::empty_links`], so
+//! writers that reuse `empty_links_list` never place row to the page of other
+//! version, and deleted rows of closed pages are still skipped on read (see
+//! [`SpaceInfoPage::page_empty_links`]).
+//!
+//! Columns are matched between versions by their ids, so renamed column keeps
+//! it's values, dropped column's values are skipped and added column gets
+//! it's default value in rows written before.
+
+use eyre::bail;
+
+use crate::page::space_info::{ColumnSchema, SchemaVersion};
+use crate::page::PageId;
+use crate::persistence::data::{ColumnType, DataTypeValue};
+use crate::{Link, SpaceInfoPage};
+
+/// One change of the space's row schema.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaChange {
+    /// Adds column to the end of the row. Rows written before get `default`
+    /// value, or type's default value if it's not set.
+    AddColumn {
+        name: String,
+        ty: ColumnType,
+        default: Option<DataTypeValue>,
+    },
+    DropColumn {
+        name: String,
+    },
+    RenameColumn {
+        from: String,
+        to: String,
+    },
+}
+
+impl<Pk> SpaceInfoPage<Pk> {
+    /// Returns version and columns of the schema which rows of the data page
+    /// were written under.
+    pub fn page_schema(&self, page_id: PageId) -> (u32, &[ColumnSchema]) {
+        self.schema_history
+            .iter()
+            .find(|v| page_id <= v.last_page_id)
+            .map(|v| (v.version, v.columns.as_slice()))
+            .unwrap_or((self.schema_version, self.columns.as_slice()))
+    }
+
+    /// Returns empty links of the data page's schema version, they contain
+    /// page's deleted rows.
+    pub fn page_empty_links(&self, page_id: PageId) -> &[Link] {
+        self.schema_history
+            .iter()
+            .find(|v| page_id <= v.last_page_id)
+            .map(|v| v.empty_links.as_slice())
+            .unwrap_or(self.empty_links_list.as_slice())
+    }
+
+    /// Moves empty links of the pages closed by schema change from
+    /// `empty_links_list` to their schema versions.
+    pub(crate) fn close_empty_links(&mut self) {
+        let links = std::mem::take(&mut self.empty_links_list);
+        for link in links {
+            match self
+                .schema_history
+                .iter_mut()
+                .find(|v| link.page_id <= v.last_page_id)
+            {
+                Some(version) => version.empty_links.push(link),
+                None => self.empty_links_list.push(link),
+            }
+        }
+    }
+
+    /// Applies `changes` to the row schema as one new schema version. Nothing
+    /// is changed if some change can not be applied.
+    ///
+    /// Current columns are moved to the schema history if the space has data
+    /// pages written under them. These pages are closed together with their
+    /// empty links, so rows of the new version are written to the new pages.
+    pub fn evolve_schema(&mut self, changes: &[SchemaChange]) -> eyre::Result<()> {
+        let mut columns = self.columns.clone();
+        let mut primary_key_fields = self.primary_key_fields.clone();
+        let mut secondary_index_schemas = self.secondary_index_schemas.clone();
+        let mut next_id = self
+            .columns
+            .iter()
+            .chain(self.schema_history.iter().flat_map(|v| &v.columns))
+            .map(|c| c.id + 1)
+            .max()
+            .unwrap_or(0);
+
+        for change in changes {
+            match change {
+                SchemaChange::AddColumn { name, ty, default } => {
+                    if columns.iter().any(|c| &c.name == name) {
+                        bail!("Column `{}` already exists", name)
+                    }
+                    if let Some(default) = default {
+                        if !default.is_of_type(ty) {
+                            bail!(
+                                "Default value `{}` of the column `{}` is not `{}`",
+                                default,
+                                name,
+                                ty
+                            )
+                        }
+                    }
+                    let mut column = ColumnSchema::new(next_id, name, ty.clone());
+                    column.default = default.clone();
+                    columns.push(column);
+                    next_id += 1;
+                }
+                SchemaChange::DropColumn { name } => {
+                    let Some(pos) = columns.iter().position(|c| &c.name == name) else {
+                        bail!("Column `{}` is not found", name)
+                    };
+                    if primary_key_fields.contains(name) {
+                        bail!("Primary key column `{}` can not be dropped", name)
+                    }
+                    if secondary_index_schemas
+                        .iter()
+                        .flat_map(|s| &s.columns)
+                        .any(|c| &c.name == name)
+                    {
+                        bail!("Indexed column `{}` can not be dropped", name)
+                    }
+                    columns.remove(pos);
+                }
+                SchemaChange::RenameColumn { from, to } => {
+                    if columns.iter().any(|c| &c.name == to) {
+                        bail!("Column `{}` already exists", to)
+                    }
+                    let Some(column) = columns.iter_mut().find(|c| &c.name == from) else {
+                        bail!("Column `{}` is not found", from)
+                    };
+                    column.name = to.clone();
+                    for field in primary_key_fields.iter_mut().filter(|f| *f == from) {
+                        *field = to.clone();
+                    }
+                    for schema in &mut secondary_index_schemas {
+                        if &schema.name == from {
+                            schema.name = to.clone();
+                        }
+                        for column in schema.columns.iter_mut().filter(|c| &c.name == from) {
+                            column.name = to.clone();
+                        }
+                    }
+                }
+            }
+        }
+
+        let last_page_id = PageId::from(self.page_count);
+        let has_pages = !last_page_id.is_empty()
+            && self
+                .schema_history
+                .last()
+                .map(|v| v.last_page_id < last_page_id)
+                .unwrap_or(true);
+        let replaced = std::mem::replace(&mut self.columns, columns);
+        if has_pages {
+            self.schema_history.push(SchemaVersion {
+                version: self.schema_version,
+                columns: replaced,
+                last_page_id,
+                empty_links: vec![],
+            });
+            self.close_empty_links();
+        }
+        self.schema_version += 1;
+        self.primary_key_fields = primary_key_fields;
+        self.secondary_index_schemas = secondary_index_schemas;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SchemaChange;
+    use crate::persistence::data::{ColumnType, DataTypeValue};
+    use crate::{ColumnSchema, IndexSchema, Link, SpaceInfoPage};
+
+    fn space_info(page_count: u32) -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+                ColumnSchema::new(2, "age", ColumnType::U32),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![IndexSchema::single("name", ColumnType::String)],
+            empty_links_list: vec![
+                Link {
+                    page_id: 1.into(),
+                    offset: 0,
+                    length: 24,
+                },
+                Link {
+                    page_id: 3.into(),
+                    offset: 48,
+                    length: 24,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_evolve_schema() {
+        let mut info = space_info(2);
+        info.evolve_schema(&[
+            SchemaChange::DropColumn {
+                name: "age".to_string(),
+            },
+            SchemaChange::RenameColumn {
+                from: "name".to_string(),
+                to: "full_name".to_string(),
+            },
+            SchemaChange::AddColumn {
+                name: "score".to_string(),
+                ty: ColumnType::F64,
+                default: Some(DataTypeValue::F64(1.5)),
+            },
+        ])
+        .unwrap();
+
+        let mut score = ColumnSchema::new(3, "score", ColumnType::F64);
+        score.default = Some(DataTypeValue::F64(1.5));
+        assert_eq!(
+            info.columns,
+            vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "full_name", ColumnType::String),
+                score,
+            ]
+        );
+        assert_eq!(info.schema_version, 1);
+        assert_eq!(info.schema_history.len(), 1);
+        assert_eq!(info.schema_history[0].columns, space_info(2).columns);
+        assert_eq!(
+            info.secondary_index_schemas,
+            vec![IndexSchema::single("full_name", ColumnType::String)]
+        );
+        let links = space_info(2).empty_links_list;
+        assert_eq!(info.empty_links_list, vec![links[1]]);
+        assert_eq!(info.schema_history[0].empty_links, vec![links[0]]);
+        assert_eq!(info.page_empty_links(1.into()), &[links[0]]);
+        assert_eq!(info.page_empty_links(3.into()), &[links[1]]);
+
+        assert_eq!(info.page_schema(1.into()).0, 0);
+        assert_eq!(info.page_schema(2.into()).0, 0);
+        assert_eq!(info.page_schema(3.into()).0, 1);
+    }
+
+    #[test]
+    fn test_evolve_schema_without_new_pages() {
+        let mut info = space_info(2);
+        let add = |name: &str| SchemaChange::AddColumn {
+            name: name.to_string(),
+            ty: ColumnType::U8,
+            default: None,
+        };
+        info.evolve_schema(&[add("a")]).unwrap();
+        info.evolve_schema(&[add("b")]).unwrap();
+        assert_eq!(info.schema_version, 2);
+        assert_eq!(info.schema_history.len(), 1);
+        assert_eq!(info.page_schema(2.into()).0, 0);
+
+        info.page_count = 4;
+        info.evolve_schema(&[add("c")]).unwrap();
+        assert_eq!(info.schema_history.len(), 2);
+        assert_eq!(info.page_schema(2.into()).0, 0);
+        assert_eq!(info.page_schema(4.into()).0, 2);
+        assert_eq!(info.page_schema(5.into()).0, 3);
+        assert_eq!(info.schema_history[1].empty_links.len(), 1);
+        assert!(info.empty_links_list.is_empty());
+        assert_eq!(
+            info.columns.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+
+        let mut empty = space_info(0);
+        empty.evolve_schema(&[add("a")]).unwrap();
+        assert!(empty.schema_history.is_empty());
+    }
+
+    #[test]
+    fn test_evolve_schema_errors() {
+        let mut info = space_info(2);
+        let err = info
+            .evolve_schema(&[
+                SchemaChange::DropColumn {
+                    name: "age".to_string(),
+                },
+                SchemaChange::DropColumn {
+                    name: "name".to_string(),
+                },
+            ])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Indexed column `name` can not be dropped");
+        assert_eq!(info, space_info(2));
+
+        let err = info
+            .evolve_schema(&[SchemaChange::RenameColumn {
+                from: "age".to_string(),
+                to: "id".to_string(),
+            }])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Column `id` already exists");
+
+        let err = info
+            .evolve_schema(&[SchemaChange::AddColumn {
+                name: "score".to_string(),
+                ty: ColumnType::F64,
+                default: Some(DataTypeValue::U8(1)),
+            }])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Default value `1` of the column `score` is not `f64`"
+        );
+    }
+}
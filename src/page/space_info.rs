@@ -1,9 +1,13 @@
 //! [`SpaceInfoPage`] declaration.
 
+use crate::page::PageId;
+use crate::persistence::data::{ColumnType, DataTypeValue};
 use crate::util::Persistable;
 use crate::{space, Link};
 
 use data_bucket_codegen::Persistable;
+use eyre::bail;
+use rkyv::util::AlignedVec;
 use rkyv::{Archive, Deserialize, Serialize};
 
 pub type SpaceName = String;
@@ -22,7 +26,7 @@ pub(crate) struct SpaceInfoPageV1<Pk = ()> {
     pub empty_links_list: Vec<Link>,
 }
 
-/// Current SpaceInfoPage format (version 2+) - with version field.
+/// SpaceInfoPage format version 2 - with version field.
 /// Internal struct for serialization, converted to public SpaceInfoPage.
 #[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize, Persistable)]
 pub(crate) struct SpaceInfoPageV2<Pk = ()> {
@@ -37,56 +41,239 @@ pub(crate) struct SpaceInfoPageV2<Pk = ()> {
     pub empty_links_list: Vec<Link>,
 }
 
-impl<Pk> From<SpaceInfoPageV1<Pk>> for SpaceInfoPage<Pk> {
-    fn from(v1: SpaceInfoPageV1<Pk>) -> Self {
-        SpaceInfoPage {
+/// SpaceInfoPage format version 3 - with typed column schema, schema versions
+/// and composite secondary index schemas.
+/// Internal struct for serialization, converted to public SpaceInfoPage.
+#[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize, Persistable)]
+pub(crate) struct SpaceInfoPageV3<Pk = ()> {
+    pub id: space::Id,
+    pub page_count: u32,
+    pub pk_gen_state: Pk,
+    pub name: SpaceName,
+    pub version: u32,
+    pub columns: Vec<ColumnSchema>,
+    pub schema_version: u32,
+    pub schema_history: Vec<SchemaVersion>,
+    pub primary_key_fields: Vec<String>,
+    pub secondary_index_schemas: Vec<IndexSchema>,
+    pub empty_links_list: Vec<Link>,
+}
+
+/// Description of one column of the space's row.
+#[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize)]
+pub struct ColumnSchema {
+    /// Identifier of the column, which is unique within the space and is not
+    /// changed when column is renamed.
+    pub id: u32,
+    pub name: String,
+    pub ty: ColumnType,
+    pub nullable: bool,
+    /// Value that is used for the column if it's not set.
+    pub default: Option<DataTypeValue>,
+}
+
+impl ColumnSchema {
+    /// Creates schema of the not nullable column without default value.
+    pub fn new(id: u32, name: impl Into<String>, ty: ColumnType) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            ty,
+            nullable: false,
+            default: None,
+        }
+    }
+
+    /// Returns type of the column's value in the row. Values of the nullable
+    /// column are archived as `Option` of the column's type.
+    pub fn row_type(&self) -> ColumnType {
+        if self.nullable {
+            ColumnType::Option(Box::new(self.ty.clone()))
+        } else {
+            self.ty.clone()
+        }
+    }
+
+    /// Creates columns' schemas from the legacy `row_schema`. Columns get ids
+    /// in order they appear in it. Returns error if some column's type is not
+    /// supported.
+    pub fn from_row_schema<S1: AsRef<str>, S2: AsRef<str>>(
+        row_schema: &[(S1, S2)],
+    ) -> eyre::Result<Vec<Self>> {
+        row_schema
+            .iter()
+            .enumerate()
+            .map(|(id, (name, ty))| {
+                let ty = ty.as_ref().parse().map_err(|e: eyre::Report| {
+                    e.wrap_err(format!("Column `{}` has invalid type", name.as_ref()))
+                })?;
+                Ok(Self::new(id as u32, name.as_ref(), ty))
+            })
+            .collect()
+    }
+}
+
+/// Row schema that was replaced by schema change. Data pages up to
+/// `last_page_id` that don't belong to older versions hold rows written under
+/// this version.
+#[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+    pub columns: Vec<ColumnSchema>,
+    pub last_page_id: PageId,
+    /// Empty links of the version's data pages. They are moved out of
+    /// [`SpaceInfoPage::empty_links_list`] when version is replaced, so rows
+    /// of the newer versions are never placed to these pages.
+    pub empty_links: Vec<Link>,
+}
+
+/// Order in which values of the index key's column are sorted.
+#[derive(Archive, Clone, Copy, Deserialize, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+#[rkyv(derive(Debug))]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Description of one column of the secondary index's key.
+#[derive(Archive, Clone, Deserialize, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct IndexColumn {
+    /// Name of the column as it is described in `columns`.
+    pub name: String,
+    pub ty: ColumnType,
+    pub order: SortOrder,
+}
+
+/// Description of the secondary index's key. Single column indexes have one
+/// column, composite indexes have columns in same order as they appear in the
+/// key tuple. Columns with [`SortOrder::Desc`] are wrapped in [`Desc`].
+///
+/// [`Desc`]: crate::Desc
+#[derive(Archive, Clone, Deserialize, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct IndexSchema {
+    pub name: String,
+    pub columns: Vec<IndexColumn>,
+}
+
+impl IndexSchema {
+    /// Creates schema of the single column ascending index, which is named
+    /// as it's column.
+    pub fn single(column: impl Into<String>, ty: ColumnType) -> Self {
+        let name = column.into();
+        Self {
+            columns: vec![IndexColumn {
+                name: name.clone(),
+                ty,
+                order: SortOrder::Asc,
+            }],
+            name,
+        }
+    }
+
+    pub fn is_composite(&self) -> bool {
+        self.columns.len() > 1
+    }
+
+    /// Returns indexed column of the not composite index.
+    pub fn single_column(&self) -> Option<&IndexColumn> {
+        match self.columns.as_slice() {
+            [column] => Some(column),
+            _ => None,
+        }
+    }
+}
+
+/// Creates index schemas from the legacy `secondary_index_types`. Returns
+/// error if some index's type is not supported.
+fn schemas_from_types(types: &[(String, String)]) -> eyre::Result<Vec<IndexSchema>> {
+    types
+        .iter()
+        .map(|(column, ty)| {
+            let ty = ty.parse().map_err(|e: eyre::Report| {
+                e.wrap_err(format!("Index on `{}` has invalid type", column))
+            })?;
+            Ok(IndexSchema::single(column, ty))
+        })
+        .collect()
+}
+
+impl<Pk> TryFrom<SpaceInfoPageV1<Pk>> for SpaceInfoPage<Pk> {
+    type Error = eyre::Report;
+
+    fn try_from(v1: SpaceInfoPageV1<Pk>) -> eyre::Result<Self> {
+        Ok(SpaceInfoPage {
             version: 0,
             id: v1.id,
             page_count: v1.page_count,
             pk_gen_state: v1.pk_gen_state,
             name: v1.name,
-            row_schema: v1.row_schema,
+            columns: ColumnSchema::from_row_schema(&v1.row_schema)?,
+            schema_version: 0,
+            schema_history: vec![],
             primary_key_fields: v1.primary_key_fields,
-            secondary_index_types: v1.secondary_index_types,
+            secondary_index_schemas: schemas_from_types(&v1.secondary_index_types)?,
             empty_links_list: v1.empty_links_list,
-        }
+        })
     }
 }
 
-impl<Pk> From<SpaceInfoPageV2<Pk>> for SpaceInfoPage<Pk> {
-    fn from(v2: SpaceInfoPageV2<Pk>) -> Self {
-        SpaceInfoPage {
+impl<Pk> TryFrom<SpaceInfoPageV2<Pk>> for SpaceInfoPage<Pk> {
+    type Error = eyre::Report;
+
+    fn try_from(v2: SpaceInfoPageV2<Pk>) -> eyre::Result<Self> {
+        Ok(SpaceInfoPage {
             version: v2.version,
             id: v2.id,
             page_count: v2.page_count,
             pk_gen_state: v2.pk_gen_state,
             name: v2.name,
-            row_schema: v2.row_schema,
+            columns: ColumnSchema::from_row_schema(&v2.row_schema)?,
+            schema_version: 0,
+            schema_history: vec![],
             primary_key_fields: v2.primary_key_fields,
-            secondary_index_types: v2.secondary_index_types,
+            secondary_index_schemas: schemas_from_types(&v2.secondary_index_types)?,
             empty_links_list: v2.empty_links_list,
+        })
+    }
+}
+
+impl<Pk> From<SpaceInfoPageV3<Pk>> for SpaceInfoPage<Pk> {
+    fn from(v3: SpaceInfoPageV3<Pk>) -> Self {
+        SpaceInfoPage {
+            version: v3.version,
+            id: v3.id,
+            page_count: v3.page_count,
+            pk_gen_state: v3.pk_gen_state,
+            name: v3.name,
+            columns: v3.columns,
+            schema_version: v3.schema_version,
+            schema_history: v3.schema_history,
+            primary_key_fields: v3.primary_key_fields,
+            secondary_index_schemas: v3.secondary_index_schemas,
+            empty_links_list: v3.empty_links_list,
         }
     }
 }
 
-impl<Pk: Clone> From<SpaceInfoPage<Pk>> for SpaceInfoPageV2<Pk> {
+impl<Pk: Clone> From<SpaceInfoPage<Pk>> for SpaceInfoPageV3<Pk> {
     fn from(page: SpaceInfoPage<Pk>) -> Self {
-        SpaceInfoPageV2 {
+        SpaceInfoPageV3 {
             version: page.version,
             id: page.id,
             page_count: page.page_count,
             pk_gen_state: page.pk_gen_state,
             name: page.name,
-            row_schema: page.row_schema,
+            columns: page.columns,
+            schema_version: page.schema_version,
+            schema_history: page.schema_history,
             primary_key_fields: page.primary_key_fields,
-            secondary_index_types: page.secondary_index_types,
+            secondary_index_schemas: page.secondary_index_schemas,
             empty_links_list: page.empty_links_list,
         }
     }
 }
 
-// TODO: Minor. Add some schema description in `SpaceIndo`
-
 /// Internal information about a `Space`. Always appears first before all other
 /// pages in a `Space`.
 #[derive(Archive, Clone, Deserialize, Debug, PartialEq, Serialize)]
@@ -96,9 +283,16 @@ pub struct SpaceInfoPage<Pk = ()> {
     pub pk_gen_state: Pk,
     pub name: SpaceName,
     pub version: u32,
-    pub row_schema: Vec<(String, String)>,
+    /// Schemas of the row's columns in order they appear in the row.
+    pub columns: Vec<ColumnSchema>,
+    /// Version of the `columns`, it's increased by every schema change.
+    pub schema_version: u32,
+    /// Previous versions of the row schema, oldest first. Rows of the data
+    /// pages written under them are upgraded on read.
+    pub schema_history: Vec<SchemaVersion>,
     pub primary_key_fields: Vec<String>,
-    pub secondary_index_types: Vec<(String, String)>,
+    /// Key schemas of the secondary indexes, including composite ones.
+    pub secondary_index_schemas: Vec<IndexSchema>,
     pub empty_links_list: Vec<Link>,
 }
 
@@ -116,34 +310,106 @@ where
                 rkyv::rancor::Error,
             >,
         >,
-    <Pk as Archive>::Archived: rkyv::Deserialize<Pk, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
+    <Pk as Archive>::Archived:
+        rkyv::Deserialize<Pk, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
 {
     fn as_bytes(&self) -> impl AsRef<[u8]> + Send {
-        let v2 = SpaceInfoPageV2 {
+        let v3 = SpaceInfoPageV3 {
             version: self.version,
             id: self.id,
             page_count: self.page_count,
             pk_gen_state: self.pk_gen_state.clone(),
             name: self.name.clone(),
-            row_schema: self.row_schema.clone(),
+            columns: self.columns.clone(),
+            schema_version: self.schema_version,
+            schema_history: self.schema_history.clone(),
             primary_key_fields: self.primary_key_fields.clone(),
-            secondary_index_types: self.secondary_index_types.clone(),
+            secondary_index_schemas: self.secondary_index_schemas.clone(),
             empty_links_list: self.empty_links_list.clone(),
         };
-        rkyv::to_bytes::<rkyv::rancor::Error>(&v2).unwrap()
+        rkyv::to_bytes::<rkyv::rancor::Error>(&v3).unwrap()
     }
 
+    /// # Panics
+    ///
+    /// Panics if legacy page's `row_schema` has unsupported types or `version`
+    /// is not supported, [`SpaceInfoPage::try_from_bytes`] and
+    /// [`parse_space_info`] return error instead.
+    ///
+    /// [`parse_space_info`]: crate::page::parse_space_info
     fn from_bytes(bytes: &[u8], version: u32) -> Self {
+        Self::try_from_bytes(bytes, version).expect("space info should be supported")
+    }
+}
+
+impl<Pk> SpaceInfoPage<Pk>
+where
+    Pk: Archive
+        + for<'a> rkyv::Serialize<
+            rkyv::rancor::Strategy<
+                rkyv::ser::Serializer<
+                    rkyv::util::AlignedVec,
+                    rkyv::ser::allocator::ArenaHandle<'a>,
+                    rkyv::ser::sharing::Share,
+                >,
+                rkyv::rancor::Error,
+            >,
+        >,
+    <Pk as Archive>::Archived:
+        rkyv::Deserialize<Pk, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
+{
+    /// Reads [`SpaceInfoPage`] of the given data `version`. Pages of the
+    /// versions before typed column schema are migrated, error is returned if
+    /// their `row_schema` has unsupported types or `version` is unknown.
+    pub fn try_from_bytes(bytes: &[u8], version: u32) -> eyre::Result<Self> {
+        match version {
+            1 => SpaceInfoPageV1::from_bytes(bytes, version).try_into(),
+            2 => SpaceInfoPageV2::from_bytes(bytes, version).try_into(),
+            3 => Ok(SpaceInfoPageV3::from_bytes(bytes, version).into()),
+            _ => bail!("Space info version {} is not supported", version),
+        }
+    }
+}
+
+impl<Pk> SpaceInfoPage<Pk> {
+    /// Returns columns in the legacy `row_schema` form, as `(name, type)`
+    /// pairs.
+    #[deprecated(note = "use `columns` instead")]
+    pub fn row_schema(&self) -> Vec<(String, String)> {
+        self.columns
+            .iter()
+            .map(|c| (c.name.clone(), c.row_type().to_string()))
+            .collect()
+    }
+}
+
+impl SpaceInfoPage {
+    /// Checks that `bytes` are valid archived [`SpaceInfoPage`] of the given
+    /// data `version`, so they can be safely read with
+    /// [`Persistable::from_bytes`].
+    pub fn check_bytes(bytes: &[u8], version: u32) -> eyre::Result<()> {
+        // Archived bytes are validated only if they are aligned.
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(bytes);
         match version {
             1 => {
-                let v1 = SpaceInfoPageV1::from_bytes(bytes, version);
-                v1.into()
+                rkyv::access::<<SpaceInfoPageV1 as Archive>::Archived, rkyv::rancor::Error>(
+                    &aligned,
+                )?;
             }
-            _ => {
-                let v2 = SpaceInfoPageV2::from_bytes(bytes, version);
-                v2.into()
+            2 => {
+                rkyv::access::<<SpaceInfoPageV2 as Archive>::Archived, rkyv::rancor::Error>(
+                    &aligned,
+                )?;
             }
+            3 => {
+                rkyv::access::<<SpaceInfoPageV3 as Archive>::Archived, rkyv::rancor::Error>(
+                    &aligned,
+                )?;
+            }
+            _ => bail!("Space info version {} is not supported", version),
         }
+        Ok(())
     }
 }
 
@@ -159,10 +425,17 @@ impl Interval {
 
 #[cfg(test)]
 mod test {
-    use super::{SpaceInfoPage, SpaceInfoPageV1, SpaceInfoPageV2};
-    use crate::page::INNER_PAGE_SIZE;
+    use super::{
+        ColumnSchema, IndexColumn, IndexSchema, SortOrder, SpaceInfoPage, SpaceInfoPageV1,
+        SpaceInfoPageV2,
+    };
+    use crate::page::{parse_space_info, persist_page};
+    use crate::page::{DATA_VERSION, INNER_PAGE_SIZE};
+    use crate::persistence::data::{ColumnType, DataTypeValue};
     use crate::util::Persistable;
+    use crate::{GeneralHeader, GeneralPage, PageType, PAGE_SIZE};
     use rkyv::Archive;
+    use tokio::fs::File;
 
     #[test]
     fn test_as_bytes() {
@@ -171,16 +444,246 @@ mod test {
             page_count: 0,
             name: "Test".to_string(),
             version: 1,
-            row_schema: vec![],
+            columns: vec![],
+            schema_version: 0,
+            schema_history: vec![],
             primary_key_fields: vec![],
             pk_gen_state: (),
             empty_links_list: vec![],
-            secondary_index_types: vec![],
+            secondary_index_schemas: vec![],
         };
         let bytes = info.as_bytes();
         assert!(bytes.as_ref().len() < INNER_PAGE_SIZE)
     }
 
+    #[test]
+    fn test_composite_schema_roundtrip() {
+        let schema = IndexSchema {
+            name: "name_age_idx".to_string(),
+            columns: vec![
+                IndexColumn {
+                    name: "name".to_string(),
+                    ty: ColumnType::String,
+                    order: SortOrder::Asc,
+                },
+                IndexColumn {
+                    name: "age".to_string(),
+                    ty: ColumnType::U32,
+                    order: SortOrder::Desc,
+                },
+            ],
+        };
+        let info: SpaceInfoPage = SpaceInfoPage {
+            id: 1.into(),
+            page_count: 0,
+            name: "Test".to_string(),
+            version: 3,
+            columns: vec![
+                ColumnSchema::new(0, "name", ColumnType::String),
+                ColumnSchema::new(1, "age", ColumnType::U32),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec![],
+            pk_gen_state: (),
+            empty_links_list: vec![],
+            secondary_index_schemas: vec![schema.clone()],
+        };
+        let bytes = info.as_bytes();
+
+        let parsed: SpaceInfoPage = SpaceInfoPage::from_bytes(bytes.as_ref(), DATA_VERSION);
+        assert_eq!(parsed, info);
+        assert!(parsed.secondary_index_schemas[0].is_composite());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_typed_columns_roundtrip() {
+        let mut age = ColumnSchema::new(3, "age", ColumnType::U32);
+        age.default = Some(DataTypeValue::U32(18));
+        let mut nickname = ColumnSchema::new(5, "nickname", ColumnType::String);
+        nickname.nullable = true;
+        let info: SpaceInfoPage = SpaceInfoPage {
+            id: 2.into(),
+            page_count: 4,
+            name: "Test".to_string(),
+            version: 1,
+            columns: vec![ColumnSchema::new(0, "id", ColumnType::U64), age, nickname],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            pk_gen_state: (),
+            empty_links_list: vec![],
+            secondary_index_schemas: vec![],
+        };
+        let bytes = info.as_bytes();
+        SpaceInfoPage::check_bytes(bytes.as_ref(), DATA_VERSION).unwrap();
+
+        let parsed: SpaceInfoPage = SpaceInfoPage::from_bytes(bytes.as_ref(), DATA_VERSION);
+        assert_eq!(parsed, info);
+        assert_eq!(
+            parsed.row_schema(),
+            vec![
+                ("id".to_string(), "u64".to_string()),
+                ("age".to_string(), "u32".to_string()),
+                ("nickname".to_string(), "Option<String>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_composite_columns_roundtrip() {
+        let mut tags = ColumnSchema::new(
+            1,
+            "tags",
+            ColumnType::Vec(Box::new(ColumnType::Option(Box::new(ColumnType::String)))),
+        );
+        tags.default = Some(DataTypeValue::Vec(vec![DataTypeValue::Option(None)]));
+        let shape_type: ColumnType = "enum {Empty, Rect {min: (i16, i16), max: (i16, i16)}}"
+            .parse()
+            .unwrap();
+        let mut shape = ColumnSchema::new(2, "shape", shape_type.clone());
+        shape.default = Some(shape_type.default_value());
+        let info: SpaceInfoPage = SpaceInfoPage {
+            id: 2.into(),
+            page_count: 4,
+            name: "Test".to_string(),
+            version: 1,
+            columns: vec![ColumnSchema::new(0, "id", ColumnType::Uuid), tags, shape],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            pk_gen_state: (),
+            empty_links_list: vec![],
+            secondary_index_schemas: vec![],
+        };
+        let bytes = info.as_bytes();
+        SpaceInfoPage::check_bytes(bytes.as_ref(), DATA_VERSION).unwrap();
+
+        let parsed: SpaceInfoPage = SpaceInfoPage::from_bytes(bytes.as_ref(), DATA_VERSION);
+        assert_eq!(parsed, info);
+        assert_eq!(
+            parsed.row_schema(),
+            vec![
+                ("id".to_string(), "Uuid".to_string()),
+                ("tags".to_string(), "Vec<Option<String>>".to_string()),
+                (
+                    "shape".to_string(),
+                    "enum {Empty, Rect {min: (i16, i16), max: (i16, i16)}}".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_version() {
+        let info: SpaceInfoPage = SpaceInfoPage {
+            id: 0.into(),
+            page_count: 0,
+            name: "Test".to_string(),
+            version: 1,
+            columns: vec![],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec![],
+            pk_gen_state: (),
+            empty_links_list: vec![],
+            secondary_index_schemas: vec![],
+        };
+        let bytes = info.as_bytes();
+        let err =
+            SpaceInfoPage::<()>::try_from_bytes(bytes.as_ref(), DATA_VERSION + 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Space info version {} is not supported", DATA_VERSION + 1)
+        );
+        assert!(SpaceInfoPage::check_bytes(bytes.as_ref(), DATA_VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn test_migration_with_unknown_type() {
+        let old_info: SpaceInfoPageV2 = SpaceInfoPageV2 {
+            id: 20.into(),
+            page_count: 7,
+            pk_gen_state: (),
+            name: "table".to_string(),
+            version: 2,
+            row_schema: vec![("col".to_string(), "HashMap<u8, u8>".to_string())],
+            primary_key_fields: vec![],
+            secondary_index_types: vec![],
+            empty_links_list: vec![],
+        };
+        let bytes = old_info.as_bytes();
+
+        let err = SpaceInfoPage::<()>::try_from_bytes(bytes.as_ref(), 2).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Column `col` has invalid type: Data type `HashMap<u8, u8>` is not supported"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_legacy_space_info_with_unknown_type() {
+        let mut page = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::SpaceInfo, 20.into()),
+            inner: SpaceInfoPageV2::<()> {
+                id: 20.into(),
+                page_count: 0,
+                pk_gen_state: (),
+                name: "table".to_string(),
+                version: 2,
+                row_schema: vec![("col".to_string(), "MyType".to_string())],
+                primary_key_fields: vec![],
+                secondary_index_types: vec![],
+                empty_links_list: vec![],
+            },
+        };
+        page.header.data_version = 2;
+        let filename = "tests/data/space_info_legacy_unknown_type.wt";
+        let mut file = File::create(filename).await.unwrap();
+        persist_page(&mut page, &mut file).await.unwrap();
+
+        let mut file = File::open(filename).await.unwrap();
+        let err = parse_space_info::<PAGE_SIZE>(&mut file).await.unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Column `col` has invalid type: Data type `MyType` is not supported"
+        );
+    }
+
+    #[test]
+    fn test_migration_from_v2_schemas() {
+        let old_info: SpaceInfoPageV2 = SpaceInfoPageV2 {
+            id: 20.into(),
+            page_count: 7,
+            pk_gen_state: (),
+            name: "table".to_string(),
+            version: 2,
+            row_schema: vec![("col".to_string(), "u64".to_string())],
+            primary_key_fields: vec![],
+            secondary_index_types: vec![("col".to_string(), "u64".to_string())],
+            empty_links_list: vec![],
+        };
+        let bytes = old_info.as_bytes();
+
+        let migrated: SpaceInfoPage = SpaceInfoPage::from_bytes(bytes.as_ref(), 2);
+        assert_eq!(
+            migrated.secondary_index_schemas,
+            vec![IndexSchema::single("col", ColumnType::U64)]
+        );
+        assert!(!migrated.secondary_index_schemas[0].is_composite());
+
+        let mut old_info = old_info.clone();
+        old_info.secondary_index_types[0].1 = "MyType".to_string();
+        let bytes = old_info.as_bytes();
+        let err = SpaceInfoPage::<()>::try_from_bytes(bytes.as_ref(), 2).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Index on `col` has invalid type: Data type `MyType` is not supported"
+        );
+    }
+
     #[test]
     fn test_migration_from_v1() {
         let old_info: SpaceInfoPageV1 = SpaceInfoPageV1 {
@@ -194,13 +697,16 @@ mod test {
             empty_links_list: vec![],
         };
 
-        let migrated: SpaceInfoPage = old_info.into();
+        let migrated: SpaceInfoPage = old_info.try_into().unwrap();
 
         assert_eq!(migrated.version, 0);
         assert_eq!(migrated.id, 42.into());
         assert_eq!(migrated.page_count, 5);
         assert_eq!(migrated.name, "legacy_table");
-        assert_eq!(migrated.row_schema, vec![("col1".to_string(), "i32".to_string())]);
+        assert_eq!(
+            migrated.columns,
+            vec![ColumnSchema::new(0, "col1", ColumnType::I32)]
+        );
         assert_eq!(migrated.primary_key_fields, vec!["col1".to_string()]);
     }
 
@@ -224,7 +730,7 @@ mod test {
         let deserialized: SpaceInfoPageV1 =
             rkyv::deserialize::<_, rkyv::rancor::Error>(archived).unwrap();
 
-        let migrated: SpaceInfoPage = deserialized.into();
+        let migrated: SpaceInfoPage = deserialized.try_into().unwrap();
 
         assert_eq!(migrated.version, 0);
         assert_eq!(migrated.id, 10.into());
@@ -292,4 +798,4 @@ mod test {
         assert_eq!(page_from_v2.version, 5);
         assert_eq!(page_from_v2.id, 200.into());
     }
-}
\ No newline at end of file
+}
@@ -29,4 +29,8 @@ pub enum PageType {
     IndexUnsized = 30,
     /// Index's table of contests `Page` type. Is used to determine node's `PageId`.
     IndexTableOfContents = 31,
+    /// Index for `String` keys with prefix compression `Page` type.
+    IndexPrefixCompressed = 32,
+    /// Bloom filter of the index node `Page` type.
+    IndexBloomFilter = 33,
 }
@@ -320,7 +320,9 @@ pub async fn parse_space_info<const PAGE_SIZE: usize>(
     let mut buffer = vec![0u8; header.data_length as usize];
     file.read_exact(&mut buffer).await?;
 
-    Ok(SpaceInfoPage::from_bytes(&buffer, header.data_version))
+    // Legacy pages can have unsupported column types, so error is returned
+    // instead of panic of `Persistable::from_bytes`.
+    SpaceInfoPage::try_from_bytes(&buffer, header.data_version)
 }
 
 // pub fn read_index_pages<T, const PAGE_SIZE: usize>(
@@ -0,0 +1,674 @@
+//! Export of the space's live rows to Arrow [`RecordBatch`]'s.
+//!
+//! Every data page is converted to one batch, so export keeps only one page
+//! in memory. Columns are mapped to Arrow types:
+//!
+//! - integers up to 64 bits, floats and `bool` to the same Arrow types,
+//! - `i128` and `u128` to `Decimal256(39, 0)`, as not every `i128` fits in
+//!   `Decimal128(38, 0)`,
+//! - `String` and `char` to `Utf8`,
+//! - `Uuid` to `FixedSizeBinary(16)` with `arrow.uuid` extension name,
+//!   `PackedNanoid` to `FixedSizeBinary` of it's packed length,
+//! - `Option` to nullable field of the inner type, so `Some(None)` is null,
+//! - `Vec` to `List` and struct to `Struct`,
+//! - enum to `Struct` with `variant` name and nullable field for every
+//!   variant with fields, which is set only for the row's variant.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Decimal256Array, FixedSizeBinaryArray, Float32Array, Float64Array,
+    Int16Array, Int32Array, Int64Array, Int8Array, ListArray, RecordBatch, StringArray,
+    StructArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow_buffer::{i256, Buffer, NullBuffer, OffsetBuffer};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use eyre::eyre;
+use tokio::fs::File;
+
+use crate::page::{parse_data_page, parse_space_info};
+use crate::persistence::data::{
+    data_page_row_links, parse_data_page_ids, ColumnType, DataTypeValue, FieldType, RowReader,
+    VariantType,
+};
+use crate::persistence::export::{column_positions, ExportOptions, ExportReport};
+use crate::{ColumnSchema, SpaceInfoPage, INNER_PAGE_SIZE, PAGE_SIZE};
+
+/// Name of the enum's field with the variant's name.
+const VARIANT_FIELD: &str = "variant";
+
+/// Returns Arrow schema of the `columns`.
+pub fn arrow_schema<'a>(columns: impl IntoIterator<Item = &'a ColumnSchema>) -> Schema {
+    Schema::new(
+        columns
+            .into_iter()
+            .map(|c| arrow_field(&c.name, &c.row_type()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Returns Arrow schema of the space's exported columns. All columns are
+/// exported if `columns` is empty.
+pub fn space_arrow_schema(space_info: &SpaceInfoPage, columns: &[String]) -> eyre::Result<Schema> {
+    let positions = column_positions(space_info, columns)?;
+    Ok(arrow_schema(
+        positions.iter().map(|p| &space_info.columns[*p]),
+    ))
+}
+
+/// Returns Arrow field of the column. Field is nullable if column is
+/// `Option`.
+pub fn arrow_field(name: &str, ty: &ColumnType) -> Field {
+    let (ty, nullable) = match ty {
+        ColumnType::Option(inner) => (inner.as_ref(), true),
+        ty => (ty, false),
+    };
+    let field = Field::new(name, arrow_data_type(ty), nullable);
+    match ty {
+        ColumnType::Uuid => field.with_metadata(HashMap::from([(
+            "ARROW:extension:name".to_string(),
+            "arrow.uuid".to_string(),
+        )])),
+        _ => field,
+    }
+}
+
+pub fn arrow_data_type(ty: &ColumnType) -> DataType {
+    match ty {
+        ColumnType::String | ColumnType::Char => DataType::Utf8,
+        ColumnType::I128 => DataType::Decimal256(39, 0),
+        ColumnType::I64 => DataType::Int64,
+        ColumnType::I32 => DataType::Int32,
+        ColumnType::I16 => DataType::Int16,
+        ColumnType::I8 => DataType::Int8,
+        ColumnType::U128 => DataType::Decimal256(39, 0),
+        ColumnType::U64 => DataType::UInt64,
+        ColumnType::U32 => DataType::UInt32,
+        ColumnType::U16 => DataType::UInt16,
+        ColumnType::U8 => DataType::UInt8,
+        ColumnType::F64 | ColumnType::OrderedF64 => DataType::Float64,
+        ColumnType::F32 | ColumnType::OrderedF32 => DataType::Float32,
+        ColumnType::Bool => DataType::Boolean,
+        ColumnType::Uuid => DataType::FixedSizeBinary(16),
+        ColumnType::PackedNanoid { bytes, .. } => DataType::FixedSizeBinary(*bytes as i32),
+        ColumnType::Option(inner) => arrow_data_type(inner),
+        ColumnType::Vec(inner) => DataType::List(Arc::new(arrow_field("item", inner))),
+        ColumnType::Struct(fields) => DataType::Struct(struct_fields(fields)),
+        ColumnType::Enum(variants) => DataType::Struct(enum_fields(variants)),
+    }
+}
+
+fn struct_fields(fields: &[FieldType]) -> Fields {
+    fields.iter().map(|f| arrow_field(&f.name, &f.ty)).collect()
+}
+
+fn enum_fields(variants: &[VariantType]) -> Fields {
+    std::iter::once(Field::new(VARIANT_FIELD, DataType::Utf8, false))
+        .chain(
+            variants
+                .iter()
+                .filter(|v| !v.fields.is_empty())
+                .map(|v| Field::new(&v.name, DataType::Struct(struct_fields(&v.fields)), true)),
+        )
+        .collect()
+}
+
+/// Converts rows to the [`RecordBatch`] of the `schema`. Rows have values of
+/// the `types` in the same order.
+pub fn record_batch(
+    schema: SchemaRef,
+    types: &[ColumnType],
+    rows: &[Vec<DataTypeValue>],
+) -> eyre::Result<RecordBatch> {
+    let columns = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let values = rows
+                .iter()
+                .map(|row| {
+                    row.get(i)
+                        .ok_or_else(|| eyre!("Row has no column {}", i))
+                        .map(Some)
+                })
+                .collect::<eyre::Result<Vec<_>>>()?;
+            array(ty, &values)
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Calls `on_batch` for the live rows of every data page of the space file.
+pub async fn export_record_batches<F>(
+    file: &mut File,
+    options: &ExportOptions,
+    mut on_batch: F,
+) -> eyre::Result<ExportReport>
+where
+    F: FnMut(RecordBatch) -> eyre::Result<()>,
+{
+    let space_info = parse_space_info::<PAGE_SIZE>(file).await?;
+    let reader = RowReader::new(&space_info)?;
+    let positions = column_positions(&space_info, &options.columns)?;
+    let schema = Arc::new(arrow_schema(
+        positions.iter().map(|p| &space_info.columns[*p]),
+    ));
+    let types = positions
+        .iter()
+        .map(|p| space_info.columns[*p].row_type())
+        .collect::<Vec<_>>();
+
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let mut report = ExportReport::default();
+    for index in parse_data_page_ids(file).await? {
+        if report.rows >= limit {
+            break;
+        }
+        let page = parse_data_page::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(file, index).await?;
+        report.pages_read += 1;
+        let page_id = page.header.page_id;
+        let links = data_page_row_links(
+            &page,
+            reader.layout(page_id),
+            space_info.page_empty_links(page_id),
+        )?;
+        let rows = links
+            .into_iter()
+            .take(limit - report.rows)
+            .map(|link| Ok(reader.read_columns(page_id, page.inner.get_at(link)?, &positions)?))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        if rows.is_empty() {
+            continue;
+        }
+        report.rows += rows.len();
+        on_batch(record_batch(schema.clone(), &types, &rows)?)?;
+    }
+
+    Ok(report)
+}
+
+/// Writes live rows of the space file to `output` as Arrow IPC file.
+pub async fn export_space_ipc<W: Write>(
+    file: &mut File,
+    options: &ExportOptions,
+    output: W,
+) -> eyre::Result<ExportReport> {
+    let space_info = parse_space_info::<PAGE_SIZE>(file).await?;
+    let schema = space_arrow_schema(&space_info, &options.columns)?;
+    let mut writer = FileWriter::try_new(output, &schema)?;
+    let report = export_record_batches(file, options, |batch| Ok(writer.write(&batch)?)).await?;
+    writer.finish()?;
+
+    Ok(report)
+}
+
+/// Converts values of the type `ty` to Arrow array. `None` values are null.
+fn array(ty: &ColumnType, values: &[Option<&DataTypeValue>]) -> eyre::Result<ArrayRef> {
+    Ok(match ty {
+        ColumnType::String => Arc::new(StringArray::from(scalars(ty, values, |v| match v {
+            DataTypeValue::String(s) => Some(s.as_str()),
+            _ => None,
+        })?)),
+        ColumnType::Char => Arc::new(StringArray::from(scalars(ty, values, |v| match v {
+            DataTypeValue::Char(c) => Some(c.to_string()),
+            _ => None,
+        })?)),
+        ColumnType::I128 => Arc::new(
+            Decimal256Array::from(scalars(ty, values, |v| match v {
+                DataTypeValue::I128(v) => Some(i256::from_i128(*v)),
+                _ => None,
+            })?)
+            .with_precision_and_scale(39, 0)?,
+        ),
+        ColumnType::I64 => Arc::new(Int64Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::I64(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::I32 => Arc::new(Int32Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::I32(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::I16 => Arc::new(Int16Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::I16(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::I8 => Arc::new(Int8Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::I8(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::U128 => Arc::new(
+            Decimal256Array::from(scalars(ty, values, |v| match v {
+                DataTypeValue::U128(v) => Some(i256::from_parts(*v, 0)),
+                _ => None,
+            })?)
+            .with_precision_and_scale(39, 0)?,
+        ),
+        ColumnType::U64 => Arc::new(UInt64Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::U64(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::U32 => Arc::new(UInt32Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::U32(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::U16 => Arc::new(UInt16Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::U16(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::U8 => Arc::new(UInt8Array::from(scalars(ty, values, |v| match v {
+            DataTypeValue::U8(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::F64 | ColumnType::OrderedF64 => {
+            Arc::new(Float64Array::from(scalars(ty, values, |v| match v {
+                DataTypeValue::F64(v) => Some(*v),
+                DataTypeValue::OrderedF64(v) => Some(v.0),
+                _ => None,
+            })?))
+        }
+        ColumnType::F32 | ColumnType::OrderedF32 => {
+            Arc::new(Float32Array::from(scalars(ty, values, |v| match v {
+                DataTypeValue::F32(v) => Some(*v),
+                DataTypeValue::OrderedF32(v) => Some(v.0),
+                _ => None,
+            })?))
+        }
+        ColumnType::Bool => Arc::new(BooleanArray::from(scalars(ty, values, |v| match v {
+            DataTypeValue::Bool(v) => Some(*v),
+            _ => None,
+        })?)),
+        ColumnType::Uuid => {
+            let bytes = scalars(ty, values, |v| match v {
+                DataTypeValue::Uuid(v) => Some(v.as_bytes().to_vec()),
+                _ => None,
+            })?;
+            fixed_size_binary(16, bytes)?
+        }
+        ColumnType::PackedNanoid { bytes: size, .. } => {
+            let bytes = scalars(ty, values, |v| match v {
+                DataTypeValue::PackedNanoid(v) if v.len() == *size as usize => Some(v.clone()),
+                _ => None,
+            })?;
+            fixed_size_binary(*size as i32, bytes)?
+        }
+        ColumnType::Option(inner) => {
+            let values = values
+                .iter()
+                .map(|v| match v {
+                    None | Some(DataTypeValue::Option(None)) => Ok(None),
+                    Some(DataTypeValue::Option(Some(v))) => Ok(Some(v.as_ref())),
+                    Some(v) => Err(mismatch(v, ty)),
+                })
+                .collect::<eyre::Result<Vec<_>>>()?;
+            array(inner, &values)?
+        }
+        ColumnType::Vec(inner) => {
+            let mut lengths = Vec::with_capacity(values.len());
+            let mut items = vec![];
+            for value in values {
+                match value {
+                    None => lengths.push(0),
+                    Some(DataTypeValue::Vec(values)) => {
+                        lengths.push(values.len());
+                        items.extend(values.iter().map(Some))
+                    }
+                    Some(v) => return Err(mismatch(v, ty)),
+                }
+            }
+            Arc::new(ListArray::try_new(
+                Arc::new(arrow_field("item", inner)),
+                OffsetBuffer::from_lengths(lengths),
+                array(inner, &items)?,
+                nulls(values),
+            )?)
+        }
+        ColumnType::Struct(fields) => {
+            let values = values
+                .iter()
+                .map(|v| match v {
+                    None => Ok(None),
+                    Some(DataTypeValue::Struct(values)) if has_fields(values, fields) => {
+                        Ok(Some(values.as_slice()))
+                    }
+                    Some(v) => Err(mismatch(v, ty)),
+                })
+                .collect::<eyre::Result<Vec<_>>>()?;
+            struct_array(fields, &values)?
+        }
+        ColumnType::Enum(variants) => {
+            let mut names = Vec::with_capacity(values.len());
+            for value in values {
+                match value {
+                    None => names.push(None),
+                    Some(DataTypeValue::Enum {
+                        variant,
+                        fields: values,
+                    }) if variants
+                        .iter()
+                        .any(|v| &v.name == variant && has_fields(values, &v.fields)) =>
+                    {
+                        names.push(Some(variant.as_str()))
+                    }
+                    Some(v) => return Err(mismatch(v, ty)),
+                }
+            }
+            let mut children: Vec<ArrayRef> = vec![Arc::new(StringArray::from(names.clone()))];
+            for variant in variants.iter().filter(|v| !v.fields.is_empty()) {
+                let values = values
+                    .iter()
+                    .map(|v| match v {
+                        Some(DataTypeValue::Enum {
+                            variant: name,
+                            fields,
+                        }) if name == &variant.name => Some(fields.as_slice()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                children.push(struct_array(&variant.fields, &values)?)
+            }
+            Arc::new(StructArray::try_new(
+                enum_fields(variants),
+                children,
+                nulls(values),
+            )?)
+        }
+    })
+}
+
+/// Collects values of the scalar type, `get` returns `None` for values of
+/// other types.
+fn scalars<'a, T>(
+    ty: &ColumnType,
+    values: &[Option<&'a DataTypeValue>],
+    get: impl Fn(&'a DataTypeValue) -> Option<T>,
+) -> eyre::Result<Vec<Option<T>>> {
+    values
+        .iter()
+        .map(|v| match v {
+            None => Ok(None),
+            Some(v) => get(v).map(Some).ok_or_else(|| mismatch(v, ty)),
+        })
+        .collect()
+}
+
+fn fixed_size_binary(size: i32, values: Vec<Option<Vec<u8>>>) -> eyre::Result<ArrayRef> {
+    let nulls = null_buffer(values.iter().map(Option::is_some));
+    let bytes = values
+        .into_iter()
+        .flat_map(|v| v.unwrap_or_else(|| vec![0; size as usize]))
+        .collect::<Vec<_>>();
+    Ok(Arc::new(FixedSizeBinaryArray::try_new(
+        size,
+        Buffer::from_vec(bytes),
+        nulls,
+    )?))
+}
+
+fn struct_array(
+    fields: &[FieldType],
+    values: &[Option<&[(String, DataTypeValue)]>],
+) -> eyre::Result<ArrayRef> {
+    let nulls = null_buffer(values.iter().map(Option::is_some));
+    if fields.is_empty() {
+        return Ok(Arc::new(StructArray::new_empty_fields(values.len(), nulls)));
+    }
+    let children = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let values = values
+                .iter()
+                .map(|v| v.map(|v| &v[i].1))
+                .collect::<Vec<_>>();
+            array(&field.ty, &values)
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    Ok(Arc::new(StructArray::try_new(
+        struct_fields(fields),
+        children,
+        nulls,
+    )?))
+}
+
+fn nulls(values: &[Option<&DataTypeValue>]) -> Option<NullBuffer> {
+    null_buffer(values.iter().map(Option::is_some))
+}
+
+/// Returns `None` if all values are valid.
+fn null_buffer(valid: impl Iterator<Item = bool>) -> Option<NullBuffer> {
+    let nulls = NullBuffer::from(valid.collect::<Vec<_>>());
+    Some(nulls).filter(|n| n.null_count() > 0)
+}
+
+fn has_fields(values: &[(String, DataTypeValue)], fields: &[FieldType]) -> bool {
+    values.len() == fields.len() && values.iter().zip(fields).all(|((n, _), f)| n == &f.name)
+}
+
+fn mismatch(value: &DataTypeValue, ty: &ColumnType) -> eyre::Report {
+    eyre!("Value `{}` is not `{}`", value, ty)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal256Type, Float64Type, UInt32Type, UInt64Type, UInt8Type};
+    use arrow_array::Array;
+    use arrow_buffer::i256;
+    use arrow_ipc::reader::FileReader;
+    use arrow_schema::{DataType, Field, Fields};
+    use proptest::prelude::*;
+    use tokio::fs::File;
+
+    use super::{arrow_field, arrow_schema, export_space_ipc, record_batch};
+    use crate::persistence::data::util::test::rows;
+    use crate::persistence::data::{encode_row, persist_data_rows, ColumnType, DataTypeValue};
+    use crate::persistence::export::ExportOptions;
+    use crate::{ColumnSchema, SpaceInfoPage};
+
+    fn columns(columns: &[(&str, &str)]) -> Vec<ColumnSchema> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, (name, ty))| {
+                ColumnSchema::new(i as u32, *name, ColumnType::from_str(ty).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_arrow_field() {
+        let field = |ty: &str| arrow_field("c", &ColumnType::from_str(ty).unwrap());
+        assert_eq!(field("u64"), Field::new("c", DataType::UInt64, false));
+        assert_eq!(
+            field("Option<i128>"),
+            Field::new("c", DataType::Decimal256(39, 0), true)
+        );
+        assert_eq!(
+            field("Vec<Option<String>>"),
+            Field::new(
+                "c",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                false
+            )
+        );
+        assert_eq!(
+            field("enum {A, B(u8)}"),
+            Field::new(
+                "c",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("variant", DataType::Utf8, false),
+                    Field::new(
+                        "B",
+                        DataType::Struct(Fields::from(vec![Field::new(
+                            "0",
+                            DataType::UInt8,
+                            false
+                        )])),
+                        true
+                    ),
+                ])),
+                false
+            )
+        );
+        assert_eq!(
+            field("Uuid").metadata().get("ARROW:extension:name"),
+            Some(&"arrow.uuid".to_string())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_record_batch_random_values((types, rows) in rows(1..5, 0..5)) {
+            let columns = types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| ColumnSchema::new(i as u32, format!("c{i}"), ty.clone()))
+                .collect::<Vec<_>>();
+            let schema = Arc::new(arrow_schema(&columns));
+            let batch = record_batch(schema, &types, &rows).unwrap();
+            prop_assert_eq!(batch.num_rows(), rows.len());
+        }
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let columns = columns(&[
+            ("id", "i128"),
+            ("parent", "Option<u32>"),
+            ("tags", "Vec<String>"),
+            ("state", "Option<enum {Off, On {level: u8}}>"),
+        ]);
+        let types = columns.iter().map(|c| c.ty.clone()).collect::<Vec<_>>();
+        let on = DataTypeValue::Enum {
+            variant: "On".to_string(),
+            fields: vec![("level".to_string(), 3u8.into())],
+        };
+        let rows = vec![
+            vec![
+                (-1i128).into(),
+                DataTypeValue::Option(None),
+                DataTypeValue::Vec(vec!["a".to_string().into(), "b".to_string().into()]),
+                DataTypeValue::Option(Some(Box::new(on))),
+            ],
+            vec![
+                2i128.into(),
+                DataTypeValue::Option(Some(Box::new(7u32.into()))),
+                DataTypeValue::Vec(vec![]),
+                DataTypeValue::Option(None),
+            ],
+            vec![
+                i128::MIN.into(),
+                DataTypeValue::Option(None),
+                DataTypeValue::Vec(vec![]),
+                DataTypeValue::Option(None),
+            ],
+        ];
+        let batch = record_batch(Arc::new(arrow_schema(&columns)), &types, &rows).unwrap();
+
+        let ids = batch.column(0).as_primitive::<Decimal256Type>();
+        assert_eq!(ids.values(), &[-1, 2, i128::MIN].map(i256::from_i128));
+        ids.validate_decimal_precision(39).unwrap();
+        let parents = batch.column(1).as_primitive::<UInt32Type>();
+        assert!(parents.is_null(0));
+        assert_eq!(parents.value(1), 7);
+        let tags = batch.column(2).as_list::<i32>();
+        assert_eq!(tags.value_offsets(), &[0, 2, 2, 2]);
+        assert_eq!(tags.values().as_string::<i32>().value(1), "b");
+        let states = batch.column(3).as_struct();
+        assert!(states.is_valid(0) && states.is_null(1) && states.is_null(2));
+        assert_eq!(states.column(0).as_string::<i32>().value(0), "On");
+        let on = states.column(1).as_struct();
+        assert!(on.is_valid(0) && on.is_null(1));
+        assert_eq!(on.column(0).as_primitive::<UInt8Type>().value(0), 3);
+
+        let err = record_batch(
+            Arc::new(arrow_schema(&columns)),
+            &types,
+            &[vec![
+                1u64.into(),
+                DataTypeValue::Option(None),
+                DataTypeValue::Vec(vec![]),
+                DataTypeValue::Option(None),
+            ]],
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Value `1` is not `i128`");
+    }
+
+    #[tokio::test]
+    async fn test_export_space_ipc() {
+        let filename = "tests/data/export_arrow.wt";
+        let info = SpaceInfoPage {
+            id: 1.into(),
+            page_count: 0,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: columns(&[("id", "u64"), ("name", "String"), ("score", "f64")]),
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![],
+            empty_links_list: vec![],
+        };
+        let types = info
+            .columns
+            .iter()
+            .map(|c| c.ty.clone())
+            .collect::<Vec<_>>();
+        let rows = (0..2000u64)
+            .map(|id| {
+                let row = vec![
+                    id.into(),
+                    format!("row {id}").into(),
+                    (id as f64 / 2.0).into(),
+                ];
+                (encode_row(&types, &row).unwrap(), row)
+            })
+            .collect::<Vec<_>>();
+        let mut file = File::create(filename).await.unwrap();
+        let rows = rows.iter().map(|(b, row)| (b.as_slice(), row.clone()));
+        persist_data_rows(&mut file, &info, rows).await.unwrap();
+
+        let mut file = File::open(filename).await.unwrap();
+        let options = ExportOptions {
+            columns: vec!["score".to_string(), "id".to_string()],
+            limit: Some(1500),
+            ..ExportOptions::default()
+        };
+        let mut output = vec![];
+        let report = export_space_ipc(&mut file, &options, &mut output)
+            .await
+            .unwrap();
+        assert_eq!(report.rows, 1500);
+
+        let reader = FileReader::try_new(Cursor::new(output), None).unwrap();
+        let names = reader
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["score", "id"]);
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.len(), report.pages_read);
+        assert!(batches.len() > 1);
+        let ids = batches
+            .iter()
+            .flat_map(|b| b.column(1).as_primitive::<UInt64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (0..1500).collect::<Vec<_>>());
+        let last = batches.last().unwrap();
+        let scores = last.column(0).as_primitive::<Float64Type>();
+        assert_eq!(scores.value(scores.len() - 1), 749.5);
+    }
+}
@@ -0,0 +1,647 @@
+//! Bounds checked decoding of archived rows.
+//!
+//! Unlike [`DataType::from_pointer`], decoder doesn't read anything through
+//! raw pointers. Every read is checked against the buffer, relative pointers
+//! of strings and vectors are checked to point into the buffer and all values
+//! that have invalid bit patterns (`bool`, `char`, UTF-8 of strings, tags of
+//! `Option`'s and enums) are validated, so any bytes can be decoded with any
+//! schema without undefined behaviour.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+use derive_more::{Display, Error};
+
+use crate::persistence::data::layout::{
+    out_of_line_string, ARCHIVED_STRING_SIZE, STRING_INLINE_FILL,
+};
+use crate::persistence::data::util::advance_accum_for_padding;
+use crate::persistence::data::{
+    compare_values, ColumnType, DataType, DataTypeValue, FieldType, RowLayout, VariantType,
+};
+
+/// Error of the archived row decoding. Positions are offsets in the decoded
+/// buffer.
+#[derive(Clone, Debug, Display, Eq, Error, PartialEq)]
+pub enum DecodeError {
+    /// Buffer can not contain row's root struct.
+    #[display("Buffer of {len} bytes is shorter than root struct of {size} bytes")]
+    BufferTooShort { len: usize, size: usize },
+    /// Value's bytes or out of line data are not in the buffer.
+    #[display("{len} bytes at {pos} are out of buffer")]
+    OutOfBounds { pos: usize, len: usize },
+    /// Relative pointer at `pos` points before the buffer's start.
+    #[display("Relative pointer {offset} at {pos} points out of buffer")]
+    InvalidPointer { pos: usize, offset: i32 },
+    /// String's bytes are not valid UTF-8.
+    #[display("String at {pos} is not valid UTF-8")]
+    InvalidUtf8 { pos: usize },
+    /// Archived `bool` or `char` has invalid bit pattern.
+    #[display("Value {value} at {pos} is not valid `{ty}`")]
+    InvalidValue {
+        pos: usize,
+        ty: ColumnType,
+        value: u32,
+    },
+    /// Tag of the `Option` or enum doesn't match any of it's variants.
+    #[display("Tag {tag} at {pos} is not valid for `{ty}`")]
+    InvalidTag { pos: usize, ty: ColumnType, tag: u8 },
+    /// Vector's elements exceed count of the elements that are left to
+    /// decode. Count of all decoded elements is limited by buffer's length,
+    /// as vectors can point to the same bytes.
+    #[display("{len} elements of vector at {pos} exceed {left} elements left to decode")]
+    TooManyElements { pos: usize, len: usize, left: usize },
+}
+
+/// Decodes archived row which columns have given `types`. Row's root struct
+/// should be placed at the end of `buf`.
+pub fn decode_row(buf: &[u8], types: &[ColumnType]) -> Result<Vec<DataTypeValue>, DecodeError> {
+    let mut offsets = Vec::with_capacity(types.len());
+    let mut accum = 0;
+    let mut align = 1;
+    for ty in types {
+        let offset = advance_accum_for_padding(accum, ty.archived_align());
+        offsets.push(offset);
+        accum = offset + ty.archived_size();
+        align = align.max(ty.archived_align());
+    }
+    let size = advance_accum_for_padding(accum, align);
+    let start = root_start(buf, size)?;
+
+    let decoder = Decoder::new(buf);
+    types
+        .iter()
+        .zip(offsets)
+        .map(|(ty, offset)| decoder.value(ty, start + offset))
+        .collect()
+}
+
+/// Decodes only `columns` of the archived row in the given order. Other
+/// columns are skipped by their offsets in the root struct, so their out of
+/// line data is not even read. Row's root struct should be placed at the end
+/// of `buf`.
+///
+/// # Panics
+///
+/// Panics if some of `columns` is not a column of the `layout`.
+pub fn decode_columns(
+    buf: &[u8],
+    layout: &RowLayout,
+    columns: &[usize],
+) -> Result<Vec<DataTypeValue>, DecodeError> {
+    let start = root_start(buf, layout.size())?;
+    let decoder = Decoder::new(buf);
+    columns
+        .iter()
+        .map(|column| {
+            let (offset, ty) = layout_column(layout, *column);
+            decoder.value(ty, start + offset)
+        })
+        .collect()
+}
+
+/// Compares archived value of the row's `column` with `value` without
+/// decoding it, so nothing is allocated. Values are ordered as by
+/// [`compare_values`]. Row's root struct should be placed at the end of
+/// `buf`.
+///
+/// # Panics
+///
+/// Panics if `column` is not a column of the `layout`.
+pub fn compare_column(
+    buf: &[u8],
+    layout: &RowLayout,
+    column: usize,
+    value: &DataTypeValue,
+) -> Result<Option<Ordering>, DecodeError> {
+    let start = root_start(buf, layout.size())?;
+    let (offset, ty) = layout_column(layout, column);
+    Decoder::new(buf).compare(ty, start + offset, value)
+}
+
+/// Returns position of the root struct of `size` bytes placed at the end of
+/// `buf`.
+fn root_start(buf: &[u8], size: usize) -> Result<usize, DecodeError> {
+    buf.len()
+        .checked_sub(size)
+        .ok_or(DecodeError::BufferTooShort {
+            len: buf.len(),
+            size,
+        })
+}
+
+fn layout_column(layout: &RowLayout, column: usize) -> (usize, &ColumnType) {
+    layout
+        .column(column)
+        .unwrap_or_else(|| panic!("column {column} should be in the row layout"))
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    /// Count of the vectors' elements that can be decoded yet.
+    elements_left: Cell<usize>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            elements_left: Cell::new(buf.len()),
+        }
+    }
+
+    fn bytes(&self, pos: usize, len: usize) -> Result<&'a [u8], DecodeError> {
+        pos.checked_add(len)
+            .and_then(|end| self.buf.get(pos..end))
+            .ok_or(DecodeError::OutOfBounds { pos, len })
+    }
+
+    fn array<const N: usize>(&self, pos: usize) -> Result<[u8; N], DecodeError> {
+        let bytes = self.bytes(pos, N)?;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn u32(&self, pos: usize) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.array(pos)?))
+    }
+
+    /// Returns position which relative pointer placed at `pos` points to.
+    fn relative(&self, pos: usize, offset: i32) -> Result<usize, DecodeError> {
+        pos.checked_add_signed(offset as isize)
+            .ok_or(DecodeError::InvalidPointer { pos, offset })
+    }
+
+    fn value(&self, ty: &ColumnType, pos: usize) -> Result<DataTypeValue, DecodeError> {
+        Ok(match ty {
+            ColumnType::String => self.str(pos)?.to_string().into(),
+            ColumnType::I128 => i128::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::I64 => i64::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::I32 => i32::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::I16 => i16::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::I8 => i8::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::U128 => u128::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::U64 => u64::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::U32 => self.u32(pos)?.into(),
+            ColumnType::U16 => u16::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::U8 => u8::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::F64 => f64::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::F32 => f32::from_le_bytes(self.array(pos)?).into(),
+            ColumnType::Bool => match self.array::<1>(pos)?[0] {
+                0 => false.into(),
+                1 => true.into(),
+                value => {
+                    return Err(DecodeError::InvalidValue {
+                        pos,
+                        ty: ty.clone(),
+                        value: value as u32,
+                    })
+                }
+            },
+            ColumnType::Char => {
+                let value = self.u32(pos)?;
+                char::from_u32(value)
+                    .ok_or(DecodeError::InvalidValue {
+                        pos,
+                        ty: ty.clone(),
+                        value,
+                    })?
+                    .into()
+            }
+            ColumnType::Uuid => uuid::Uuid::from_bytes(self.array(pos)?).into(),
+            ColumnType::OrderedF64 => {
+                DataTypeValue::OrderedF64(f64::from_le_bytes(self.array(pos)?).into())
+            }
+            ColumnType::OrderedF32 => {
+                DataTypeValue::OrderedF32(f32::from_le_bytes(self.array(pos)?).into())
+            }
+            ColumnType::PackedNanoid { bytes, .. } => {
+                DataTypeValue::PackedNanoid(self.bytes(pos, *bytes as usize)?.to_vec())
+            }
+            ColumnType::Option(inner) => match self.option(ty, pos)? {
+                Some(pos) => DataTypeValue::Option(Some(Box::new(self.value(inner, pos)?))),
+                None => DataTypeValue::Option(None),
+            },
+            ColumnType::Vec(inner) => {
+                let (start, len) = self.vec(inner, pos)?;
+                let left = self.elements_left.get();
+                let Some(rest) = left.checked_sub(len) else {
+                    return Err(DecodeError::TooManyElements { pos, len, left });
+                };
+                self.elements_left.set(rest);
+                let size = inner.archived_size();
+                DataTypeValue::Vec(
+                    (0..len)
+                        .map(|i| self.value(inner, start + i * size))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            ColumnType::Struct(fields) => DataTypeValue::Struct(self.fields(fields, pos, 0)?),
+            ColumnType::Enum(variants) => {
+                let variant = self.variant(ty, variants, pos)?;
+                DataTypeValue::Enum {
+                    variant: variant.name.clone(),
+                    fields: self.fields(&variant.fields, pos, 1)?,
+                }
+            }
+        })
+    }
+
+    /// Compares archived value of the type `ty` placed at `pos` with `value`.
+    /// Strings and composite values are compared in place, other values are
+    /// decoded, which doesn't allocate.
+    fn compare(
+        &self,
+        ty: &ColumnType,
+        pos: usize,
+        value: &DataTypeValue,
+    ) -> Result<Option<Ordering>, DecodeError> {
+        Ok(match (ty, value) {
+            (ColumnType::String, DataTypeValue::String(v)) => Some(self.str(pos)?.cmp(v)),
+            (ColumnType::PackedNanoid { bytes, .. }, DataTypeValue::PackedNanoid(v)) => {
+                Some(self.bytes(pos, *bytes as usize)?.cmp(v))
+            }
+            (ColumnType::Option(inner), DataTypeValue::Option(v)) => {
+                match (self.option(ty, pos)?, v) {
+                    (Some(pos), Some(v)) => self.compare(inner, pos, v)?,
+                    (archived, v) => Some(archived.is_some().cmp(&v.is_some())),
+                }
+            }
+            (ColumnType::Vec(inner), DataTypeValue::Vec(values)) => {
+                let (start, len) = self.vec(inner, pos)?;
+                let size = inner.archived_size();
+                for (i, v) in values.iter().enumerate().take(len) {
+                    let ordering = self.compare(inner, start + i * size, v)?;
+                    if ordering != Some(Ordering::Equal) {
+                        return Ok(ordering);
+                    }
+                }
+                Some(len.cmp(&values.len()))
+            }
+            (ColumnType::Struct(fields), DataTypeValue::Struct(values)) => {
+                self.compare_fields(fields, pos, 0, values)?
+            }
+            (ColumnType::Enum(variants), DataTypeValue::Enum { variant, fields }) => {
+                let archived = self.variant(ty, variants, pos)?;
+                let Some(other) = variants.iter().find(|v| &v.name == variant) else {
+                    return Ok(None);
+                };
+                match archived.discriminant.cmp(&other.discriminant) {
+                    Ordering::Equal => self.compare_fields(&archived.fields, pos, 1, fields)?,
+                    ordering => Some(ordering),
+                }
+            }
+            (
+                ColumnType::String
+                | ColumnType::PackedNanoid { .. }
+                | ColumnType::Option(_)
+                | ColumnType::Vec(_)
+                | ColumnType::Struct(_)
+                | ColumnType::Enum(_),
+                _,
+            ) => None,
+            _ => compare_values(ty, &self.value(ty, pos)?, value),
+        })
+    }
+
+    fn str(&self, pos: usize) -> Result<&'a str, DecodeError> {
+        let bytes = self.bytes(pos, ARCHIVED_STRING_SIZE)?;
+        let bytes = match out_of_line_string(bytes) {
+            Some((_, len)) => {
+                let offset = i32::from_le_bytes(self.array(pos + 4)?);
+                self.bytes(self.relative(pos, offset)?, len)?
+            }
+            None => {
+                let len = bytes
+                    .iter()
+                    .position(|b| *b == STRING_INLINE_FILL)
+                    .unwrap_or(ARCHIVED_STRING_SIZE);
+                &bytes[..len]
+            }
+        };
+        std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { pos })
+    }
+
+    /// Returns position of the `Option`'s value if it's `Some`.
+    fn option(&self, ty: &ColumnType, pos: usize) -> Result<Option<usize>, DecodeError> {
+        match self.array::<1>(pos)?[0] {
+            0 => Ok(None),
+            1 => Ok(Some(pos + ty.archived_align())),
+            tag => Err(DecodeError::InvalidTag {
+                pos,
+                ty: ty.clone(),
+                tag,
+            }),
+        }
+    }
+
+    /// Returns position and count of the vector's elements, which are checked
+    /// to be in the buffer.
+    fn vec(&self, inner: &ColumnType, pos: usize) -> Result<(usize, usize), DecodeError> {
+        let offset = i32::from_le_bytes(self.array(pos)?);
+        let len = self.u32(pos + 4)? as usize;
+        let start = self.relative(pos, offset)?;
+        let Some(elements_len) = len.checked_mul(inner.archived_size()) else {
+            return Err(DecodeError::OutOfBounds { pos: start, len });
+        };
+        self.bytes(start, elements_len)?;
+        Ok((start, len))
+    }
+
+    fn variant<'t>(
+        &self,
+        ty: &ColumnType,
+        variants: &'t [VariantType],
+        pos: usize,
+    ) -> Result<&'t VariantType, DecodeError> {
+        let tag = self.array::<1>(pos)?[0];
+        variants
+            .iter()
+            .find(|v| v.discriminant == tag)
+            .ok_or(DecodeError::InvalidTag {
+                pos,
+                ty: ty.clone(),
+                tag,
+            })
+    }
+
+    /// Decodes fields of the `#[repr(C)]` struct placed at `pos`, first of
+    /// them follows `offset` bytes.
+    fn fields(
+        &self,
+        fields: &[FieldType],
+        pos: usize,
+        offset: usize,
+    ) -> Result<Vec<(String, DataTypeValue)>, DecodeError> {
+        field_positions(fields, pos, offset)
+            .map(|(field, pos)| Ok((field.name.clone(), self.value(&field.ty, pos)?)))
+            .collect()
+    }
+
+    /// Compares fields of the `#[repr(C)]` struct placed at `pos` with
+    /// `values` in fields order.
+    fn compare_fields(
+        &self,
+        fields: &[FieldType],
+        pos: usize,
+        offset: usize,
+        values: &[(String, DataTypeValue)],
+    ) -> Result<Option<Ordering>, DecodeError> {
+        if values.len() != fields.len() {
+            return Ok(None);
+        }
+        for ((field, pos), (name, value)) in field_positions(fields, pos, offset).zip(values) {
+            if &field.name != name {
+                return Ok(None);
+            }
+            let ordering = self.compare(&field.ty, pos, value)?;
+            if ordering != Some(Ordering::Equal) {
+                return Ok(ordering);
+            }
+        }
+        Ok(Some(Ordering::Equal))
+    }
+}
+
+/// Returns positions of the `#[repr(C)]` struct's fields, first of them
+/// follows `offset` bytes of the struct placed at `pos`.
+fn field_positions(
+    fields: &[FieldType],
+    pos: usize,
+    offset: usize,
+) -> impl Iterator<Item = (&FieldType, usize)> {
+    let mut accum = offset;
+    fields.iter().map(move |field| {
+        let offset = advance_accum_for_padding(accum, field.ty.archived_align());
+        accum = offset + field.ty.archived_size();
+        (field, pos + offset)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    use super::{decode_columns, decode_row, DecodeError};
+    use crate::persistence::data::util::test::row;
+    use crate::persistence::data::{encode_row, ColumnType, DataTypeValue, RowLayout};
+
+    fn schemas() -> Vec<Vec<ColumnType>> {
+        [
+            &["u8", "String", "i64"][..],
+            &["bool", "char", "Option<u32>", "Vec<String>"],
+            &[
+                "Uuid",
+                "PackedNanoid<21, 16>",
+                "OrderedFloat<f32>",
+                "Option<Vec<Option<String>>>",
+            ],
+            &[
+                "{x: i16, label: String}",
+                "enum {A, B(String, u64) = 4, C {v: Vec<u16>}}",
+                "Vec<(u8, String)>",
+            ],
+        ]
+        .iter()
+        .map(|types| {
+            types
+                .iter()
+                .map(|ty| ColumnType::from_str(ty).unwrap())
+                .collect()
+        })
+        .collect()
+    }
+
+    /// Returns strategy of the rows of one of the [`schemas`].
+    fn schema_rows() -> impl Strategy<Value = (Vec<ColumnType>, Vec<DataTypeValue>)> {
+        select(schemas()).prop_flat_map(|types| {
+            let row = row(&types);
+            (Just(types), row)
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn test_decode_random_rows((types, row) in schema_rows()) {
+            let layout = RowLayout::from_types(&types);
+            let bytes = encode_row(&types, &row).unwrap();
+            prop_assert_eq!(decode_row(&bytes, &types).unwrap(), row);
+            prop_assert!(layout.row_lengths(&bytes).any(|l| l == bytes.len()));
+        }
+
+        /// Decoding of arbitrary bytes should never panic.
+        #[test]
+        fn test_decode_arbitrary_bytes(
+            types in select(schemas()),
+            buf in vec(any::<u8>(), 0..160),
+        ) {
+            let _ = decode_row(&buf, &types);
+        }
+
+        /// Decoding of damaged rows should never panic and rows accepted by
+        /// layout should be decoded.
+        #[test]
+        fn test_decode_damaged_rows(
+            (types, row) in schema_rows(),
+            damage in vec((any::<prop::sample::Index>(), any::<u8>()), 1..4),
+            cut in proptest::option::weighted(0.25, any::<prop::sample::Index>()),
+        ) {
+            let layout = RowLayout::from_types(&types);
+            let mut buf = encode_row(&types, &row).unwrap();
+            for (pos, byte) in damage {
+                let pos = pos.index(buf.len());
+                buf[pos] = byte;
+            }
+            if let Some(cut) = cut {
+                buf.drain(..cut.index(buf.len()));
+            }
+            let decoded = decode_row(&buf, &types);
+            if layout.row_lengths(&buf).any(|l| l == buf.len()) {
+                prop_assert!(decoded.is_ok(), "{:?}", decoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_row_errors() {
+        let types = |s: &[&str]| {
+            s.iter()
+                .map(|ty| ColumnType::from_str(ty).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let string = types(&["String"]);
+        let bytes = encode_row(&string, &["long enough string".to_string().into()]).unwrap();
+
+        assert_eq!(
+            decode_row(&bytes[..4], &string),
+            Err(DecodeError::BufferTooShort { len: 4, size: 8 })
+        );
+        // String's bytes are placed before the root struct at 20.
+        assert_eq!(
+            decode_row(&bytes[18..], &string),
+            Err(DecodeError::InvalidPointer {
+                pos: 2,
+                offset: -20
+            })
+        );
+        let mut invalid = bytes.clone();
+        invalid[24..28].copy_from_slice(&(-4i32).to_le_bytes());
+        assert_eq!(
+            decode_row(&invalid, &string),
+            Err(DecodeError::OutOfBounds { pos: 16, len: 18 })
+        );
+        let mut invalid = bytes.clone();
+        invalid[0] = 0xff;
+        assert_eq!(
+            decode_row(&invalid, &string),
+            Err(DecodeError::InvalidUtf8 { pos: 20 })
+        );
+
+        let bool = types(&["bool"]);
+        assert_eq!(
+            decode_row(&[2], &bool),
+            Err(DecodeError::InvalidValue {
+                pos: 0,
+                ty: ColumnType::Bool,
+                value: 2
+            })
+        );
+        let option = types(&["Option<u8>"]);
+        assert_eq!(
+            decode_row(&[3, 0], &option).unwrap_err().to_string(),
+            "Tag 3 at 0 is not valid for `Option<u8>`"
+        );
+        let vec = types(&["Vec<u32>"]);
+        let mut huge = vec![0; 8];
+        huge[4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            decode_row(&huge, &vec),
+            Err(DecodeError::OutOfBounds {
+                pos: 0,
+                len: u32::MAX as usize * 4
+            })
+        );
+    }
+
+    /// Inner vectors of the crafted row point to the same elements, so they
+    /// can't be decoded all without allocating more elements than buffer has.
+    #[test]
+    fn test_decode_nested_vec_budget() {
+        let types = vec![ColumnType::from_str("Vec<Vec<u32>>").unwrap()];
+        let crafted = |outer_len: usize| {
+            let mut buf = (0..64u32).flat_map(u32::to_le_bytes).collect::<Vec<_>>();
+            for i in 0..64 {
+                let pos: i32 = 256 + i * 8;
+                buf.extend_from_slice(&(-pos).to_le_bytes());
+                buf.extend_from_slice(&64u32.to_le_bytes());
+            }
+            buf.extend_from_slice(&(-512i32).to_le_bytes());
+            buf.extend_from_slice(&(outer_len as u32).to_le_bytes());
+            buf
+        };
+
+        let buf = crafted(8);
+        let inner = (0..64u32).map(Into::into).collect::<Vec<_>>();
+        assert_eq!(
+            decode_row(&buf, &types).unwrap(),
+            vec![DataTypeValue::Vec(vec![DataTypeValue::Vec(inner); 8])]
+        );
+
+        // 64 + 11 * 64 elements of 776 are decoded before 12th inner vector.
+        let buf = crafted(64);
+        assert_eq!(
+            decode_row(&buf, &types),
+            Err(DecodeError::TooManyElements {
+                pos: 256 + 11 * 8,
+                len: 64,
+                left: 8
+            })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_decode_columns(
+            (types, row) in schema_rows(),
+            columns in vec(any::<prop::sample::Index>(), 0..4),
+        ) {
+            let layout = RowLayout::from_types(&types);
+            let bytes = encode_row(&types, &row).unwrap();
+            let columns = columns
+                .iter()
+                .map(|c| c.index(types.len()))
+                .collect::<Vec<_>>();
+            let expected = columns.iter().map(|c| row[*c].clone()).collect::<Vec<_>>();
+            prop_assert_eq!(decode_columns(&bytes, &layout, &columns).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_decode_columns_skips_other_columns() {
+        let types = ["String", "u32"]
+            .iter()
+            .map(|ty| ColumnType::from_str(ty).unwrap())
+            .collect::<Vec<_>>();
+        let layout = RowLayout::from_types(&types);
+        let mut bytes = encode_row(
+            &types,
+            &["long enough string".to_string().into(), 7u32.into()],
+        )
+        .unwrap();
+        // String's bytes are not valid UTF-8 anymore.
+        bytes[0] = 0xff;
+        assert_eq!(
+            decode_columns(&bytes, &layout, &[1]).unwrap(),
+            vec![7u32.into()]
+        );
+        assert_eq!(
+            decode_columns(&bytes, &layout, &[1, 0]),
+            Err(DecodeError::InvalidUtf8 { pos: 20 })
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Encoding of rows into the same bytes as `rkyv` produces for a struct with
+//! row's columns as fields.
+//!
+//! Archived row consists of out of line data (strings longer than inline
+//! capacity and vectors' elements) in fields order, zero padding up to root
+//! struct's alignment and root struct. Root struct's fields are placed in
+//! fields order with zero padding to their alignment. Out of line data of the
+//! vector's elements is placed before the elements. Nested structs and enums
+//! are placed inline, their out of line data follows their fields order.
+
+use eyre::bail;
+
+use crate::persistence::data::{ColumnType, DataType, DataTypeValue, FieldType, VariantType};
+
+/// Length of the archived string representation.
+const ARCHIVED_STRING_SIZE: usize = 8;
+/// Unused bytes of the inlined archived string are filled with this value.
+const STRING_INLINE_FILL: u8 = 0xff;
+/// Tag of the out of line archived string's first byte.
+const STRING_OUT_OF_LINE_TAG: u32 = 0x80;
+
+/// Position of the value's out of line data.
+enum Resolver {
+    /// Value has no out of line data.
+    Inline,
+    /// String's bytes or vector's elements are placed at this position.
+    Pos(usize),
+    /// Resolver of the `Option`'s value.
+    Some(Box<Resolver>),
+    /// Resolvers of the struct's or enum variant's fields.
+    Fields(Vec<Resolver>),
+}
+
+fn pad(len: usize, align: usize) -> usize {
+    len.div_ceil(align) * align
+}
+
+fn le_bytes(value: &DataTypeValue) -> Vec<u8> {
+    match value {
+        DataTypeValue::I128(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::I64(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::I32(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::I16(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::I8(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::U128(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::U64(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::U32(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::U16(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::U8(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::F64(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::F32(v) => v.to_le_bytes().to_vec(),
+        DataTypeValue::Bool(v) => vec![*v as u8],
+        DataTypeValue::Char(v) => (*v as u32).to_le_bytes().to_vec(),
+        DataTypeValue::Uuid(v) => v.as_bytes().to_vec(),
+        DataTypeValue::OrderedF64(v) => v.0.to_le_bytes().to_vec(),
+        DataTypeValue::OrderedF32(v) => v.0.to_le_bytes().to_vec(),
+        DataTypeValue::PackedNanoid(v) => v.clone(),
+        DataTypeValue::String(_)
+        | DataTypeValue::Option(_)
+        | DataTypeValue::Vec(_)
+        | DataTypeValue::Struct(_)
+        | DataTypeValue::Enum { .. } => {
+            unreachable!("composite values are encoded separately")
+        }
+    }
+}
+
+/// Writes out of line data of the value.
+fn serialize(ty: &ColumnType, value: &DataTypeValue, bytes: &mut Vec<u8>) -> Resolver {
+    match (ty, value) {
+        (_, DataTypeValue::String(s)) if s.len() > ARCHIVED_STRING_SIZE => {
+            let pos = bytes.len();
+            bytes.extend_from_slice(s.as_bytes());
+            Resolver::Pos(pos)
+        }
+        (ColumnType::Option(inner), DataTypeValue::Option(Some(value))) => {
+            Resolver::Some(Box::new(serialize(inner, value, bytes)))
+        }
+        (ColumnType::Vec(inner), DataTypeValue::Vec(values)) => {
+            let resolvers = values
+                .iter()
+                .map(|v| serialize(inner, v, bytes))
+                .collect::<Vec<_>>();
+            let pos = pad(bytes.len(), inner.archived_align());
+            bytes.resize(pos, 0);
+            for (value, resolver) in values.iter().zip(resolvers) {
+                resolve(inner, value, resolver, bytes)
+            }
+            Resolver::Pos(pos)
+        }
+        (ColumnType::Struct(fields), DataTypeValue::Struct(values)) => {
+            Resolver::Fields(serialize_fields(fields, values, bytes))
+        }
+        (ColumnType::Enum(variants), DataTypeValue::Enum { variant, fields }) => {
+            let variant = find_variant(variants, variant);
+            Resolver::Fields(serialize_fields(&variant.fields, fields, bytes))
+        }
+        _ => Resolver::Inline,
+    }
+}
+
+fn serialize_fields(
+    fields: &[FieldType],
+    values: &[(String, DataTypeValue)],
+    bytes: &mut Vec<u8>,
+) -> Vec<Resolver> {
+    fields
+        .iter()
+        .zip(values)
+        .map(|(field, (_, value))| serialize(&field.ty, value, bytes))
+        .collect()
+}
+
+/// Returns enum's variant named `name`. Value should be checked to be of the
+/// enum's type before.
+fn find_variant<'a>(variants: &'a [VariantType], name: &str) -> &'a VariantType {
+    variants
+        .iter()
+        .find(|v| v.name == name)
+        .expect("value should be of the enum's type")
+}
+
+/// Writes archived value at the end of `bytes`, which should be aligned for
+/// the value's type.
+fn resolve(ty: &ColumnType, value: &DataTypeValue, resolver: Resolver, bytes: &mut Vec<u8>) {
+    let field_start = bytes.len();
+    match (ty, value, resolver) {
+        (_, DataTypeValue::String(s), Resolver::Pos(string_start)) => {
+            let len = s.len() as u32;
+            let len = (len & 0b0011_1111) | STRING_OUT_OF_LINE_TAG | ((len >> 6) << 8);
+            let offset = string_start as i32 - field_start as i32;
+            bytes.extend_from_slice(&len.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        (_, DataTypeValue::String(s), _) => {
+            bytes.extend_from_slice(s.as_bytes());
+            bytes.resize(field_start + ARCHIVED_STRING_SIZE, STRING_INLINE_FILL);
+        }
+        (ColumnType::Option(inner), DataTypeValue::Option(Some(value)), Resolver::Some(r)) => {
+            bytes.push(1);
+            bytes.resize(field_start + inner.archived_align(), 0);
+            resolve(inner, value, *r, bytes)
+        }
+        (_, DataTypeValue::Option(_), _) => bytes.resize(field_start + ty.archived_size(), 0),
+        (_, DataTypeValue::Vec(values), Resolver::Pos(elements_start)) => {
+            let offset = elements_start as i32 - field_start as i32;
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        }
+        (ColumnType::Struct(fields), DataTypeValue::Struct(values), Resolver::Fields(r)) => {
+            resolve_fields(fields, values, r, bytes);
+            bytes.resize(field_start + ty.archived_size(), 0);
+        }
+        (
+            ColumnType::Enum(variants),
+            DataTypeValue::Enum { variant, fields },
+            Resolver::Fields(r),
+        ) => {
+            let variant = find_variant(variants, variant);
+            bytes.push(variant.discriminant);
+            resolve_fields(&variant.fields, fields, r, bytes);
+            bytes.resize(field_start + ty.archived_size(), 0);
+        }
+        (_, value, _) => bytes.extend_from_slice(&le_bytes(value)),
+    }
+}
+
+/// Writes archived fields of the `#[repr(C)]` struct with zero padding to
+/// their alignment.
+fn resolve_fields(
+    fields: &[FieldType],
+    values: &[(String, DataTypeValue)],
+    resolvers: Vec<Resolver>,
+    bytes: &mut Vec<u8>,
+) {
+    for ((field, (_, value)), resolver) in fields.iter().zip(values).zip(resolvers) {
+        let field_start = pad(bytes.len(), field.ty.archived_align());
+        bytes.resize(field_start, 0);
+        resolve(&field.ty, value, resolver, bytes);
+    }
+}
+
+/// Returns archived bytes of the row which columns have given `types`. Row's
+/// length is aligned to the root struct's alignment, so rows can be placed
+/// one after another. Returns error if some value doesn't match it's column's
+/// type.
+pub fn encode_row(types: &[ColumnType], row: &[DataTypeValue]) -> eyre::Result<Vec<u8>> {
+    if types.len() != row.len() {
+        bail!("Row has {} values instead of {}", row.len(), types.len())
+    }
+    if let Some((ty, value)) = types.iter().zip(row).find(|(ty, v)| !v.is_of_type(ty)) {
+        bail!("Value `{}` is not `{}`", value, ty)
+    }
+
+    let mut bytes = vec![];
+    let resolvers = types
+        .iter()
+        .zip(row)
+        .map(|(ty, value)| serialize(ty, value, &mut bytes))
+        .collect::<Vec<_>>();
+
+    let struct_align = types.iter().map(|t| t.archived_align()).max().unwrap_or(1);
+    let struct_start = pad(bytes.len(), struct_align);
+    bytes.resize(struct_start, 0);
+    for ((ty, value), resolver) in types.iter().zip(row).zip(resolvers) {
+        let field_start = pad(bytes.len(), ty.archived_align());
+        bytes.resize(field_start, 0);
+        resolve(ty, value, resolver, &mut bytes);
+    }
+    let end = struct_start + pad(bytes.len() - struct_start, struct_align);
+    bytes.resize(end, 0);
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use rkyv::{Archive, Deserialize, Serialize};
+    use uuid::Uuid;
+
+    use super::encode_row;
+    use crate::persistence::data::rkyv_data::parse_archived_columns;
+    use crate::persistence::data::{ColumnType, DataTypeValue};
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub string1: String,
+        pub int1: u32,
+        pub string2: String,
+        pub int2: i128,
+        pub float1: f64,
+        pub int3: u8,
+    }
+
+    #[test]
+    fn test_encode_row() {
+        for i in 0..20 {
+            let row = Row {
+                string1: "a".repeat(i),
+                int1: i as u32,
+                string2: "b".repeat(20 - i),
+                int2: -(i as i128),
+                float1: i as f64 / 3.0,
+                int3: i as u8,
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            let encoded = encode_row(
+                &[
+                    ColumnType::String,
+                    ColumnType::U32,
+                    ColumnType::String,
+                    ColumnType::I128,
+                    ColumnType::F64,
+                    ColumnType::U8,
+                ],
+                &[
+                    DataTypeValue::String(row.string1),
+                    DataTypeValue::U32(row.int1),
+                    DataTypeValue::String(row.string2),
+                    DataTypeValue::I128(row.int2),
+                    DataTypeValue::F64(row.float1),
+                    DataTypeValue::U8(row.int3),
+                ],
+            )
+            .unwrap();
+            assert_eq!(encoded, bytes.as_slice());
+        }
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct CompositeRow {
+        pub flag: bool,
+        pub id: Uuid,
+        pub parent: Option<u64>,
+        pub tags: Vec<String>,
+        pub history: Option<Vec<Option<String>>>,
+        pub matrix: Vec<Vec<u16>>,
+        pub letter: char,
+    }
+
+    #[test]
+    fn test_encode_composite_row() {
+        let types = [
+            "bool",
+            "Uuid",
+            "Option<u64>",
+            "Vec<String>",
+            "Option<Vec<Option<String>>>",
+            "Vec<Vec<u16>>",
+            "char",
+        ]
+        .map(|ty| ColumnType::from_str(ty).unwrap());
+        for i in 0..12 {
+            let row = CompositeRow {
+                flag: i % 2 == 0,
+                id: Uuid::from_u128(i as u128),
+                parent: (i % 3 != 0).then_some(i as u64),
+                tags: (0..i % 4).map(|j| "t".repeat(j * 5)).collect(),
+                history: (i % 3 != 1)
+                    .then(|| (0..i % 3).map(|j| (j > 0).then(|| "h".repeat(i))).collect()),
+                matrix: (0..i % 3).map(|j| (0..j as u16).collect()).collect(),
+                letter: char::from(b'a' + i as u8),
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            let values = parse_archived_columns(&bytes, &types);
+            let encoded = encode_row(&types, &values).unwrap();
+            assert_eq!(encoded, bytes.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_encode_row_errors() {
+        let err = encode_row(&[ColumnType::U8], &[]).unwrap_err();
+        assert_eq!(err.to_string(), "Row has 0 values instead of 1");
+        let err = encode_row(
+            &[ColumnType::Option(Box::new(ColumnType::U8))],
+            &[DataTypeValue::Option(Some(Box::new(DataTypeValue::U16(1))))],
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Value `1` is not `Option<u8>`");
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    enum Event {
+        Created,
+        Renamed { from: String, to: String },
+        Moved(u8, (i64, i64)),
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct NestedRow {
+        pub id: u16,
+        pub event: Event,
+        pub history: Vec<Event>,
+        pub last: Option<Event>,
+    }
+
+    fn nested_row(i: usize) -> NestedRow {
+        let event = |j: usize| match (i + j) % 3 {
+            0 => Event::Created,
+            1 => Event::Renamed {
+                from: "f".repeat(i + j),
+                to: "t".repeat(j * 3),
+            },
+            _ => Event::Moved(j as u8, (i as i64, -(j as i64))),
+        };
+        NestedRow {
+            id: i as u16,
+            event: event(0),
+            history: (0..i % 4).map(event).collect(),
+            last: i.is_multiple_of(2).then(|| event(1)),
+        }
+    }
+
+    const EVENT_TYPE: &str =
+        "enum {Created, Renamed {from: String, to: String}, Moved(u8, (i64, i64))}";
+
+    #[test]
+    fn test_encode_nested_row() {
+        let types = [
+            "u16".to_string(),
+            EVENT_TYPE.to_string(),
+            format!("Vec<{EVENT_TYPE}>"),
+            format!("Option<{EVENT_TYPE}>"),
+        ]
+        .map(|ty| ColumnType::from_str(&ty).unwrap());
+        for i in 0..12 {
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&nested_row(i)).unwrap();
+            let values = parse_archived_columns(&bytes, &types);
+            let encoded = encode_row(&types, &values).unwrap();
+            assert_eq!(encoded, bytes.as_slice());
+        }
+    }
+}
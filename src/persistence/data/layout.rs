@@ -0,0 +1,549 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::page::ColumnSchema;
+use crate::persistence::data::util::advance_accum_for_padding;
+use crate::persistence::data::{ColumnType, DataType, FieldType};
+
+/// Archived string is stored out of line if it's first byte has `10xx_xxxx`
+/// form, otherwise it's bytes are inlined.
+const STRING_TAG_MASK: u8 = 0xc0;
+const STRING_OUT_OF_LINE_TAG: u8 = 0x80;
+pub(super) const ARCHIVED_STRING_SIZE: usize = 8;
+/// Unused bytes of the inlined archived string are filled with this value.
+pub(super) const STRING_INLINE_FILL: u8 = 0xff;
+
+/// Layout of the row's archived root struct, which is computed from the
+/// row's columns.
+///
+/// Rows are stored in data pages one after another without any length
+/// prefix. Archived row consists of out of line data (strings longer than
+/// inline capacity) followed by padding and root struct, so row's length can
+/// be found by searching for root struct position whose strings and vectors
+/// point exactly at the out of line data before it. `rkyv` fills padding with
+/// zeroes, so padding is checked too.
+#[derive(Clone, Debug)]
+pub struct RowLayout {
+    columns: Vec<ColumnLayout>,
+    padding: Vec<Range<usize>>,
+    size: usize,
+    align: usize,
+}
+
+#[derive(Clone, Debug)]
+struct ColumnLayout {
+    offset: usize,
+    ty: ColumnType,
+}
+
+impl RowLayout {
+    /// Creates layout of the row described by legacy `row_schema`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some column's type is not supported, [`RowLayout::try_new`]
+    /// returns error instead.
+    pub fn new<S1: AsRef<str>, S2: AsRef<str>>(columns: &[(S1, S2)]) -> Self {
+        Self::try_new(columns).expect("data type should be supported")
+    }
+
+    /// Creates layout of the row described by legacy `row_schema`. Returns
+    /// error if some column's type is not supported.
+    pub fn try_new<S1: AsRef<str>, S2: AsRef<str>>(columns: &[(S1, S2)]) -> eyre::Result<Self> {
+        let types = columns
+            .iter()
+            .map(|(_, ty)| ColumnType::from_str(ty.as_ref()))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        Ok(Self::from_types(&types))
+    }
+
+    /// Creates layout of the row described by [`SpaceInfoPage::columns`].
+    /// Nullable columns are laid out as `Option` of the column's type.
+    ///
+    /// [`SpaceInfoPage::columns`]: crate::SpaceInfoPage::columns
+    pub fn from_columns(columns: &[ColumnSchema]) -> eyre::Result<Self> {
+        let types = columns
+            .iter()
+            .map(ColumnSchema::row_type)
+            .collect::<Vec<_>>();
+        Ok(Self::from_types(&types))
+    }
+
+    pub(crate) fn from_types(types: &[ColumnType]) -> Self {
+        let mut accum = 0;
+        let mut align = 1;
+        let mut layout = vec![];
+        let mut padding = vec![];
+        for ty in types {
+            let offset = advance_accum_for_padding(accum, ty.archived_align());
+            if offset != accum {
+                padding.push(accum..offset)
+            }
+            align = align.max(ty.archived_align());
+            layout.push(ColumnLayout {
+                offset,
+                ty: ty.clone(),
+            });
+            accum = offset + ty.archived_size();
+        }
+        let size = advance_accum_for_padding(accum, align);
+        if size != accum {
+            padding.push(accum..size)
+        }
+
+        Self {
+            columns: layout,
+            padding,
+            size,
+            align,
+        }
+    }
+
+    /// Returns size of the row's archived root struct.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns alignment of the row's archived root struct.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns offset in the root struct and type of the column at `column`
+    /// position.
+    pub(crate) fn column(&self, column: usize) -> Option<(usize, &ColumnType)> {
+        self.columns.get(column).map(|c| (c.offset, &c.ty))
+    }
+
+    /// Returns length of the archived row which starts at the beginning of
+    /// `buf`. Returns `None` if no valid row can be found in `buf`.
+    ///
+    /// It's the shortest of [`RowLayout::row_lengths`], which is not always
+    /// the real row's length, so it should be used only if row's [`Link`] is
+    /// not known.
+    ///
+    /// [`Link`]: crate::Link
+    pub fn row_length(&self, buf: &[u8]) -> Option<usize> {
+        self.row_lengths(buf).next()
+    }
+
+    /// Returns all possible lengths of the archived row which starts at the
+    /// beginning of `buf` in ascending order. Out of line strings' bytes can
+    /// look like a valid root struct, so more than one length can be found.
+    ///
+    /// Every root struct position in `buf` is checked, so it takes time linear
+    /// of `buf`'s length. Whole rows' sequence should be checked to choose the
+    /// right length, like [`data_page_row_links`] does.
+    ///
+    /// [`data_page_row_links`]: crate::persistence::data::data_page_row_links
+    pub fn row_lengths<'a>(&'a self, buf: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        (0..=buf.len())
+            .map_while(move |out_of_line_len| {
+                let struct_start = advance_accum_for_padding(out_of_line_len, self.align);
+                (struct_start + self.size <= buf.len()).then_some((out_of_line_len, struct_start))
+            })
+            .filter(move |(out_of_line_len, struct_start)| {
+                self.is_valid_struct_position(buf, *struct_start, *out_of_line_len)
+            })
+            .map(|(_, struct_start)| struct_start + self.size)
+    }
+
+    /// Checks if out of line data of the struct placed at `struct_start`
+    /// fills `0..out_of_line_len` range of the `buf` exactly in fields order
+    /// and all padding bytes are zeroed.
+    fn is_valid_struct_position(
+        &self,
+        buf: &[u8],
+        struct_start: usize,
+        out_of_line_len: usize,
+    ) -> bool {
+        if buf[out_of_line_len..struct_start].iter().any(|b| *b != 0) {
+            return false;
+        }
+        for range in &self.padding {
+            let range = struct_start + range.start..struct_start + range.end;
+            if buf[range].iter().any(|b| *b != 0) {
+                return false;
+            }
+        }
+
+        let mut cursor = 0usize;
+        for column in &self.columns {
+            let pos = struct_start + column.offset;
+            if !is_valid_value(&column.ty, buf, pos, &mut cursor, out_of_line_len) {
+                return false;
+            }
+        }
+        cursor == out_of_line_len
+    }
+}
+
+/// Checks archived value of the type `ty` placed at `pos`. It's out of line
+/// data should start exactly at `cursor` and end before `end`, `cursor` is
+/// moved past it.
+fn is_valid_value(ty: &ColumnType, buf: &[u8], pos: usize, cursor: &mut usize, end: usize) -> bool {
+    match ty {
+        ColumnType::String => {
+            let bytes = &buf[pos..pos + ARCHIVED_STRING_SIZE];
+            let Some((relative, len)) = out_of_line_string(bytes) else {
+                return is_valid_inline_string(bytes);
+            };
+            if pos as isize + relative != *cursor as isize
+                || len <= ARCHIVED_STRING_SIZE
+                || *cursor + len > end
+                || std::str::from_utf8(&buf[*cursor..*cursor + len]).is_err()
+            {
+                return false;
+            }
+            *cursor += len;
+            true
+        }
+        ColumnType::Bool => buf[pos] <= 1,
+        ColumnType::Char => {
+            let c = u32::from_le_bytes(buf[pos..pos + 4].try_into().expect("slice has 4 bytes"));
+            char::from_u32(c).is_some()
+        }
+        ColumnType::Option(inner) => {
+            let value_pos = pos + inner.archived_align();
+            match buf[pos] {
+                0 => buf[pos + 1..pos + ty.archived_size()]
+                    .iter()
+                    .all(|b| *b == 0),
+                1 => {
+                    buf[pos + 1..value_pos].iter().all(|b| *b == 0)
+                        && is_valid_value(inner, buf, value_pos, cursor, end)
+                }
+                _ => false,
+            }
+        }
+        ColumnType::Vec(inner) => {
+            let offset =
+                i32::from_le_bytes(buf[pos..pos + 4].try_into().expect("slice has 4 bytes"));
+            let len =
+                u32::from_le_bytes(buf[pos + 4..pos + 8].try_into().expect("slice has 4 bytes"));
+            let size = inner.archived_size();
+            let Some(start) = pos.checked_add_signed(offset as isize) else {
+                return false;
+            };
+            let Some(elements_end) = (len as usize)
+                .checked_mul(size)
+                .and_then(|l| l.checked_add(start))
+            else {
+                return false;
+            };
+            // Zero sized elements take no bytes, so their count is limited by
+            // buffer's length as in decoder. Elements should be aligned, so
+            // their fields are not read past them.
+            if elements_end > end
+                || (size == 0 && len as usize > buf.len())
+                || advance_accum_for_padding(start, inner.archived_align()) != start
+            {
+                return false;
+            }
+            // Out of line data of the elements is placed before them. Zero
+            // sized elements have no bytes, so they are all the same.
+            let checked = if size == 0 { len.min(1) } else { len };
+            for i in 0..checked as usize {
+                if !is_valid_value(inner, buf, start + i * size, cursor, start) {
+                    return false;
+                }
+            }
+            let aligned = advance_accum_for_padding(*cursor, inner.archived_align());
+            if aligned != start || buf[*cursor..aligned].iter().any(|b| *b != 0) {
+                return false;
+            }
+            *cursor = elements_end;
+            true
+        }
+        ColumnType::Struct(fields) => {
+            is_valid_fields(fields, buf, pos, pos + ty.archived_size(), cursor, end)
+        }
+        ColumnType::Enum(variants) => {
+            let Some(variant) = variants.iter().find(|v| v.discriminant == buf[pos]) else {
+                return false;
+            };
+            is_valid_fields(
+                &variant.fields,
+                buf,
+                pos + 1,
+                pos + ty.archived_size(),
+                cursor,
+                end,
+            )
+        }
+        _ => true,
+    }
+}
+
+/// Checks fields of the `#[repr(C)]` struct placed from `pos` to
+/// `struct_end`. Padding between fields and after them is checked to be
+/// zeroed, as shorter enum variants are zero padded too.
+fn is_valid_fields(
+    fields: &[FieldType],
+    buf: &[u8],
+    pos: usize,
+    struct_end: usize,
+    cursor: &mut usize,
+    end: usize,
+) -> bool {
+    let mut accum = pos;
+    for field in fields {
+        let field_pos = advance_accum_for_padding(accum, field.ty.archived_align());
+        if buf[accum..field_pos].iter().any(|b| *b != 0)
+            || !is_valid_value(&field.ty, buf, field_pos, cursor, end)
+        {
+            return false;
+        }
+        accum = field_pos + field.ty.archived_size();
+    }
+    buf[accum..struct_end].iter().all(|b| *b == 0)
+}
+
+/// Checks that inlined archived string is valid UTF-8 followed by fill bytes.
+fn is_valid_inline_string(bytes: &[u8]) -> bool {
+    let len = bytes
+        .iter()
+        .position(|b| *b == STRING_INLINE_FILL)
+        .unwrap_or(ARCHIVED_STRING_SIZE);
+    bytes[len..].iter().all(|b| *b == STRING_INLINE_FILL)
+        && std::str::from_utf8(&bytes[..len]).is_ok()
+}
+
+/// Returns `(relative offset, length)` of the archived string's out of line
+/// bytes or `None` if string is inlined.
+pub(super) fn out_of_line_string(bytes: &[u8]) -> Option<(isize, usize)> {
+    if bytes[0] & STRING_TAG_MASK != STRING_OUT_OF_LINE_TAG {
+        return None;
+    }
+    let len = u32::from_le_bytes(bytes[0..4].try_into().expect("slice has 4 bytes"));
+    let len = (len & 0b0011_1111) | ((len & !0xff) >> 2);
+    let offset = i32::from_le_bytes(bytes[4..8].try_into().expect("slice has 4 bytes"));
+    Some((offset as isize, len as usize))
+}
+
+#[cfg(test)]
+mod test {
+    use super::RowLayout;
+    use crate::page::ColumnSchema;
+    use crate::persistence::data::ColumnType;
+    use rkyv::{Archive, Deserialize, Serialize};
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub string1: String,
+        pub int1: u32,
+        pub string2: String,
+        pub int2: u8,
+        pub float1: f64,
+        pub int3: u8,
+    }
+
+    #[test]
+    fn test_row_layout_size() {
+        let layout = RowLayout::new(&[
+            ("string1", "String"),
+            ("int1", "u32"),
+            ("string2", "String"),
+            ("int2", "u8"),
+            ("float1", "f64"),
+            ("int3", "u8"),
+        ]);
+        assert_eq!(layout.size(), size_of::<ArchivedRow>());
+        assert_eq!(layout.align(), align_of::<ArchivedRow>());
+    }
+
+    #[test]
+    fn test_row_length() {
+        let layout = RowLayout::new(&[
+            ("string1", "String"),
+            ("int1", "u32"),
+            ("string2", "String"),
+            ("int2", "u8"),
+            ("float1", "f64"),
+            ("int3", "u8"),
+        ]);
+        let mut buf = vec![];
+        let mut lengths = vec![];
+        for i in 0..20 {
+            let row = Row {
+                string1: "a".repeat(i),
+                int1: i as u32,
+                string2: "b".repeat(20 - i),
+                int2: i as u8,
+                float1: i as f64,
+                int3: 0,
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            lengths.push(bytes.len());
+            buf.extend_from_slice(bytes.as_slice());
+        }
+
+        let mut offset = 0;
+        for len in lengths {
+            assert_eq!(layout.row_length(&buf[offset..]), Some(len));
+            offset += len;
+        }
+        assert_eq!(layout.row_length(&buf[offset..]), None);
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct CompositeRow {
+        pub flag: bool,
+        pub parent: Option<u32>,
+        pub tags: Vec<String>,
+        pub history: Option<Vec<Option<String>>>,
+        pub letter: char,
+        pub matrix: Vec<Vec<u16>>,
+    }
+
+    #[test]
+    fn test_row_length_composite_types() {
+        let layout = RowLayout::new(&[
+            ("flag", "bool"),
+            ("parent", "Option<u32>"),
+            ("tags", "Vec<String>"),
+            ("history", "Option<Vec<Option<String>>>"),
+            ("letter", "char"),
+            ("matrix", "Vec<Vec<u16>>"),
+        ]);
+        let mut buf = vec![];
+        let mut lengths = vec![];
+        for i in 0..20 {
+            let row = CompositeRow {
+                flag: i % 2 == 0,
+                parent: (i % 3 != 0).then_some(i as u32),
+                tags: (0..i % 4).map(|j| "t".repeat(j * 4 + i)).collect(),
+                history: (i % 3 != 1)
+                    .then(|| (0..i % 3).map(|j| (j > 0).then(|| "h".repeat(i))).collect()),
+                letter: 'ж',
+                matrix: (0..i % 3).map(|j| (0..j as u16).collect()).collect(),
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            lengths.push(bytes.len());
+            buf.extend_from_slice(bytes.as_slice());
+        }
+
+        let mut offset = 0;
+        for len in lengths {
+            assert_eq!(layout.row_length(&buf[offset..]), Some(len));
+            offset += len;
+        }
+        assert_eq!(layout.row_length(&buf[offset..]), None);
+    }
+
+    #[test]
+    fn test_row_layout_from_columns() {
+        let columns = ColumnSchema::from_row_schema(&[
+            ("string1", "String"),
+            ("int1", "u32"),
+            ("string2", "String"),
+            ("int2", "u8"),
+            ("float1", "f64"),
+            ("int3", "u8"),
+        ])
+        .unwrap();
+        let layout = RowLayout::from_columns(&columns).unwrap();
+        assert_eq!(layout.size(), size_of::<ArchivedRow>());
+        assert_eq!(layout.align(), align_of::<ArchivedRow>());
+
+        // Nullable column is laid out as `Option`.
+        let mut nullable = ColumnSchema::new(0, "int1", ColumnType::U32);
+        nullable.nullable = true;
+        let layout = RowLayout::from_columns(&[nullable]).unwrap();
+        assert_eq!(layout.size(), size_of::<rkyv::Archived<Option<u32>>>());
+        assert_eq!(layout.align(), align_of::<rkyv::Archived<Option<u32>>>());
+    }
+
+    #[test]
+    fn test_row_layout_unknown_type() {
+        let err = RowLayout::try_new(&[("int1", "u32"), ("map1", "HashMap<u8, u8>")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Data type `HashMap<u8, u8>` is not supported"
+        );
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    enum Event {
+        Created,
+        Renamed { from: String, to: String },
+        Moved(u8, (i64, i64)),
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct NestedRow {
+        pub id: u16,
+        pub event: Event,
+        pub history: Vec<Event>,
+        pub point: (u8, String),
+    }
+
+    #[test]
+    fn test_row_length_nested_types() {
+        let event_type =
+            "enum {Created, Renamed {from: String, to: String}, Moved(u8, (i64, i64))}";
+        let history_type = format!("Vec<{event_type}>");
+        let layout = RowLayout::new(&[
+            ("id", "u16"),
+            ("event", event_type),
+            ("history", history_type.as_str()),
+            ("point", "(u8, String)"),
+        ]);
+        let mut buf = vec![];
+        let mut lengths = vec![];
+        for i in 0..20 {
+            let event = |j: usize| match (i + j) % 3 {
+                0 => Event::Created,
+                1 => Event::Renamed {
+                    from: "f".repeat(i + j),
+                    to: "t".repeat(j * 5),
+                },
+                _ => Event::Moved(j as u8, (i as i64, -(j as i64))),
+            };
+            let row = NestedRow {
+                id: i as u16,
+                event: event(0),
+                history: (0..i % 4).map(event).collect(),
+                point: (i as u8, "p".repeat(i)),
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            lengths.push(bytes.len());
+            buf.extend_from_slice(bytes.as_slice());
+        }
+
+        let mut offset = 0;
+        for len in lengths {
+            assert_eq!(layout.row_length(&buf[offset..]), Some(len));
+            offset += len;
+        }
+        assert_eq!(layout.row_length(&buf[offset..]), None);
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Empty {}
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct EmptyElementsRow {
+        pub id: u32,
+        pub empty: Vec<Empty>,
+    }
+
+    #[test]
+    fn test_row_length_zero_sized_elements() {
+        let layout = RowLayout::new(&[("id", "u32"), ("empty", "Vec<{}>")]);
+        let row = EmptyElementsRow {
+            id: 1,
+            empty: vec![Empty {}, Empty {}, Empty {}],
+        };
+        let mut bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row)
+            .unwrap()
+            .to_vec();
+        assert_eq!(layout.row_length(&bytes), Some(bytes.len()));
+
+        // Huge count of zero sized elements should be rejected, not checked
+        // one by one.
+        let len_pos = bytes.len() - 4;
+        bytes[len_pos..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(layout.row_length(&bytes), None);
+    }
+}
@@ -1,13 +1,45 @@
+mod decode;
+mod encode;
+mod layout;
+mod predicate;
 pub mod rkyv_data;
+mod rows;
 mod types;
-mod util;
+mod upgrade;
+pub(crate) mod util;
 
-pub use types::DataTypeValue;
+pub use decode::{compare_column, decode_columns, decode_row, DecodeError};
+pub use encode::encode_row;
+pub use layout::RowLayout;
+pub use predicate::{compare_values, CompareOp, Predicate};
+pub use rows::{
+    data_page_row_links, data_page_row_links_by_index, parse_data_page_ids, parse_data_page_rows,
+    read_data_rows,
+};
+pub(crate) use rows::{persist_data_rows, split_rows};
+pub use types::{ColumnType, DataTypeValue, FieldType, VariantType};
+pub use upgrade::{RowReader, RowUpgrade};
+
+use util::{advance_accum_for_padding, advance_pointer_for_padding};
 
 pub trait DataType {
-    fn advance_accum(&self, accum: &mut usize);
+    /// Returns size of the value's archived representation.
+    fn archived_size(&self) -> usize;
+    /// Returns alignment of the value's archived representation.
+    fn archived_align(&self) -> usize;
+    fn advance_accum(&self, accum: &mut usize) {
+        *accum = advance_accum_for_padding(*accum, self.archived_align());
+        *accum += self.archived_size();
+    }
+    /// Reads archived value by raw pointers without any checks, so `pointer`
+    /// should point to valid archived value. [`decode_row`] should be used
+    /// for bytes that are not known to be valid.
     #[allow(clippy::wrong_self_convention)]
     fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue;
-    fn advance_pointer_for_padding(&self, pointer: &mut *const u8, start_pointer: *const u8);
-    fn advance_pointer(&self, pointer: &mut *const u8);
+    fn advance_pointer_for_padding(&self, pointer: &mut *const u8, start_pointer: *const u8) {
+        *pointer = advance_pointer_for_padding(*pointer, start_pointer, self.archived_align());
+    }
+    fn advance_pointer(&self, pointer: &mut *const u8) {
+        *pointer = unsafe { pointer.add(self.archived_size()) };
+    }
 }
@@ -0,0 +1,460 @@
+//! Filtering of archived rows by their columns' values.
+//!
+//! [`Predicate`] is evaluated on the archived row's bytes, so compared
+//! columns are not decoded and nothing is allocated for rows that don't
+//! match. Values are ordered as by derived `PartialOrd` of the row's compiled
+//! type: numbers by their values, strings and packed nanoids by their bytes,
+//! `None` is less than any `Some`, vectors and structs are compared
+//! lexicographically and enums by their discriminants and then by fields.
+
+use std::cmp::Ordering;
+use std::convert::Infallible;
+
+use derive_more::Display;
+use eyre::bail;
+
+use crate::persistence::data::{
+    compare_column, ColumnType, DataTypeValue, DecodeError, FieldType, RowLayout,
+};
+
+/// Operator of the column's value comparison.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum CompareOp {
+    #[display("=")]
+    Eq,
+    #[display("!=")]
+    Ne,
+    #[display("<")]
+    Lt,
+    #[display("<=")]
+    Le,
+    #[display(">")]
+    Gt,
+    #[display(">=")]
+    Ge,
+}
+
+impl CompareOp {
+    /// Returns `true` if column's value compared with predicate's value as
+    /// `ordering` satisfies the operator. Values that are not comparable,
+    /// like `NaN`'s, satisfy only [`CompareOp::Ne`].
+    pub fn matches(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Self::Eq => ordering == Some(Ordering::Equal),
+            Self::Ne => ordering != Some(Ordering::Equal),
+            Self::Lt => ordering == Some(Ordering::Less),
+            Self::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Self::Gt => ordering == Some(Ordering::Greater),
+            Self::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Condition on the row's columns, which are referenced by their positions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    /// Compares value of the `column` with `value` as `column op value`.
+    Compare {
+        column: usize,
+        op: CompareOp,
+        value: DataTypeValue,
+    },
+    /// Matches if all predicates match, so empty `And` matches any row.
+    And(Vec<Predicate>),
+    /// Matches if any of predicates matches, so empty `Or` matches no rows.
+    Or(Vec<Predicate>),
+}
+
+impl Predicate {
+    pub fn compare<V: Into<DataTypeValue>>(column: usize, op: CompareOp, value: V) -> Self {
+        Self::Compare {
+            column,
+            op,
+            value: value.into(),
+        }
+    }
+
+    pub fn and(self, other: Predicate) -> Self {
+        match self {
+            Self::And(mut predicates) => {
+                predicates.push(other);
+                Self::And(predicates)
+            }
+            predicate => Self::And(vec![predicate, other]),
+        }
+    }
+
+    pub fn or(self, other: Predicate) -> Self {
+        match self {
+            Self::Or(mut predicates) => {
+                predicates.push(other);
+                Self::Or(predicates)
+            }
+            predicate => Self::Or(vec![predicate, other]),
+        }
+    }
+
+    /// Returns error if some compared column is not one of `types` or it's
+    /// value is not of the column's type.
+    pub fn validate(&self, types: &[ColumnType]) -> eyre::Result<()> {
+        match self {
+            Self::Compare { column, value, .. } => {
+                let Some(ty) = types.get(*column) else {
+                    bail!("Row has no column {}", column)
+                };
+                if !value.is_of_type(ty) {
+                    bail!("Value `{}` of the column {} is not `{}`", value, column, ty)
+                }
+                Ok(())
+            }
+            Self::And(predicates) | Self::Or(predicates) => predicates
+                .iter()
+                .try_for_each(|predicate| predicate.validate(types)),
+        }
+    }
+
+    /// Evaluates predicate on the archived row which root struct is placed
+    /// at the end of `buf`. Columns which are not compared are not read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some compared column is not a column of the `layout`,
+    /// [`Predicate::validate`] checks it.
+    pub fn matches(&self, buf: &[u8], layout: &RowLayout) -> Result<bool, DecodeError> {
+        self.evaluate(&mut |column, value| compare_column(buf, layout, column, value))
+    }
+
+    /// Evaluates predicate on the decoded row which columns have given
+    /// `types`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some compared column is not in the row.
+    pub fn matches_row(&self, row: &[DataTypeValue], types: &[ColumnType]) -> bool {
+        let result = self.evaluate(&mut |column, value| {
+            Ok::<_, Infallible>(compare_values(&types[column], &row[column], value))
+        });
+        match result {
+            Ok(matches) => matches,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Evaluates predicate with `compare`, which compares value of the column
+    /// with given value. `And` and `Or` stop on the first predicate which
+    /// decides their result.
+    pub(crate) fn evaluate<E>(
+        &self,
+        compare: &mut impl FnMut(usize, &DataTypeValue) -> Result<Option<Ordering>, E>,
+    ) -> Result<bool, E> {
+        match self {
+            Self::Compare { column, op, value } => Ok(op.matches(compare(*column, value)?)),
+            Self::And(predicates) => {
+                for predicate in predicates {
+                    if !predicate.evaluate(compare)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Self::Or(predicates) => {
+                for predicate in predicates {
+                    if predicate.evaluate(compare)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Compares two values of the type `ty`. Returns `None` if some of them is
+/// not of this type or they are not comparable, like `NaN`'s.
+pub fn compare_values(ty: &ColumnType, a: &DataTypeValue, b: &DataTypeValue) -> Option<Ordering> {
+    match (ty, a, b) {
+        (ColumnType::Option(inner), DataTypeValue::Option(a), DataTypeValue::Option(b)) => {
+            match (a, b) {
+                (Some(a), Some(b)) => compare_values(inner, a, b),
+                (a, b) => Some(a.is_some().cmp(&b.is_some())),
+            }
+        }
+        (ColumnType::Vec(inner), DataTypeValue::Vec(a), DataTypeValue::Vec(b)) => {
+            for (a, b) in a.iter().zip(b) {
+                let ordering = compare_values(inner, a, b);
+                if ordering != Some(Ordering::Equal) {
+                    return ordering;
+                }
+            }
+            Some(a.len().cmp(&b.len()))
+        }
+        (ColumnType::Struct(fields), DataTypeValue::Struct(a), DataTypeValue::Struct(b)) => {
+            compare_fields(fields, a, b)
+        }
+        (
+            ColumnType::Enum(variants),
+            DataTypeValue::Enum {
+                variant: a,
+                fields: a_fields,
+            },
+            DataTypeValue::Enum {
+                variant: b,
+                fields: b_fields,
+            },
+        ) => {
+            let a = variants.iter().find(|v| &v.name == a)?;
+            let b = variants.iter().find(|v| &v.name == b)?;
+            match a.discriminant.cmp(&b.discriminant) {
+                Ordering::Equal => compare_fields(&a.fields, a_fields, b_fields),
+                ordering => Some(ordering),
+            }
+        }
+        _ if !a.is_of_type(ty) || !b.is_of_type(ty) => None,
+        (_, DataTypeValue::String(a), DataTypeValue::String(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::I128(a), DataTypeValue::I128(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::I64(a), DataTypeValue::I64(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::I32(a), DataTypeValue::I32(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::I16(a), DataTypeValue::I16(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::I8(a), DataTypeValue::I8(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::U128(a), DataTypeValue::U128(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::U64(a), DataTypeValue::U64(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::U32(a), DataTypeValue::U32(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::U16(a), DataTypeValue::U16(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::U8(a), DataTypeValue::U8(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::F64(a), DataTypeValue::F64(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::F32(a), DataTypeValue::F32(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::Bool(a), DataTypeValue::Bool(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::Char(a), DataTypeValue::Char(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::Uuid(a), DataTypeValue::Uuid(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::OrderedF64(a), DataTypeValue::OrderedF64(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::OrderedF32(a), DataTypeValue::OrderedF32(b)) => a.partial_cmp(b),
+        (_, DataTypeValue::PackedNanoid(a), DataTypeValue::PackedNanoid(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+/// Compares struct's or enum variant's fields lexicographically.
+fn compare_fields(
+    fields: &[FieldType],
+    a: &[(String, DataTypeValue)],
+    b: &[(String, DataTypeValue)],
+) -> Option<Ordering> {
+    if a.len() != fields.len() || b.len() != fields.len() {
+        return None;
+    }
+    for ((field, (a_name, a)), (b_name, b)) in fields.iter().zip(a).zip(b) {
+        if a_name != &field.name || b_name != &field.name {
+            return None;
+        }
+        let ordering = compare_values(&field.ty, a, b);
+        if ordering != Some(Ordering::Equal) {
+            return ordering;
+        }
+    }
+    Some(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use std::str::FromStr;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use proptest::strategy::{Union, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    use super::{compare_values, CompareOp, Predicate};
+    use crate::persistence::data::util::test::{column_type, row, value};
+    use crate::persistence::data::{
+        compare_column, encode_row, ColumnType, DataTypeValue, RowLayout,
+    };
+
+    const OPS: [CompareOp; 6] = [
+        CompareOp::Eq,
+        CompareOp::Ne,
+        CompareOp::Lt,
+        CompareOp::Le,
+        CompareOp::Gt,
+        CompareOp::Ge,
+    ];
+
+    #[test]
+    fn test_compare_values() {
+        let ty = |s: &str| ColumnType::from_str(s).unwrap();
+        let some = |v: DataTypeValue| DataTypeValue::Option(Some(Box::new(v)));
+        let variant = |name: &str| DataTypeValue::Enum {
+            variant: name.to_string(),
+            fields: vec![],
+        };
+
+        assert_eq!(
+            compare_values(
+                &ty("Option<u8>"),
+                &DataTypeValue::Option(None),
+                &some(0u8.into())
+            ),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_values(
+                &ty("Vec<u8>"),
+                &DataTypeValue::Vec(vec![1u8.into()]),
+                &DataTypeValue::Vec(vec![1u8.into(), 0u8.into()])
+            ),
+            Some(Ordering::Less)
+        );
+        // Enums are ordered by discriminants, not by variants' names.
+        let status = ty("enum {B, A = 5}");
+        assert_eq!(
+            compare_values(&status, &variant("A"), &variant("B")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_values(&status, &variant("C"), &variant("B")), None);
+        assert_eq!(
+            compare_values(&ColumnType::F64, &f64::NAN.into(), &1.0.into()),
+            None
+        );
+        assert_eq!(
+            compare_values(&ColumnType::U32, &1u32.into(), &1u8.into()),
+            None
+        );
+
+        assert!(CompareOp::Ne.matches(None));
+        for op in OPS.into_iter().filter(|op| *op != CompareOp::Ne) {
+            assert!(!op.matches(None), "{op}");
+        }
+        assert!(CompareOp::Le.matches(Some(Ordering::Equal)));
+        assert!(!CompareOp::Gt.matches(Some(Ordering::Equal)));
+    }
+
+    /// Returns strategy of the type with two values of it.
+    fn two_values() -> impl Strategy<Value = (ColumnType, DataTypeValue, DataTypeValue)> {
+        column_type().prop_flat_map(|ty| {
+            let values = (value(&ty), value(&ty));
+            (Just(ty), values).prop_map(|(ty, (a, b))| (ty, a, b))
+        })
+    }
+
+    proptest! {
+        /// Archived values should be compared in the same way as decoded ones.
+        #[test]
+        fn test_compare_column_random_values((ty, a, b) in two_values()) {
+            let types = vec![ty.clone()];
+            let layout = RowLayout::from_types(&types);
+            let ordering = compare_values(&ty, &a, &b);
+            prop_assert_eq!(compare_values(&ty, &a, &a), Some(Ordering::Equal));
+            prop_assert_eq!(compare_values(&ty, &b, &a), ordering.map(Ordering::reverse));
+
+            let bytes = encode_row(&types, std::slice::from_ref(&a)).unwrap();
+            prop_assert_eq!(compare_column(&bytes, &layout, 0, &b).unwrap(), ordering);
+            prop_assert_eq!(
+                compare_column(&bytes, &layout, 0, &a).unwrap(),
+                Some(Ordering::Equal)
+            );
+        }
+    }
+
+    fn predicate_types() -> Vec<ColumnType> {
+        [
+            "u64",
+            "String",
+            "Option<i32>",
+            "Vec<String>",
+            "{x: i16, label: String}",
+            "enum {A, B(String, u64) = 4, C {v: Vec<u16>}}",
+            "f64",
+        ]
+        .iter()
+        .map(|ty| ColumnType::from_str(ty).unwrap())
+        .collect()
+    }
+
+    /// Returns strategy of the predicates on columns of `types`. Compared
+    /// values are row's own values or values of the columns' types, so equal
+    /// values are compared too.
+    fn predicate(types: &[ColumnType], row: &[DataTypeValue]) -> impl Strategy<Value = Predicate> {
+        let compares = types
+            .iter()
+            .zip(row)
+            .enumerate()
+            .map(|(column, (ty, own))| {
+                let value = prop_oneof![Just(own.clone()), value(ty)];
+                (select(OPS.to_vec()), value)
+                    .prop_map(move |(op, value)| Predicate::compare(column, op, value))
+            })
+            .collect::<Vec<_>>();
+        Union::new(compares).prop_recursive(3, 32, 3, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..4).prop_map(Predicate::And),
+                vec(inner, 0..4).prop_map(Predicate::Or),
+            ]
+        })
+    }
+
+    fn row_with_predicate() -> impl Strategy<Value = (Vec<DataTypeValue>, Predicate)> {
+        row(&predicate_types()).prop_flat_map(|row| {
+            let predicate = predicate(&predicate_types(), &row);
+            (Just(row), predicate)
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn test_predicate_matches((row, predicate) in row_with_predicate()) {
+            let types = predicate_types();
+            let layout = RowLayout::from_types(&types);
+            let bytes = encode_row(&types, &row).unwrap();
+            predicate.validate(&types).unwrap();
+            prop_assert_eq!(
+                predicate.matches(&bytes, &layout).unwrap(),
+                predicate.matches_row(&row, &types),
+                "{:?}",
+                predicate
+            );
+        }
+    }
+
+    /// Generated predicates should match some rows, but not all of them.
+    #[test]
+    fn test_predicate_strategy() {
+        let types = predicate_types();
+        let layout = RowLayout::from_types(&types);
+        let mut runner = TestRunner::deterministic();
+        let mut matched = 0;
+        for _ in 0..2000 {
+            let (row, predicate) = row_with_predicate()
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            let bytes = encode_row(&types, &row).unwrap();
+            matched += predicate.matches(&bytes, &layout).unwrap() as usize;
+        }
+        assert!(matched > 0 && matched < 2000);
+    }
+
+    #[test]
+    fn test_predicate_validate() {
+        let types = [ColumnType::U64, ColumnType::String];
+        let predicate = Predicate::compare(0, CompareOp::Eq, 1u64).and(Predicate::compare(
+            2,
+            CompareOp::Eq,
+            1u64,
+        ));
+        assert_eq!(
+            predicate.validate(&types).unwrap_err().to_string(),
+            "Row has no column 2"
+        );
+        let predicate = Predicate::compare(1, CompareOp::Lt, 1u64);
+        assert_eq!(
+            predicate.validate(&types).unwrap_err().to_string(),
+            "Value `1` of the column 1 is not `String`"
+        );
+        assert!(Predicate::And(vec![]).validate(&types).is_ok());
+        assert!(Predicate::And(vec![]).matches_row(&[], &[]));
+        assert!(!Predicate::Or(vec![]).matches_row(&[], &[]));
+    }
+}
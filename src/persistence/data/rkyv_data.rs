@@ -1,45 +1,69 @@
-use crate::persistence::data::types::DataTypeValue;
 use std::str::FromStr;
 
+use crate::persistence::data::types::{ColumnType, DataTypeValue};
+use crate::persistence::data::{decode_row, encode_row};
+
+/// Parses archived row described by legacy `row_schema`.
+///
+/// # Panics
+///
+/// Panics if some column's type is not supported or `buf` is not a valid
+/// archived row.
 pub fn parse_archived_row<S1: AsRef<str>, S2: AsRef<str>>(
     buf: &[u8],
     columns: &[(S1, S2)],
 ) -> Vec<DataTypeValue> {
-    let mut data_length: usize = {
-        let mut accum: usize = 0;
-        for column in columns.iter() {
-            let value =
-                DataTypeValue::from_str(column.1.as_ref()).expect("data type should be supported");
-            let data_type = value.as_data_type();
-            data_type.advance_accum(&mut accum);
-        }
-        accum
-    };
-    if !data_length.is_multiple_of(4) {
-        data_length += 4 - data_length % 4;
-    }
-
-    let start_pointer = unsafe { buf.as_ptr().add(buf.len()).sub(data_length) };
-    let mut current_pointer = start_pointer;
-    let mut output: Vec<_> = vec![];
-    for column in columns.iter() {
-        let value =
-            DataTypeValue::from_str(column.1.as_ref()).expect("data type should be supported");
-        let data_type = value.as_data_type();
-        let deserialized = data_type.from_pointer(current_pointer, start_pointer);
-        data_type.advance_pointer_for_padding(&mut current_pointer, start_pointer);
-        output.push(deserialized);
-        data_type.advance_pointer(&mut current_pointer);
-    }
-    output
+    let types = columns
+        .iter()
+        .map(|column| {
+            ColumnType::from_str(column.1.as_ref()).expect("data type should be supported")
+        })
+        .collect::<Vec<_>>();
+    parse_archived_columns(buf, &types)
+}
+
+/// Parses archived row which columns have given `types`.
+///
+/// # Panics
+///
+/// Panics if `buf` is not a valid archived row, [`decode_row`] returns error
+/// instead.
+pub fn parse_archived_columns(buf: &[u8], types: &[ColumnType]) -> Vec<DataTypeValue> {
+    decode_row(buf, types).unwrap_or_else(|e| panic!("archived row should be valid: {e}"))
+}
+
+/// Archives row described by legacy `row_schema`. Bytes are the same as
+/// `rkyv` produces for the struct with these columns as fields, so they can
+/// be read by [`parse_archived_row`] and by the compiled row type. Returns
+/// error if some column's type is not supported or value doesn't match it.
+pub fn encode_archived_row<S1: AsRef<str>, S2: AsRef<str>>(
+    columns: &[(S1, S2)],
+    row: &[DataTypeValue],
+) -> eyre::Result<Vec<u8>> {
+    let types = columns
+        .iter()
+        .map(|column| ColumnType::from_str(column.1.as_ref()))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    encode_row(&types, row)
 }
 
 #[cfg(test)]
 mod test {
-    use super::parse_archived_row;
-    use crate::persistence::data::types::DataTypeValue;
+    use std::str::FromStr;
+
+    use super::{encode_archived_row, parse_archived_row};
+    use crate::persistence::data::types::{ColumnType, DataTypeValue};
+    use crate::persistence::data::util::test::{row, rows};
+    use crate::persistence::data::RowLayout;
+    use ordered_float::OrderedFloat;
+    use proptest::prelude::*;
+    use psc_nanoid::alphabet::Base64UrlAlphabet;
+    use psc_nanoid::packed::PackedNanoid;
+    use psc_nanoid::Nanoid;
+    use rkyv::util::AlignedVec;
     use rkyv::{Archive, Deserialize, Serialize};
     use std::f64::consts::PI;
+    use uuid::Uuid;
 
     #[derive(Archive, Serialize, Deserialize, Debug)]
     struct Struct1 {
@@ -147,4 +171,417 @@ mod test {
             ]
         )
     }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Struct5 {
+        pub id: u64,
+        pub age: u8,
+        pub big: i128,
+        pub tag: String,
+    }
+
+    #[test]
+    fn test_parse_archived_row_trailing_padding() {
+        let buffer = rkyv::to_bytes::<rkyv::rancor::Error>(&Struct5 {
+            id: 1,
+            age: 2,
+            big: -3,
+            tag: "trailing padding".to_string(),
+        })
+        .unwrap();
+        let parsed = parse_archived_row(
+            &buffer,
+            &[
+                ("id".to_string(), "u64".to_string()),
+                ("age".to_string(), "u8".to_string()),
+                ("big".to_string(), "i128".to_string()),
+                ("tag".to_string(), "String".to_string()),
+            ],
+        );
+        assert_eq!(
+            parsed,
+            [
+                DataTypeValue::U64(1),
+                DataTypeValue::U8(2),
+                DataTypeValue::I128(-3),
+                DataTypeValue::String("trailing padding".to_string()),
+            ]
+        )
+    }
+
+    #[derive(Archive, Serialize, Deserialize)]
+    struct Struct6 {
+        pub flag: bool,
+        pub letter: char,
+        pub id: Uuid,
+        pub nanoid: PackedNanoid<21, 16, Base64UrlAlphabet>,
+        pub score: OrderedFloat<f64>,
+        pub ratio: OrderedFloat<f32>,
+        pub parent: Option<u64>,
+        pub nickname: Option<String>,
+        pub tags: Vec<String>,
+        pub counts: Vec<u16>,
+        pub history: Option<Vec<Option<String>>>,
+        pub matrix: Vec<Vec<u64>>,
+        pub flag2: bool,
+    }
+
+    #[test]
+    fn test_parse_archived_row_composite_types() {
+        let id = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let nanoid =
+            PackedNanoid::<21, 16, Base64UrlAlphabet>::pack(&Nanoid::<21, _>::new()).unwrap();
+        let buffer = rkyv::to_bytes::<rkyv::rancor::Error>(&Struct6 {
+            flag: true,
+            letter: 'ж',
+            id,
+            nanoid,
+            score: OrderedFloat(PI),
+            ratio: OrderedFloat(0.5),
+            parent: Some(7),
+            nickname: None,
+            tags: vec!["short".to_string(), "long enough tag".to_string()],
+            counts: vec![],
+            history: Some(vec![None, Some("previous long value".to_string())]),
+            matrix: vec![vec![1, 2], vec![], vec![3]],
+            flag2: false,
+        })
+        .unwrap();
+        let parsed = parse_archived_row(
+            &buffer,
+            &[
+                ("flag", "bool"),
+                ("letter", "char"),
+                ("id", "Uuid"),
+                ("nanoid", "PackedNanoid<21, 16, Base64UrlAlphabet>"),
+                ("score", "OrderedFloat<f64>"),
+                ("ratio", "OrderedFloat<f32>"),
+                ("parent", "Option<u64>"),
+                ("nickname", "Option<String>"),
+                ("tags", "Vec<String>"),
+                ("counts", "Vec<u16>"),
+                ("history", "Option<Vec<Option<String>>>"),
+                ("matrix", "Vec<Vec<u64>>"),
+                ("flag2", "bool"),
+            ],
+        );
+        let string = |s: &str| Box::new(DataTypeValue::String(s.to_string()));
+        assert_eq!(
+            parsed,
+            [
+                DataTypeValue::Bool(true),
+                DataTypeValue::Char('ж'),
+                DataTypeValue::Uuid(id),
+                DataTypeValue::PackedNanoid(nanoid.as_bytes().to_vec()),
+                DataTypeValue::OrderedF64(OrderedFloat(PI)),
+                DataTypeValue::OrderedF32(OrderedFloat(0.5)),
+                DataTypeValue::Option(Some(Box::new(DataTypeValue::U64(7)))),
+                DataTypeValue::Option(None),
+                DataTypeValue::Vec(vec![*string("short"), *string("long enough tag")]),
+                DataTypeValue::Vec(vec![]),
+                DataTypeValue::Option(Some(Box::new(DataTypeValue::Vec(vec![
+                    DataTypeValue::Option(None),
+                    DataTypeValue::Option(Some(string("previous long value"))),
+                ])))),
+                DataTypeValue::Vec(vec![
+                    DataTypeValue::Vec(vec![DataTypeValue::U64(1), DataTypeValue::U64(2)]),
+                    DataTypeValue::Vec(vec![]),
+                    DataTypeValue::Vec(vec![DataTypeValue::U64(3)]),
+                ]),
+                DataTypeValue::Bool(false),
+            ]
+        )
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Point {
+        pub x: i16,
+        pub label: String,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    enum Status {
+        Active,
+        Blocked = 5,
+        Deleted,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    enum Shape {
+        Empty,
+        Circle(Point, u64),
+        Rect { min: Point, max: Point },
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Struct7 {
+        pub flag: bool,
+        pub point: Point,
+        pub status: Status,
+        pub shapes: Vec<Shape>,
+        pub pair: (u8, String),
+        pub shape: Option<Shape>,
+        pub id: u32,
+    }
+
+    #[test]
+    fn test_parse_archived_row_nested_types() {
+        let point = |x: i16, label: &str| Point {
+            x,
+            label: label.to_string(),
+        };
+        let buffer = rkyv::to_bytes::<rkyv::rancor::Error>(&Struct7 {
+            flag: true,
+            point: point(-3, "origin of the shapes"),
+            status: Status::Blocked,
+            shapes: vec![
+                Shape::Circle(point(1, "center"), 10),
+                Shape::Empty,
+                Shape::Rect {
+                    min: point(2, "long enough min label"),
+                    max: point(4, "max"),
+                },
+            ],
+            pair: (7, "long enough pair string".to_string()),
+            shape: Some(Shape::Empty),
+            id: 42,
+        })
+        .unwrap();
+        let point_type = "{x: i16, label: String}";
+        let shape_type =
+            format!("enum {{Empty, Circle({point_type}, u64), Rect {{min: {point_type}, max: {point_type}}}}}");
+        let parsed = parse_archived_row(
+            &buffer,
+            &[
+                ("flag", "bool"),
+                ("point", point_type),
+                ("status", "enum {Active, Blocked = 5, Deleted}"),
+                ("shapes", &format!("Vec<{shape_type}>")),
+                ("pair", "(u8, String)"),
+                ("shape", &format!("Option<{shape_type}>")),
+                ("id", "u32"),
+            ],
+        );
+
+        let point = |x: i16, label: &str| {
+            DataTypeValue::Struct(vec![
+                ("x".to_string(), DataTypeValue::I16(x)),
+                (
+                    "label".to_string(),
+                    DataTypeValue::String(label.to_string()),
+                ),
+            ])
+        };
+        let variant = |variant: &str, fields: Vec<(&str, DataTypeValue)>| DataTypeValue::Enum {
+            variant: variant.to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        };
+        assert_eq!(
+            parsed,
+            [
+                DataTypeValue::Bool(true),
+                point(-3, "origin of the shapes"),
+                variant("Blocked", vec![]),
+                DataTypeValue::Vec(vec![
+                    variant(
+                        "Circle",
+                        vec![("0", point(1, "center")), ("1", DataTypeValue::U64(10))]
+                    ),
+                    variant("Empty", vec![]),
+                    variant(
+                        "Rect",
+                        vec![
+                            ("min", point(2, "long enough min label")),
+                            ("max", point(4, "max")),
+                        ]
+                    ),
+                ]),
+                DataTypeValue::Struct(vec![
+                    ("0".to_string(), DataTypeValue::U8(7)),
+                    (
+                        "1".to_string(),
+                        DataTypeValue::String("long enough pair string".to_string())
+                    ),
+                ]),
+                DataTypeValue::Option(Some(Box::new(variant("Empty", vec![])))),
+                DataTypeValue::U32(42),
+            ]
+        );
+        assert_eq!(
+            parsed[3].to_string(),
+            "[Circle({x: 1, label: center}, 10), Empty, \
+             Rect {min: {x: 2, label: long enough min label}, max: {x: 4, label: max}}]"
+        );
+    }
+
+    proptest! {
+        /// Encoded rows of any schema should be decoded to the same values
+        /// and accepted by the row layout.
+        #[test]
+        fn test_encode_archived_row_random_schemas((types, rows) in rows(1..6, 0..20)) {
+            let columns = types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (format!("c{i}"), ty.to_string()))
+                .collect::<Vec<_>>();
+            for (ty, (_, s)) in types.iter().zip(&columns) {
+                prop_assert_eq!(&ColumnType::from_str(s).unwrap(), ty, "{}", s);
+            }
+            let layout = RowLayout::from_types(&types);
+            for row in rows {
+                let bytes = encode_archived_row(&columns, &row).unwrap();
+                prop_assert_eq!(parse_archived_row(&bytes, &columns), row);
+                prop_assert!(layout.row_lengths(&bytes).any(|l| l == bytes.len()));
+            }
+        }
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Struct8 {
+        pub name: String,
+        pub id: u64,
+        pub delta: i32,
+        pub small: i8,
+        pub flag: bool,
+        pub letter: char,
+        pub ratio: f32,
+        pub score: OrderedFloat<f64>,
+        pub uuid: Uuid,
+        pub parent: Option<u16>,
+        pub tags: Vec<String>,
+        pub point: Point,
+        pub status: Status,
+        pub shapes: Vec<Shape>,
+        pub pair: (u8, String),
+        pub shape: Option<Shape>,
+        pub big: i128,
+    }
+
+    fn point_value(point: &Point) -> DataTypeValue {
+        DataTypeValue::Struct(vec![
+            ("x".to_string(), point.x.into()),
+            ("label".to_string(), point.label.clone().into()),
+        ])
+    }
+
+    fn shape_value(shape: &Shape) -> DataTypeValue {
+        let variant = |variant: &str, fields: Vec<(&str, DataTypeValue)>| DataTypeValue::Enum {
+            variant: variant.to_string(),
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        };
+        match shape {
+            Shape::Empty => variant("Empty", vec![]),
+            Shape::Circle(center, radius) => variant(
+                "Circle",
+                vec![("0", point_value(center)), ("1", (*radius).into())],
+            ),
+            Shape::Rect { min, max } => variant(
+                "Rect",
+                vec![("min", point_value(min)), ("max", point_value(max))],
+            ),
+        }
+    }
+
+    /// Returns values of the row's columns in fields order.
+    fn struct8_values(row: &Struct8) -> Vec<DataTypeValue> {
+        let status = match row.status {
+            Status::Active => "Active",
+            Status::Blocked => "Blocked",
+            Status::Deleted => "Deleted",
+        };
+        vec![
+            row.name.clone().into(),
+            row.id.into(),
+            row.delta.into(),
+            row.small.into(),
+            row.flag.into(),
+            row.letter.into(),
+            row.ratio.into(),
+            DataTypeValue::OrderedF64(row.score),
+            row.uuid.into(),
+            DataTypeValue::Option(row.parent.map(|p| Box::new(p.into()))),
+            DataTypeValue::Vec(row.tags.iter().cloned().map(Into::into).collect()),
+            point_value(&row.point),
+            DataTypeValue::Enum {
+                variant: status.to_string(),
+                fields: vec![],
+            },
+            DataTypeValue::Vec(row.shapes.iter().map(shape_value).collect()),
+            DataTypeValue::Struct(vec![
+                ("0".to_string(), row.pair.0.into()),
+                ("1".to_string(), row.pair.1.clone().into()),
+            ]),
+            DataTypeValue::Option(row.shape.as_ref().map(|s| Box::new(shape_value(s)))),
+            row.big.into(),
+        ]
+    }
+
+    fn struct8_columns() -> Vec<(&'static str, String)> {
+        let point_type = "{x: i16, label: String}";
+        let shape_type = format!(
+            "enum {{Empty, Circle({point_type}, u64), Rect {{min: {point_type}, max: {point_type}}}}}"
+        );
+        [
+            ("name", "String".to_string()),
+            ("id", "u64".to_string()),
+            ("delta", "i32".to_string()),
+            ("small", "i8".to_string()),
+            ("flag", "bool".to_string()),
+            ("letter", "char".to_string()),
+            ("ratio", "f32".to_string()),
+            ("score", "OrderedFloat<f64>".to_string()),
+            ("uuid", "Uuid".to_string()),
+            ("parent", "Option<u16>".to_string()),
+            ("tags", "Vec<String>".to_string()),
+            ("point", point_type.to_string()),
+            ("status", "enum {Active, Blocked = 5, Deleted}".to_string()),
+            ("shapes", format!("Vec<{shape_type}>")),
+            ("pair", "(u8, String)".to_string()),
+            ("shape", format!("Option<{shape_type}>")),
+            ("big", "i128".to_string()),
+        ]
+        .into()
+    }
+
+    fn struct8_types() -> Vec<ColumnType> {
+        struct8_columns()
+            .iter()
+            .map(|(_, ty)| ColumnType::from_str(ty).unwrap())
+            .collect()
+    }
+
+    proptest! {
+        /// Row encoded from values should be the same struct that `rkyv`
+        /// archives to the same bytes.
+        #[test]
+        fn test_encode_archived_row_matches_rkyv(values in row(&struct8_types())) {
+            let columns = struct8_columns();
+            let bytes = encode_archived_row(&columns, &values).unwrap();
+            let mut aligned = AlignedVec::<16>::new();
+            aligned.extend_from_slice(&bytes);
+            let row = rkyv::from_bytes::<Struct8, rkyv::rancor::Error>(&aligned).unwrap();
+            prop_assert_eq!(struct8_values(&row), values.clone());
+            let expected = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            prop_assert_eq!(bytes.as_slice(), expected.as_slice());
+            prop_assert_eq!(parse_archived_row(&bytes, &columns), values);
+        }
+    }
+
+    #[test]
+    fn test_encode_archived_row_errors() {
+        let err = encode_archived_row(&[("map", "HashMap<u8, u8>")], &[DataTypeValue::U8(1)])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Data type `HashMap<u8, u8>` is not supported"
+        );
+        let err = encode_archived_row(&[("id", "u32")], &[DataTypeValue::U8(1)]).unwrap_err();
+        assert_eq!(err.to_string(), "Value `1` is not `u32`");
+    }
 }
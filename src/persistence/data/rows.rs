@@ -0,0 +1,431 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use eyre::bail;
+use tokio::fs::File;
+
+use crate::page::{
+    parse_data_pages_batch, parse_general_header_by_index, parse_space_info, persist_page,
+    persist_pages_batch,
+};
+use crate::persistence::data::layout::RowLayout;
+use crate::persistence::data::{DataTypeValue, RowReader};
+use crate::{
+    DataPage, GeneralHeader, GeneralPage, Link, PageType, SpaceInfoPage, INNER_PAGE_SIZE, PAGE_SIZE,
+};
+
+/// Returns indexes of all [`PageType::Data`] pages of the space file.
+pub async fn parse_data_page_ids(file: &mut File) -> eyre::Result<Vec<u32>> {
+    let file_length = file.metadata().await?.len();
+    let page_count = file_length.div_ceil(PAGE_SIZE as u64) as u32;
+
+    let mut ids = vec![];
+    // `0`'s page in file is info page.
+    for index in 1..page_count {
+        let header = parse_general_header_by_index(file, index).await?;
+        if header.page_type == PageType::Data {
+            ids.push(index)
+        }
+    }
+
+    Ok(ids)
+}
+
+/// Returns [`Link`]'s of all live rows of the data page. Rows that are listed
+/// in `empty_links` are skipped.
+///
+/// Rows are stored without their lengths, so page is split into rows by
+/// searching for valid archived rows with [`RowLayout::row_lengths`]. It takes
+/// quadratic time of the page's length and fails if page's rows can be split
+/// in more than one way. If rows' [`Link`]'s are known from the primary index,
+/// [`data_page_row_links_by_index`] should be used instead.
+pub fn data_page_row_links<const INNER_PAGE_SIZE: usize>(
+    page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>,
+    layout: &RowLayout,
+    empty_links: &[Link],
+) -> eyre::Result<Vec<Link>> {
+    data_page_row_links_by_index(page, layout, empty_links, &[])
+}
+
+/// Returns [`Link`]'s of all live rows of the data page like
+/// [`data_page_row_links`], but rows listed in `index_links` are taken as is,
+/// so only parts of the page between them are split into rows. Primary
+/// index's [`Link`]'s of the page's rows should be passed as `index_links`.
+pub fn data_page_row_links_by_index<const INNER_PAGE_SIZE: usize>(
+    page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>,
+    layout: &RowLayout,
+    empty_links: &[Link],
+    index_links: &[Link],
+) -> eyre::Result<Vec<Link>> {
+    let page_id = page.header.page_id;
+    let length = (page.inner.length as usize).min(INNER_PAGE_SIZE);
+    // Known parts of the page, which are holes or rows from the index.
+    let mut known = empty_links
+        .iter()
+        .filter(|l| l.page_id == page_id)
+        .map(|l| (l, false))
+        .chain(
+            index_links
+                .iter()
+                .filter(|l| l.page_id == page_id)
+                .map(|l| (l, true)),
+        )
+        .collect::<Vec<_>>();
+    known.sort_by_key(|(l, _)| l.offset);
+    let mut known = known.into_iter().peekable();
+
+    let mut links = vec![];
+    let mut offset = 0usize;
+    while offset < length {
+        while let Some((link, is_row)) = known.next_if(|(l, _)| (l.offset as usize) < offset) {
+            if is_row {
+                bail!("Row {:?} overlaps other row of the page", link)
+            }
+        }
+        if let Some((link, is_row)) = known.next_if(|(l, _)| l.offset as usize == offset) {
+            if is_row {
+                if link.length == 0 || offset + link.length as usize > length {
+                    bail!("Row {:?} is out of page with length {}", link, length)
+                }
+                links.push(*link);
+            }
+            offset += link.length as usize;
+            continue;
+        }
+
+        let end = known
+            .peek()
+            .map(|(l, _)| l.offset as usize)
+            .unwrap_or(length)
+            .min(length);
+        let lengths = split_rows(layout, &page.inner.data[offset..end]).map_err(|e| {
+            e.wrap_err(format!(
+                "Rows at page {} in range {}..{} can not be parsed",
+                page_id, offset, end
+            ))
+        })?;
+        for row_length in lengths {
+            links.push(Link {
+                page_id,
+                offset: offset as u32,
+                length: row_length as u32,
+            });
+            offset += row_length;
+        }
+    }
+    if let Some((link, true)) = known.find(|(_, is_row)| *is_row) {
+        bail!("Row {:?} is out of page with length {}", link, length)
+    }
+
+    Ok(links)
+}
+
+/// Splits `segment` into rows' lengths, so rows fill it completely. Returns
+/// error if `segment` can not be split into rows or can be split in more than
+/// one way.
+pub(crate) fn split_rows(layout: &RowLayout, segment: &[u8]) -> eyre::Result<Vec<usize>> {
+    // Possible rows' lengths at offsets where rows can start.
+    let mut starts = BTreeMap::new();
+    let mut queue = BTreeSet::from([0]);
+    while let Some(offset) = queue.pop_first() {
+        if offset == segment.len() {
+            continue;
+        }
+        let lengths = layout
+            .row_lengths(&segment[offset..])
+            .filter(|len| *len > 0)
+            .collect::<Vec<_>>();
+        queue.extend(lengths.iter().map(|len| offset + len));
+        starts.insert(offset, lengths);
+    }
+
+    // Count of the ways to split the rest of the segment from the offset and
+    // first row's length of the first way. Counts above `2` are not needed.
+    let mut splits = HashMap::from([(segment.len(), (1u8, 0usize))]);
+    for (offset, lengths) in starts.iter().rev() {
+        let mut count = 0u8;
+        let mut first = 0;
+        for len in lengths {
+            let Some((rest, _)) = splits.get(&(offset + len)) else {
+                continue;
+            };
+            if count == 0 {
+                first = *len;
+            }
+            count = (count + rest).min(2);
+        }
+        splits.insert(*offset, (count, first));
+    }
+    match splits[&0].0 {
+        0 => bail!("Segment can not be split into rows"),
+        1 => {}
+        _ => bail!("Segment can be split into rows in more than one way"),
+    }
+
+    let mut lengths = vec![];
+    let mut offset = 0;
+    while offset < segment.len() {
+        let len = splits[&offset].1;
+        lengths.push(len);
+        offset += len;
+    }
+    Ok(lengths)
+}
+
+/// Returns all live rows of the data page with their [`Link`]'s. Rows are
+/// upgraded to the current schema version.
+pub fn parse_data_page_rows<const INNER_PAGE_SIZE: usize>(
+    page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>,
+    space_info: &SpaceInfoPage,
+    reader: &RowReader,
+) -> eyre::Result<Vec<(Link, Vec<DataTypeValue>)>> {
+    let page_id = page.header.page_id;
+    let links = data_page_row_links(
+        page,
+        reader.layout(page_id),
+        space_info.page_empty_links(page_id),
+    )?;
+    let mut rows = Vec::with_capacity(links.len());
+    for link in links {
+        let row = reader.read_row(page_id, page.inner.get_at(link)?)?;
+        rows.push((link, row))
+    }
+
+    Ok(rows)
+}
+
+/// Reads [`SpaceInfoPage`] and all live rows of the space file. Rows are
+/// upgraded to the current schema version.
+pub async fn read_data_rows(
+    file: &mut File,
+) -> eyre::Result<(SpaceInfoPage, Vec<(Link, Vec<DataTypeValue>)>)> {
+    let space_info = parse_space_info::<PAGE_SIZE>(file).await?;
+    let reader = RowReader::new(&space_info)?;
+    let ids = parse_data_page_ids(file).await?;
+    let pages = parse_data_pages_batch::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(file, ids).await?;
+
+    let mut rows = vec![];
+    for page in pages {
+        rows.extend(parse_data_page_rows(&page, &space_info, &reader)?);
+    }
+
+    Ok((space_info, rows))
+}
+
+/// Writes rows to the new space's data file. Rows are packed into data pages
+/// one after another, written [`SpaceInfoPage`] gets new `page_count`, empty
+/// `empty_links_list` and no schema history, so rows should be archived under
+/// the current schema version. Returns written [`SpaceInfoPage`] and rows
+/// with their new [`Link`]'s.
+pub(crate) async fn persist_data_rows<'a>(
+    file: &mut File,
+    space_info: &SpaceInfoPage,
+    rows: impl IntoIterator<Item = (&'a [u8], Vec<DataTypeValue>)>,
+) -> eyre::Result<(SpaceInfoPage, Vec<(Link, Vec<DataTypeValue>)>)> {
+    let space_id = space_info.id;
+    let mut pages: Vec<GeneralPage<DataPage<INNER_PAGE_SIZE>>> = vec![];
+    let mut linked_rows = vec![];
+    for (bytes, values) in rows {
+        if bytes.len() > INNER_PAGE_SIZE {
+            bail!("Row with length {} does not fit in page", bytes.len())
+        }
+        let fits = pages
+            .last()
+            .map(|p| p.inner.length as usize + bytes.len() <= INNER_PAGE_SIZE)
+            .unwrap_or(false);
+        if !fits {
+            let header = match pages.last_mut() {
+                Some(previous) => previous.header.follow(),
+                None => GeneralHeader::new(1.into(), PageType::Data, space_id),
+            };
+            pages.push(GeneralPage {
+                header,
+                inner: DataPage {
+                    length: 0,
+                    data: [0; INNER_PAGE_SIZE],
+                },
+            })
+        }
+        let page = pages.last_mut().expect("page is added before");
+        let offset = page.inner.length as usize;
+        page.inner.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        page.inner.length += bytes.len() as u32;
+        let link = Link {
+            page_id: page.header.page_id,
+            offset: offset as u32,
+            length: bytes.len() as u32,
+        };
+        linked_rows.push((link, values))
+    }
+
+    let space_info = SpaceInfoPage {
+        page_count: pages.len() as u32,
+        schema_history: vec![],
+        empty_links_list: vec![],
+        ..space_info.clone()
+    };
+    let mut info = GeneralPage {
+        header: GeneralHeader::new(0.into(), PageType::SpaceInfo, space_id),
+        inner: space_info.clone(),
+    };
+    persist_page(&mut info, file).await?;
+    persist_pages_batch(pages, file).await?;
+
+    Ok((space_info, linked_rows))
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{Archive, Deserialize, Serialize};
+
+    use super::{
+        data_page_row_links, data_page_row_links_by_index, parse_data_page_rows, split_rows,
+    };
+    use crate::persistence::data::layout::RowLayout;
+    use crate::persistence::data::{DataTypeValue, RowReader};
+    use crate::{
+        ColumnSchema, DataPage, GeneralHeader, GeneralPage, Link, PageType, SpaceInfoPage,
+    };
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub id: u64,
+        pub name: String,
+    }
+
+    fn schema() -> Vec<(String, String)> {
+        vec![
+            ("id".to_string(), "u64".to_string()),
+            ("name".to_string(), "String".to_string()),
+        ]
+    }
+
+    fn page(rows: &[Row]) -> GeneralPage<DataPage<1024>> {
+        let mut data = [0u8; 1024];
+        let mut length = 0;
+        for row in rows {
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(row).unwrap();
+            data[length..length + bytes.len()].copy_from_slice(bytes.as_slice());
+            length += bytes.len();
+        }
+        GeneralPage {
+            header: GeneralHeader::new(1.into(), PageType::Data, 0.into()),
+            inner: DataPage {
+                length: length as u32,
+                data,
+            },
+        }
+    }
+
+    #[test]
+    fn test_row_links_with_holes() {
+        let rows = (0..5)
+            .map(|i| Row {
+                id: i,
+                name: format!("name is {}", "x".repeat(i as usize * 3)),
+            })
+            .collect::<Vec<_>>();
+        let page = page(&rows);
+        let layout = RowLayout::new(&schema());
+        let links = data_page_row_links(&page, &layout, &[]).unwrap();
+        assert_eq!(links.len(), 5);
+
+        let empty = vec![links[1], links[3]];
+        let live = data_page_row_links(&page, &layout, &empty).unwrap();
+        assert_eq!(live, vec![links[0], links[2], links[4]]);
+    }
+
+    #[test]
+    fn test_row_links_by_index() {
+        let rows = (0..5)
+            .map(|i| Row {
+                id: i,
+                name: format!("name is {}", "x".repeat(i as usize * 3)),
+            })
+            .collect::<Vec<_>>();
+        let mut page = page(&rows);
+        let layout = RowLayout::new(&schema());
+        let links = data_page_row_links(&page, &layout, &[]).unwrap();
+
+        // Broken row can't be found by layout, but it's known from index.
+        let broken = links[2];
+        page.inner.data[broken.offset as usize..(broken.offset + broken.length) as usize]
+            .fill(0xc0);
+        assert!(data_page_row_links(&page, &layout, &[]).is_err());
+        let parsed = data_page_row_links_by_index(&page, &layout, &[], &[broken]).unwrap();
+        assert_eq!(parsed, links);
+        let parsed = data_page_row_links_by_index(&page, &layout, &[links[1]], &[broken]).unwrap();
+        assert_eq!(parsed, vec![links[0], links[2], links[3], links[4]]);
+
+        let overlapping = Link {
+            offset: broken.offset + 4,
+            ..broken
+        };
+        assert!(data_page_row_links_by_index(&page, &layout, &[], &[broken, overlapping]).is_err());
+        let out_of_page = Link {
+            offset: page.inner.length,
+            ..broken
+        };
+        assert!(data_page_row_links_by_index(&page, &layout, &[], &[out_of_page]).is_err());
+    }
+
+    #[test]
+    fn test_split_rows() {
+        let rows = (0..3)
+            .map(|i| Row {
+                id: i,
+                name: "x".repeat(i as usize * 7),
+            })
+            .collect::<Vec<_>>();
+        let page = page(&rows);
+        let layout = RowLayout::new(&schema());
+        let length = page.inner.length as usize;
+        let lengths = split_rows(&layout, &page.inner.data[..length]).unwrap();
+        assert_eq!(lengths.len(), 3);
+        assert_eq!(lengths.iter().sum::<usize>(), length);
+        assert!(split_rows(&layout, &page.inner.data[..length - 1]).is_err());
+        assert_eq!(split_rows(&layout, &[]).unwrap(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let rows = (0..3)
+            .map(|i| Row {
+                id: i,
+                name: format!("row number {i}"),
+            })
+            .collect::<Vec<_>>();
+        let page = page(&rows);
+        let mut space_info = SpaceInfoPage {
+            id: 0.into(),
+            page_count: 1,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: ColumnSchema::from_row_schema(&schema()).unwrap(),
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![],
+            empty_links_list: vec![],
+        };
+        let reader = RowReader::new(&space_info).unwrap();
+        let parsed = parse_data_page_rows(&page, &space_info, &reader).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(
+            parsed[2].1,
+            vec![
+                DataTypeValue::U64(2),
+                DataTypeValue::String("row number 2".to_string())
+            ]
+        );
+
+        space_info.empty_links_list.push(Link {
+            page_id: 2.into(),
+            ..parsed[0].0
+        });
+        space_info.empty_links_list.push(parsed[0].0);
+        let parsed = parse_data_page_rows(&page, &space_info, &reader).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].1[0], DataTypeValue::U64(1));
+    }
+}
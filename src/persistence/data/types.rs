@@ -2,16 +2,29 @@ use std::str::FromStr;
 
 use derive_more::derive::Display;
 use derive_more::From;
+use eyre::{bail, eyre};
+use ordered_float::OrderedFloat;
+use psc_nanoid::packed::AlphabetPackExt;
+use psc_nanoid::PackedNanoid;
 use rkyv::primitive::{
     ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16, ArchivedI32, ArchivedI64, ArchivedU128,
     ArchivedU16, ArchivedU32, ArchivedU64,
 };
 use rkyv::string::ArchivedString;
+use rkyv::vec::ArchivedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::persistence::data::util::{advance_accum_for_padding, advance_pointer_for_padding};
 use crate::persistence::data::DataType;
 
-#[derive(Debug, Display, From, PartialEq)]
+#[derive(Archive, Clone, Debug, Deserialize, Display, From, PartialEq, Serialize)]
+#[rkyv(serialize_bounds(
+    __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+    __S::Error: rkyv::rancor::Source,
+))]
+#[rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))]
+#[rkyv(bytecheck(bounds(__C: rkyv::validation::ArchiveContext)))]
 pub enum DataTypeValue {
     String(String),
     I128(i128),
@@ -26,106 +39,633 @@ pub enum DataTypeValue {
     U8(u8),
     F64(f64),
     F32(f32),
+    Bool(bool),
+    Char(char),
+    Uuid(Uuid),
+    OrderedF64(OrderedFloat<f64>),
+    OrderedF32(OrderedFloat<f32>),
+    /// Packed bytes of the `PackedNanoid`, it's displayed as hex string.
+    #[display("{}", _0.iter().map(|b| format!("{b:02x}")).collect::<String>())]
+    #[from(skip)]
+    PackedNanoid(Vec<u8>),
+    #[display("{}", _0.as_ref().map(ToString::to_string).unwrap_or_else(|| "null".to_string()))]
+    Option(#[rkyv(omit_bounds)] Option<Box<DataTypeValue>>),
+    #[display("[{}]", _0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Vec(#[rkyv(omit_bounds)] Vec<DataTypeValue>),
+    /// Values of the struct's fields in fields order.
+    #[display("{}", display_fields(_0.iter().map(|(n, v)| (n.as_str(), v))))]
+    #[from(skip)]
+    Struct(#[rkyv(omit_bounds)] Vec<(String, DataTypeValue)>),
+    /// Value of the enum's `variant` with values of it's fields.
+    #[display("{}", display_variant(variant, fields.iter().map(|(n, v)| (n.as_str(), v))))]
+    #[from(skip)]
+    Enum {
+        variant: String,
+        #[rkyv(omit_bounds)]
+        fields: Vec<(String, DataTypeValue)>,
+    },
 }
 
 impl DataTypeValue {
-    pub fn as_data_type(&self) -> &dyn DataType {
-        match self {
-            Self::String(s) => s,
-            Self::I128(i) => i,
-            Self::I64(i) => i,
-            Self::I32(i) => i,
-            Self::I16(i) => i,
-            Self::I8(i) => i,
-            Self::U128(u) => u,
-            Self::U64(u) => u,
-            Self::U32(u) => u,
-            Self::U16(u) => u,
-            Self::U8(u) => u,
-            Self::F64(f) => f,
-            Self::F32(f) => f,
+    /// Returns `true` if value can be stored in the column of type `ty`.
+    /// `None` and empty `Vec` values fit any `Option` and `Vec` types.
+    pub fn is_of_type(&self, ty: &ColumnType) -> bool {
+        match (self, ty) {
+            (Self::PackedNanoid(v), ColumnType::PackedNanoid { bytes, .. }) => {
+                v.len() == *bytes as usize
+            }
+            (Self::Option(v), ColumnType::Option(inner)) => {
+                v.as_ref().map(|v| v.is_of_type(inner)).unwrap_or(true)
+            }
+            (Self::Vec(values), ColumnType::Vec(inner)) => {
+                values.iter().all(|v| v.is_of_type(inner))
+            }
+            (Self::Struct(values), ColumnType::Struct(fields)) => are_of_types(values, fields),
+            (Self::Enum { variant, fields }, ColumnType::Enum(variants)) => variants
+                .iter()
+                .find(|v| &v.name == variant)
+                .map(|v| are_of_types(fields, &v.fields))
+                .unwrap_or(false),
+            (Self::String(_), ColumnType::String)
+            | (Self::I128(_), ColumnType::I128)
+            | (Self::I64(_), ColumnType::I64)
+            | (Self::I32(_), ColumnType::I32)
+            | (Self::I16(_), ColumnType::I16)
+            | (Self::I8(_), ColumnType::I8)
+            | (Self::U128(_), ColumnType::U128)
+            | (Self::U64(_), ColumnType::U64)
+            | (Self::U32(_), ColumnType::U32)
+            | (Self::U16(_), ColumnType::U16)
+            | (Self::U8(_), ColumnType::U8)
+            | (Self::F64(_), ColumnType::F64)
+            | (Self::F32(_), ColumnType::F32)
+            | (Self::Bool(_), ColumnType::Bool)
+            | (Self::Char(_), ColumnType::Char)
+            | (Self::Uuid(_), ColumnType::Uuid)
+            | (Self::OrderedF64(_), ColumnType::OrderedF64)
+            | (Self::OrderedF32(_), ColumnType::OrderedF32) => true,
+            _ => false,
         }
     }
 }
 
+/// Returns `true` if `values` are named as `fields` and are of their types.
+fn are_of_types(values: &[(String, DataTypeValue)], fields: &[FieldType]) -> bool {
+    values.len() == fields.len()
+        && values
+            .iter()
+            .zip(fields)
+            .all(|((name, value), field)| name == &field.name && value.is_of_type(&field.ty))
+}
+
 impl FromStr for DataTypeValue {
-    type Err = ();
+    type Err = eyre::Report;
+
+    /// Returns default value of the type described by `s`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<ColumnType>()?.default_value())
+    }
+}
+
+/// Logical type of the row's column. It's displayed and parsed in the same
+/// form as types are described in the legacy `row_schema`. Nested structs
+/// are described as `{x: u32, y: String}`, tuples as `(u32, String)` and
+/// enums as `enum {A, B(u32), C {x: u8} = 5}`.
+#[derive(Archive, Clone, Debug, Deserialize, Display, Eq, Hash, PartialEq, Serialize)]
+#[rkyv(derive(Debug))]
+#[rkyv(serialize_bounds(
+    __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+    __S::Error: rkyv::rancor::Source,
+))]
+#[rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))]
+#[rkyv(bytecheck(bounds(
+    __C: rkyv::validation::ArchiveContext,
+    __C::Error: rkyv::rancor::Source,
+)))]
+pub enum ColumnType {
+    #[display("String")]
+    String,
+    #[display("i128")]
+    I128,
+    #[display("i64")]
+    I64,
+    #[display("i32")]
+    I32,
+    #[display("i16")]
+    I16,
+    #[display("i8")]
+    I8,
+    #[display("u128")]
+    U128,
+    #[display("u64")]
+    U64,
+    #[display("u32")]
+    U32,
+    #[display("u16")]
+    U16,
+    #[display("u8")]
+    U8,
+    #[display("f64")]
+    F64,
+    #[display("f32")]
+    F32,
+    #[display("bool")]
+    Bool,
+    #[display("char")]
+    Char,
+    #[display("Uuid")]
+    Uuid,
+    #[display("OrderedFloat<f64>")]
+    OrderedF64,
+    #[display("OrderedFloat<f32>")]
+    OrderedF32,
+    /// `PackedNanoid` of `chars` characters packed into `bytes` bytes. It's
+    /// alphabet is not needed to read packed bytes, so it's not stored.
+    #[display("PackedNanoid<{chars}, {bytes}>")]
+    PackedNanoid { chars: u32, bytes: u32 },
+    #[display("Option<{_0}>")]
+    Option(#[rkyv(omit_bounds)] Box<ColumnType>),
+    #[display("Vec<{_0}>")]
+    Vec(#[rkyv(omit_bounds)] Box<ColumnType>),
+    /// Struct archived with `#[repr(C)]` layout. Tuple is a struct which
+    /// fields are named by their positions.
+    #[display("{}", display_fields(_0.iter().map(|f| (f.name.as_str(), &f.ty))))]
+    Struct(#[rkyv(omit_bounds)] Vec<FieldType>),
+    /// Enum archived with `#[repr(u8)]` layout, which `rkyv` uses for all
+    /// archived enums: `u8` tag followed by the variant's fields as in
+    /// `#[repr(C)]` struct.
+    #[display("{}", display_variants(_0))]
+    Enum(#[rkyv(omit_bounds)] Vec<VariantType>),
+}
+
+/// Field of the struct or enum variant's column type.
+#[derive(Archive, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[rkyv(derive(Debug))]
+#[rkyv(serialize_bounds(
+    __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+    __S::Error: rkyv::rancor::Source,
+))]
+#[rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))]
+#[rkyv(bytecheck(bounds(
+    __C: rkyv::validation::ArchiveContext,
+    __C::Error: rkyv::rancor::Source,
+)))]
+pub struct FieldType {
+    pub name: String,
+    #[rkyv(omit_bounds)]
+    pub ty: ColumnType,
+}
+
+impl FieldType {
+    pub fn new<S: Into<String>>(name: S, ty: ColumnType) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// Variant of the enum's column type.
+#[derive(Archive, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[rkyv(derive(Debug))]
+#[rkyv(serialize_bounds(
+    __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+    __S::Error: rkyv::rancor::Source,
+))]
+#[rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))]
+#[rkyv(bytecheck(bounds(
+    __C: rkyv::validation::ArchiveContext,
+    __C::Error: rkyv::rancor::Source,
+)))]
+pub struct VariantType {
+    pub name: String,
+    /// Value of the archived enum's tag for this variant.
+    pub discriminant: u8,
+    #[rkyv(omit_bounds)]
+    pub fields: Vec<FieldType>,
+}
+
+/// Displays fields as `(a, b)` if they are named by their positions and as
+/// `{x: a, y: b}` otherwise.
+fn display_fields<'a, T: std::fmt::Display + 'a>(
+    fields: impl Iterator<Item = (&'a str, &'a T)> + Clone,
+) -> String {
+    if is_tuple(fields.clone().map(|(name, _)| name)) {
+        let fields = fields.map(|(_, v)| v.to_string()).collect::<Vec<_>>();
+        format!("({})", fields.join(", "))
+    } else {
+        let fields = fields
+            .map(|(name, v)| format!("{name}: {v}"))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+fn display_variant<'a, T: std::fmt::Display + 'a>(
+    name: &str,
+    fields: impl Iterator<Item = (&'a str, &'a T)> + Clone,
+) -> String {
+    if fields.clone().next().is_none() {
+        return name.to_string();
+    }
+    let fields = display_fields(fields);
+    if fields.starts_with('(') {
+        format!("{name}{fields}")
+    } else {
+        format!("{name} {fields}")
+    }
+}
+
+/// Displays variants as `enum {A, B(u32) = 5}`, discriminant is displayed
+/// only if it doesn't follow previous variant's one.
+fn display_variants(variants: &[VariantType]) -> String {
+    let mut next = 0;
+    let variants = variants
+        .iter()
+        .map(|v| {
+            let mut s = display_variant(&v.name, v.fields.iter().map(|f| (f.name.as_str(), &f.ty)));
+            if v.discriminant as u16 != next {
+                s = format!("{s} = {}", v.discriminant)
+            }
+            next = v.discriminant as u16 + 1;
+            s
+        })
+        .collect::<Vec<_>>();
+    format!("enum {{{}}}", variants.join(", "))
+}
+
+fn is_tuple<'a>(names: impl Iterator<Item = &'a str>) -> bool {
+    names.enumerate().all(|(i, name)| name == i.to_string())
+}
+
+impl ColumnType {
+    /// Returns default value of the type.
+    pub fn default_value(&self) -> DataTypeValue {
+        match self {
+            Self::String => String::default().into(),
+            Self::I128 => i128::default().into(),
+            Self::I64 => i64::default().into(),
+            Self::I32 => i32::default().into(),
+            Self::I16 => i16::default().into(),
+            Self::I8 => i8::default().into(),
+            Self::U128 => u128::default().into(),
+            Self::U64 => u64::default().into(),
+            Self::U32 => u32::default().into(),
+            Self::U16 => u16::default().into(),
+            Self::U8 => u8::default().into(),
+            Self::F64 => f64::default().into(),
+            Self::F32 => f32::default().into(),
+            Self::Bool => bool::default().into(),
+            Self::Char => char::default().into(),
+            Self::Uuid => Uuid::default().into(),
+            Self::OrderedF64 => OrderedFloat::<f64>::default().into(),
+            Self::OrderedF32 => OrderedFloat::<f32>::default().into(),
+            Self::PackedNanoid { bytes, .. } => {
+                DataTypeValue::PackedNanoid(vec![0; *bytes as usize])
+            }
+            Self::Option(_) => DataTypeValue::Option(None),
+            Self::Vec(_) => DataTypeValue::Vec(vec![]),
+            Self::Struct(fields) => DataTypeValue::Struct(default_fields(fields)),
+            // Enum's first variant is used as default.
+            Self::Enum(variants) => DataTypeValue::Enum {
+                variant: variants[0].name.clone(),
+                fields: default_fields(&variants[0].fields),
+            },
+        }
+    }
+
+    /// Returns [`DataType`] of the type which is not composite.
+    ///
+    /// # Panics
+    ///
+    /// Panics for `PackedNanoid`, `Option`, `Vec`, struct and enum types, as
+    /// their layout depends on type's parameters.
+    fn scalar(&self) -> &'static dyn DataType {
+        static EMPTY_STRING: String = String::new();
+        static NIL_UUID: Uuid = Uuid::nil();
+        match self {
+            Self::String => &EMPTY_STRING,
+            Self::I128 => &0i128,
+            Self::I64 => &0i64,
+            Self::I32 => &0i32,
+            Self::I16 => &0i16,
+            Self::I8 => &0i8,
+            Self::U128 => &0u128,
+            Self::U64 => &0u64,
+            Self::U32 => &0u32,
+            Self::U16 => &0u16,
+            Self::U8 => &0u8,
+            Self::F64 => &0f64,
+            Self::F32 => &0f32,
+            Self::Bool => &false,
+            Self::Char => &'\0',
+            Self::Uuid => &NIL_UUID,
+            Self::OrderedF64 => &OrderedFloat(0f64),
+            Self::OrderedF32 => &OrderedFloat(0f32),
+            Self::PackedNanoid { .. }
+            | Self::Option(_)
+            | Self::Vec(_)
+            | Self::Struct(_)
+            | Self::Enum(_) => {
+                unreachable!("composite types are handled separately")
+            }
+        }
+    }
+}
+
+fn default_fields(fields: &[FieldType]) -> Vec<(String, DataTypeValue)> {
+    fields
+        .iter()
+        .map(|f| (f.name.clone(), f.ty.default_value()))
+        .collect()
+}
+
+impl FromStr for ColumnType {
+    type Err = eyre::Report;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let unsupported = || eyre!("Data type `{}` is not supported", s);
+        if let Some(inner) = generic_args(s, "Option") {
+            let inner = inner.parse().map_err(|_| unsupported())?;
+            return Ok(Self::Option(Box::new(inner)));
+        }
+        if let Some(inner) = generic_args(s, "Vec") {
+            let inner = inner.parse().map_err(|_| unsupported())?;
+            return Ok(Self::Vec(Box::new(inner)));
+        }
+        if let Some(inner) = generic_args(s, "OrderedFloat") {
+            return Ok(match inner.trim() {
+                "f64" => Self::OrderedF64,
+                "f32" => Self::OrderedF32,
+                _ => bail!("Data type `{}` is not supported", s),
+            });
+        }
+        if let Some(args) = generic_args(s, "PackedNanoid") {
+            // Alphabet can follow characters and bytes count.
+            let args = args.split(',').map(str::trim).collect::<Vec<_>>();
+            let (Some(Ok(chars)), Some(Ok(bytes))) = (
+                args.first().map(|a| a.parse()),
+                args.get(1).map(|a| a.parse()),
+            ) else {
+                bail!("Data type `{}` is not supported", s)
+            };
+            if args.len() > 3 {
+                bail!("Data type `{}` is not supported", s)
+            }
+            return Ok(Self::PackedNanoid { chars, bytes });
+        }
+        if let Some(variants) = s.strip_prefix("enum") {
+            let variants = variants
+                .trim_start()
+                .strip_prefix('{')
+                .and_then(|v| v.strip_suffix('}'))
+                .and_then(parse_variants)
+                .ok_or_else(unsupported)?;
+            return Ok(Self::Enum(variants));
+        }
+        if let Some(fields) = parse_fields(s) {
+            return Ok(Self::Struct(fields.ok_or_else(unsupported)?));
+        }
+
         Ok(match s {
-            "String" => String::default().into(),
-            "i128" => i128::default().into(),
-            "i64" => i64::default().into(),
-            "i32" => i32::default().into(),
-            "i16" => i16::default().into(),
-            "i8" => i8::default().into(),
-            "u128" => u128::default().into(),
-            "u64" => u64::default().into(),
-            "u32" => u32::default().into(),
-            "u16" => u16::default().into(),
-            "u8" => u8::default().into(),
-            "f64" => f64::default().into(),
-            "f32" => f32::default().into(),
-            _ => unreachable!(),
+            "String" => Self::String,
+            "i128" => Self::I128,
+            "i64" => Self::I64,
+            "i32" => Self::I32,
+            "i16" => Self::I16,
+            "i8" => Self::I8,
+            "u128" => Self::U128,
+            "u64" => Self::U64,
+            "u32" => Self::U32,
+            "u16" => Self::U16,
+            "u8" => Self::U8,
+            "f64" => Self::F64,
+            "f32" => Self::F32,
+            "bool" => Self::Bool,
+            "char" => Self::Char,
+            "Uuid" | "uuid::Uuid" => Self::Uuid,
+            s => bail!("Data type `{}` is not supported", s),
         })
     }
 }
 
-impl DataType for String {
-    fn advance_accum(&self, accum: &mut usize) {
-        *accum = advance_accum_for_padding(*accum, 4);
-        *accum += size_of::<ArchivedString>();
+/// Parses struct's fields if `s` is `{x: a, y: b}` or `(a, b)`. Returns
+/// `None` if `s` is not a struct and `Some(None)` if it's invalid.
+fn parse_fields(s: &str) -> Option<Option<Vec<FieldType>>> {
+    if let Some(fields) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        let fields = split_top_level(fields)
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| Some(FieldType::new(i.to_string(), ty.parse().ok()?)))
+            .collect();
+        return Some(fields);
+    }
+    let fields = s.strip_prefix('{')?.strip_suffix('}')?;
+    let fields = split_top_level(fields)
+        .into_iter()
+        .map(|field| {
+            let (name, ty) = field.split_once(':')?;
+            let name = name.trim();
+            is_identifier(name).then_some(FieldType::new(name, ty.parse().ok()?))
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|fields| has_unique_names(fields.iter().map(|f| f.name.as_str())));
+    Some(fields)
+}
+
+/// Parses enum's variants described as `A, B(a, b), C {x: a} = 5`.
+fn parse_variants(s: &str) -> Option<Vec<VariantType>> {
+    let mut next = 0u16;
+    let variants = split_top_level(s)
+        .into_iter()
+        .map(|variant| {
+            // `=` of the nested enum's discriminant is followed by brackets.
+            let discriminant = variant
+                .rsplit_once('=')
+                .filter(|(_, d)| !d.contains(['>', ')', '}']));
+            let (variant, discriminant) = match discriminant {
+                Some((variant, discriminant)) => {
+                    (variant.trim(), discriminant.trim().parse().ok()?)
+                }
+                None => (variant, u8::try_from(next).ok()?),
+            };
+            next = discriminant as u16 + 1;
+            let name_len = variant
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(variant.len());
+            let (name, fields) = variant.split_at(name_len);
+            let fields = match fields.trim() {
+                "" => vec![],
+                fields => parse_fields(fields)??,
+            };
+            is_identifier(name).then(|| VariantType {
+                name: name.to_string(),
+                discriminant,
+                fields,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let is_valid = !variants.is_empty()
+        && has_unique_names(variants.iter().map(|v| v.name.as_str()))
+        && variants.iter().enumerate().all(|(i, v)| {
+            variants[..i]
+                .iter()
+                .all(|p| p.discriminant != v.discriminant)
+        });
+    is_valid.then_some(variants)
+}
+
+/// Splits `s` by commas which are not nested into brackets. Trailing comma
+/// is allowed.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = s[start..].trim();
+    if !last.is_empty() {
+        parts.push(last)
+    }
+    parts
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn has_unique_names<'a>(names: impl Iterator<Item = &'a str> + Clone) -> bool {
+    names
+        .clone()
+        .enumerate()
+        .all(|(i, name)| names.clone().take(i).all(|n| n != name))
+}
+
+/// Returns arguments of the generic type `s` if it's `name<args>`.
+fn generic_args<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('<')?
+        .strip_suffix('>')
+}
+
+impl DataType for ColumnType {
+    fn archived_size(&self) -> usize {
+        match self {
+            Self::PackedNanoid { bytes, .. } => *bytes as usize,
+            Self::Option(inner) => option_size(inner.as_ref()),
+            Self::Vec(_) => size_of::<ArchivedVec<u8>>(),
+            Self::Struct(fields) => struct_size(0, fields, self.archived_align()),
+            Self::Enum(variants) => variants
+                .iter()
+                .map(|v| struct_size(1, &v.fields, self.archived_align()))
+                .max()
+                .unwrap_or(1),
+            ty => ty.scalar().archived_size(),
+        }
+    }
+
+    fn archived_align(&self) -> usize {
+        match self {
+            Self::PackedNanoid { .. } => 1,
+            Self::Option(inner) => inner.archived_align(),
+            Self::Vec(_) => align_of::<ArchivedVec<u8>>(),
+            Self::Struct(fields) => fields_align(fields),
+            Self::Enum(variants) => variants
+                .iter()
+                .map(|v| fields_align(&v.fields))
+                .max()
+                .unwrap_or(1),
+            ty => ty.scalar().archived_align(),
+        }
     }
 
     fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue {
-        let current_pointer = advance_pointer_for_padding(pointer, start_pointer, 4);
-        let archived_ptr: *const ArchivedString = current_pointer.cast();
-        unsafe { (*archived_ptr).to_string() }.into()
+        match self {
+            Self::PackedNanoid { bytes, .. } => {
+                packed_nanoid_from_pointer(pointer, *bytes as usize)
+            }
+            Self::Option(inner) => option_from_pointer(inner.as_ref(), pointer, start_pointer),
+            Self::Vec(inner) => vec_from_pointer(inner.as_ref(), pointer, start_pointer),
+            Self::Struct(fields) => {
+                let current_pointer =
+                    advance_pointer_for_padding(pointer, start_pointer, self.archived_align());
+                DataTypeValue::Struct(fields_from_pointer(fields, current_pointer, start_pointer))
+            }
+            Self::Enum(variants) => {
+                let current_pointer =
+                    advance_pointer_for_padding(pointer, start_pointer, self.archived_align());
+                let tag = unsafe { *current_pointer };
+                // Unknown tag is returned as variant's name, so invalid bytes
+                // don't produce invalid values.
+                let Some(variant) = variants.iter().find(|v| v.discriminant == tag) else {
+                    return DataTypeValue::Enum {
+                        variant: tag.to_string(),
+                        fields: vec![],
+                    };
+                };
+                let fields_pointer = unsafe { current_pointer.add(1) };
+                DataTypeValue::Enum {
+                    variant: variant.name.clone(),
+                    fields: fields_from_pointer(&variant.fields, fields_pointer, start_pointer),
+                }
+            }
+            ty => ty.scalar().from_pointer(pointer, start_pointer),
+        }
     }
+}
 
-    fn advance_pointer_for_padding(&self, pointer: &mut *const u8, start_pointer: *const u8) {
-        *pointer = advance_pointer_for_padding(*pointer, start_pointer, 4);
+impl DataType for String {
+    fn archived_size(&self) -> usize {
+        size_of::<ArchivedString>()
     }
 
-    fn advance_pointer(&self, pointer: &mut *const u8) {
-        *pointer = unsafe { pointer.add(size_of::<ArchivedString>()) };
+    fn archived_align(&self) -> usize {
+        align_of::<ArchivedString>()
+    }
+
+    fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue {
+        let current_pointer =
+            advance_pointer_for_padding(pointer, start_pointer, align_of::<ArchivedString>());
+        let archived_ptr: *const ArchivedString = current_pointer.cast();
+        unsafe { (*archived_ptr).to_string() }.into()
     }
 }
 
 macro_rules! impl_datatype {
     ($datatype:ty, $archived_datatype:ty, $datatype_value:expr) => {
         impl DataType for $datatype {
-            fn advance_accum(&self, accum: &mut usize) {
-                *accum = advance_accum_for_padding(*accum, size_of::<$archived_datatype>());
-                *accum += size_of::<$archived_datatype>();
+            fn archived_size(&self) -> usize {
+                size_of::<$archived_datatype>()
+            }
+
+            fn archived_align(&self) -> usize {
+                align_of::<$archived_datatype>()
             }
 
             fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue {
                 let current_pointer = advance_pointer_for_padding(
                     pointer,
                     start_pointer,
-                    size_of::<$archived_datatype>(),
+                    align_of::<$archived_datatype>(),
                 );
                 let archived_ptr: *const $archived_datatype = current_pointer.cast();
 
                 $datatype_value(unsafe { (*archived_ptr) }.into())
             }
-
-            fn advance_pointer_for_padding(
-                &self,
-                pointer: &mut *const u8,
-                start_pointer: *const u8,
-            ) {
-                *pointer = advance_pointer_for_padding(
-                    *pointer,
-                    start_pointer,
-                    size_of::<$archived_datatype>(),
-                );
-            }
-
-            fn advance_pointer(&self, pointer: &mut *const u8) {
-                *pointer = unsafe { pointer.add(size_of::<$archived_datatype>()) };
-            }
         }
     };
 }
@@ -142,3 +682,334 @@ impl_datatype! {u16, ArchivedU16, DataTypeValue::U16}
 impl_datatype! {u8, u8, DataTypeValue::U8}
 impl_datatype! {f64, ArchivedF64, DataTypeValue::F64}
 impl_datatype! {f32, ArchivedF32, DataTypeValue::F32}
+// Archived `bool` and `char` are read as integers, so invalid bytes don't
+// produce invalid values.
+impl_datatype! {bool, u8, |b: u8| DataTypeValue::Bool(b != 0)}
+impl_datatype! {char, ArchivedU32, |c: u32| {
+    DataTypeValue::Char(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER))
+}}
+impl_datatype! {Uuid, Uuid, DataTypeValue::Uuid}
+impl_datatype! {OrderedFloat<f64>, ArchivedF64, |f: f64| DataTypeValue::OrderedF64(OrderedFloat(f))}
+impl_datatype! {OrderedFloat<f32>, ArchivedF32, |f: f32| DataTypeValue::OrderedF32(OrderedFloat(f))}
+
+impl<const N: usize, const B: usize, A: AlphabetPackExt> DataType for PackedNanoid<N, B, A> {
+    fn archived_size(&self) -> usize {
+        B
+    }
+
+    fn archived_align(&self) -> usize {
+        1
+    }
+
+    fn from_pointer(&self, pointer: *const u8, _: *const u8) -> DataTypeValue {
+        packed_nanoid_from_pointer(pointer, B)
+    }
+}
+
+impl<T: DataType + Default> DataType for Option<T> {
+    fn archived_size(&self) -> usize {
+        option_size(&T::default())
+    }
+
+    fn archived_align(&self) -> usize {
+        T::default().archived_align()
+    }
+
+    fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue {
+        option_from_pointer(&T::default(), pointer, start_pointer)
+    }
+}
+
+impl<T: DataType + Default> DataType for Vec<T> {
+    fn archived_size(&self) -> usize {
+        size_of::<ArchivedVec<u8>>()
+    }
+
+    fn archived_align(&self) -> usize {
+        align_of::<ArchivedVec<u8>>()
+    }
+
+    fn from_pointer(&self, pointer: *const u8, start_pointer: *const u8) -> DataTypeValue {
+        vec_from_pointer(&T::default(), pointer, start_pointer)
+    }
+}
+
+fn fields_align(fields: &[FieldType]) -> usize {
+    fields
+        .iter()
+        .map(|f| f.ty.archived_align())
+        .max()
+        .unwrap_or(1)
+}
+
+/// Returns size of the `#[repr(C)]` struct with `fields` placed after
+/// `offset` bytes, padded to struct's `align`.
+fn struct_size(offset: usize, fields: &[FieldType], align: usize) -> usize {
+    let mut accum = offset;
+    for field in fields {
+        field.ty.advance_accum(&mut accum);
+    }
+    advance_accum_for_padding(accum, align)
+}
+
+/// Reads fields of the `#[repr(C)]` struct, first of them is placed at
+/// `pointer` or after it's padding.
+fn fields_from_pointer(
+    fields: &[FieldType],
+    pointer: *const u8,
+    start_pointer: *const u8,
+) -> Vec<(String, DataTypeValue)> {
+    let mut current_pointer = pointer;
+    fields
+        .iter()
+        .map(|field| {
+            let value = field.ty.from_pointer(current_pointer, start_pointer);
+            field
+                .ty
+                .advance_pointer_for_padding(&mut current_pointer, start_pointer);
+            field.ty.advance_pointer(&mut current_pointer);
+            (field.name.clone(), value)
+        })
+        .collect()
+}
+
+fn packed_nanoid_from_pointer(pointer: *const u8, bytes: usize) -> DataTypeValue {
+    let packed = unsafe { std::slice::from_raw_parts(pointer, bytes) };
+    DataTypeValue::PackedNanoid(packed.to_vec())
+}
+
+/// Archived `Option` is `u8` tag followed by the value aligned to it's
+/// alignment.
+fn option_size(inner: &dyn DataType) -> usize {
+    inner.archived_align() + inner.archived_size()
+}
+
+fn option_from_pointer(
+    inner: &dyn DataType,
+    pointer: *const u8,
+    start_pointer: *const u8,
+) -> DataTypeValue {
+    let current_pointer =
+        advance_pointer_for_padding(pointer, start_pointer, inner.archived_align());
+    if unsafe { *current_pointer } == 0 {
+        return DataTypeValue::Option(None);
+    }
+    let value_pointer = unsafe { current_pointer.add(inner.archived_align()) };
+    let value = inner.from_pointer(value_pointer, start_pointer);
+    DataTypeValue::Option(Some(Box::new(value)))
+}
+
+/// Archived `Vec` is relative pointer to the elements followed by their
+/// count. Elements are stored before the root struct.
+fn vec_from_pointer(
+    inner: &dyn DataType,
+    pointer: *const u8,
+    start_pointer: *const u8,
+) -> DataTypeValue {
+    let current_pointer =
+        advance_pointer_for_padding(pointer, start_pointer, align_of::<ArchivedVec<u8>>());
+    let offset: i32 = unsafe { *current_pointer.cast::<ArchivedI32>() }.into();
+    let len: u32 = unsafe { *current_pointer.add(4).cast::<ArchivedU32>() }.into();
+    let elements_pointer = unsafe { current_pointer.offset(offset as isize) };
+    let values = (0..len as usize)
+        .map(|i| {
+            let element_pointer = unsafe { elements_pointer.add(i * inner.archived_size()) };
+            inner.from_pointer(element_pointer, elements_pointer)
+        })
+        .collect();
+    DataTypeValue::Vec(values)
+}
+
+macro_rules! impl_try_from_value {
+    ($datatype:ty, $datatype_value:path) => {
+        impl TryFrom<DataTypeValue> for $datatype {
+            type Error = eyre::Report;
+
+            fn try_from(value: DataTypeValue) -> Result<Self, Self::Error> {
+                match value {
+                    $datatype_value(v) => Ok(v),
+                    v => Err(eyre::eyre!(
+                        "Value `{:?}` can not be converted into `{}`",
+                        v,
+                        stringify!($datatype)
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_value! {String, DataTypeValue::String}
+impl_try_from_value! {i128, DataTypeValue::I128}
+impl_try_from_value! {i64, DataTypeValue::I64}
+impl_try_from_value! {i32, DataTypeValue::I32}
+impl_try_from_value! {i16, DataTypeValue::I16}
+impl_try_from_value! {i8, DataTypeValue::I8}
+impl_try_from_value! {u128, DataTypeValue::U128}
+impl_try_from_value! {u64, DataTypeValue::U64}
+impl_try_from_value! {u32, DataTypeValue::U32}
+impl_try_from_value! {u16, DataTypeValue::U16}
+impl_try_from_value! {u8, DataTypeValue::U8}
+impl_try_from_value! {f64, DataTypeValue::F64}
+impl_try_from_value! {f32, DataTypeValue::F32}
+impl_try_from_value! {bool, DataTypeValue::Bool}
+impl_try_from_value! {char, DataTypeValue::Char}
+impl_try_from_value! {Uuid, DataTypeValue::Uuid}
+impl_try_from_value! {OrderedFloat<f64>, DataTypeValue::OrderedF64}
+impl_try_from_value! {OrderedFloat<f32>, DataTypeValue::OrderedF32}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::{ColumnType, DataTypeValue, FieldType, VariantType};
+
+    #[test]
+    fn test_column_type_from_str() {
+        for s in [
+            "u64",
+            "bool",
+            "char",
+            "Uuid",
+            "OrderedFloat<f32>",
+            "PackedNanoid<21, 16>",
+            "Option<String>",
+            "Vec<Option<Vec<OrderedFloat<f64>>>>",
+        ] {
+            assert_eq!(ColumnType::from_str(s).unwrap().to_string(), s);
+        }
+        assert_eq!(
+            ColumnType::from_str("Option< Vec<uuid::Uuid> >").unwrap(),
+            ColumnType::Option(Box::new(ColumnType::Vec(Box::new(ColumnType::Uuid))))
+        );
+        assert_eq!(
+            ColumnType::from_str("PackedNanoid<21, 16, Base64UrlAlphabet>").unwrap(),
+            ColumnType::PackedNanoid {
+                chars: 21,
+                bytes: 16
+            }
+        );
+        for s in [
+            "Vec<u8",
+            "Option<bool, u8>",
+            "OrderedFloat<u8>",
+            "PackedNanoid<21>",
+        ] {
+            assert_eq!(
+                ColumnType::from_str(s).unwrap_err().to_string(),
+                format!("Data type `{}` is not supported", s)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_of_type() {
+        let ty = ColumnType::from_str("Vec<Option<u16>>").unwrap();
+        assert!(ty.default_value().is_of_type(&ty));
+        assert!(DataTypeValue::Vec(vec![
+            DataTypeValue::Option(None),
+            DataTypeValue::Option(Some(Box::new(DataTypeValue::U16(1)))),
+        ])
+        .is_of_type(&ty));
+        assert!(!DataTypeValue::Vec(vec![DataTypeValue::U16(1)]).is_of_type(&ty));
+        assert!(
+            !DataTypeValue::PackedNanoid(vec![0; 15]).is_of_type(&ColumnType::PackedNanoid {
+                chars: 21,
+                bytes: 16
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_column_type_from_str() {
+        for s in [
+            "{x: u32, name: String}",
+            "(u8, Option<String>)",
+            "()",
+            "enum {A, B = 5, C}",
+            "enum {Empty, Circle({x: i16, y: i16}, u64), Rect {min: (i16, i16), max: (i16, i16)} = 7}",
+            "Vec<enum {A(Vec<{x: uuid::Uuid}>)}>",
+            "enum {A {x: enum {B, C = 3}}, D(enum {E = 2}) = 4}",
+        ] {
+            let ty = ColumnType::from_str(s).unwrap();
+            assert_eq!(ColumnType::from_str(&ty.to_string()).unwrap(), ty);
+        }
+        assert_eq!(
+            ColumnType::from_str("enum { A, B(u8) = 3, C { x: u8 }, }").unwrap(),
+            ColumnType::Enum(vec![
+                VariantType {
+                    name: "A".to_string(),
+                    discriminant: 0,
+                    fields: vec![],
+                },
+                VariantType {
+                    name: "B".to_string(),
+                    discriminant: 3,
+                    fields: vec![FieldType::new("0", ColumnType::U8)],
+                },
+                VariantType {
+                    name: "C".to_string(),
+                    discriminant: 4,
+                    fields: vec![FieldType::new("x", ColumnType::U8)],
+                },
+            ])
+        );
+        assert_eq!(
+            ColumnType::from_str("enum {A, B = 3, C {x: u8}}")
+                .unwrap()
+                .to_string(),
+            "enum {A, B = 3, C {x: u8}}"
+        );
+        for s in [
+            "{x: u32, x: u8}",
+            "{x u32}",
+            "{1x: u32}",
+            "(u8, HashMap<u8, u8>)",
+            "enum {}",
+            "enum {A = 1, B = 1}",
+            "enum {A, A}",
+            "enum {A = 255, B}",
+        ] {
+            assert_eq!(
+                ColumnType::from_str(s).unwrap_err().to_string(),
+                format!("Data type `{}` is not supported", s)
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_is_of_type() {
+        let ty = ColumnType::from_str("enum {A, B {x: u8, y: (u16, bool)}}").unwrap();
+        let default = ty.default_value();
+        assert_eq!(default.to_string(), "A");
+        assert!(default.is_of_type(&ty));
+        let value = DataTypeValue::Enum {
+            variant: "B".to_string(),
+            fields: vec![
+                ("x".to_string(), DataTypeValue::U8(1)),
+                (
+                    "y".to_string(),
+                    DataTypeValue::Struct(vec![
+                        ("0".to_string(), DataTypeValue::U16(2)),
+                        ("1".to_string(), DataTypeValue::Bool(true)),
+                    ]),
+                ),
+            ],
+        };
+        assert!(value.is_of_type(&ty));
+        assert_eq!(value.to_string(), "B {x: 1, y: (2, true)}");
+        let unknown = DataTypeValue::Enum {
+            variant: "C".to_string(),
+            fields: vec![],
+        };
+        assert!(!unknown.is_of_type(&ty));
+        let DataTypeValue::Enum { variant, .. } = value else {
+            unreachable!()
+        };
+        let missing_fields = DataTypeValue::Enum {
+            variant,
+            fields: vec![],
+        };
+        assert!(!missing_fields.is_of_type(&ty));
+    }
+}
@@ -0,0 +1,428 @@
+use eyre::bail;
+
+use crate::page::{ColumnSchema, PageId};
+use crate::persistence::data::{
+    compare_column, compare_values, decode_columns, decode_row, ColumnType, DataTypeValue,
+    DecodeError, Predicate, RowLayout,
+};
+use crate::SpaceInfoPage;
+
+/// Conversion of rows written under one schema version to another. Columns
+/// are matched by their ids.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowUpgrade {
+    sources: Vec<ColumnSource>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ColumnSource {
+    /// Value is taken from the source row's column at this position.
+    Column(usize),
+    /// Column doesn't exist in the source row.
+    Default(DataTypeValue),
+}
+
+impl RowUpgrade {
+    /// Returns error if some column has different types in `from` and `to`.
+    pub fn new(from: &[ColumnSchema], to: &[ColumnSchema]) -> eyre::Result<Self> {
+        let mut sources = Vec::with_capacity(to.len());
+        for column in to {
+            match from.iter().position(|c| c.id == column.id) {
+                Some(pos) => {
+                    if from[pos].row_type() != column.row_type() {
+                        bail!(
+                            "Column `{}` has type `{}` instead of `{}`",
+                            column.name,
+                            from[pos].row_type(),
+                            column.row_type()
+                        )
+                    }
+                    sources.push(ColumnSource::Column(pos))
+                }
+                None => sources.push(ColumnSource::Default(
+                    column
+                        .default
+                        .clone()
+                        .unwrap_or_else(|| column.row_type().default_value()),
+                )),
+            }
+        }
+
+        Ok(Self { sources })
+    }
+
+    /// Returns `true` if rows are not changed by the upgrade.
+    pub fn is_identity(&self) -> bool {
+        self.sources
+            .iter()
+            .enumerate()
+            .all(|(i, s)| *s == ColumnSource::Column(i))
+    }
+
+    pub fn apply(&self, row: Vec<DataTypeValue>) -> Vec<DataTypeValue> {
+        if self.is_identity() && row.len() == self.sources.len() {
+            return row;
+        }
+        self.sources
+            .iter()
+            .map(|source| match source {
+                ColumnSource::Column(pos) => row[*pos].clone(),
+                ColumnSource::Default(value) => value.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Reads rows of the space's data pages which were written under any of the
+/// space's schema versions and upgrades them to the current version.
+#[derive(Clone, Debug)]
+pub struct RowReader {
+    /// Schema versions from [`SpaceInfoPage::schema_history`] followed by the
+    /// current one.
+    versions: Vec<VersionReader>,
+}
+
+#[derive(Clone, Debug)]
+struct VersionReader {
+    version: u32,
+    /// Is `None` for the current version.
+    last_page_id: Option<PageId>,
+    layout: RowLayout,
+    types: Vec<ColumnType>,
+    upgrade: RowUpgrade,
+}
+
+impl VersionReader {
+    fn new(
+        version: u32,
+        last_page_id: Option<PageId>,
+        columns: &[ColumnSchema],
+        current: &[ColumnSchema],
+    ) -> eyre::Result<Self> {
+        Ok(Self {
+            version,
+            last_page_id,
+            layout: RowLayout::from_columns(columns)?,
+            types: columns.iter().map(ColumnSchema::row_type).collect(),
+            upgrade: RowUpgrade::new(columns, current)?,
+        })
+    }
+}
+
+impl RowReader {
+    pub fn new<Pk>(space_info: &SpaceInfoPage<Pk>) -> eyre::Result<Self> {
+        let mut versions = Vec::with_capacity(space_info.schema_history.len() + 1);
+        for version in &space_info.schema_history {
+            versions.push(VersionReader::new(
+                version.version,
+                Some(version.last_page_id),
+                &version.columns,
+                &space_info.columns,
+            )?)
+        }
+        versions.push(VersionReader::new(
+            space_info.schema_version,
+            None,
+            &space_info.columns,
+            &space_info.columns,
+        )?);
+
+        Ok(Self { versions })
+    }
+
+    fn version(&self, page_id: PageId) -> &VersionReader {
+        self.versions
+            .iter()
+            .find(|v| v.last_page_id.map(|id| page_id <= id).unwrap_or(true))
+            .expect("current version is always last")
+    }
+
+    /// Returns layout of the rows stored in the data page.
+    pub fn layout(&self, page_id: PageId) -> &RowLayout {
+        &self.version(page_id).layout
+    }
+
+    /// Returns types of the current schema version's columns.
+    pub fn types(&self) -> &[ColumnType] {
+        &self
+            .versions
+            .last()
+            .expect("current version is always last")
+            .types
+    }
+
+    /// Returns version of the schema which rows of the data page were
+    /// written under.
+    pub fn schema_version(&self, page_id: PageId) -> u32 {
+        self.version(page_id).version
+    }
+
+    /// Returns `true` if rows of the data page are written under the current
+    /// schema version.
+    pub fn is_current(&self, page_id: PageId) -> bool {
+        self.version(page_id).last_page_id.is_none()
+    }
+
+    /// Decodes row of the data page and upgrades it to the current schema
+    /// version. `bytes` should be the row found by the page's layout.
+    pub fn read_row(
+        &self,
+        page_id: PageId,
+        bytes: &[u8],
+    ) -> Result<Vec<DataTypeValue>, DecodeError> {
+        let version = self.version(page_id);
+        let row = decode_row(bytes, &version.types)?;
+        Ok(version.upgrade.apply(row))
+    }
+
+    /// Decodes only `columns` of the data page's row, other columns are not
+    /// read. Columns are positions in the current schema version, columns
+    /// that are not stored in the row get their default values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some of `columns` is not a column of the current version.
+    pub fn read_columns(
+        &self,
+        page_id: PageId,
+        bytes: &[u8],
+        columns: &[usize],
+    ) -> Result<Vec<DataTypeValue>, DecodeError> {
+        let version = self.version(page_id);
+        let sources = &version.upgrade.sources;
+        let stored = columns
+            .iter()
+            .filter_map(|column| match &sources[*column] {
+                ColumnSource::Column(pos) => Some(*pos),
+                ColumnSource::Default(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let mut stored = decode_columns(bytes, &version.layout, &stored)?.into_iter();
+        Ok(columns
+            .iter()
+            .map(|column| match &sources[*column] {
+                ColumnSource::Column(_) => stored.next().expect("stored column is decoded"),
+                ColumnSource::Default(value) => value.clone(),
+            })
+            .collect())
+    }
+
+    /// Evaluates `predicate` on the data page's archived row. Predicate's
+    /// columns are positions in the current schema version, columns that
+    /// are not stored in the row are compared by their default values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some compared column is not a column of the current
+    /// version, [`Predicate::validate`] checks it.
+    pub fn matches(
+        &self,
+        page_id: PageId,
+        bytes: &[u8],
+        predicate: &Predicate,
+    ) -> Result<bool, DecodeError> {
+        let version = self.version(page_id);
+        let types = self.types();
+        predicate.evaluate(
+            &mut |column, value| match &version.upgrade.sources[column] {
+                ColumnSource::Column(pos) => compare_column(bytes, &version.layout, *pos, value),
+                ColumnSource::Default(default) => {
+                    Ok(compare_values(&types[column], default, value))
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{Archive, Deserialize, Serialize};
+
+    use super::{RowReader, RowUpgrade};
+    use crate::persistence::data::{ColumnType, CompareOp, DataTypeValue, Predicate};
+    use crate::{ColumnSchema, SchemaChange, SpaceInfoPage};
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct RowV0 {
+        pub id: u64,
+        pub name: String,
+        pub age: u32,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct RowV1 {
+        pub id: u64,
+        pub full_name: String,
+        pub score: f64,
+    }
+
+    fn space_info() -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count: 2,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+                ColumnSchema::new(2, "age", ColumnType::U32),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![],
+            empty_links_list: vec![],
+        }
+    }
+
+    #[test]
+    fn test_row_upgrade() {
+        let from = space_info().columns;
+        let mut info = space_info();
+        info.evolve_schema(&[
+            SchemaChange::DropColumn {
+                name: "age".to_string(),
+            },
+            SchemaChange::AddColumn {
+                name: "score".to_string(),
+                ty: ColumnType::F64,
+                default: None,
+            },
+        ])
+        .unwrap();
+
+        let upgrade = RowUpgrade::new(&from, &info.columns).unwrap();
+        assert!(!upgrade.is_identity());
+        assert_eq!(
+            upgrade.apply(vec![
+                DataTypeValue::U64(1),
+                DataTypeValue::String("a".to_string()),
+                DataTypeValue::U32(30),
+            ]),
+            vec![
+                DataTypeValue::U64(1),
+                DataTypeValue::String("a".to_string()),
+                DataTypeValue::F64(0.0),
+            ]
+        );
+        assert!(RowUpgrade::new(&info.columns, &info.columns)
+            .unwrap()
+            .is_identity());
+
+        let mut changed = from.clone();
+        changed[2].ty = ColumnType::U64;
+        assert!(RowUpgrade::new(&from, &changed).is_err());
+    }
+
+    /// Returns space which first two pages are written under the first
+    /// schema version, `age` column is dropped and `score` is added then.
+    fn evolved_space_info() -> SpaceInfoPage {
+        let mut info = space_info();
+        info.evolve_schema(&[
+            SchemaChange::DropColumn {
+                name: "age".to_string(),
+            },
+            SchemaChange::RenameColumn {
+                from: "name".to_string(),
+                to: "full_name".to_string(),
+            },
+            SchemaChange::AddColumn {
+                name: "score".to_string(),
+                ty: ColumnType::F64,
+                default: Some(DataTypeValue::F64(0.5)),
+            },
+        ])
+        .unwrap();
+        info
+    }
+
+    #[test]
+    fn test_row_reader() {
+        let reader = RowReader::new(&evolved_space_info()).unwrap();
+
+        let old = rkyv::to_bytes::<rkyv::rancor::Error>(&RowV0 {
+            id: 1,
+            name: "first row".to_string(),
+            age: 30,
+        })
+        .unwrap();
+        assert_eq!(reader.schema_version(2.into()), 0);
+        assert!(!reader.is_current(2.into()));
+        assert!(reader
+            .layout(2.into())
+            .row_lengths(&old)
+            .any(|l| l == old.len()));
+        assert_eq!(
+            reader.read_row(2.into(), &old).unwrap(),
+            vec![
+                DataTypeValue::U64(1),
+                DataTypeValue::String("first row".to_string()),
+                DataTypeValue::F64(0.5),
+            ]
+        );
+
+        let new = rkyv::to_bytes::<rkyv::rancor::Error>(&RowV1 {
+            id: 2,
+            full_name: "second row".to_string(),
+            score: 2.5,
+        })
+        .unwrap();
+        assert_eq!(reader.schema_version(3.into()), 1);
+        assert!(reader.is_current(3.into()));
+        assert!(reader
+            .layout(3.into())
+            .row_lengths(&new)
+            .any(|l| l == new.len()));
+        assert_eq!(
+            reader.read_row(3.into(), &new).unwrap(),
+            vec![
+                DataTypeValue::U64(2),
+                DataTypeValue::String("second row".to_string()),
+                DataTypeValue::F64(2.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_reader_projection() {
+        let reader = RowReader::new(&evolved_space_info()).unwrap();
+        let old = rkyv::to_bytes::<rkyv::rancor::Error>(&RowV0 {
+            id: 1,
+            name: "first row".to_string(),
+            age: 30,
+        })
+        .unwrap();
+        let new = rkyv::to_bytes::<rkyv::rancor::Error>(&RowV1 {
+            id: 2,
+            full_name: "second row".to_string(),
+            score: 2.5,
+        })
+        .unwrap();
+
+        assert_eq!(
+            reader.read_columns(2.into(), &old, &[2, 0]).unwrap(),
+            vec![DataTypeValue::F64(0.5), DataTypeValue::U64(1)]
+        );
+        assert_eq!(
+            reader.read_columns(3.into(), &new, &[2, 0]).unwrap(),
+            vec![DataTypeValue::F64(2.5), DataTypeValue::U64(2)]
+        );
+        assert_eq!(reader.read_columns(3.into(), &new, &[]).unwrap(), vec![]);
+
+        let predicate = Predicate::compare(2, CompareOp::Gt, 1.0).or(Predicate::compare(
+            1,
+            CompareOp::Eq,
+            "first row".to_string(),
+        ));
+        assert!(reader.matches(2.into(), &old, &predicate).unwrap());
+        assert!(reader.matches(3.into(), &new, &predicate).unwrap());
+        let predicate = Predicate::compare(2, CompareOp::Lt, 1.0).and(Predicate::compare(
+            0,
+            CompareOp::Ge,
+            1u64,
+        ));
+        assert!(reader.matches(2.into(), &old, &predicate).unwrap());
+        assert!(!reader.matches(3.into(), &new, &predicate).unwrap());
+    }
+}
@@ -21,3 +21,172 @@ pub fn advance_pointer_for_padding(
     }
     current_pointer
 }
+
+#[cfg(test)]
+pub mod test {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use proptest::strategy::Union;
+
+    use crate::persistence::data::{ColumnType, DataTypeValue, FieldType, VariantType};
+
+    /// Returns strategy of the types which nesting is not deeper than `3`.
+    /// Structs and enum variants can have no fields.
+    pub fn column_type() -> impl Strategy<Value = ColumnType> {
+        let scalars = select(vec![
+            ColumnType::String,
+            ColumnType::I128,
+            ColumnType::I64,
+            ColumnType::I32,
+            ColumnType::I16,
+            ColumnType::I8,
+            ColumnType::U128,
+            ColumnType::U64,
+            ColumnType::U32,
+            ColumnType::U16,
+            ColumnType::U8,
+            ColumnType::F64,
+            ColumnType::F32,
+            ColumnType::Bool,
+            ColumnType::Char,
+            ColumnType::Uuid,
+            ColumnType::OrderedF64,
+            ColumnType::OrderedF32,
+            ColumnType::PackedNanoid {
+                chars: 21,
+                bytes: 16,
+            },
+        ]);
+        scalars.prop_recursive(3, 32, 4, |inner| {
+            let structs = |inner: BoxedStrategy<ColumnType>, tuple: bool| {
+                vec(inner, 0..4).prop_map(move |types| ColumnType::Struct(fields(types, tuple)))
+            };
+            let variants =
+                vec((0..3u8, any::<bool>(), vec(inner.clone(), 0..4)), 1..4).prop_map(|variants| {
+                    let mut discriminant = 0;
+                    let variants = variants
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (skip, tuple, types))| {
+                            discriminant += skip;
+                            let variant = VariantType {
+                                name: format!("V{i}"),
+                                discriminant,
+                                fields: fields(types, tuple),
+                            };
+                            discriminant += 1;
+                            variant
+                        })
+                        .collect();
+                    ColumnType::Enum(variants)
+                });
+            prop_oneof![
+                inner
+                    .clone()
+                    .prop_map(|ty| ColumnType::Option(Box::new(ty))),
+                inner.clone().prop_map(|ty| ColumnType::Vec(Box::new(ty))),
+                structs(inner.clone(), false),
+                structs(inner, true),
+                variants,
+            ]
+        })
+    }
+
+    fn fields(types: Vec<ColumnType>, tuple: bool) -> Vec<FieldType> {
+        types
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let name = if tuple {
+                    i.to_string()
+                } else {
+                    format!("f{i}")
+                };
+                FieldType::new(name, ty)
+            })
+            .collect()
+    }
+
+    /// Returns strategy of the values of the type `ty`. Floats are finite.
+    pub fn value(ty: &ColumnType) -> BoxedStrategy<DataTypeValue> {
+        let fields = |fields: &[FieldType]| {
+            let names = fields.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+            fields
+                .iter()
+                .map(|f| value(&f.ty))
+                .collect::<Vec<_>>()
+                .prop_map(move |values| names.iter().cloned().zip(values).collect::<Vec<_>>())
+        };
+        match ty {
+            ColumnType::String => vec(select(vec!['a', 'ж', '€']), 0..41)
+                .prop_map(|chars| chars.into_iter().collect::<String>().into())
+                .boxed(),
+            ColumnType::I128 => any::<i128>().prop_map(Into::into).boxed(),
+            ColumnType::I64 => any::<i64>().prop_map(Into::into).boxed(),
+            ColumnType::I32 => any::<i32>().prop_map(Into::into).boxed(),
+            ColumnType::I16 => any::<i16>().prop_map(Into::into).boxed(),
+            ColumnType::I8 => any::<i8>().prop_map(Into::into).boxed(),
+            ColumnType::U128 => any::<u128>().prop_map(Into::into).boxed(),
+            ColumnType::U64 => any::<u64>().prop_map(Into::into).boxed(),
+            ColumnType::U32 => any::<u32>().prop_map(Into::into).boxed(),
+            ColumnType::U16 => any::<u16>().prop_map(Into::into).boxed(),
+            ColumnType::U8 => any::<u8>().prop_map(Into::into).boxed(),
+            ColumnType::F64 => f64s().prop_map(Into::into).boxed(),
+            ColumnType::F32 => f32s().prop_map(Into::into).boxed(),
+            ColumnType::Bool => any::<bool>().prop_map(Into::into).boxed(),
+            ColumnType::Char => (0..0xd800u32)
+                .prop_map(|c| char::from_u32(c).expect("not surrogate").into())
+                .boxed(),
+            ColumnType::Uuid => any::<u128>()
+                .prop_map(|v| uuid::Uuid::from_u128(v).into())
+                .boxed(),
+            ColumnType::OrderedF64 => f64s()
+                .prop_map(|v| DataTypeValue::OrderedF64(v.into()))
+                .boxed(),
+            ColumnType::OrderedF32 => f32s()
+                .prop_map(|v| DataTypeValue::OrderedF32(v.into()))
+                .boxed(),
+            ColumnType::PackedNanoid { bytes, .. } => vec(any::<u8>(), *bytes as usize)
+                .prop_map(DataTypeValue::PackedNanoid)
+                .boxed(),
+            ColumnType::Option(inner) => proptest::option::of(value(inner))
+                .prop_map(|v| DataTypeValue::Option(v.map(Box::new)))
+                .boxed(),
+            ColumnType::Vec(inner) => vec(value(inner), 0..4).prop_map(DataTypeValue::Vec).boxed(),
+            ColumnType::Struct(f) => fields(f).prop_map(DataTypeValue::Struct).boxed(),
+            ColumnType::Enum(variants) => Union::new(variants.iter().map(|variant| {
+                let name = variant.name.clone();
+                fields(&variant.fields).prop_map(move |fields| DataTypeValue::Enum {
+                    variant: name.clone(),
+                    fields,
+                })
+            }))
+            .boxed(),
+        }
+    }
+
+    fn f64s() -> impl Strategy<Value = f64> {
+        prop::num::f64::NORMAL | prop::num::f64::SUBNORMAL | prop::num::f64::ZERO
+    }
+
+    fn f32s() -> impl Strategy<Value = f32> {
+        prop::num::f32::NORMAL | prop::num::f32::SUBNORMAL | prop::num::f32::ZERO
+    }
+
+    /// Returns strategy of the rows which columns have given `types`.
+    pub fn row(types: &[ColumnType]) -> impl Strategy<Value = Vec<DataTypeValue>> {
+        types.iter().map(value).collect::<Vec<_>>()
+    }
+
+    /// Returns strategy of the columns' types with `rows` rows of them.
+    pub fn rows(
+        columns: std::ops::Range<usize>,
+        rows: std::ops::Range<usize>,
+    ) -> impl Strategy<Value = (Vec<ColumnType>, Vec<Vec<DataTypeValue>>)> {
+        vec(column_type(), columns).prop_flat_map(move |types| {
+            let rows = vec(row(&types), rows.clone());
+            (Just(types), rows)
+        })
+    }
+}
@@ -0,0 +1,397 @@
+//! Export of the space's live rows to CSV and newline delimited JSON.
+//!
+//! Data pages are read one by one, so export keeps only one page in memory.
+//! Rows are upgraded to the current schema version and only exported columns
+//! are decoded.
+//!
+//! JSON values keep their types: numbers are written as numbers, but 64 and
+//! 128 bit integers are written as strings, as JSON parsers read numbers as
+//! `f64` and lose integers wider than 53 bits. Non finite floats are written
+//! as `null`, `Option`'s as their values or `null`, tuples as arrays, structs
+//! as objects and enums as `serde` writes them by default. CSV fields are
+//! written as values are displayed, `None` is an empty field and vectors,
+//! structs and enums are written as JSON.
+
+use std::io::Write;
+
+use eyre::eyre;
+use tokio::fs::File;
+
+use crate::page::{parse_data_page, parse_space_info};
+use crate::persistence::data::{
+    data_page_row_links, parse_data_page_ids, DataTypeValue, RowReader,
+};
+use crate::{SpaceInfoPage, INNER_PAGE_SIZE, PAGE_SIZE};
+
+/// Output format of the exported rows.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// CSV with header line.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Names of the exported columns in output order. All columns are
+    /// exported if it's empty.
+    pub columns: Vec<String>,
+    /// Maximum count of the exported rows.
+    pub limit: Option<usize>,
+}
+
+/// Summary of the exported space.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExportReport {
+    pub pages_read: usize,
+    pub rows: usize,
+}
+
+/// Writes live rows of the space file to `output`. Returns error if some of
+/// the exported columns is not found or some row can not be decoded.
+pub async fn export_space<W: Write>(
+    file: &mut File,
+    options: &ExportOptions,
+    output: &mut W,
+) -> eyre::Result<ExportReport> {
+    let space_info = parse_space_info::<PAGE_SIZE>(file).await?;
+    let reader = RowReader::new(&space_info)?;
+    let positions = column_positions(&space_info, &options.columns)?;
+    let header = positions
+        .iter()
+        .map(|pos| space_info.columns[*pos].name.as_str())
+        .collect::<Vec<_>>();
+    if options.format == ExportFormat::Csv {
+        writeln!(output, "{}", csv_line(header.iter().map(|c| csv_field(c))))?;
+    }
+
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let mut report = ExportReport::default();
+    for index in parse_data_page_ids(file).await? {
+        if report.rows >= limit {
+            break;
+        }
+        let page = parse_data_page::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(file, index).await?;
+        report.pages_read += 1;
+        let page_id = page.header.page_id;
+        let links = data_page_row_links(
+            &page,
+            reader.layout(page_id),
+            space_info.page_empty_links(page_id),
+        )?;
+        for link in links.into_iter().take(limit - report.rows) {
+            let row = reader.read_columns(page_id, page.inner.get_at(link)?, &positions)?;
+            let line = match options.format {
+                ExportFormat::Csv => csv_line(row.iter().map(csv_value)),
+                ExportFormat::Ndjson => json_object(&header, &row),
+            };
+            writeln!(output, "{}", line)?;
+            report.rows += 1;
+        }
+    }
+    output.flush()?;
+
+    Ok(report)
+}
+
+/// Returns positions of the exported `columns`, all columns are exported if
+/// `columns` is empty.
+pub(crate) fn column_positions(
+    space_info: &SpaceInfoPage,
+    columns: &[String],
+) -> eyre::Result<Vec<usize>> {
+    if columns.is_empty() {
+        return Ok((0..space_info.columns.len()).collect());
+    }
+    columns
+        .iter()
+        .map(|name| {
+            space_info
+                .columns
+                .iter()
+                .position(|c| &c.name == name)
+                .ok_or_else(|| eyre!("Column `{}` is not found", name))
+        })
+        .collect()
+}
+
+/// Returns JSON object with the row's values named by `header`.
+pub fn json_object<S: AsRef<str>>(header: &[S], row: &[DataTypeValue]) -> String {
+    let fields = header
+        .iter()
+        .zip(row)
+        .map(|(column, value)| format!("{}: {}", json_string(column.as_ref()), json_value(value)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Returns JSON value of the value, see module's docs for the types mapping.
+pub(crate) fn json_value(value: &DataTypeValue) -> String {
+    match value {
+        DataTypeValue::String(s) => json_string(s),
+        DataTypeValue::I128(_)
+        | DataTypeValue::I64(_)
+        | DataTypeValue::U128(_)
+        | DataTypeValue::U64(_) => json_string(&value.to_string()),
+        DataTypeValue::F64(f) if !f.is_finite() => "null".to_string(),
+        DataTypeValue::F32(f) if !f.is_finite() => "null".to_string(),
+        DataTypeValue::OrderedF64(f) if !f.is_finite() => "null".to_string(),
+        DataTypeValue::OrderedF32(f) if !f.is_finite() => "null".to_string(),
+        DataTypeValue::Char(_) | DataTypeValue::Uuid(_) | DataTypeValue::PackedNanoid(_) => {
+            json_string(&value.to_string())
+        }
+        DataTypeValue::Option(value) => value
+            .as_ref()
+            .map(|v| json_value(v))
+            .unwrap_or_else(|| "null".to_string()),
+        DataTypeValue::Vec(values) => format!(
+            "[{}]",
+            values.iter().map(json_value).collect::<Vec<_>>().join(", ")
+        ),
+        DataTypeValue::Struct(fields) => json_fields(fields),
+        // Enums are written as `serde` does by default: unit variant as it's
+        // name and variant with fields as object with single key.
+        DataTypeValue::Enum { variant, fields } if fields.is_empty() => json_string(variant),
+        DataTypeValue::Enum { variant, fields } => {
+            format!("{{{}: {}}}", json_string(variant), json_fields(fields))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Writes fields named by their positions as array and other fields as
+/// object.
+fn json_fields(fields: &[(String, DataTypeValue)]) -> String {
+    if fields
+        .iter()
+        .enumerate()
+        .all(|(i, (name, _))| *name == i.to_string())
+    {
+        let values = fields
+            .iter()
+            .map(|(_, v)| json_value(v))
+            .collect::<Vec<_>>();
+        return format!("[{}]", values.join(", "));
+    }
+    let fields = fields
+        .iter()
+        .map(|(name, v)| format!("{}: {}", json_string(name), json_value(v)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Returns JSON string literal of `s`. It's used by the tools' JSON reports
+/// too, so all JSON output is escaped the same way.
+pub fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Returns CSV field of the value. `None` is written as empty field, values
+/// which are displayed ambiguously are written as JSON.
+pub fn csv_value(value: &DataTypeValue) -> String {
+    match value {
+        DataTypeValue::String(s) => csv_field(s),
+        DataTypeValue::Option(None) => String::new(),
+        DataTypeValue::Option(Some(value)) => csv_value(value),
+        DataTypeValue::Vec(_) | DataTypeValue::Struct(_) | DataTypeValue::Enum { .. } => {
+            csv_field(&json_value(value))
+        }
+        value => csv_field(&value.to_string()),
+    }
+}
+
+/// Quotes the field if it contains delimiter, quote or line break.
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    fields.collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use tokio::fs::File;
+
+    use super::{export_space, json_value, ExportFormat, ExportOptions, ExportReport};
+    use crate::persistence::data::{encode_row, persist_data_rows, ColumnType, DataTypeValue};
+    use crate::{ColumnSchema, SpaceInfoPage};
+
+    async fn write_space(filename: &str, rows: usize) {
+        let columns = [
+            ("id", "u64"),
+            ("name", "String"),
+            ("score", "f64"),
+            ("parent", "Option<u32>"),
+            ("tags", "Vec<String>"),
+            ("point", "(i16, bool)"),
+        ];
+        let info = SpaceInfoPage {
+            id: 1.into(),
+            page_count: 0,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: columns
+                .iter()
+                .enumerate()
+                .map(|(i, (name, ty))| {
+                    ColumnSchema::new(i as u32, *name, ColumnType::from_str(ty).unwrap())
+                })
+                .collect(),
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![],
+            empty_links_list: vec![],
+        };
+        let types = info
+            .columns
+            .iter()
+            .map(|c| c.ty.clone())
+            .collect::<Vec<_>>();
+        let rows = (0..rows as u64)
+            .map(|id| {
+                let row = vec![
+                    id.into(),
+                    match id {
+                        0 => "comma, \"quoted\"\nline".to_string(),
+                        id => format!("row {id}"),
+                    }
+                    .into(),
+                    if id == 1 { f64::NAN } else { id as f64 / 2.0 }.into(),
+                    DataTypeValue::Option((id % 2 == 0).then(|| Box::new((id as u32).into()))),
+                    DataTypeValue::Vec(vec!["a,b".to_string().into(); id as usize % 3]),
+                    DataTypeValue::Struct(vec![
+                        ("0".to_string(), (-(id as i16)).into()),
+                        ("1".to_string(), (id > 1).into()),
+                    ]),
+                ];
+                let bytes = encode_row(&types, &row).unwrap();
+                (bytes, row)
+            })
+            .collect::<Vec<_>>();
+
+        let mut file = File::create(filename).await.unwrap();
+        let rows = rows.iter().map(|(b, row)| (b.as_slice(), row.clone()));
+        persist_data_rows(&mut file, &info, rows).await.unwrap();
+    }
+
+    async fn export(filename: &str, options: ExportOptions) -> (ExportReport, String) {
+        let mut file = File::open(filename).await.unwrap();
+        let mut output = vec![];
+        let report = export_space(&mut file, &options, &mut output)
+            .await
+            .unwrap();
+        (report, String::from_utf8(output).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_csv() {
+        let filename = "tests/data/export_csv.wt";
+        write_space(filename, 3).await;
+        let (report, csv) = export(filename, ExportOptions::default()).await;
+        assert_eq!(report.rows, 3);
+        assert_eq!(
+            csv,
+            "id,name,score,parent,tags,point\n\
+             0,\"comma, \"\"quoted\"\"\nline\",0,0,[],\"[0, false]\"\n\
+             1,row 1,NaN,,\"[\"\"a,b\"\"]\",\"[-1, false]\"\n\
+             2,row 2,1,2,\"[\"\"a,b\"\", \"\"a,b\"\"]\",\"[-2, true]\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_ndjson() {
+        let filename = "tests/data/export_ndjson.wt";
+        write_space(filename, 3).await;
+        let options = ExportOptions {
+            format: ExportFormat::Ndjson,
+            ..ExportOptions::default()
+        };
+        let (_, json) = export(filename, options).await;
+        assert_eq!(
+            json,
+            "{\"id\": \"0\", \"name\": \"comma, \\\"quoted\\\"\\nline\", \"score\": 0, \
+             \"parent\": 0, \"tags\": [], \"point\": [0, false]}\n\
+             {\"id\": \"1\", \"name\": \"row 1\", \"score\": null, \
+             \"parent\": null, \"tags\": [\"a,b\"], \"point\": [-1, false]}\n\
+             {\"id\": \"2\", \"name\": \"row 2\", \"score\": 1, \
+             \"parent\": 2, \"tags\": [\"a,b\", \"a,b\"], \"point\": [-2, true]}\n"
+        );
+    }
+
+    #[test]
+    fn test_json_wide_integers() {
+        assert_eq!(
+            json_value(&DataTypeValue::U64(u64::MAX)),
+            "\"18446744073709551615\""
+        );
+        assert_eq!(json_value(&DataTypeValue::I128(-1)), "\"-1\"");
+        assert_eq!(json_value(&DataTypeValue::U32(u32::MAX)), "4294967295");
+        assert_eq!(json_value(&DataTypeValue::I32(-1)), "-1");
+    }
+
+    #[tokio::test]
+    async fn test_export_columns_and_limit() {
+        let filename = "tests/data/export_columns.wt";
+        write_space(filename, 2000).await;
+        let options = ExportOptions {
+            format: ExportFormat::Ndjson,
+            columns: vec!["name".to_string(), "id".to_string()],
+            limit: Some(3),
+        };
+        let (report, json) = export(filename, options).await;
+        assert_eq!(
+            report,
+            ExportReport {
+                pages_read: 1,
+                rows: 3
+            }
+        );
+        assert_eq!(
+            json.lines().nth(2),
+            Some("{\"name\": \"row 2\", \"id\": \"2\"}")
+        );
+
+        let (report, csv) = export(filename, ExportOptions::default()).await;
+        assert_eq!(report.rows, 2000);
+        assert!(report.pages_read > 1);
+        // Name of the first row has line break.
+        assert_eq!(csv.lines().count(), 2002);
+        assert!(csv.ends_with("1999,row 1999,999.5,,\"[\"\"a,b\"\"]\",\"[-1999, true]\"\n"));
+
+        let options = ExportOptions {
+            columns: vec!["unknown".to_string()],
+            ..ExportOptions::default()
+        };
+        let mut file = File::open(filename).await.unwrap();
+        let err = export_space(&mut file, &options, &mut vec![])
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Column `unknown` is not found");
+    }
+}
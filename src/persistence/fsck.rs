@@ -0,0 +1,842 @@
+//! File level consistency check of the space's data and index files.
+//!
+//! Every page's [`GeneralHeader`] is validated and checked to have the file's
+//! `data_version` and `space_id`, it's position as `page_id` and a
+//! [`PageType`] that is expected in this kind of file. `previous_id` and
+//! `next_id` of the pages should point to each other. Pages' data is parsed
+//! with the page's type: data pages are split into rows, index and table of
+//! contents pages are read with the index's key type. All found problems are
+//! collected into [`FileReport`] instead of failing on first of them.
+
+use std::fmt::Debug;
+use std::io::SeekFrom;
+
+use rkyv::api::high::{HighDeserializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::page::PageId;
+use crate::persistence::data::{data_page_row_links, ColumnType, RowReader};
+use crate::{
+    space, BloomFilterPage, DataPage, GeneralHeader, GeneralPage, IndexPage, PageType, Persistable,
+    PrefixIndexPage, SizeMeasurable, SpaceInfoPage, TableOfContentsPage, UnsizedIndexPage,
+    VariableSizeMeasurable, DATA_VERSION, GENERAL_HEADER_SIZE, INNER_PAGE_SIZE, PAGE_SIZE,
+};
+
+/// Kind of the checked file. It's determined by the file's [`SpaceInfoPage`]:
+/// index files have no `row_schema` and describe only their own index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileKind {
+    Data,
+    Index,
+}
+
+impl FileKind {
+    fn of(space_info: &SpaceInfoPage) -> Self {
+        if space_info.columns.is_empty() && space_info.secondary_index_schemas.len() == 1 {
+            FileKind::Index
+        } else {
+            FileKind::Data
+        }
+    }
+
+    fn allows(&self, page_type: PageType) -> bool {
+        match self {
+            FileKind::Data => matches!(page_type, PageType::Empty | PageType::Data),
+            FileKind::Index => matches!(
+                page_type,
+                PageType::Empty
+                    | PageType::Index
+                    | PageType::IndexUnsized
+                    | PageType::IndexTableOfContents
+                    | PageType::IndexPrefixCompressed
+                    | PageType::IndexBloomFilter
+            ),
+        }
+    }
+}
+
+/// Problem found in the file. `page_id` is the page's position in the file,
+/// which can differ from the header's `page_id`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileViolation {
+    /// File has no pages at all.
+    EmptyFile,
+    /// File ends before the page's header or data.
+    TruncatedPage {
+        page_id: PageId,
+        expected: u64,
+        actual: u64,
+    },
+    /// Page's header bytes are not a valid archived [`GeneralHeader`].
+    InvalidHeader { page_id: PageId, reason: String },
+    /// Header's `data_version` is not known by this version of the crate.
+    UnsupportedDataVersion { page_id: PageId, data_version: u32 },
+    /// Header's `data_version` differs from the [`SpaceInfoPage`]'s one.
+    DataVersionMismatch {
+        page_id: PageId,
+        data_version: u32,
+        expected: u32,
+    },
+    /// Header's `space_id` differs from [`SpaceInfoPage::id`].
+    SpaceIdMismatch {
+        page_id: PageId,
+        space_id: space::Id,
+        expected: space::Id,
+    },
+    /// Header's `page_id` is not equal to the page's position in the file.
+    PageIdMismatch {
+        page_id: PageId,
+        header_page_id: PageId,
+    },
+    /// Page's type is not expected at this position or in this kind of file.
+    UnexpectedPageType {
+        page_id: PageId,
+        page_type: PageType,
+    },
+    /// Header's `data_length` is bigger than page's capacity.
+    DataLengthOutOfBounds { page_id: PageId, data_length: u32 },
+    /// Page that is set as `next_id` is not found or has other `previous_id`.
+    BrokenNextLink { page_id: PageId, next_id: PageId },
+    /// Page that is set as `previous_id` is not found or has other `next_id`.
+    BrokenPreviousLink {
+        page_id: PageId,
+        previous_id: PageId,
+    },
+    /// [`SpaceInfoPage::page_count`] is not equal to count of the pages that
+    /// follow [`SpaceInfoPage`] in the file.
+    PageCountMismatch { page_count: u32, actual: u32 },
+    /// Page's data can not be parsed with the page's type.
+    PageNotParsed {
+        page_id: PageId,
+        page_type: PageType,
+        reason: String,
+    },
+}
+
+impl FileViolation {
+    /// Returns name of the violation's kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FileViolation::EmptyFile => "EmptyFile",
+            FileViolation::TruncatedPage { .. } => "TruncatedPage",
+            FileViolation::InvalidHeader { .. } => "InvalidHeader",
+            FileViolation::UnsupportedDataVersion { .. } => "UnsupportedDataVersion",
+            FileViolation::DataVersionMismatch { .. } => "DataVersionMismatch",
+            FileViolation::SpaceIdMismatch { .. } => "SpaceIdMismatch",
+            FileViolation::PageIdMismatch { .. } => "PageIdMismatch",
+            FileViolation::UnexpectedPageType { .. } => "UnexpectedPageType",
+            FileViolation::DataLengthOutOfBounds { .. } => "DataLengthOutOfBounds",
+            FileViolation::BrokenNextLink { .. } => "BrokenNextLink",
+            FileViolation::BrokenPreviousLink { .. } => "BrokenPreviousLink",
+            FileViolation::PageCountMismatch { .. } => "PageCountMismatch",
+            FileViolation::PageNotParsed { .. } => "PageNotParsed",
+        }
+    }
+
+    /// Returns page where violation was found. File level violations have no
+    /// page.
+    pub fn page_id(&self) -> Option<PageId> {
+        match self {
+            FileViolation::EmptyFile | FileViolation::PageCountMismatch { .. } => None,
+            FileViolation::TruncatedPage { page_id, .. }
+            | FileViolation::InvalidHeader { page_id, .. }
+            | FileViolation::UnsupportedDataVersion { page_id, .. }
+            | FileViolation::DataVersionMismatch { page_id, .. }
+            | FileViolation::SpaceIdMismatch { page_id, .. }
+            | FileViolation::PageIdMismatch { page_id, .. }
+            | FileViolation::UnexpectedPageType { page_id, .. }
+            | FileViolation::DataLengthOutOfBounds { page_id, .. }
+            | FileViolation::BrokenNextLink { page_id, .. }
+            | FileViolation::BrokenPreviousLink { page_id, .. }
+            | FileViolation::PageNotParsed { page_id, .. } => Some(*page_id),
+        }
+    }
+
+    /// Returns human readable description of the violation.
+    pub fn message(&self) -> String {
+        match self {
+            FileViolation::EmptyFile => "file has no pages".to_string(),
+            FileViolation::TruncatedPage {
+                expected, actual, ..
+            } => format!("page needs {expected} bytes, but only {actual} are left in file"),
+            FileViolation::InvalidHeader { reason, .. } => format!("invalid header: {reason}"),
+            FileViolation::UnsupportedDataVersion { data_version, .. } => {
+                format!("data version {data_version} is not supported, latest is {DATA_VERSION}")
+            }
+            FileViolation::DataVersionMismatch {
+                data_version,
+                expected,
+                ..
+            } => format!("data version {data_version} differs from space's {expected}"),
+            FileViolation::SpaceIdMismatch {
+                space_id, expected, ..
+            } => format!("space id {space_id} differs from space's {expected}"),
+            FileViolation::PageIdMismatch { header_page_id, .. } => {
+                format!("header has page id {header_page_id}")
+            }
+            FileViolation::UnexpectedPageType { page_type, .. } => {
+                format!("page of type {page_type} is not expected here")
+            }
+            FileViolation::DataLengthOutOfBounds { data_length, .. } => {
+                format!("data length {data_length} is bigger than page capacity {INNER_PAGE_SIZE}")
+            }
+            FileViolation::BrokenNextLink { next_id, .. } => {
+                format!("next page {next_id} does not point back to this page")
+            }
+            FileViolation::BrokenPreviousLink { previous_id, .. } => {
+                format!("previous page {previous_id} does not point to this page")
+            }
+            FileViolation::PageCountMismatch { page_count, actual } => {
+                format!("space info has page count {page_count}, but file has {actual} pages")
+            }
+            FileViolation::PageNotParsed {
+                page_type, reason, ..
+            } => format!("{page_type} page can not be parsed: {reason}"),
+        }
+    }
+}
+
+/// Result of the file check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileReport {
+    /// Kind of the file, `None` if [`SpaceInfoPage`] can not be read.
+    pub kind: Option<FileKind>,
+    pub file_length: u64,
+    pub pages_checked: usize,
+    pub violations: Vec<FileViolation>,
+}
+
+impl FileReport {
+    /// Returns `true` if no violations were found.
+    pub fn is_consistent(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Information from the [`SpaceInfoPage`] that is needed to check other
+/// pages.
+struct SpaceContext {
+    space_info: SpaceInfoPage,
+    data_version: u32,
+    kind: FileKind,
+    reader: Option<RowReader>,
+}
+
+/// Checks whole space's data or index file. Errors are returned only if file
+/// can not be read, all problems of the file's content are returned in
+/// [`FileReport`].
+pub async fn check_file(file: &mut File) -> eyre::Result<FileReport> {
+    let file_length = file.metadata().await?.len();
+    let pages_count = file_length.div_ceil(PAGE_SIZE as u64) as u32;
+    let mut report = FileReport {
+        file_length,
+        ..Default::default()
+    };
+    if pages_count == 0 {
+        report.violations.push(FileViolation::EmptyFile);
+        return Ok(report);
+    }
+
+    let mut headers = Vec::with_capacity(pages_count as usize);
+    let mut context = None;
+    for index in 0..pages_count {
+        let page_id = index.into();
+        let Some((header, data)) =
+            read_page(file, page_id, file_length, &mut report.violations).await?
+        else {
+            headers.push(None);
+            continue;
+        };
+        report.pages_checked += 1;
+        headers.push(Some(header));
+        if header.page_id != page_id {
+            report.violations.push(FileViolation::PageIdMismatch {
+                page_id,
+                header_page_id: header.page_id,
+            })
+        }
+        if header.data_version == 0 || header.data_version > DATA_VERSION {
+            report
+                .violations
+                .push(FileViolation::UnsupportedDataVersion {
+                    page_id,
+                    data_version: header.data_version,
+                });
+            continue;
+        }
+
+        if index == 0 {
+            context = check_space_info(&header, data.as_deref(), &mut report.violations);
+            report.kind = context.as_ref().map(|c| c.kind);
+            continue;
+        }
+        let Some(context) = &context else {
+            continue;
+        };
+        if let Some(violation) = check_header(&header, page_id, context) {
+            report.violations.push(violation);
+            continue;
+        }
+        if let Some(data) = data {
+            if let Err(reason) = check_page_data(&header, &data, context) {
+                report.violations.push(FileViolation::PageNotParsed {
+                    page_id,
+                    page_type: header.page_type,
+                    reason,
+                })
+            }
+        }
+    }
+
+    check_links(&headers, &mut report.violations);
+    if let Some(context) = &context {
+        if context.space_info.page_count != pages_count - 1 {
+            report.violations.push(FileViolation::PageCountMismatch {
+                page_count: context.space_info.page_count,
+                actual: pages_count - 1,
+            })
+        }
+    }
+
+    Ok(report)
+}
+
+/// Reads page's header and data. Returns `None` if header can not be read and
+/// data is `None` if it's out of page's capacity or file's bounds. Found
+/// problems are added to `violations`.
+pub(crate) async fn read_page(
+    file: &mut File,
+    page_id: PageId,
+    file_length: u64,
+    violations: &mut Vec<FileViolation>,
+) -> eyre::Result<Option<(GeneralHeader, Option<Vec<u8>>)>> {
+    let start = u32::from(page_id) as u64 * PAGE_SIZE as u64;
+    let available = file_length - start;
+    if available < GENERAL_HEADER_SIZE as u64 {
+        violations.push(FileViolation::TruncatedPage {
+            page_id,
+            expected: GENERAL_HEADER_SIZE as u64,
+            actual: available,
+        });
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut buffer = [0; GENERAL_HEADER_SIZE];
+    file.read_exact(&mut buffer).await?;
+    let header = match check_archived::<GeneralHeader>(&buffer) {
+        Ok(()) => GeneralHeader::from_bytes(&buffer, DATA_VERSION),
+        Err(reason) => {
+            violations.push(FileViolation::InvalidHeader { page_id, reason });
+            return Ok(None);
+        }
+    };
+
+    if header.data_length as usize > INNER_PAGE_SIZE {
+        violations.push(FileViolation::DataLengthOutOfBounds {
+            page_id,
+            data_length: header.data_length,
+        });
+        return Ok(Some((header, None)));
+    }
+    let expected = (GENERAL_HEADER_SIZE + header.data_length as usize) as u64;
+    if available < expected {
+        violations.push(FileViolation::TruncatedPage {
+            page_id,
+            expected,
+            actual: available,
+        });
+        return Ok(Some((header, None)));
+    }
+    let mut data = vec![0; header.data_length as usize];
+    file.read_exact(&mut data).await?;
+
+    Ok(Some((header, Some(data))))
+}
+
+fn check_space_info(
+    header: &GeneralHeader,
+    data: Option<&[u8]>,
+    violations: &mut Vec<FileViolation>,
+) -> Option<SpaceContext> {
+    let page_id = header.page_id;
+    if header.page_type != PageType::SpaceInfo {
+        violations.push(FileViolation::UnexpectedPageType {
+            page_id,
+            page_type: header.page_type,
+        });
+        return None;
+    }
+    let data = data?;
+    if let Err(e) = SpaceInfoPage::check_bytes(data, header.data_version) {
+        violations.push(FileViolation::PageNotParsed {
+            page_id,
+            page_type: header.page_type,
+            reason: e.to_string(),
+        });
+        return None;
+    }
+    let space_info = match SpaceInfoPage::try_from_bytes(data, header.data_version) {
+        Ok(space_info) => space_info,
+        Err(e) => {
+            violations.push(FileViolation::PageNotParsed {
+                page_id,
+                page_type: header.page_type,
+                reason: format!("{e:#}"),
+            });
+            return None;
+        }
+    };
+    if header.space_id != space_info.id {
+        violations.push(FileViolation::SpaceIdMismatch {
+            page_id,
+            space_id: header.space_id,
+            expected: space_info.id,
+        })
+    }
+    // Data pages can not be split into rows if layout is not supported.
+    let reader = match RowReader::new(&space_info) {
+        Ok(reader) => Some(reader),
+        Err(e) => {
+            violations.push(FileViolation::PageNotParsed {
+                page_id,
+                page_type: header.page_type,
+                reason: format!("row schema is not supported: {e}"),
+            });
+            None
+        }
+    };
+
+    Some(SpaceContext {
+        kind: FileKind::of(&space_info),
+        space_info,
+        data_version: header.data_version,
+        reader,
+    })
+}
+
+/// Checks that header of the page that follows [`SpaceInfoPage`] belongs to
+/// the space.
+fn check_header(
+    header: &GeneralHeader,
+    page_id: PageId,
+    context: &SpaceContext,
+) -> Option<FileViolation> {
+    if header.data_version != context.data_version {
+        return Some(FileViolation::DataVersionMismatch {
+            page_id,
+            data_version: header.data_version,
+            expected: context.data_version,
+        });
+    }
+    if header.space_id != context.space_info.id {
+        return Some(FileViolation::SpaceIdMismatch {
+            page_id,
+            space_id: header.space_id,
+            expected: context.space_info.id,
+        });
+    }
+    if !context.kind.allows(header.page_type) {
+        return Some(FileViolation::UnexpectedPageType {
+            page_id,
+            page_type: header.page_type,
+        });
+    }
+    None
+}
+
+fn check_page_data(
+    header: &GeneralHeader,
+    data: &[u8],
+    context: &SpaceContext,
+) -> Result<(), String> {
+    match header.page_type {
+        PageType::Empty => Ok(()),
+        PageType::Data => {
+            let Some(reader) = &context.reader else {
+                return Ok(());
+            };
+            let layout = reader.layout(header.page_id);
+            let mut page = GeneralPage {
+                header: *header,
+                inner: DataPage {
+                    length: data.len() as u32,
+                    data: [0; INNER_PAGE_SIZE],
+                },
+            };
+            page.inner.data[..data.len()].copy_from_slice(data);
+            data_page_row_links(
+                &page,
+                layout,
+                context.space_info.page_empty_links(header.page_id),
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+        PageType::IndexPrefixCompressed => {
+            PrefixIndexPage::<{ INNER_PAGE_SIZE as u32 }>::check_bytes(data)
+                .map_err(|e| e.to_string())
+        }
+        PageType::IndexBloomFilter => check_archived::<BloomFilterPage>(data),
+        page_type => match context.space_info.secondary_index_schemas[0].single_column() {
+            Some(column) => check_index_page_data(page_type, &column.ty, data),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Parses index or table of contents page with the index's key type. Pages of
+/// the composite index or index with not supported key type are not checked.
+fn check_index_page_data(page_type: PageType, ty: &ColumnType, data: &[u8]) -> Result<(), String> {
+    match ty {
+        ColumnType::String => check_unsized_index_page::<String>(page_type, data),
+        ColumnType::I128 => check_sized_index_page::<i128>(page_type, data),
+        ColumnType::I64 => check_sized_index_page::<i64>(page_type, data),
+        ColumnType::I32 => check_sized_index_page::<i32>(page_type, data),
+        ColumnType::I16 => check_sized_index_page::<i16>(page_type, data),
+        ColumnType::I8 => check_sized_index_page::<i8>(page_type, data),
+        ColumnType::U128 => check_sized_index_page::<u128>(page_type, data),
+        ColumnType::U64 => check_sized_index_page::<u64>(page_type, data),
+        ColumnType::U32 => check_sized_index_page::<u32>(page_type, data),
+        ColumnType::U16 => check_sized_index_page::<u16>(page_type, data),
+        ColumnType::U8 => check_sized_index_page::<u8>(page_type, data),
+        _ => Ok(()),
+    }
+}
+
+fn check_sized_index_page<T>(page_type: PageType, data: &[u8]) -> Result<(), String>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>>
+        + for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>
+        + Ord,
+{
+    match page_type {
+        PageType::IndexTableOfContents => check_table_of_contents_page::<T>(data),
+        // Index page is persisted by parts, so it's checked part by part.
+        PageType::Index => IndexPage::<T>::check_bytes(data).map_err(|e| e.to_string()),
+        page_type => Err(format!("{page_type} page is not used by sized index")),
+    }
+}
+
+fn check_unsized_index_page<T>(page_type: PageType, data: &[u8]) -> Result<(), String>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>>
+        + Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>
+        + for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>
+        + Ord,
+{
+    match page_type {
+        PageType::IndexTableOfContents => check_table_of_contents_page::<T>(data),
+        // Unsized index page is persisted by parts, so it's checked part by
+        // part.
+        PageType::IndexUnsized => {
+            UnsizedIndexPage::<T, { INNER_PAGE_SIZE as u32 }>::check_bytes(data)
+                .map_err(|e| e.to_string())
+        }
+        page_type => Err(format!("{page_type} page is not used by unsized index")),
+    }
+}
+
+fn check_table_of_contents_page<T>(data: &[u8]) -> Result<(), String>
+where
+    T: Archive
+        + Debug
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>>
+        + for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>
+        + Ord,
+{
+    TableOfContentsPage::<T>::check_bytes(data).map_err(|e| e.to_string())
+}
+
+/// Checks that `previous_id` and `next_id` of the pages point to each other.
+fn check_links(headers: &[Option<GeneralHeader>], violations: &mut Vec<FileViolation>) {
+    let header_at = |page_id: PageId| {
+        headers
+            .get(u32::from(page_id) as usize)
+            .and_then(Option::as_ref)
+    };
+    for (index, header) in headers.iter().enumerate() {
+        let Some(header) = header else {
+            continue;
+        };
+        let page_id = (index as u32).into();
+        let none = PageId::from(0);
+        if header.next_id != none
+            && header_at(header.next_id).map(|next| next.previous_id) != Some(page_id)
+        {
+            violations.push(FileViolation::BrokenNextLink {
+                page_id,
+                next_id: header.next_id,
+            })
+        }
+        if header.previous_id != none
+            && header_at(header.previous_id).map(|previous| previous.next_id) != Some(page_id)
+        {
+            violations.push(FileViolation::BrokenPreviousLink {
+                page_id,
+                previous_id: header.previous_id,
+            })
+        }
+    }
+}
+
+/// Checks that `bytes` are valid archived `T`.
+fn check_archived<T>(bytes: &[u8]) -> Result<(), String>
+where
+    T: Archive,
+    <T as Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rkyv::rancor::Error>>,
+{
+    // Archived bytes are validated only if they are aligned.
+    let mut aligned = AlignedVec::<16>::new();
+    aligned.extend_from_slice(bytes);
+    rkyv::access::<<T as Archive>::Archived, rkyv::rancor::Error>(&aligned)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use tokio::fs::File;
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    use super::{check_file, FileKind, FileViolation};
+    use crate::persistence::data::parse_data_page_rows;
+    use crate::persistence::data::{ColumnType, RowReader};
+    use crate::persistence::rebuild::{extract_secondary_index_entries, persist_secondary_index};
+    use crate::{
+        persist_page, persist_pages_batch, ColumnSchema, DataPage, GeneralHeader, GeneralPage,
+        IndexSchema, PageType, SpaceInfoPage, DATA_VERSION, INNER_PAGE_SIZE, PAGE_SIZE,
+    };
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub id: u64,
+        pub name: String,
+    }
+
+    fn space_info(page_count: u32) -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![IndexSchema::single("name", ColumnType::String)],
+            empty_links_list: vec![],
+        }
+    }
+
+    fn data_pages(count: u32) -> Vec<GeneralPage<DataPage<INNER_PAGE_SIZE>>> {
+        let mut header = GeneralHeader::new(1.into(), PageType::Data, 1.into());
+        let mut pages = vec![];
+        for page in 0..count {
+            let mut data = [0u8; INNER_PAGE_SIZE];
+            let mut length = 0;
+            for i in 0..20 {
+                let row = Row {
+                    id: (page * 20 + i) as u64,
+                    name: format!("name {:03}", page * 20 + i),
+                };
+                let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+                data[length..length + bytes.len()].copy_from_slice(bytes.as_slice());
+                length += bytes.len();
+            }
+            if page != 0 {
+                header = header.follow();
+            }
+            pages.push(GeneralPage {
+                header,
+                inner: DataPage {
+                    length: length as u32,
+                    data,
+                },
+            });
+        }
+        // Headers are copied before `follow` updates their `next_id`.
+        for i in 1..pages.len() {
+            pages[i - 1].header.next_id = pages[i].header.page_id;
+        }
+        pages
+    }
+
+    async fn write_data_file(
+        filename: &str,
+        info: SpaceInfoPage,
+        pages: Vec<GeneralPage<DataPage<INNER_PAGE_SIZE>>>,
+    ) {
+        let mut file = File::create(filename).await.unwrap();
+        let mut info = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::SpaceInfo, info.id),
+            inner: info,
+        };
+        persist_page(&mut info, &mut file).await.unwrap();
+        persist_pages_batch(pages, &mut file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_consistent_data_file() {
+        let filename = "tests/data/fsck_data.wt";
+        write_data_file(filename, space_info(3), data_pages(3)).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        assert_eq!(report.kind, Some(FileKind::Data));
+        assert_eq!(report.pages_checked, 4);
+    }
+
+    #[tokio::test]
+    async fn test_consistent_index_file() {
+        let info = space_info(1);
+        let reader = RowReader::new(&info).unwrap();
+        let mut rows = vec![];
+        for page in data_pages(3) {
+            rows.extend(parse_data_page_rows(&page, &info, &reader).unwrap());
+        }
+        let mut indexes = extract_secondary_index_entries(&info, &rows).unwrap();
+
+        let filename = "tests/data/fsck_name_index.wt";
+        let mut file = File::create(filename).await.unwrap();
+        persist_secondary_index(&mut file, &info, indexes.remove(0), 0.1)
+            .await
+            .unwrap();
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        assert_eq!(report.kind, Some(FileKind::Index));
+        assert!(report.pages_checked > 3);
+    }
+
+    #[tokio::test]
+    async fn test_header_violations() {
+        let mut pages = data_pages(4);
+        pages[1].header.space_id = 2.into();
+        pages[2].header.data_version = 1;
+        pages[3].header.previous_id = 1.into();
+        let filename = "tests/data/fsck_headers.wt";
+        write_data_file(filename, space_info(3), pages).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert_eq!(
+            report.violations,
+            vec![
+                FileViolation::SpaceIdMismatch {
+                    page_id: 2.into(),
+                    space_id: 2.into(),
+                    expected: 1.into(),
+                },
+                FileViolation::DataVersionMismatch {
+                    page_id: 3.into(),
+                    data_version: 1,
+                    expected: DATA_VERSION,
+                },
+                FileViolation::BrokenNextLink {
+                    page_id: 3.into(),
+                    next_id: 4.into(),
+                },
+                FileViolation::BrokenPreviousLink {
+                    page_id: 4.into(),
+                    previous_id: 1.into(),
+                },
+                FileViolation::PageCountMismatch {
+                    page_count: 3,
+                    actual: 4,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_file() {
+        let mut pages = data_pages(3);
+        pages[0].header.page_type = PageType::Index;
+        let filename = "tests/data/fsck_corrupted.wt";
+        write_data_file(filename, space_info(3), pages).await;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(filename)
+            .await
+            .unwrap();
+        // Invalid `PageType` of the second data page.
+        file.seek(std::io::SeekFrom::Start(PAGE_SIZE as u64 * 2 + 20))
+            .await
+            .unwrap();
+        file.write_all(&[0xff, 0xff]).await.unwrap();
+        // Third data page's rows are cut.
+        file.set_len(PAGE_SIZE as u64 * 3 + 100).await.unwrap();
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert_eq!(report.pages_checked, 3);
+        let kinds = report
+            .violations
+            .iter()
+            .map(|v| (v.kind(), v.page_id().map(u32::from)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("UnexpectedPageType", Some(1)),
+                ("InvalidHeader", Some(2)),
+                ("TruncatedPage", Some(3)),
+                ("BrokenNextLink", Some(1)),
+                ("BrokenPreviousLink", Some(3)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_file() {
+        let filename = "tests/data/fsck_empty.wt";
+        File::create(filename).await.unwrap();
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert_eq!(report.violations, vec![FileViolation::EmptyFile]);
+        assert_eq!(report.kind, None);
+    }
+}
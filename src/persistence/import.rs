@@ -0,0 +1,1134 @@
+//! Import of rows from CSV and newline delimited JSON into a new space.
+//!
+//! Values are read in the same form as [`export`] writes them, so exported
+//! rows can be imported back. CSV should start with a header line, which
+//! names columns of the fields. Empty CSV field is `None` of the `Option`
+//! column and vectors, structs and enums are written as JSON, variants
+//! without fields can also be written by their names. Floats read
+//! from JSON `null` are `NaN`, as export writes non finite floats as `null`.
+//! Columns that are not set get their default value if it's set or `None`
+//! if they are `Option`'s. Blank lines are skipped.
+//!
+//! [`export`]: crate::persistence::export
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+use derive_more::{Display, Error};
+use eyre::{bail, eyre};
+use tokio::fs::File;
+
+use crate::page::ColumnSchema;
+use crate::persistence::data::{
+    compare_values, encode_row, persist_data_rows, ColumnType, DataTypeValue, FieldType,
+};
+use crate::persistence::export::json_string;
+use crate::persistence::rebuild::{extract_secondary_index_entries, SecondaryIndexEntries};
+use crate::{Link, SpaceInfoPage, INNER_PAGE_SIZE};
+
+/// Input format of the imported rows.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ImportFormat {
+    /// CSV with header line.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// Error of the imported row. `line` is the number of the row's first line,
+/// starting from `1`.
+#[derive(Clone, Debug, Display, Eq, Error, PartialEq)]
+#[display("Line {line}: {reason}")]
+pub struct ImportError {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Summary of the imported space.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub pages_written: usize,
+}
+
+/// Space written by [`import_space`].
+#[derive(Clone, Debug)]
+pub struct ImportedSpace {
+    pub space_info: SpaceInfoPage,
+    /// Rows with their [`Link`]'s.
+    pub rows: Vec<(Link, Vec<DataTypeValue>)>,
+    pub report: ImportReport,
+}
+
+impl ImportedSpace {
+    /// Returns entries of the primary index followed by entries of the
+    /// secondary indexes. Primary index is built if primary key's column is
+    /// not indexed by secondary index already. Returns error if primary key
+    /// has several columns, as index on composite key can not be built.
+    pub fn index_entries(&self) -> eyre::Result<Vec<SecondaryIndexEntries>> {
+        let mut indexes = vec![];
+        match self.space_info.primary_key_fields.as_slice() {
+            [] => {}
+            [column] => {
+                let is_secondary = self
+                    .space_info
+                    .secondary_index_schemas
+                    .iter()
+                    .filter_map(|s| s.single_column())
+                    .any(|c| &c.name == column);
+                let Some(pos) = self
+                    .space_info
+                    .columns
+                    .iter()
+                    .position(|c| &c.name == column)
+                else {
+                    bail!("Primary key column `{}` is not found in row schema", column)
+                };
+                if !is_secondary {
+                    indexes.push(SecondaryIndexEntries {
+                        column: column.clone(),
+                        ty: self.space_info.columns[pos].row_type(),
+                        entries: self
+                            .rows
+                            .iter()
+                            .map(|(link, row)| (row[pos].clone(), *link))
+                            .collect(),
+                    })
+                }
+            }
+            fields => bail!(
+                "Index on composite primary key ({}) can not be built",
+                fields.join(", ")
+            ),
+        }
+        indexes.extend(extract_secondary_index_entries(
+            &self.space_info,
+            &self.rows,
+        )?);
+
+        Ok(indexes)
+    }
+}
+
+/// Imports rows of the `input` to the new space's data file. `space_info`
+/// describes the new space, rows are packed into data pages one after
+/// another. Returns [`ImportError`] if some row is not valid or it's primary
+/// key is already used, and error if some primary key column is not found.
+pub async fn import_space(
+    input: &str,
+    format: ImportFormat,
+    space_info: &SpaceInfoPage,
+    output: &mut File,
+) -> eyre::Result<ImportedSpace> {
+    let rows = parse_rows(input, format, &space_info.columns)?;
+
+    let key_positions = space_info
+        .primary_key_fields
+        .iter()
+        .map(|f| {
+            space_info
+                .columns
+                .iter()
+                .position(|c| &c.name == f)
+                .ok_or_else(|| eyre!("Primary key column `{}` is not found in row schema", f))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let mut keys = HashMap::new();
+    let types = space_info
+        .columns
+        .iter()
+        .map(ColumnSchema::row_type)
+        .collect::<Vec<_>>();
+    let key_types = key_positions
+        .iter()
+        .map(|p| types[*p].clone())
+        .collect::<Vec<_>>();
+    let mut encoded = Vec::with_capacity(rows.len());
+    for (line, row) in rows {
+        if !key_positions.is_empty() {
+            let key = PrimaryKey {
+                types: &key_types,
+                values: key_positions.iter().map(|p| row[*p].clone()).collect(),
+            };
+            if let Some(first) = keys.insert(key, line) {
+                return Err(ImportError {
+                    line,
+                    reason: format!("Primary key is already used at line {}", first),
+                }
+                .into());
+            }
+        }
+        let bytes = encode_row(&types, &row).map_err(|e| ImportError {
+            line,
+            reason: e.to_string(),
+        })?;
+        if bytes.len() > INNER_PAGE_SIZE {
+            return Err(ImportError {
+                line,
+                reason: format!("Row with length {} does not fit in page", bytes.len()),
+            }
+            .into());
+        }
+        encoded.push((bytes, row))
+    }
+
+    let report_rows = encoded.len();
+    let rows = encoded.iter().map(|(b, row)| (b.as_slice(), row.clone()));
+    let (space_info, rows) = persist_data_rows(output, space_info, rows).await?;
+    let report = ImportReport {
+        rows: report_rows,
+        pages_written: space_info.page_count as usize,
+    };
+
+    Ok(ImportedSpace {
+        space_info,
+        rows,
+        report,
+    })
+}
+
+/// Primary key of the imported row. Keys are equal if their values are equal
+/// by [`compare_values`], so `NaN` is not equal to itself and `0.0` is equal
+/// to `-0.0`.
+struct PrimaryKey<'a> {
+    types: &'a [ColumnType],
+    values: Vec<DataTypeValue>,
+}
+
+impl PartialEq for PrimaryKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.types
+            .iter()
+            .zip(self.values.iter().zip(&other.values))
+            .all(|(ty, (a, b))| compare_values(ty, a, b) == Some(Ordering::Equal))
+    }
+}
+
+impl Eq for PrimaryKey<'_> {}
+
+impl Hash for PrimaryKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for value in &self.values {
+            hash_value(value, state)
+        }
+    }
+}
+
+/// Hashes value so values that are equal by [`compare_values`] have equal
+/// hashes. Zero floats are hashed the same regardless of their sign.
+fn hash_value<H: Hasher>(value: &DataTypeValue, state: &mut H) {
+    std::mem::discriminant(value).hash(state);
+    match value {
+        DataTypeValue::String(v) => v.hash(state),
+        DataTypeValue::I128(v) => v.hash(state),
+        DataTypeValue::I64(v) => v.hash(state),
+        DataTypeValue::I32(v) => v.hash(state),
+        DataTypeValue::I16(v) => v.hash(state),
+        DataTypeValue::I8(v) => v.hash(state),
+        DataTypeValue::U128(v) => v.hash(state),
+        DataTypeValue::U64(v) => v.hash(state),
+        DataTypeValue::U32(v) => v.hash(state),
+        DataTypeValue::U16(v) => v.hash(state),
+        DataTypeValue::U8(v) => v.hash(state),
+        DataTypeValue::F64(v) => (*v != 0.0).then(|| v.to_bits()).hash(state),
+        DataTypeValue::F32(v) => (*v != 0.0).then(|| v.to_bits()).hash(state),
+        DataTypeValue::Bool(v) => v.hash(state),
+        DataTypeValue::Char(v) => v.hash(state),
+        DataTypeValue::Uuid(v) => v.hash(state),
+        DataTypeValue::OrderedF64(v) => v.hash(state),
+        DataTypeValue::OrderedF32(v) => v.hash(state),
+        DataTypeValue::PackedNanoid(v) => v.hash(state),
+        DataTypeValue::Option(v) => {
+            if let Some(v) = v {
+                hash_value(v, state)
+            }
+        }
+        DataTypeValue::Vec(values) => {
+            values.len().hash(state);
+            for v in values {
+                hash_value(v, state)
+            }
+        }
+        DataTypeValue::Struct(fields) => {
+            for (_, v) in fields {
+                hash_value(v, state)
+            }
+        }
+        DataTypeValue::Enum { variant, fields } => {
+            variant.hash(state);
+            for (_, v) in fields {
+                hash_value(v, state)
+            }
+        }
+    }
+}
+
+/// Parses rows of the `input` with numbers of their first lines. Rows have
+/// values of all `columns` in columns' order.
+pub fn parse_rows(
+    input: &str,
+    format: ImportFormat,
+    columns: &[ColumnSchema],
+) -> Result<Vec<(usize, Vec<DataTypeValue>)>, ImportError> {
+    match format {
+        ImportFormat::Csv => parse_csv_rows(input, columns),
+        ImportFormat::Ndjson => parse_ndjson_rows(input, columns),
+    }
+}
+
+fn parse_csv_rows(
+    input: &str,
+    columns: &[ColumnSchema],
+) -> Result<Vec<(usize, Vec<DataTypeValue>)>, ImportError> {
+    let mut records = csv_records(input)?.into_iter();
+    let Some((header_line, header)) = records.next() else {
+        return Ok(vec![]);
+    };
+    let positions = header
+        .iter()
+        .map(|name| {
+            columns
+                .iter()
+                .position(|c| &c.name == name)
+                .ok_or_else(|| ImportError {
+                    line: header_line,
+                    reason: format!("Column `{}` is not found", name),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let missing = missing_values(columns, &positions, header_line)?;
+
+    records
+        .map(|(line, fields)| {
+            if fields.len() != header.len() {
+                return Err(ImportError {
+                    line,
+                    reason: format!(
+                        "Row has {} fields instead of {}",
+                        fields.len(),
+                        header.len()
+                    ),
+                });
+            }
+            let mut row = missing.clone();
+            for (field, pos) in fields.iter().zip(&positions) {
+                let column = &columns[*pos];
+                row[*pos] =
+                    Some(
+                        parse_text(&column.row_type(), field).map_err(|reason| ImportError {
+                            line,
+                            reason: format!("Column `{}`: {}", column.name, reason),
+                        })?,
+                    );
+            }
+            Ok((line, row.into_iter().flatten().collect()))
+        })
+        .collect()
+}
+
+fn parse_ndjson_rows(
+    input: &str,
+    columns: &[ColumnSchema],
+) -> Result<Vec<(usize, Vec<DataTypeValue>)>, ImportError> {
+    let mut rows = vec![];
+    for (number, text) in input.lines().enumerate() {
+        let line = number + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let error = |reason| ImportError { line, reason };
+        let Json::Object(entries) = parse_json(text).map_err(error)? else {
+            return Err(error("Row is not a JSON object".to_string()));
+        };
+        let positions = entries
+            .iter()
+            .map(|(name, _)| {
+                columns
+                    .iter()
+                    .position(|c| &c.name == name)
+                    .ok_or_else(|| error(format!("Column `{}` is not found", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut row = missing_values(columns, &positions, line)?;
+        for ((_, json), pos) in entries.iter().zip(positions) {
+            let column = &columns[pos];
+            row[pos] = Some(
+                json_to_value(&column.row_type(), json)
+                    .map_err(|reason| error(format!("Column `{}`: {}", column.name, reason)))?,
+            );
+        }
+        rows.push((line, row.into_iter().flatten().collect()))
+    }
+
+    Ok(rows)
+}
+
+/// Returns row where columns which are not at `positions` have their
+/// default values and other columns are not set yet.
+fn missing_values(
+    columns: &[ColumnSchema],
+    positions: &[usize],
+    line: usize,
+) -> Result<Vec<Option<DataTypeValue>>, ImportError> {
+    let mut row = vec![None; columns.len()];
+    for (pos, column) in columns.iter().enumerate() {
+        match positions.iter().filter(|p| **p == pos).count() {
+            0 => {
+                row[pos] = Some(match (&column.default, column.row_type()) {
+                    (Some(default), _) => default.clone(),
+                    (None, ColumnType::Option(_)) => DataTypeValue::Option(None),
+                    (None, _) => {
+                        return Err(ImportError {
+                            line,
+                            reason: format!("Column `{}` is not set", column.name),
+                        })
+                    }
+                })
+            }
+            1 => {}
+            _ => {
+                return Err(ImportError {
+                    line,
+                    reason: format!("Column `{}` is set more than once", column.name),
+                })
+            }
+        }
+    }
+    Ok(row)
+}
+
+/// Splits CSV into records with numbers of their first lines. Quoted fields
+/// can contain delimiters, line breaks and doubled quotes.
+fn csv_records(input: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let mut records = vec![];
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    while chars.peek().is_some() {
+        let first_line = line;
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        loop {
+            match chars.next() {
+                None if in_quotes => {
+                    return Err(ImportError {
+                        line: first_line,
+                        reason: "Quoted field is not closed".to_string(),
+                    })
+                }
+                None => {
+                    fields.push(field);
+                    break;
+                }
+                Some('"') if in_quotes => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        field.push('"')
+                    } else {
+                        in_quotes = false
+                    }
+                }
+                Some('"') if field.is_empty() && !quoted => {
+                    quoted = true;
+                    in_quotes = true
+                }
+                Some(c) if in_quotes => {
+                    if c == '\n' {
+                        line += 1
+                    }
+                    field.push(c)
+                }
+                Some(',') => {
+                    fields.push(std::mem::take(&mut field));
+                    quoted = false
+                }
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') => {
+                    line += 1;
+                    fields.push(field);
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        let is_blank = fields.len() == 1 && fields[0].is_empty() && !quoted;
+        if !is_blank {
+            records.push((first_line, fields))
+        }
+    }
+
+    Ok(records)
+}
+
+/// Parses value of the type `ty` written as CSV field.
+fn parse_text(ty: &ColumnType, s: &str) -> Result<DataTypeValue, String> {
+    fn number<T: FromStr>(s: &str, ty: &ColumnType) -> Result<T, String> {
+        s.parse().map_err(|_| format!("`{}` is not `{}`", s, ty))
+    }
+    let invalid = || format!("`{}` is not `{}`", s, ty);
+    Ok(match ty {
+        ColumnType::String => s.to_string().into(),
+        ColumnType::I128 => number::<i128>(s, ty)?.into(),
+        ColumnType::I64 => number::<i64>(s, ty)?.into(),
+        ColumnType::I32 => number::<i32>(s, ty)?.into(),
+        ColumnType::I16 => number::<i16>(s, ty)?.into(),
+        ColumnType::I8 => number::<i8>(s, ty)?.into(),
+        ColumnType::U128 => number::<u128>(s, ty)?.into(),
+        ColumnType::U64 => number::<u64>(s, ty)?.into(),
+        ColumnType::U32 => number::<u32>(s, ty)?.into(),
+        ColumnType::U16 => number::<u16>(s, ty)?.into(),
+        ColumnType::U8 => number::<u8>(s, ty)?.into(),
+        ColumnType::F64 => number::<f64>(s, ty)?.into(),
+        ColumnType::F32 => number::<f32>(s, ty)?.into(),
+        ColumnType::Bool => number::<bool>(s, ty)?.into(),
+        ColumnType::Char => number::<char>(s, ty)?.into(),
+        ColumnType::Uuid => uuid::Uuid::parse_str(s).map_err(|_| invalid())?.into(),
+        ColumnType::OrderedF64 => DataTypeValue::OrderedF64(number::<f64>(s, ty)?.into()),
+        ColumnType::OrderedF32 => DataTypeValue::OrderedF32(number::<f32>(s, ty)?.into()),
+        ColumnType::PackedNanoid { bytes, .. } => {
+            if !s.is_ascii() || s.len() != *bytes as usize * 2 {
+                return Err(invalid());
+            }
+            let packed = (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            DataTypeValue::PackedNanoid(packed)
+        }
+        ColumnType::Option(_) if s.is_empty() => DataTypeValue::Option(None),
+        ColumnType::Option(inner) => DataTypeValue::Option(Some(Box::new(parse_text(inner, s)?))),
+        ColumnType::Enum(_) if !s.starts_with(['"', '{']) => {
+            json_to_value(ty, &Json::String(s.to_string()))?
+        }
+        ColumnType::Vec(_) | ColumnType::Struct(_) | ColumnType::Enum(_) => {
+            json_to_value(ty, &parse_json(s)?)?
+        }
+    })
+}
+
+/// Converts JSON written by [`json_value`] to the value of the type `ty`.
+///
+/// [`json_value`]: crate::persistence::export::json_value
+fn json_to_value(ty: &ColumnType, json: &Json) -> Result<DataTypeValue, String> {
+    let invalid = || format!("`{}` is not `{}`", json, ty);
+    Ok(match (ty, json) {
+        (ColumnType::Option(_), Json::Null) => DataTypeValue::Option(None),
+        (ColumnType::Option(inner), json) => {
+            DataTypeValue::Option(Some(Box::new(json_to_value(inner, json)?)))
+        }
+        (ColumnType::Vec(inner), Json::Array(items)) => DataTypeValue::Vec(
+            items
+                .iter()
+                .map(|item| json_to_value(inner, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        (ColumnType::Struct(fields), json) => {
+            DataTypeValue::Struct(json_to_fields(fields, json).ok_or_else(invalid)??)
+        }
+        (ColumnType::Enum(variants), Json::String(name)) => {
+            let variant = variants
+                .iter()
+                .find(|v| &v.name == name && v.fields.is_empty())
+                .ok_or_else(invalid)?;
+            DataTypeValue::Enum {
+                variant: variant.name.clone(),
+                fields: vec![],
+            }
+        }
+        (ColumnType::Enum(variants), Json::Object(entries)) => {
+            let [(name, fields)] = entries.as_slice() else {
+                return Err(invalid());
+            };
+            let variant = variants
+                .iter()
+                .find(|v| &v.name == name)
+                .ok_or_else(invalid)?;
+            DataTypeValue::Enum {
+                variant: variant.name.clone(),
+                fields: json_to_fields(&variant.fields, fields).ok_or_else(invalid)??,
+            }
+        }
+        (
+            ColumnType::F64 | ColumnType::F32 | ColumnType::OrderedF64 | ColumnType::OrderedF32,
+            Json::Null,
+        ) => parse_text(ty, "NaN")?,
+        (
+            ColumnType::I128
+            | ColumnType::I64
+            | ColumnType::I32
+            | ColumnType::I16
+            | ColumnType::I8
+            | ColumnType::U128
+            | ColumnType::U64
+            | ColumnType::U32
+            | ColumnType::U16
+            | ColumnType::U8
+            | ColumnType::F64
+            | ColumnType::F32
+            | ColumnType::OrderedF64
+            | ColumnType::OrderedF32,
+            Json::Number(n),
+        ) => parse_text(ty, n)?,
+        (
+            ColumnType::String
+            | ColumnType::Char
+            | ColumnType::Uuid
+            | ColumnType::PackedNanoid { .. }
+            | ColumnType::I128
+            | ColumnType::I64
+            | ColumnType::U128
+            | ColumnType::U64,
+            Json::String(s),
+        ) => parse_text(ty, s)?,
+        (ColumnType::Bool, Json::Bool(b)) => (*b).into(),
+        _ => return Err(invalid()),
+    })
+}
+
+/// Converts JSON array of fields named by their positions or JSON object to
+/// the fields. Returns `None` if JSON doesn't have fields' shape.
+fn json_to_fields(
+    fields: &[FieldType],
+    json: &Json,
+) -> Option<Result<Vec<(String, DataTypeValue)>, String>> {
+    let is_tuple = fields
+        .iter()
+        .enumerate()
+        .all(|(i, f)| f.name == i.to_string());
+    let values = match json {
+        Json::Array(items) if is_tuple && items.len() == fields.len() => items.iter().collect(),
+        Json::Object(entries) if entries.len() == fields.len() => fields
+            .iter()
+            .map(|f| entries.iter().find(|(n, _)| n == &f.name).map(|(_, v)| v))
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+    Some(
+        fields
+            .iter()
+            .zip(values)
+            .map(|(field, json)| Ok((field.name.clone(), json_to_value(&field.ty, json)?)))
+            .collect(),
+    )
+}
+
+/// Parsed JSON value. Numbers keep their text, so integers are not rounded.
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Number(n) => write!(f, "{}", n),
+            Self::String(s) => write!(f, "{}", json_string(s)),
+            Self::Array(items) => {
+                let items = items.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Self::Object(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", json_string(k), v))
+                    .collect::<Vec<_>>();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+        }
+    }
+}
+
+fn parse_json(s: &str) -> Result<Json, String> {
+    let mut parser = JsonParser {
+        s,
+        chars: s.char_indices().peekable(),
+    };
+    let value = parser.value()?;
+    parser.whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some((pos, _)) => Err(format!("Unexpected `{}` after JSON value", &s[pos..])),
+    }
+}
+
+struct JsonParser<'a> {
+    s: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl JsonParser<'_> {
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, _)) => Err(format!("Expected `{}` at {}", expected, pos)),
+            None => Err(format!("Expected `{}` at the end", expected)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        let Some(&(pos, c)) = self.chars.peek() else {
+            return Err("JSON value is expected".to_string());
+        };
+        match c {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => Ok(Json::String(self.string()?)),
+            '-' | '0'..='9' => {
+                let mut end = pos;
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|(_, c)| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+                {
+                    end = i + c.len_utf8()
+                }
+                let number = &self.s[pos..end];
+                if number.parse::<f64>().is_err() {
+                    return Err(format!("`{}` is not a JSON number", number));
+                }
+                Ok(Json::Number(number.to_string()))
+            }
+            _ => {
+                for (literal, value) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                ] {
+                    if self.s[pos..].starts_with(literal) {
+                        for _ in 0..literal.len() {
+                            self.chars.next();
+                        }
+                        return Ok(value);
+                    }
+                }
+                Err(format!("Unexpected `{}` at {}", c, pos))
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = vec![];
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                self.expect(']')?;
+                return Ok(Json::Array(items));
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut entries = vec![];
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.whitespace();
+            if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                self.expect('}')?;
+                return Ok(Json::Object(entries));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let Some((pos, c)) = self.chars.next() else {
+                return Err("JSON string is not closed".to_string());
+            };
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = match self.chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let high = self.hex_code()?;
+                            let code = if (0xd800..0xdc00).contains(&high) {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex_code()?;
+                                0x10000 + ((high - 0xd800) << 10) + low.wrapping_sub(0xdc00)
+                            } else {
+                                high
+                            };
+                            char::from_u32(code)
+                                .ok_or_else(|| format!("Invalid escaped character at {}", pos))?
+                        }
+                        _ => return Err(format!("Invalid escape at {}", pos)),
+                    };
+                    s.push(escaped)
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex_code(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|(_, c)| c.to_digit(16))
+                .ok_or_else(|| "Invalid `\\u` escape".to_string())?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use tokio::fs::File;
+
+    use super::{import_space, parse_rows, ImportError, ImportFormat};
+    use crate::persistence::data::util::test::{column_type, row};
+    use crate::persistence::data::{read_data_rows, ColumnType, DataTypeValue};
+    use crate::persistence::export::{csv_field, csv_value, json_object};
+    use crate::persistence::fsck::check_file;
+    use crate::{ColumnSchema, IndexSchema, SpaceInfoPage};
+
+    fn columns(columns: &[(&str, &str)]) -> Vec<ColumnSchema> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, (name, ty))| {
+                ColumnSchema::new(i as u32, *name, ColumnType::from_str(ty).unwrap())
+            })
+            .collect()
+    }
+
+    fn space_info(columns: Vec<ColumnSchema>) -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count: 0,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns,
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![IndexSchema::single("name", ColumnType::String)],
+            empty_links_list: vec![],
+        }
+    }
+
+    /// Returns `true` if value of the type can't be read back as it was
+    /// written: `Some(None)` is written as `None` and `Some("")` is written
+    /// to CSV as empty field.
+    fn is_lossy(ty: &ColumnType, csv: bool) -> bool {
+        match ty {
+            ColumnType::Option(inner) => {
+                matches!(**inner, ColumnType::Option(_))
+                    || (csv && **inner == ColumnType::String)
+                    || is_lossy(inner, false)
+            }
+            ColumnType::Vec(inner) => is_lossy(inner, false),
+            ColumnType::Struct(fields) => fields.iter().any(|f| is_lossy(&f.ty, false)),
+            ColumnType::Enum(variants) => variants
+                .iter()
+                .any(|v| v.fields.iter().any(|f| is_lossy(&f.ty, false))),
+            _ => false,
+        }
+    }
+
+    /// Returns strategy of the format with types of the columns and three
+    /// rows of them. First column is `u32`, as row of one empty field would
+    /// be a blank line.
+    fn format_rows(
+    ) -> impl Strategy<Value = (ImportFormat, Vec<ColumnType>, Vec<Vec<DataTypeValue>>)> {
+        (
+            select(vec![ImportFormat::Csv, ImportFormat::Ndjson]),
+            vec(column_type(), 0..4),
+        )
+            .prop_flat_map(|(format, types)| {
+                let types = std::iter::once(ColumnType::U32)
+                    .chain(
+                        types
+                            .into_iter()
+                            .filter(|ty| !is_lossy(ty, format == ImportFormat::Csv)),
+                    )
+                    .collect::<Vec<_>>();
+                let rows = vec(row(&types), 3);
+                (Just(format), Just(types), rows)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_rows_random_values((format, types, rows) in format_rows()) {
+            let columns = types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| ColumnSchema::new(i as u32, format!("c{i}"), ty.clone()))
+                .collect::<Vec<_>>();
+            let header = columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+
+            let mut input = String::new();
+            if format == ImportFormat::Csv {
+                let header = header.iter().map(|h| csv_field(h)).collect::<Vec<_>>();
+                input.push_str(&header.join(","));
+                input.push('\n');
+            }
+            for row in &rows {
+                match format {
+                    ImportFormat::Csv => {
+                        let fields = row.iter().map(csv_value).collect::<Vec<_>>();
+                        input.push_str(&fields.join(","))
+                    }
+                    ImportFormat::Ndjson => input.push_str(&json_object(&header, row)),
+                }
+                input.push('\n')
+            }
+
+            let parsed = parse_rows(&input, format, &columns);
+            prop_assert!(parsed.is_ok(), "{:?}\n{}", parsed, input);
+            let parsed = parsed
+                .unwrap()
+                .into_iter()
+                .map(|(_, row)| row)
+                .collect::<Vec<_>>();
+            prop_assert_eq!(parsed, rows, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let columns = columns(&[
+            ("id", "u64"),
+            ("name", "String"),
+            ("parent", "Option<u32>"),
+            ("point", "{x: i16, y: i16}"),
+            ("state", "enum {Off, On(u8)}"),
+        ]);
+        let csv = "name,id,state,point\r\n\
+                   \"multi\nline, \"\"quoted\"\"\",1,Off,\"{\"\"x\"\": 1, \"\"y\"\": -1}\"\r\n\
+                   \r\n\
+                   plain,2,\"{\"\"On\"\": [3]}\",\"{\"\"y\"\": 0, \"\"x\"\": 2}\"\r\n";
+        let rows = parse_rows(csv, ImportFormat::Csv, &columns).unwrap();
+        let point = |x: i16, y: i16| {
+            DataTypeValue::Struct(vec![
+                ("x".to_string(), x.into()),
+                ("y".to_string(), y.into()),
+            ])
+        };
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    2,
+                    vec![
+                        1u64.into(),
+                        "multi\nline, \"quoted\"".to_string().into(),
+                        DataTypeValue::Option(None),
+                        point(1, -1),
+                        DataTypeValue::Enum {
+                            variant: "Off".to_string(),
+                            fields: vec![],
+                        },
+                    ]
+                ),
+                (
+                    5,
+                    vec![
+                        2u64.into(),
+                        "plain".to_string().into(),
+                        DataTypeValue::Option(None),
+                        point(2, 0),
+                        DataTypeValue::Enum {
+                            variant: "On".to_string(),
+                            fields: vec![("0".to_string(), 3u8.into())],
+                        },
+                    ]
+                ),
+            ]
+        );
+
+        let json = "{\"id\": 1, \"name\": \"\\u00e9\\ud83d\\ude00\", \"parent\": 7, \
+                    \"point\": {\"x\": 0, \"y\": 0}, \"state\": \"Off\"}\n";
+        let rows = parse_rows(json, ImportFormat::Ndjson, &columns).unwrap();
+        assert_eq!(rows[0].1[1], "é😀".to_string().into());
+        assert_eq!(
+            rows[0].1[2],
+            DataTypeValue::Option(Some(Box::new(7u32.into())))
+        );
+    }
+
+    #[test]
+    fn test_parse_rows_errors() {
+        let columns = columns(&[("id", "u64"), ("name", "String"), ("score", "f32")]);
+        let error = |input: &str, format| parse_rows(input, format, &columns).unwrap_err();
+        let csv = "id,name,score\n1,\"two\nlines\",0.5\n\n-2,b,1\n";
+        assert_eq!(
+            error(csv, ImportFormat::Csv),
+            ImportError {
+                line: 5,
+                reason: "Column `id`: `-2` is not `u64`".to_string()
+            }
+        );
+        assert_eq!(
+            error("id,name,score\n1,a\n", ImportFormat::Csv).to_string(),
+            "Line 2: Row has 2 fields instead of 3"
+        );
+        assert_eq!(
+            error("id,name\n1,a\n", ImportFormat::Csv).to_string(),
+            "Line 1: Column `score` is not set"
+        );
+        assert_eq!(
+            error("id,name,score,age\n", ImportFormat::Csv).to_string(),
+            "Line 1: Column `age` is not found"
+        );
+        assert_eq!(
+            error("id,name,score\n1,\"a,0\n", ImportFormat::Csv).to_string(),
+            "Line 2: Quoted field is not closed"
+        );
+        let json = "{\"id\": 1, \"name\": \"a\", \"score\": 1}\n\
+                    {\"id\": 2, \"name\": 3, \"score\": 1}\n";
+        assert_eq!(
+            error(json, ImportFormat::Ndjson).to_string(),
+            "Line 2: Column `name`: `3` is not `String`"
+        );
+        assert_eq!(
+            error("\n[1]\n", ImportFormat::Ndjson).to_string(),
+            "Line 2: Row is not a JSON object"
+        );
+        assert_eq!(
+            error("{\"id\": 1, \"id\": 2}", ImportFormat::Ndjson).to_string(),
+            "Line 1: Column `id` is set more than once"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_space() {
+        let filename = "tests/data/import.wt";
+        let mut columns = columns(&[("id", "u64"), ("name", "String"), ("score", "f32")]);
+        columns[2].default = Some(0.5f32.into());
+        let info = space_info(columns);
+        let input = (0..2000)
+            .map(|id| format!("{{\"id\": {id}, \"name\": \"row {id}\"}}\n"))
+            .collect::<String>();
+        let mut file = File::create(filename).await.unwrap();
+        let imported = import_space(&input, ImportFormat::Ndjson, &info, &mut file)
+            .await
+            .unwrap();
+        assert_eq!(imported.report.rows, 2000);
+        assert!(imported.report.pages_written > 1);
+        let indexes = imported.index_entries().unwrap();
+        let names = indexes
+            .iter()
+            .map(|i| i.column.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["id", "name"]);
+        assert_eq!(indexes[0].ty, ColumnType::U64);
+        assert_eq!(indexes[0].entries.len(), 2000);
+
+        let mut file = File::open(filename).await.unwrap();
+        assert!(check_file(&mut file).await.unwrap().is_consistent());
+        let (space_info, rows) = read_data_rows(&mut file).await.unwrap();
+        assert_eq!(
+            space_info.page_count as usize,
+            imported.report.pages_written
+        );
+        assert_eq!(rows, imported.rows);
+        assert_eq!(
+            rows[1999].1,
+            vec![1999u64.into(), "row 1999".to_string().into(), 0.5f32.into()]
+        );
+
+        let input = "id,name,score\n1,a,0\n2,b,0\n1,c,0\n";
+        let mut file = File::create(filename).await.unwrap();
+        let err = import_space(input, ImportFormat::Csv, &info, &mut file)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 4: Primary key is already used at line 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_space_primary_key() {
+        let filename = "tests/data/import_primary_key.wt";
+        let mut info = space_info(columns(&[("id", "f64"), ("name", "String")]));
+        let import = |input: &'static str, info: SpaceInfoPage| async move {
+            let mut file = File::create(filename).await.unwrap();
+            import_space(input, ImportFormat::Csv, &info, &mut file).await
+        };
+
+        let err = import("id,name\n0,a\n-0,b\n", info.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 3: Primary key is already used at line 2"
+        );
+        let imported = import("id,name\nNaN,a\nNaN,b\n", info.clone())
+            .await
+            .unwrap();
+        assert_eq!(imported.report.rows, 2);
+
+        info.primary_key_fields = vec!["id".to_string(), "name".to_string()];
+        let imported = import("id,name\n1,a\n1,b\n", info.clone()).await.unwrap();
+        assert_eq!(
+            imported.index_entries().unwrap_err().to_string(),
+            "Index on composite primary key (id, name) can not be built"
+        );
+
+        info.primary_key_fields = vec!["key".to_string()];
+        let err = import("id,name\n1,a\n", info).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Primary key column `key` is not found in row schema"
+        );
+    }
+}
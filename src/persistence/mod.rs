@@ -1,6 +1,15 @@
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod data;
+pub mod export;
+pub mod fsck;
+pub mod import;
 mod index;
+pub mod rebuild;
+pub mod rewrite;
+pub mod salvage;
 mod table;
+pub mod verify;
 
 pub use index::PersistableIndex;
 pub use table::PersistableTable;
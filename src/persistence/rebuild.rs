@@ -0,0 +1,351 @@
+//! Rebuild of secondary indexes from the space's data pages.
+//!
+//! Rebuilt index file has next layout: `0`'s page is [`SpaceInfoPage`] of the
+//! index, `1`'s page is the first page of [`TableOfContentsPage`] chain and
+//! all index pages go after it. If table of contents does not fit in one page,
+//! it's next pages are placed after index pages.
+//!
+//! [`TableOfContentsPage`]: crate::TableOfContentsPage
+
+use std::fmt::Debug;
+
+use eyre::{bail, WrapErr};
+use rkyv::api::high::HighDeserializer;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+
+use crate::persistence::data::{ColumnType, DataTypeValue};
+use crate::{
+    bulk_load_index_pages, bulk_load_unsized_index_pages, persist_page, persist_pages_batch,
+    persist_table_of_contents_chain, BulkLoadConfig, BulkLoadedIndex, GeneralHeader, GeneralPage,
+    IndexColumn, IndexSchema, Link, PageType, Persistable, SizeMeasurable, SpaceInfoPage,
+    INNER_PAGE_SIZE,
+};
+
+/// [`PageId`] of the index's first [`TableOfContentsPage`].
+///
+/// [`PageId`]: crate::page::PageId
+/// [`TableOfContentsPage`]: crate::TableOfContentsPage
+const TOC_PAGE_ID: u32 = 1;
+
+/// Entries of one secondary index extracted from the space's rows.
+#[derive(Clone, Debug)]
+pub struct SecondaryIndexEntries {
+    /// Name of the indexed column.
+    pub column: String,
+    /// Type of the indexed column's values.
+    pub ty: ColumnType,
+    pub entries: Vec<(DataTypeValue, Link)>,
+}
+
+/// Summary of the rebuilt index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebuildReport {
+    pub column: String,
+    pub entries_count: usize,
+    pub index_pages_count: usize,
+}
+
+/// Extracts indexed columns' values from rows for all indexes listed in
+/// [`SpaceInfoPage::secondary_index_schemas`]. Returns error if some index is
+/// composite, as it can not be rebuilt.
+pub fn extract_secondary_index_entries(
+    space_info: &SpaceInfoPage,
+    rows: &[(Link, Vec<DataTypeValue>)],
+) -> eyre::Result<Vec<SecondaryIndexEntries>> {
+    let mut indexes = vec![];
+    for schema in &space_info.secondary_index_schemas {
+        let Some(IndexColumn {
+            name: column, ty, ..
+        }) = schema.single_column()
+        else {
+            bail!("Composite index `{}` can not be rebuilt", schema.name)
+        };
+        let Some(pos) = space_info.columns.iter().position(|c| &c.name == column) else {
+            bail!("Indexed column `{}` is not found in row schema", column)
+        };
+        let entries = rows
+            .iter()
+            .map(|(link, row)| (row[pos].clone(), *link))
+            .collect();
+        indexes.push(SecondaryIndexEntries {
+            column: column.clone(),
+            ty: ty.clone(),
+            entries,
+        })
+    }
+
+    Ok(indexes)
+}
+
+/// Writes index file for the extracted entries. Entries are sorted and packed
+/// into pages using [`BulkLoadConfig::fill_factor`].
+pub async fn persist_secondary_index(
+    file: &mut File,
+    space_info: &SpaceInfoPage,
+    index: SecondaryIndexEntries,
+    fill_factor: f64,
+) -> eyre::Result<RebuildReport> {
+    let config = BulkLoadConfig {
+        fill_factor,
+        space_id: space_info.id,
+        first_page_id: (TOC_PAGE_ID + 1).into(),
+    };
+    let column = index.column.clone();
+    let entries_count = index.entries.len();
+    let index_pages_count = match &index.ty {
+        ColumnType::String => {
+            let values = convert_entries::<String>(index.entries)?;
+            let loaded =
+                bulk_load_unsized_index_pages::<_, { INNER_PAGE_SIZE as u32 }>(values, config)
+                    .wrap_err_with(|| format!("Index for `{column}` can not be built"))?;
+            persist_loaded_index(file, space_info, &index.column, &index.ty, loaded).await?
+        }
+        ColumnType::I128 => persist_sized::<i128>(file, space_info, index, config).await?,
+        ColumnType::I64 => persist_sized::<i64>(file, space_info, index, config).await?,
+        ColumnType::I32 => persist_sized::<i32>(file, space_info, index, config).await?,
+        ColumnType::I16 => persist_sized::<i16>(file, space_info, index, config).await?,
+        ColumnType::I8 => persist_sized::<i8>(file, space_info, index, config).await?,
+        ColumnType::U128 => persist_sized::<u128>(file, space_info, index, config).await?,
+        ColumnType::U64 => persist_sized::<u64>(file, space_info, index, config).await?,
+        ColumnType::U32 => persist_sized::<u32>(file, space_info, index, config).await?,
+        ColumnType::U16 => persist_sized::<u16>(file, space_info, index, config).await?,
+        ColumnType::U8 => persist_sized::<u8>(file, space_info, index, config).await?,
+        ty => bail!("Index on `{}` type can not be rebuilt", ty),
+    };
+
+    Ok(RebuildReport {
+        column,
+        entries_count,
+        index_pages_count,
+    })
+}
+
+fn convert_entries<T>(entries: Vec<(DataTypeValue, Link)>) -> eyre::Result<Vec<(T, Link)>>
+where
+    T: Ord + TryFrom<DataTypeValue, Error = eyre::Report>,
+{
+    let mut values = entries
+        .into_iter()
+        .map(|(v, l)| Ok((T::try_from(v)?, l)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    values.sort();
+    Ok(values)
+}
+
+async fn persist_sized<T>(
+    file: &mut File,
+    space_info: &SpaceInfoPage,
+    index: SecondaryIndexEntries,
+    config: BulkLoadConfig,
+) -> eyre::Result<usize>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + Send
+        + Sync
+        + TryFrom<DataTypeValue, Error = eyre::Report>
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>> + Ord,
+{
+    let values = convert_entries::<T>(index.entries)?;
+    let loaded = bulk_load_index_pages(values, config)
+        .wrap_err_with(|| format!("Index for `{}` can not be built", index.column))?;
+    persist_loaded_index(file, space_info, &index.column, &index.ty, loaded).await
+}
+
+async fn persist_loaded_index<Page, T>(
+    file: &mut File,
+    space_info: &SpaceInfoPage,
+    column: &str,
+    ty: &ColumnType,
+    loaded: BulkLoadedIndex<Page, T>,
+) -> eyre::Result<usize>
+where
+    Page: Persistable + Send + Sync,
+    T: Archive
+        + Debug
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + Send
+        + Sync
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>> + Ord,
+{
+    let index_pages_count = loaded.pages.len();
+    let toc_pages_count = loaded
+        .toc
+        .clone()
+        .split_by_size(INNER_PAGE_SIZE)
+        .wrap_err_with(|| format!("Table of contents of the `{column}` index can not be built"))?
+        .len();
+
+    let mut info = GeneralPage {
+        header: GeneralHeader::new(0.into(), PageType::SpaceInfo, space_info.id),
+        inner: SpaceInfoPage {
+            id: space_info.id,
+            page_count: (index_pages_count + toc_pages_count) as u32,
+            pk_gen_state: (),
+            name: column.to_string(),
+            version: space_info.version,
+            columns: vec![],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec![],
+            secondary_index_schemas: vec![IndexSchema::single(column, ty.clone())],
+            empty_links_list: vec![],
+        },
+    };
+    persist_page(&mut info, file).await?;
+    persist_pages_batch(loaded.pages, file).await?;
+    // Additional table of contents pages are placed after index pages.
+    let next_page_id = TOC_PAGE_ID + 1 + index_pages_count as u32;
+    persist_table_of_contents_chain(
+        file,
+        loaded.toc,
+        space_info.id,
+        TOC_PAGE_ID.into(),
+        (next_page_id..).map(Into::into),
+    )
+    .await?;
+
+    Ok(index_pages_count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{extract_secondary_index_entries, persist_secondary_index};
+    use crate::persistence::data::{ColumnType, DataTypeValue};
+    use crate::{
+        parse_page, ColumnSchema, IndexPage, IndexSchema, Link, SpaceInfoPage, TableOfContentsPage,
+        UnsizedIndexPage, INNER_PAGE_SIZE,
+    };
+
+    fn space_info() -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count: 1,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+                ColumnSchema::new(2, "age", ColumnType::U32),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![
+                IndexSchema::single("name", ColumnType::String),
+                IndexSchema::single("age", ColumnType::U32),
+            ],
+            empty_links_list: vec![],
+        }
+    }
+
+    fn rows() -> Vec<(Link, Vec<DataTypeValue>)> {
+        (0..100u32)
+            .map(|i| {
+                (
+                    Link {
+                        page_id: 1.into(),
+                        offset: i * 32,
+                        length: 32,
+                    },
+                    vec![
+                        DataTypeValue::U64(i as u64),
+                        DataTypeValue::String(format!("name {:03}", 99 - i)),
+                        DataTypeValue::U32(i * 2),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_entries() {
+        let indexes = extract_secondary_index_entries(&space_info(), &rows()).unwrap();
+        assert_eq!(indexes.len(), 2);
+        assert_eq!(indexes[0].column, "name");
+        assert_eq!(
+            indexes[0].entries[0].0,
+            DataTypeValue::String("name 099".to_string())
+        );
+        assert_eq!(indexes[1].entries[3].0, DataTypeValue::U32(6));
+
+        let mut info = space_info();
+        info.secondary_index_schemas
+            .push(IndexSchema::single("unknown", ColumnType::U8));
+        assert!(extract_secondary_index_entries(&info, &rows()).is_err());
+
+        let mut info = space_info();
+        let mut composite = IndexSchema::single("name_age", ColumnType::String);
+        composite.columns[0].name = "name".to_string();
+        composite
+            .columns
+            .push(info.secondary_index_schemas[1].columns[0].clone());
+        info.secondary_index_schemas.push(composite);
+        let err = extract_secondary_index_entries(&info, &rows()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Composite index `name_age` can not be rebuilt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persist_secondary_index() {
+        let info = space_info();
+        let mut indexes = extract_secondary_index_entries(&info, &rows()).unwrap();
+
+        let filename = "tests/data/rebuild_age_index.wt";
+        let mut file = tokio::fs::File::create(filename).await.unwrap();
+        let report = persist_secondary_index(&mut file, &info, indexes.remove(1), 1.0)
+            .await
+            .unwrap();
+        assert_eq!(report.entries_count, 100);
+        assert_eq!(report.index_pages_count, 1);
+
+        let mut file = tokio::fs::File::open(filename).await.unwrap();
+        let toc = parse_page::<TableOfContentsPage<u32>, { INNER_PAGE_SIZE as u32 }>(&mut file, 1)
+            .await
+            .unwrap();
+        assert_eq!(toc.inner.get(&198), Some(2.into()));
+        let page = parse_page::<IndexPage<u32>, { INNER_PAGE_SIZE as u32 }>(&mut file, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.inner.current_length, 100);
+        assert_eq!(page.inner.index_values[10].key, 20);
+        assert_eq!(page.inner.index_values[10].link.offset, 10 * 32);
+
+        let filename = "tests/data/rebuild_name_index.wt";
+        let mut file = tokio::fs::File::create(filename).await.unwrap();
+        persist_secondary_index(&mut file, &info, indexes.remove(0), 1.0)
+            .await
+            .unwrap();
+        let mut file = tokio::fs::File::open(filename).await.unwrap();
+        let page = parse_page::<
+            UnsizedIndexPage<String, { INNER_PAGE_SIZE as u32 }>,
+            { INNER_PAGE_SIZE as u32 },
+        >(&mut file, 2)
+        .await
+        .unwrap();
+        assert_eq!(page.inner.index_values[0].key, "name 000");
+        assert_eq!(page.inner.index_values[0].link.offset, 99 * 32);
+    }
+}
@@ -0,0 +1,268 @@
+//! Offline rewrite of the space's data file.
+//!
+//! Rows of the data pages written under older schema versions are upgraded
+//! on every read. Rewrite reads all live rows, archives upgraded rows under
+//! the current schema version and writes all rows to a fresh file without
+//! schema history. Rows get new [`Link`]'s, so secondary indexes should be
+//! rebuilt after rewrite.
+
+use tokio::fs::File;
+
+use crate::page::{parse_data_pages_batch, parse_space_info};
+use crate::persistence::data::{
+    data_page_row_links, encode_row, parse_data_page_ids, persist_data_rows, DataTypeValue,
+    RowReader,
+};
+use crate::{Link, SpaceInfoPage, INNER_PAGE_SIZE, PAGE_SIZE};
+
+/// Summary of the rewritten space.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RewriteReport {
+    pub pages_read: usize,
+    pub pages_written: usize,
+    pub rows: usize,
+    /// Rows that were written under older schema versions.
+    pub rows_upgraded: usize,
+}
+
+/// Space written by [`rewrite_space`].
+#[derive(Clone, Debug)]
+pub struct RewrittenSpace {
+    pub space_info: SpaceInfoPage,
+    /// Rows with their new [`Link`]'s.
+    pub rows: Vec<(Link, Vec<DataTypeValue>)>,
+    pub report: RewriteReport,
+}
+
+/// Rewrites all live rows of the `input` space file to the `output` file
+/// under the current schema version.
+pub async fn rewrite_space(input: &mut File, output: &mut File) -> eyre::Result<RewrittenSpace> {
+    let space_info = parse_space_info::<PAGE_SIZE>(input).await?;
+    let reader = RowReader::new(&space_info)?;
+    let ids = parse_data_page_ids(input).await?;
+    let pages = parse_data_pages_batch::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(input, ids).await?;
+
+    let mut report = RewriteReport::default();
+    let mut rows = vec![];
+    for page in &pages {
+        report.pages_read += 1;
+        let page_id = page.header.page_id;
+        let links = data_page_row_links(
+            page,
+            reader.layout(page_id),
+            space_info.page_empty_links(page_id),
+        )?;
+        for link in links {
+            let bytes = page.inner.get_at(link)?;
+            let values = reader.read_row(page_id, bytes)?;
+            let bytes = if reader.is_current(page_id) {
+                bytes.to_vec()
+            } else {
+                report.rows_upgraded += 1;
+                encode_row(reader.types(), &values)?
+            };
+            rows.push((bytes, values))
+        }
+    }
+    report.rows = rows.len();
+
+    let (bytes, values): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
+    let rows = bytes.iter().map(Vec::as_slice).zip(values);
+    let (space_info, rows) = persist_data_rows(output, &space_info, rows).await?;
+    report.pages_written = space_info.page_count as usize;
+
+    Ok(RewrittenSpace {
+        space_info,
+        rows,
+        report,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use tokio::fs::File;
+
+    use super::rewrite_space;
+    use crate::persistence::data::{read_data_rows, ColumnType, DataTypeValue};
+    use crate::persistence::fsck::check_file;
+    use crate::{
+        persist_page, persist_pages_batch, ColumnSchema, DataPage, GeneralHeader, GeneralPage,
+        Link, PageType, SchemaChange, SpaceInfoPage, INNER_PAGE_SIZE,
+    };
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct RowV0 {
+        pub id: u64,
+        pub name: String,
+        pub age: u32,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct RowV1 {
+        pub id: u64,
+        pub full_name: String,
+        pub score: f64,
+    }
+
+    fn page(header: GeneralHeader, rows: &[Vec<u8>]) -> GeneralPage<DataPage<INNER_PAGE_SIZE>> {
+        let mut data = [0u8; INNER_PAGE_SIZE];
+        let mut length = 0;
+        for row in rows {
+            data[length..length + row.len()].copy_from_slice(row);
+            length += row.len();
+        }
+        GeneralPage {
+            header,
+            inner: DataPage {
+                length: length as u32,
+                data,
+            },
+        }
+    }
+
+    /// Writes space with two pages of rows written under the first schema
+    /// version and one page written under the second one. Row `5` is deleted
+    /// before the schema change.
+    async fn write_evolved_file(filename: &str) -> SpaceInfoPage {
+        let mut info = SpaceInfoPage {
+            id: 1.into(),
+            page_count: 2,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+                ColumnSchema::new(2, "age", ColumnType::U32),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![],
+            empty_links_list: vec![],
+        };
+        let old_row = |id: u64| {
+            let row = RowV0 {
+                id,
+                name: format!("old row {id}"),
+                age: id as u32,
+            };
+            rkyv::to_bytes::<rkyv::rancor::Error>(&row)
+                .unwrap()
+                .to_vec()
+        };
+        let length = old_row(0).len() as u32;
+        info.empty_links_list.push(Link {
+            page_id: 1.into(),
+            offset: 5 * length,
+            length,
+        });
+        info.evolve_schema(&[
+            SchemaChange::DropColumn {
+                name: "age".to_string(),
+            },
+            SchemaChange::RenameColumn {
+                from: "name".to_string(),
+                to: "full_name".to_string(),
+            },
+            SchemaChange::AddColumn {
+                name: "score".to_string(),
+                ty: ColumnType::F64,
+                default: Some(DataTypeValue::F64(0.5)),
+            },
+        ])
+        .unwrap();
+        info.page_count = 3;
+
+        let new_row = |id: u64| {
+            let row = RowV1 {
+                id,
+                full_name: format!("new row {id}"),
+                score: id as f64,
+            };
+            rkyv::to_bytes::<rkyv::rancor::Error>(&row)
+                .unwrap()
+                .to_vec()
+        };
+        let mut first = GeneralHeader::new(1.into(), PageType::Data, 1.into());
+        let mut second = first.follow();
+        let third = second.follow();
+        let first = page(first, &(0..10).map(old_row).collect::<Vec<_>>());
+        let second = page(second, &(10..20).map(old_row).collect::<Vec<_>>());
+        let third = page(third, &(20..30).map(new_row).collect::<Vec<_>>());
+
+        let mut file = File::create(filename).await.unwrap();
+        let mut space_info = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::SpaceInfo, 1.into()),
+            inner: info.clone(),
+        };
+        persist_page(&mut space_info, &mut file).await.unwrap();
+        persist_pages_batch(vec![first, second, third], &mut file)
+            .await
+            .unwrap();
+        info
+    }
+
+    fn expected_rows() -> Vec<Vec<DataTypeValue>> {
+        (0..30)
+            .filter(|id| *id != 5)
+            .map(|id| {
+                let (name, score) = if id < 20 {
+                    (format!("old row {id}"), 0.5)
+                } else {
+                    (format!("new row {id}"), id as f64)
+                };
+                vec![
+                    DataTypeValue::U64(id),
+                    DataTypeValue::String(name),
+                    DataTypeValue::F64(score),
+                ]
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_read_evolved_file() {
+        let filename = "tests/data/rewrite_evolved.wt";
+        let info = write_evolved_file(filename).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+
+        let mut file = File::open(filename).await.unwrap();
+        let (space_info, rows) = read_data_rows(&mut file).await.unwrap();
+        assert_eq!(space_info, info);
+        let rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+        assert_eq!(rows, expected_rows());
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_evolved_file() {
+        let filename = "tests/data/rewrite_evolved_input.wt";
+        let info = write_evolved_file(filename).await;
+
+        let mut input = File::open(filename).await.unwrap();
+        let output_filename = "tests/data/rewrite_evolved_output.wt";
+        let mut output = File::create(output_filename).await.unwrap();
+        let rewritten = rewrite_space(&mut input, &mut output).await.unwrap();
+        assert_eq!(rewritten.report.pages_read, 3);
+        assert_eq!(rewritten.report.rows, 29);
+        assert_eq!(rewritten.report.rows_upgraded, 19);
+        assert!(rewritten.space_info.schema_history.is_empty());
+        assert_eq!(rewritten.space_info.schema_version, info.schema_version);
+        assert_eq!(rewritten.space_info.columns, info.columns);
+
+        let mut file = File::open(output_filename).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+
+        let mut file = File::open(output_filename).await.unwrap();
+        let (space_info, rows) = read_data_rows(&mut file).await.unwrap();
+        assert_eq!(space_info, rewritten.space_info);
+        assert_eq!(rows, rewritten.rows);
+        let rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+        assert_eq!(rows, expected_rows());
+    }
+}
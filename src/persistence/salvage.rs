@@ -0,0 +1,519 @@
+//! Salvage of rows from damaged space's data file.
+//!
+//! Pages are read independently of each other, so damaged page does not stop
+//! reading of the following ones. Pages format has no checksums, so page is
+//! dropped if it's header is not a valid archived [`GeneralHeader`], doesn't
+//! belong to the space or it's data is out of file's bounds. Data pages that
+//! can not be split into rows are scanned for rows that are valid for
+//! [`RowLayout`], bytes between found rows are dropped. Scan can take damaged
+//! bytes for a valid row if row's schema has weak validation (for example,
+//! row without strings accepts any bytes), so rows found by scan are marked.
+//! Recovered rows are written to a fresh file, so they get new [`Link`]'s.
+
+use std::ops::Range;
+
+use eyre::bail;
+use tokio::fs::File;
+
+use crate::page::PageId;
+use crate::persistence::data::{
+    data_page_row_links, encode_row, persist_data_rows, split_rows, DataTypeValue, RowLayout,
+    RowReader,
+};
+use crate::persistence::fsck::read_page;
+use crate::{
+    space, DataPage, GeneralHeader, GeneralPage, Link, PageType, SpaceInfoPage, DATA_VERSION,
+    INNER_PAGE_SIZE, PAGE_SIZE,
+};
+
+/// Page that was dropped entirely.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DroppedPage {
+    pub page_id: PageId,
+    pub reason: String,
+}
+
+/// Part of the data page where no valid rows were found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DroppedRange {
+    pub page_id: PageId,
+    pub range: Range<u32>,
+}
+
+/// Result of the salvage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SalvageReport {
+    pub pages_read: usize,
+    /// Data pages that were split into rows completely.
+    pub pages_recovered: usize,
+    /// Data pages whose rows were found by scan.
+    pub pages_scanned: usize,
+    pub rows_recovered: usize,
+    /// Recovered rows that were found by scan of the damaged pages.
+    pub rows_from_scan: usize,
+    pub dropped_pages: Vec<DroppedPage>,
+    pub dropped_ranges: Vec<DroppedRange>,
+}
+
+impl SalvageReport {
+    /// Returns `true` if nothing was dropped.
+    pub fn is_complete(&self) -> bool {
+        self.dropped_pages.is_empty() && self.dropped_ranges.is_empty()
+    }
+}
+
+/// Row recovered from the damaged file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SalvagedRow {
+    /// [`Link`] of the row in the damaged file.
+    pub link: Link,
+    /// Archived row's bytes. Rows written under older schema versions are
+    /// archived again under the current one.
+    pub bytes: Vec<u8>,
+    pub values: Vec<DataTypeValue>,
+    /// Row was found by scan of the damaged page, so it can be made of
+    /// damaged bytes.
+    pub from_scan: bool,
+}
+
+/// Rows recovered from the damaged file.
+#[derive(Clone, Debug)]
+pub struct SalvagedSpace {
+    pub space_info: SpaceInfoPage,
+    pub rows: Vec<SalvagedRow>,
+    pub report: SalvageReport,
+}
+
+/// Reads all rows that can be recovered from the space's data file. Space's
+/// [`SpaceInfoPage`] is read from the file if `space_info` is not set, error
+/// is returned if it's damaged too.
+pub async fn salvage_rows(
+    file: &mut File,
+    space_info: Option<SpaceInfoPage>,
+) -> eyre::Result<SalvagedSpace> {
+    let file_length = file.metadata().await?.len();
+    let pages_count = file_length.div_ceil(PAGE_SIZE as u64) as u32;
+    let space_info = match space_info {
+        Some(space_info) => space_info,
+        None => read_space_info(file, file_length).await?,
+    };
+    let reader =
+        RowReader::new(&space_info).map_err(|e| e.wrap_err("Row schema is not supported"))?;
+
+    let mut report = SalvageReport::default();
+    let mut rows = vec![];
+    for index in 1..pages_count {
+        let page_id = index.into();
+        let mut violations = vec![];
+        let page = read_page(file, page_id, file_length, &mut violations).await?;
+        report.pages_read += 1;
+        if let Some(violation) = violations.first() {
+            report.dropped_pages.push(DroppedPage {
+                page_id,
+                reason: violation.message(),
+            });
+            continue;
+        }
+        let Some((header, Some(data))) = page else {
+            unreachable!("page without data is reported as violation")
+        };
+        if let Err(reason) = check_data_page_header(&header, page_id, space_info.id) {
+            report.dropped_pages.push(DroppedPage { page_id, reason });
+            continue;
+        }
+        if header.page_type == PageType::Empty {
+            continue;
+        }
+
+        let mut page = GeneralPage {
+            header,
+            inner: DataPage {
+                length: data.len() as u32,
+                data: [0; INNER_PAGE_SIZE],
+            },
+        };
+        // Header's `page_id` is not trusted, rows are linked by page's
+        // position in the file.
+        page.header.page_id = page_id;
+        page.inner.data[..data.len()].copy_from_slice(&data);
+        let layout = reader.layout(page_id);
+        let (links, from_scan) =
+            match data_page_row_links(&page, layout, space_info.page_empty_links(page_id)) {
+                Ok(links) => {
+                    report.pages_recovered += 1;
+                    (links, false)
+                }
+                Err(_) => {
+                    report.pages_scanned += 1;
+                    let (links, dropped) =
+                        scan_rows(&page, layout, space_info.page_empty_links(page_id));
+                    report.rows_from_scan += links.len();
+                    report.dropped_ranges.extend(dropped);
+                    (links, true)
+                }
+            };
+        for link in links {
+            let mut bytes = page.inner.get_at(link)?.to_vec();
+            // Layout accepts only decodable rows, but row that is not decoded
+            // anyway is dropped instead of failing whole salvage.
+            let Ok(values) = reader.read_row(page_id, &bytes) else {
+                report.dropped_ranges.push(DroppedRange {
+                    page_id,
+                    range: link.offset..link.offset + link.length,
+                });
+                continue;
+            };
+            // Rows of the older schema versions are archived again.
+            if !reader.is_current(page_id) {
+                bytes = encode_row(reader.types(), &values)?
+            }
+            rows.push(SalvagedRow {
+                link,
+                bytes,
+                values,
+                from_scan,
+            })
+        }
+    }
+    report.rows_recovered = rows.len();
+
+    Ok(SalvagedSpace {
+        space_info,
+        rows,
+        report,
+    })
+}
+
+async fn read_space_info(file: &mut File, file_length: u64) -> eyre::Result<SpaceInfoPage> {
+    let mut violations = vec![];
+    let page = read_page(file, 0.into(), file_length, &mut violations).await?;
+    if let Some(violation) = violations.first() {
+        bail!("Space info page can not be read: {}", violation.message())
+    }
+    let Some((header, Some(data))) = page else {
+        unreachable!("page without data is reported as violation")
+    };
+    if header.page_type != PageType::SpaceInfo {
+        bail!(
+            "First page has {} type instead of space info",
+            header.page_type
+        )
+    }
+    SpaceInfoPage::check_bytes(&data, header.data_version)?;
+
+    SpaceInfoPage::try_from_bytes(&data, header.data_version)
+}
+
+fn check_data_page_header(
+    header: &GeneralHeader,
+    page_id: PageId,
+    space_id: space::Id,
+) -> Result<(), String> {
+    if !matches!(header.page_type, PageType::Data | PageType::Empty) {
+        return Err(format!(
+            "page of type {} is not a data page",
+            header.page_type
+        ));
+    }
+    if header.page_id != page_id {
+        return Err(format!("header has page id {}", header.page_id));
+    }
+    if header.space_id != space_id {
+        return Err(format!("page belongs to space {}", header.space_id));
+    }
+    if header.data_version == 0 || header.data_version > DATA_VERSION {
+        return Err(format!(
+            "data version {} is not supported",
+            header.data_version
+        ));
+    }
+    Ok(())
+}
+
+/// Searches rows of the page that can not be split completely. Every part of
+/// the page between removed rows is split separately. If part can not be
+/// split, rows that chain from it's start and rows that chain to it's end are
+/// kept and bytes between them are dropped. Single row can be found in any
+/// bytes, so only rows that are chained to the known row's boundary are
+/// trusted.
+fn scan_rows(
+    page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>,
+    layout: &RowLayout,
+    empty_links: &[Link],
+) -> (Vec<Link>, Vec<DroppedRange>) {
+    let page_id = page.header.page_id;
+    let length = (page.inner.length as usize).min(INNER_PAGE_SIZE);
+    let mut holes = empty_links
+        .iter()
+        .filter(|l| l.page_id == page_id)
+        .map(|l| l.offset as usize..(l.offset + l.length) as usize)
+        .collect::<Vec<_>>();
+    holes.sort_by_key(|h| h.start);
+
+    let mut links = vec![];
+    let mut dropped = vec![];
+    let mut offset = 0;
+    while offset < length {
+        if let Some(hole) = holes.iter().find(|h| h.contains(&offset)) {
+            offset = hole.end;
+            continue;
+        }
+        let end = holes
+            .iter()
+            .map(|h| h.start)
+            .find(|start| *start > offset)
+            .unwrap_or(length)
+            .min(length);
+        let segment = &page.inner.data[offset..end];
+        let mut push_rows = |start: usize, lengths: Vec<usize>| {
+            let mut row_offset = offset + start;
+            for row_length in lengths {
+                links.push(Link {
+                    page_id,
+                    offset: row_offset as u32,
+                    length: row_length as u32,
+                });
+                row_offset += row_length;
+            }
+        };
+
+        // Rows start at offsets aligned to row's alignment.
+        let aligned = (0..segment.len())
+            .step_by(layout.align())
+            .chain([segment.len()])
+            .collect::<Vec<_>>();
+        let (suffix_start, suffix) = aligned
+            .iter()
+            .find_map(|start| {
+                split_rows(layout, &segment[*start..])
+                    .ok()
+                    .map(|l| (*start, l))
+            })
+            .expect("empty suffix is always split");
+        let (prefix_end, prefix) = aligned
+            .iter()
+            .rev()
+            .filter(|end| **end <= suffix_start)
+            .find_map(|end| split_rows(layout, &segment[..*end]).ok().map(|l| (*end, l)))
+            .expect("empty prefix is always split");
+        push_rows(0, prefix);
+        push_rows(suffix_start, suffix);
+        if prefix_end != suffix_start {
+            dropped.push(DroppedRange {
+                page_id,
+                range: (offset + prefix_end) as u32..(offset + suffix_start) as u32,
+            })
+        }
+        offset = end;
+    }
+    links.sort_by_key(|l| l.offset);
+
+    (links, dropped)
+}
+
+/// Writes salvaged rows to the new space's data file. Rows are packed into
+/// data pages one after another, [`SpaceInfoPage`] gets new `page_count` and
+/// empty `empty_links_list`. Returns written [`SpaceInfoPage`] and rows with
+/// their new [`Link`]'s, so indexes can be rebuilt.
+pub async fn persist_salvaged_space(
+    file: &mut File,
+    salvaged: &SalvagedSpace,
+) -> eyre::Result<(SpaceInfoPage, Vec<(Link, Vec<DataTypeValue>)>)> {
+    let rows = salvaged
+        .rows
+        .iter()
+        .map(|row| (row.bytes.as_slice(), row.values.clone()));
+    persist_data_rows(file, &salvaged.space_info, rows).await
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::SeekFrom;
+
+    use rkyv::{Archive, Deserialize, Serialize};
+    use tokio::fs::{File, OpenOptions};
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    use super::{persist_salvaged_space, salvage_rows, DroppedPage};
+    use crate::persistence::data::{read_data_rows, ColumnType, DataTypeValue};
+    use crate::persistence::fsck::check_file;
+    use crate::{
+        persist_page, persist_pages_batch, ColumnSchema, DataPage, GeneralHeader, GeneralPage,
+        IndexSchema, PageType, SpaceInfoPage, GENERAL_HEADER_SIZE, INNER_PAGE_SIZE, PAGE_SIZE,
+    };
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub id: u64,
+        pub name: String,
+    }
+
+    const ROWS_PER_PAGE: u64 = 20;
+
+    fn space_info(page_count: u32) -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 1.into(),
+            page_count,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![IndexSchema::single("name", ColumnType::String)],
+            empty_links_list: vec![],
+        }
+    }
+
+    /// Writes space with `count` data pages. Returns offsets of the rows in
+    /// data pages.
+    async fn write_data_file(filename: &str, count: u64) -> Vec<usize> {
+        let mut header = GeneralHeader::new(1.into(), PageType::Data, 1.into());
+        let mut pages = vec![];
+        let mut offsets = vec![];
+        for page in 0..count {
+            let mut data = [0u8; INNER_PAGE_SIZE];
+            let mut length = 0;
+            for i in 0..ROWS_PER_PAGE {
+                let id = page * ROWS_PER_PAGE + i;
+                let row = Row {
+                    id,
+                    name: format!("long name of the row {id:03}"),
+                };
+                let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+                data[length..length + bytes.len()].copy_from_slice(bytes.as_slice());
+                if page == 0 {
+                    offsets.push(length)
+                }
+                length += bytes.len();
+            }
+            if page != 0 {
+                header = header.follow();
+            }
+            pages.push(GeneralPage {
+                header,
+                inner: DataPage {
+                    length: length as u32,
+                    data,
+                },
+            });
+        }
+
+        let mut file = File::create(filename).await.unwrap();
+        let mut info = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::SpaceInfo, 1.into()),
+            inner: space_info(count as u32),
+        };
+        persist_page(&mut info, &mut file).await.unwrap();
+        persist_pages_batch(pages, &mut file).await.unwrap();
+        offsets
+    }
+
+    async fn corrupt(filename: &str, position: u64, bytes: &[u8]) {
+        let mut file = OpenOptions::new().write(true).open(filename).await.unwrap();
+        file.seek(SeekFrom::Start(position)).await.unwrap();
+        file.write_all(bytes).await.unwrap();
+    }
+
+    fn ids(values: &[Vec<DataTypeValue>]) -> Vec<u64> {
+        values
+            .iter()
+            .map(|row| match row[0] {
+                DataTypeValue::U64(id) => id,
+                _ => panic!("id should be u64"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_salvage_undamaged_file() {
+        let filename = "tests/data/salvage_undamaged.wt";
+        write_data_file(filename, 3).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        let salvaged = salvage_rows(&mut file, None).await.unwrap();
+        assert!(salvaged.report.is_complete(), "{:?}", salvaged.report);
+        assert_eq!(salvaged.report.pages_recovered, 3);
+        assert_eq!(salvaged.report.rows_recovered, 60);
+        let values = salvaged
+            .rows
+            .iter()
+            .map(|r| r.values.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids(&values), (0..60).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_salvage_damaged_file() {
+        let filename = "tests/data/salvage_damaged.wt";
+        let offsets = write_data_file(filename, 3).await;
+        // Second data page's header has invalid `PageType`.
+        corrupt(filename, PAGE_SIZE as u64 * 2 + 20, &[0xff, 0xff]).await;
+        // Third row of the first data page is damaged.
+        let row_start = (PAGE_SIZE + GENERAL_HEADER_SIZE + offsets[2]) as u64;
+        corrupt(filename, row_start, &[0xfe; 4]).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        let salvaged = salvage_rows(&mut file, None).await.unwrap();
+        let report = &salvaged.report;
+        assert_eq!(report.pages_read, 3);
+        assert_eq!(report.pages_recovered, 1);
+        assert_eq!(report.pages_scanned, 1);
+        assert_eq!(report.dropped_pages.len(), 1);
+        assert_eq!(report.dropped_pages[0].page_id, 2.into());
+        assert_eq!(report.dropped_ranges.len(), 1);
+        // Damaged row's bytes look like short valid rows, so dropped range
+        // can be shorter than the damaged row.
+        let damaged = offsets[2] as u32..offsets[3] as u32;
+        let range = &report.dropped_ranges[0].range;
+        assert!(damaged.start <= range.start && range.end == damaged.end);
+        let (valid, scanned): (Vec<_>, Vec<_>) = salvaged
+            .rows
+            .iter()
+            .filter(|r| r.from_scan)
+            .partition(|r| !damaged.contains(&r.link.offset));
+        assert_eq!(valid.len(), 19);
+        assert!(scanned.iter().all(|r| r.link.offset < range.start));
+        let values = salvaged
+            .rows
+            .iter()
+            .filter(|r| !damaged.contains(&r.link.offset) || r.link.page_id != 1.into())
+            .map(|r| r.values.clone())
+            .collect::<Vec<_>>();
+        let expected = (0..20)
+            .filter(|id| *id != 2)
+            .chain(40..60)
+            .collect::<Vec<_>>();
+        assert_eq!(ids(&values), expected);
+
+        let output = "tests/data/salvage_repaired.wt";
+        let mut file = File::create(output).await.unwrap();
+        let (space_info, rows) = persist_salvaged_space(&mut file, &salvaged).await.unwrap();
+        assert_eq!(space_info.page_count, 1);
+
+        let mut file = File::open(output).await.unwrap();
+        let report = check_file(&mut file).await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        let (_, read) = read_data_rows(&mut file).await.unwrap();
+        assert_eq!(read, rows);
+        assert_eq!(read.len(), salvaged.rows.len());
+    }
+
+    #[tokio::test]
+    async fn test_salvage_with_damaged_space_info() {
+        let filename = "tests/data/salvage_space_info.wt";
+        write_data_file(filename, 2).await;
+        corrupt(filename, 20, &[0xff, 0xff]).await;
+
+        let mut file = File::open(filename).await.unwrap();
+        assert!(salvage_rows(&mut file, None).await.is_err());
+        let salvaged = salvage_rows(&mut file, Some(space_info(0))).await.unwrap();
+        assert!(salvaged.report.is_complete());
+        assert_eq!(salvaged.report.rows_recovered, 40);
+        assert_eq!(salvaged.report.dropped_pages, Vec::<DroppedPage>::new());
+    }
+}
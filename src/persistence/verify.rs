@@ -0,0 +1,776 @@
+//! Consistency check of secondary index against space's data pages.
+//!
+//! Index pages are walked in [`TableOfContentsPage`] order. For every page
+//! it's checked that `node_id` is the page's max value and that values are
+//! sorted, so pages do not overlap each other. Every value's [`Link`] should
+//! point to the live row of some data page, row is decoded and it's indexed
+//! column is compared with the value's key. All found problems are collected
+//! into [`VerifyReport`] instead of failing on first of them.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+use eyre::bail;
+use rkyv::api::high::HighDeserializer;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+
+use crate::page::{parse_space_info, PageId};
+use crate::persistence::data::{
+    data_page_row_links, parse_data_page_ids, ColumnType, DataTypeValue, RowReader,
+};
+use crate::{
+    parse_data_pages_batch, parse_general_header_by_index, parse_page,
+    parse_table_of_contents_chain, DataPage, GeneralPage, IndexColumn, IndexPage, IndexValue, Link,
+    PageType, Persistable, SizeMeasurable, SpaceInfoPage, TableOfContentsPage, UnsizedIndexPage,
+    VariableSizeMeasurable, INNER_PAGE_SIZE, PAGE_SIZE,
+};
+
+/// [`PageId`] of the index's first [`TableOfContentsPage`] in files written by
+/// [`persist_secondary_index`].
+///
+/// [`persist_secondary_index`]: crate::persistence::rebuild::persist_secondary_index
+const TOC_PAGE_ID: u32 = 1;
+
+/// Problem found in the index. Keys are kept in their [`Debug`]
+/// representation, so report does not depend on the index's key type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IndexViolation {
+    /// Page referenced from [`TableOfContentsPage`] is not found or is not an
+    /// index page.
+    MissingIndexPage { page_id: PageId, reason: String },
+    /// Index page is not referenced from [`TableOfContentsPage`].
+    UnreferencedIndexPage { page_id: PageId },
+    /// [`TableOfContentsPage`] record's key is not equal to the page's
+    /// `node_id`.
+    TocKeyMismatch {
+        page_id: PageId,
+        toc_key: String,
+        node_id: String,
+    },
+    /// Page's `node_id` is not equal to the page's max value.
+    NodeIdNotMax {
+        page_id: PageId,
+        node_id: String,
+        max_value: String,
+    },
+    /// Value at `position` is not greater than previous value of the page.
+    UnsortedValues { page_id: PageId, position: usize },
+    /// Page's min value is not greater than previous page's `node_id`.
+    OverlappingPages {
+        page_id: PageId,
+        previous_page_id: PageId,
+    },
+    /// Data page can not be split into rows, so links to it are only checked
+    /// to be in page's bounds.
+    DataPageNotParsed { page_id: PageId, reason: String },
+    /// [`Link`] points to page that is not a data page of the space.
+    LinkToMissingDataPage { page_id: PageId, link: Link },
+    /// [`Link`] points outside of data page's written part.
+    LinkOutOfBounds { page_id: PageId, link: Link },
+    /// [`Link`] points to the row that is listed in
+    /// [`SpaceInfoPage::page_empty_links`].
+    LinkToRemovedRow { page_id: PageId, link: Link },
+    /// [`Link`] is in data page's bounds, but it is not the row's link.
+    LinkNotRowStart { page_id: PageId, link: Link },
+    /// Row that [`Link`] points to can not be decoded.
+    RowNotDecoded {
+        page_id: PageId,
+        link: Link,
+        reason: String,
+    },
+    /// Row's indexed column is not equal to the value's key.
+    ValueMismatch {
+        page_id: PageId,
+        link: Link,
+        indexed: String,
+        actual: String,
+    },
+}
+
+/// Result of the index check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerifyReport {
+    pub column: String,
+    pub pages_checked: usize,
+    pub values_checked: usize,
+    pub violations: Vec<IndexViolation>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no violations were found.
+    pub fn is_consistent(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Data pages of the space that index is checked against.
+#[derive(Debug)]
+pub struct SpaceData<const DATA_LENGTH: usize> {
+    pub space_info: SpaceInfoPage,
+    /// Is `None` if space's row schema is not supported.
+    reader: Option<RowReader>,
+    pages: HashMap<PageId, GeneralPage<DataPage<DATA_LENGTH>>>,
+    /// Live rows' [`Link`]'s of the pages which were split into rows.
+    row_links: HashMap<PageId, HashSet<Link>>,
+    /// Pages which can not be split into rows with the reason.
+    unparsed: Vec<(PageId, String)>,
+}
+
+impl<const DATA_LENGTH: usize> SpaceData<DATA_LENGTH> {
+    pub fn new(space_info: SpaceInfoPage, pages: Vec<GeneralPage<DataPage<DATA_LENGTH>>>) -> Self {
+        let reader = RowReader::new(&space_info);
+        let mut row_links = HashMap::new();
+        let mut unparsed = vec![];
+        for page in &pages {
+            let links = reader
+                .as_ref()
+                .map_err(|e| e.to_string())
+                .and_then(|reader| {
+                    let layout = reader.layout(page.header.page_id);
+                    data_page_row_links(
+                        page,
+                        layout,
+                        space_info.page_empty_links(page.header.page_id),
+                    )
+                    .map_err(|e| e.to_string())
+                });
+            match links {
+                Ok(links) => {
+                    row_links.insert(page.header.page_id, HashSet::from_iter(links));
+                }
+                Err(e) => unparsed.push((page.header.page_id, e)),
+            }
+        }
+        unparsed.sort_by_key(|(id, _)| *id);
+        let pages = pages.into_iter().map(|p| (p.header.page_id, p)).collect();
+
+        Self {
+            reader: reader.ok(),
+            space_info,
+            pages,
+            row_links,
+            unparsed,
+        }
+    }
+
+    fn is_removed(&self, link: &Link) -> bool {
+        self.space_info
+            .page_empty_links(link.page_id)
+            .iter()
+            .any(|l| {
+                l.page_id == link.page_id
+                    && l.offset <= link.offset
+                    && link.offset < l.offset + l.length.max(1)
+            })
+    }
+
+    /// Checks that `link` points to live row and returns indexed column of
+    /// this row if it can be decoded.
+    fn check_link(
+        &self,
+        page_id: PageId,
+        link: Link,
+        column: usize,
+        violations: &mut Vec<IndexViolation>,
+    ) -> Option<DataTypeValue> {
+        let Some(data_page) = self.pages.get(&link.page_id) else {
+            violations.push(IndexViolation::LinkToMissingDataPage { page_id, link });
+            return None;
+        };
+        let length = (data_page.inner.length as usize).min(DATA_LENGTH);
+        if link.length == 0 || (link.offset + link.length) as usize > length {
+            violations.push(IndexViolation::LinkOutOfBounds { page_id, link });
+            return None;
+        }
+        if self.is_removed(&link) {
+            violations.push(IndexViolation::LinkToRemovedRow { page_id, link });
+            return None;
+        }
+        // Rows of the page that was not split can not be decoded safely.
+        let row_links = self.row_links.get(&link.page_id)?;
+        if !row_links.contains(&link) {
+            violations.push(IndexViolation::LinkNotRowStart { page_id, link });
+            return None;
+        }
+
+        let bytes = data_page.inner.get_at(link).ok()?;
+        match self.reader.as_ref()?.read_row(link.page_id, bytes) {
+            Ok(row) => row.into_iter().nth(column),
+            Err(e) => {
+                violations.push(IndexViolation::RowNotDecoded {
+                    page_id,
+                    link,
+                    reason: e.to_string(),
+                });
+                None
+            }
+        }
+    }
+}
+
+impl SpaceData<INNER_PAGE_SIZE> {
+    /// Reads [`SpaceInfoPage`] and all data pages of the space file.
+    pub async fn read(file: &mut File) -> eyre::Result<Self> {
+        let space_info = parse_space_info::<PAGE_SIZE>(file).await?;
+        let ids = parse_data_page_ids(file).await?;
+        let pages =
+            parse_data_pages_batch::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(file, ids).await?;
+
+        Ok(Self::new(space_info, pages))
+    }
+}
+
+/// Common part of both index page kinds that is used by the check.
+trait VerifiedNode<T> {
+    fn node_id(&self) -> &IndexValue<T>;
+    fn values(&self) -> Vec<IndexValue<T>>;
+}
+
+impl<T> VerifiedNode<T> for IndexPage<T>
+where
+    T: Default + Clone + Ord + SizeMeasurable,
+{
+    fn node_id(&self) -> &IndexValue<T> {
+        &self.node_id
+    }
+
+    fn values(&self) -> Vec<IndexValue<T>> {
+        self.get_node().into_iter().map(Into::into).collect()
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> VerifiedNode<T> for UnsizedIndexPage<T, DATA_LENGTH>
+where
+    T: Archive
+        + Default
+        + Clone
+        + Ord
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    fn node_id(&self) -> &IndexValue<T> {
+        &self.node_id
+    }
+
+    fn values(&self) -> Vec<IndexValue<T>> {
+        self.get_node().into_iter().map(Into::into).collect()
+    }
+}
+
+/// Checks [`IndexPage`]'s of the index on `column` against space's data.
+/// `pages` should contain all pages referenced from `toc`.
+pub fn verify_index_pages<T, const DATA_LENGTH: usize>(
+    pages: &[GeneralPage<IndexPage<T>>],
+    toc: &TableOfContentsPage<T>,
+    data: &SpaceData<DATA_LENGTH>,
+    column: &str,
+) -> eyre::Result<VerifyReport>
+where
+    T: Debug + Default + Clone + Ord + SizeMeasurable + TryFrom<DataTypeValue>,
+{
+    verify(pages, toc, data, column)
+}
+
+/// Checks [`UnsizedIndexPage`]'s of the index on `column` against space's
+/// data. `pages` should contain all pages referenced from `toc`.
+pub fn verify_unsized_index_pages<T, const PAGE_DATA_LENGTH: u32, const DATA_LENGTH: usize>(
+    pages: &[GeneralPage<UnsizedIndexPage<T, PAGE_DATA_LENGTH>>],
+    toc: &TableOfContentsPage<T>,
+    data: &SpaceData<DATA_LENGTH>,
+    column: &str,
+) -> eyre::Result<VerifyReport>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Clone
+        + Ord
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + TryFrom<DataTypeValue>
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    verify(pages, toc, data, column)
+}
+
+fn verify<Page, T, const DATA_LENGTH: usize>(
+    pages: &[GeneralPage<Page>],
+    toc: &TableOfContentsPage<T>,
+    data: &SpaceData<DATA_LENGTH>,
+    column: &str,
+) -> eyre::Result<VerifyReport>
+where
+    Page: VerifiedNode<T>,
+    T: Debug + Clone + Ord + TryFrom<DataTypeValue>,
+{
+    let Some(column_pos) = data
+        .space_info
+        .columns
+        .iter()
+        .position(|c| c.name == column)
+    else {
+        bail!("Indexed column `{}` is not found in row schema", column)
+    };
+
+    let mut report = VerifyReport {
+        column: column.to_string(),
+        ..Default::default()
+    };
+    for (page_id, reason) in &data.unparsed {
+        report.violations.push(IndexViolation::DataPageNotParsed {
+            page_id: *page_id,
+            reason: reason.clone(),
+        })
+    }
+
+    let mut by_id = pages
+        .iter()
+        .map(|p| (p.header.page_id, p))
+        .collect::<HashMap<_, _>>();
+    let mut previous: Option<(PageId, IndexValue<T>)> = None;
+    for (key, page_id) in toc.iter() {
+        let page_id = *page_id;
+        let Some(page) = by_id.remove(&page_id) else {
+            report.violations.push(IndexViolation::MissingIndexPage {
+                page_id,
+                reason: "index page is not found".to_string(),
+            });
+            continue;
+        };
+        report.pages_checked += 1;
+
+        let node_id = page.inner.node_id();
+        if node_id.key != *key {
+            report.violations.push(IndexViolation::TocKeyMismatch {
+                page_id,
+                toc_key: format!("{key:?}"),
+                node_id: format!("{:?}", node_id.key),
+            })
+        }
+        let values = page.inner.values();
+        if let Some(last) = values.last() {
+            if last != node_id {
+                report.violations.push(IndexViolation::NodeIdNotMax {
+                    page_id,
+                    node_id: format!("{node_id:?}"),
+                    max_value: format!("{last:?}"),
+                })
+            }
+        }
+        for (position, pair) in values.windows(2).enumerate() {
+            if pair[0] >= pair[1] {
+                report.violations.push(IndexViolation::UnsortedValues {
+                    page_id,
+                    position: position + 1,
+                })
+            }
+        }
+        if let (Some((previous_page_id, previous_max)), Some(first)) = (&previous, values.first()) {
+            if first <= previous_max {
+                report.violations.push(IndexViolation::OverlappingPages {
+                    page_id,
+                    previous_page_id: *previous_page_id,
+                })
+            }
+        }
+        if let Some(last) = values.last() {
+            previous = Some((page_id, last.clone()));
+        }
+
+        for value in values {
+            report.values_checked += 1;
+            let Some(actual) =
+                data.check_link(page_id, value.link, column_pos, &mut report.violations)
+            else {
+                continue;
+            };
+            let matches = T::try_from(actual.clone())
+                .map(|v| v == value.key)
+                .unwrap_or(false);
+            if !matches {
+                report.violations.push(IndexViolation::ValueMismatch {
+                    page_id,
+                    link: value.link,
+                    indexed: format!("{:?}", value.key),
+                    actual: format!("{actual:?}"),
+                })
+            }
+        }
+    }
+    let mut unreferenced = by_id.into_keys().collect::<Vec<_>>();
+    unreferenced.sort();
+    for page_id in unreferenced {
+        report
+            .violations
+            .push(IndexViolation::UnreferencedIndexPage { page_id })
+    }
+
+    Ok(report)
+}
+
+/// Checks index file written by [`persist_secondary_index`] against space's
+/// data. Indexed column and it's type are taken from the index file's
+/// [`SpaceInfoPage`].
+///
+/// [`persist_secondary_index`]: crate::persistence::rebuild::persist_secondary_index
+pub async fn verify_secondary_index(
+    index_file: &mut File,
+    data: &SpaceData<INNER_PAGE_SIZE>,
+) -> eyre::Result<VerifyReport> {
+    let index_info = parse_space_info::<PAGE_SIZE>(index_file).await?;
+    let Some(IndexColumn {
+        name: column, ty, ..
+    }) = index_info
+        .secondary_index_schemas
+        .first()
+        .and_then(|s| s.single_column())
+    else {
+        bail!("Index file has no secondary index description")
+    };
+    let file_length = index_file.metadata().await?.len();
+    let page_count = file_length.div_ceil(PAGE_SIZE as u64) as u32;
+
+    match ty {
+        ColumnType::String => {
+            let toc =
+                parse_table_of_contents_chain::<String>(index_file, TOC_PAGE_ID.into()).await?;
+            let pages = read_index_pages::<UnsizedIndexPage<String, { INNER_PAGE_SIZE as u32 }>>(
+                index_file,
+                page_count,
+                PageType::IndexUnsized,
+            )
+            .await?;
+            verify_unsized_index_pages(&pages, &toc, data, column)
+        }
+        ColumnType::I128 => verify_sized::<i128>(index_file, page_count, data, column).await,
+        ColumnType::I64 => verify_sized::<i64>(index_file, page_count, data, column).await,
+        ColumnType::I32 => verify_sized::<i32>(index_file, page_count, data, column).await,
+        ColumnType::I16 => verify_sized::<i16>(index_file, page_count, data, column).await,
+        ColumnType::I8 => verify_sized::<i8>(index_file, page_count, data, column).await,
+        ColumnType::U128 => verify_sized::<u128>(index_file, page_count, data, column).await,
+        ColumnType::U64 => verify_sized::<u64>(index_file, page_count, data, column).await,
+        ColumnType::U32 => verify_sized::<u32>(index_file, page_count, data, column).await,
+        ColumnType::U16 => verify_sized::<u16>(index_file, page_count, data, column).await,
+        ColumnType::U8 => verify_sized::<u8>(index_file, page_count, data, column).await,
+        ty => bail!("Index on `{}` type can not be verified", ty),
+    }
+}
+
+async fn verify_sized<T>(
+    index_file: &mut File,
+    page_count: u32,
+    data: &SpaceData<INNER_PAGE_SIZE>,
+    column: &str,
+) -> eyre::Result<VerifyReport>
+where
+    T: Archive
+        + Debug
+        + Default
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + TryFrom<DataTypeValue>
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>> + Ord,
+{
+    let toc = parse_table_of_contents_chain::<T>(index_file, TOC_PAGE_ID.into()).await?;
+    let pages = read_index_pages::<IndexPage<T>>(index_file, page_count, PageType::Index).await?;
+    verify_index_pages(&pages, &toc, data, column)
+}
+
+/// Reads all pages of the index file with `page_type`.
+async fn read_index_pages<Page>(
+    index_file: &mut File,
+    page_count: u32,
+    page_type: PageType,
+) -> eyre::Result<Vec<GeneralPage<Page>>>
+where
+    Page: Archive + Persistable,
+    <Page as Archive>::Archived: Deserialize<Page, HighDeserializer<rkyv::rancor::Error>>,
+{
+    let mut pages = vec![];
+    for index in 0..page_count {
+        let header = parse_general_header_by_index(index_file, index).await?;
+        if header.page_type == page_type {
+            pages.push(parse_page::<Page, { INNER_PAGE_SIZE as u32 }>(index_file, index).await?);
+        }
+    }
+
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{Archive, Deserialize, Serialize};
+
+    use super::{
+        verify_index_pages, verify_secondary_index, verify_unsized_index_pages, IndexViolation,
+        SpaceData,
+    };
+    use crate::persistence::data::{
+        data_page_row_links, parse_data_page_rows, ColumnType, DataTypeValue, RowLayout, RowReader,
+    };
+    use crate::persistence::rebuild::{extract_secondary_index_entries, persist_secondary_index};
+    use crate::{
+        bulk_load_index_pages, bulk_load_unsized_index_pages, persist_page, BulkLoadConfig,
+        ColumnSchema, DataPage, GeneralHeader, GeneralPage, IndexSchema, Link, PageType,
+        SpaceInfoPage, INNER_PAGE_SIZE,
+    };
+
+    #[derive(Archive, Serialize, Deserialize, Debug)]
+    struct Row {
+        pub id: u64,
+        pub name: String,
+    }
+
+    fn space_info() -> SpaceInfoPage {
+        SpaceInfoPage {
+            id: 0.into(),
+            page_count: 1,
+            pk_gen_state: (),
+            name: "test".to_string(),
+            version: 0,
+            columns: vec![
+                ColumnSchema::new(0, "id", ColumnType::U64),
+                ColumnSchema::new(1, "name", ColumnType::String),
+            ],
+            schema_version: 0,
+            schema_history: vec![],
+            primary_key_fields: vec!["id".to_string()],
+            secondary_index_schemas: vec![IndexSchema::single("name", ColumnType::String)],
+            empty_links_list: vec![],
+        }
+    }
+
+    fn page(count: u64) -> GeneralPage<DataPage<INNER_PAGE_SIZE>> {
+        let mut data = [0u8; INNER_PAGE_SIZE];
+        let mut length = 0;
+        for i in 0..count {
+            let row = Row {
+                id: i,
+                name: format!("name {:03}", count - i),
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&row).unwrap();
+            data[length..length + bytes.len()].copy_from_slice(bytes.as_slice());
+            length += bytes.len();
+        }
+        GeneralPage {
+            header: GeneralHeader::new(1.into(), PageType::Data, 0.into()),
+            inner: DataPage {
+                length: length as u32,
+                data,
+            },
+        }
+    }
+
+    fn links(page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>) -> Vec<Link> {
+        let layout = RowLayout::from_columns(&space_info().columns).unwrap();
+        data_page_row_links(page, &layout, &[]).unwrap()
+    }
+
+    fn config() -> BulkLoadConfig {
+        BulkLoadConfig {
+            fill_factor: 0.05,
+            space_id: 0.into(),
+            first_page_id: 2.into(),
+        }
+    }
+
+    #[test]
+    fn test_consistent_index() {
+        let page = page(100);
+        let links = links(&page);
+        let data = SpaceData::new(space_info(), vec![page]);
+        let index = bulk_load_index_pages(
+            links.iter().enumerate().map(|(i, l)| (i as u64, *l)),
+            config(),
+        )
+        .unwrap();
+        assert!(index.pages.len() > 1);
+
+        let report = verify_index_pages(&index.pages, &index.toc, &data, "id").unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        assert_eq!(report.pages_checked, index.pages.len());
+        assert_eq!(report.values_checked, 100);
+
+        assert!(verify_index_pages(&index.pages, &index.toc, &data, "unknown").is_err());
+    }
+
+    #[test]
+    fn test_consistent_unsized_index() {
+        let page = page(100);
+        let links = links(&page);
+        let data = SpaceData::new(space_info(), vec![page]);
+        let mut values = links
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (format!("name {:03}", 100 - i), *l))
+            .collect::<Vec<_>>();
+        values.sort();
+        let index =
+            bulk_load_unsized_index_pages::<_, { INNER_PAGE_SIZE as u32 }>(values, config())
+                .unwrap();
+
+        let report = verify_unsized_index_pages(&index.pages, &index.toc, &data, "name").unwrap();
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        assert_eq!(report.values_checked, 100);
+    }
+
+    #[test]
+    fn test_node_violations() {
+        let page = page(100);
+        let links = links(&page);
+        let data = SpaceData::new(space_info(), vec![page]);
+        let mut index = bulk_load_index_pages(
+            links.iter().enumerate().map(|(i, l)| (i as u64, *l)),
+            config(),
+        )
+        .unwrap();
+        let broken_id = index.pages[0].header.page_id;
+        index.pages[0].inner.node_id.key = 1000;
+        let missing_id = index.pages[1].header.page_id;
+        index.pages.remove(1);
+        let slots = &mut index.pages[1].inner.slots;
+        slots.swap(0, 1);
+
+        let report = verify_index_pages(&index.pages, &index.toc, &data, "id").unwrap();
+        assert!(report.violations.iter().any(
+            |v| matches!(v, IndexViolation::NodeIdNotMax { page_id, .. } if *page_id == broken_id)
+        ));
+        assert!(report.violations.iter().any(
+            |v| matches!(v, IndexViolation::TocKeyMismatch { page_id, .. } if *page_id == broken_id)
+        ));
+        assert!(report
+            .violations
+            .iter()
+            .any(|v| matches!(v, IndexViolation::MissingIndexPage { page_id, .. } if *page_id == missing_id)));
+        assert!(report.violations.contains(&IndexViolation::UnsortedValues {
+            page_id: index.pages[1].header.page_id,
+            position: 1
+        }));
+    }
+
+    #[test]
+    fn test_link_violations() {
+        let page = page(10);
+        let links = links(&page);
+        let mut info = space_info();
+        info.empty_links_list.push(links[2]);
+        let data = SpaceData::new(info, vec![page]);
+
+        let shifted = Link {
+            offset: links[4].offset + 4,
+            ..links[4]
+        };
+        let missing = Link {
+            page_id: 7.into(),
+            ..links[5]
+        };
+        let out_of_bounds = Link {
+            offset: INNER_PAGE_SIZE as u32 - 4,
+            ..links[6]
+        };
+        let mut values = links
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i as u64, *l))
+            .collect::<Vec<_>>();
+        values[4].1 = shifted;
+        values[5].1 = missing;
+        values[6].1 = out_of_bounds;
+        values[8].0 = 9;
+        values[9].0 = 10;
+        let index = bulk_load_index_pages(values, BulkLoadConfig::default()).unwrap();
+        let page_id = index.pages[0].header.page_id;
+
+        let report = verify_index_pages(&index.pages, &index.toc, &data, "id").unwrap();
+        assert_eq!(
+            report.violations,
+            vec![
+                IndexViolation::LinkToRemovedRow {
+                    page_id,
+                    link: links[2]
+                },
+                IndexViolation::LinkNotRowStart {
+                    page_id,
+                    link: shifted
+                },
+                IndexViolation::LinkToMissingDataPage {
+                    page_id,
+                    link: missing
+                },
+                IndexViolation::LinkOutOfBounds {
+                    page_id,
+                    link: out_of_bounds
+                },
+                IndexViolation::ValueMismatch {
+                    page_id,
+                    link: links[8],
+                    indexed: "9".to_string(),
+                    actual: "U64(8)".to_string(),
+                },
+                IndexViolation::ValueMismatch {
+                    page_id,
+                    link: links[9],
+                    indexed: "10".to_string(),
+                    actual: "U64(9)".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_secondary_index() {
+        let filename = "tests/data/verify_data.wt";
+        let mut file = tokio::fs::File::create(filename).await.unwrap();
+        let mut info = GeneralPage {
+            header: GeneralHeader::new(0.into(), PageType::SpaceInfo, 0.into()),
+            inner: space_info(),
+        };
+        persist_page(&mut info, &mut file).await.unwrap();
+        let mut data_page = page(50);
+        persist_page(&mut data_page, &mut file).await.unwrap();
+
+        let mut file = tokio::fs::File::open(filename).await.unwrap();
+        let data = SpaceData::read(&mut file).await.unwrap();
+        let rows = data_page_rows(&data_page);
+        let mut indexes = extract_secondary_index_entries(&data.space_info, &rows).unwrap();
+
+        let index_filename = "tests/data/verify_name_index.wt";
+        let mut index_file = tokio::fs::File::create(index_filename).await.unwrap();
+        persist_secondary_index(&mut index_file, &data.space_info, indexes.remove(0), 0.1)
+            .await
+            .unwrap();
+
+        let mut index_file = tokio::fs::File::open(index_filename).await.unwrap();
+        let report = verify_secondary_index(&mut index_file, &data)
+            .await
+            .unwrap();
+        assert_eq!(report.column, "name");
+        assert!(report.is_consistent(), "{:?}", report.violations);
+        assert_eq!(report.values_checked, 50);
+    }
+
+    fn data_page_rows(
+        page: &GeneralPage<DataPage<INNER_PAGE_SIZE>>,
+    ) -> Vec<(Link, Vec<DataTypeValue>)> {
+        let info = space_info();
+        parse_data_page_rows(page, &info, &RowReader::new(&info).unwrap()).unwrap()
+    }
+}
@@ -0,0 +1,212 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::{SizeMeasurable, VariableSizeMeasurable};
+
+/// Wrapper for a key column that reverses it's ordering. Used as a part of
+/// composite index keys for columns that are sorted in descending order, so
+/// `(u64, Desc<String>)` is sorted by first column ascending and by second
+/// column descending.
+///
+/// Wrapper is transparent for the size measurement, so it's archived size is
+/// same as wrapped value's size.
+///
+/// Archived wrapper is ordered in reversed order too, so it can be used in
+/// index pages which compare archived keys.
+#[derive(Archive, Clone, Copy, Deserialize, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+#[rkyv(compare(PartialEq))]
+pub struct Desc<T>(pub T);
+
+impl<T: Ord> Ord for Desc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<T: Ord> PartialOrd for Desc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Derived impls would require `T` instead of `T::Archived` to implement traits.
+impl<T> Debug for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ArchivedDesc").field(&self.0).finish()
+    }
+}
+
+impl<T> PartialEq for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: Eq,
+{
+}
+
+impl<T> Ord for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<T> PartialOrd for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialOrd<Desc<T>> for ArchivedDesc<T>
+where
+    T: Archive,
+    T::Archived: PartialOrd<T>,
+{
+    fn partial_cmp(&self, other: &Desc<T>) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0).map(Ordering::reverse)
+    }
+}
+
+impl<T> From<T> for Desc<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> SizeMeasurable for Desc<T>
+where
+    T: SizeMeasurable,
+{
+    fn aligned_size(&self) -> usize {
+        self.0.aligned_size()
+    }
+
+    fn align() -> Option<usize> {
+        T::align()
+    }
+
+    fn out_of_line_size(&self) -> usize {
+        self.0.out_of_line_size()
+    }
+}
+
+impl<T> VariableSizeMeasurable for Desc<T>
+where
+    T: VariableSizeMeasurable,
+{
+    fn aligned_size(length: usize) -> usize {
+        T::aligned_size(length)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexset::cdc::change::ChangeEvent;
+    use indexset::core::pair::Pair;
+    use rkyv::to_bytes;
+
+    use crate::util::Desc;
+    use crate::{IndexPage, IndexValue, Link, Persistable, SizeMeasurable};
+
+    #[test]
+    fn test_ordering() {
+        let mut keys = vec![
+            (1u64, Desc("a".to_string())),
+            (0u64, Desc("a".to_string())),
+            (1u64, Desc("c".to_string())),
+            (1u64, Desc("b".to_string())),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                (0u64, Desc("a".to_string())),
+                (1u64, Desc("c".to_string())),
+                (1u64, Desc("b".to_string())),
+                (1u64, Desc("a".to_string())),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_size() {
+        let v = IndexValue {
+            key: (Desc(u64::MAX), 1u32, Desc(i64::MIN)),
+            link: Link::default(),
+        };
+        assert_eq!(
+            v.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&v).unwrap().len()
+        )
+    }
+
+    #[test]
+    fn test_archived_ordering() {
+        let bytes = to_bytes::<rkyv::rancor::Error>(&vec![Desc(1u64), Desc(2u64)]).unwrap();
+        let archived =
+            rkyv::access::<rkyv::Archived<Vec<Desc<u64>>>, rkyv::rancor::Error>(&bytes).unwrap();
+        assert!(archived[0] > archived[1]);
+        assert!(archived[0] > Desc(2u64));
+        assert!(archived[1] < Desc(1u64));
+        assert_eq!(archived[0], Desc(1u64));
+    }
+
+    #[test]
+    fn test_index_page() {
+        let mut page = IndexPage::new(
+            IndexValue {
+                key: Desc(3u64),
+                link: Link::default(),
+            },
+            8,
+        );
+        for (index, key) in [3u64, 2, 1].into_iter().enumerate() {
+            let event = ChangeEvent::InsertAt {
+                event_id: 0.into(),
+                max_value: Pair {
+                    key: Desc(3u64),
+                    value: Link::default(),
+                },
+                value: Pair {
+                    key: Desc(key),
+                    value: Link::default(),
+                },
+                index,
+            };
+            page.apply_change_event(event).unwrap();
+        }
+        assert_eq!(page.node_id.key, Desc(1));
+
+        let bytes = page.as_bytes();
+        let parsed = IndexPage::<Desc<u64>>::from_bytes(bytes.as_ref(), 0);
+        let keys = parsed
+            .get_node()
+            .into_iter()
+            .map(|p| p.key.0)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec![3, 2, 1]);
+        assert_eq!(parsed.node_id, page.node_id);
+    }
+}
@@ -1,5 +1,7 @@
+mod desc;
 mod persistable;
 mod sized;
 
+pub use desc::Desc;
 pub use persistable::Persistable;
 pub use sized::{align, align8, align_vec, SizeMeasurable, VariableSizeMeasurable};
@@ -38,9 +38,15 @@ pub fn align_vec<const ALIGNMENT: usize>(mut v: AlignedVec<ALIGNMENT>) -> Aligne
 pub trait SizeMeasurable {
     /// Returns approximate size of the object archiving via [`rkyv`].
     fn aligned_size(&self) -> usize;
+    /// Returns alignment of the archived object if it's known.
     fn align() -> Option<usize> {
         None
     }
+    /// Returns length of the object's data that is archived out of line,
+    /// before it's root. It's included in [`SizeMeasurable::aligned_size`].
+    fn out_of_line_size(&self) -> usize {
+        0
+    }
 }
 
 macro_rules! size_measurable_for_sized {
@@ -51,7 +57,7 @@ macro_rules! size_measurable_for_sized {
                     mem::size_of::<$t>()
                 }
                 fn align() -> Option<usize> {
-                    Some(align(mem::size_of::<$t>()))
+                    Some(mem::size_of::<$t>())
                 }
             }
         )+
@@ -70,12 +76,20 @@ impl SizeMeasurable for Uuid {
     fn aligned_size(&self) -> usize {
         16
     }
+
+    fn align() -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl<const N: usize, const B: usize, A: AlphabetPackExt> SizeMeasurable for PackedNanoid<N, B, A> {
     fn aligned_size(&self) -> usize {
         B
     }
+
+    fn align() -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl<T> SizeMeasurable for OrderedFloat<T>
@@ -85,54 +99,85 @@ where
     fn aligned_size(&self) -> usize {
         self.0.aligned_size()
     }
+
+    fn align() -> Option<usize> {
+        T::align()
+    }
 }
 
 impl SizeMeasurable for [u8; 32] {
     fn aligned_size(&self) -> usize {
         mem::size_of::<[u8; 32]>()
     }
+
+    fn align() -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl SizeMeasurable for [u8; 20] {
     fn aligned_size(&self) -> usize {
         mem::size_of::<[u8; 20]>()
     }
+
+    fn align() -> Option<usize> {
+        Some(1)
+    }
 }
 
-impl<T1, T2> SizeMeasurable for (T1, T2)
-where
-    T1: SizeMeasurable,
-    T2: SizeMeasurable,
-{
-    fn aligned_size(&self) -> usize {
-        if let Some(align) = T1::align() {
-            if align % 8 == 0 {
-                return align8(self.0.aligned_size() + self.1.aligned_size());
-            }
-        }
-        if let Some(align) = T2::align() {
-            if align % 8 == 0 {
-                return align8(self.0.aligned_size() + self.1.aligned_size());
+/// Returns alignment of the archived tuple's field. Fields without known
+/// alignment are considered `4` bytes aligned like most of archived types.
+fn field_align<T: SizeMeasurable>() -> usize {
+    T::align().unwrap_or(4)
+}
+
+const fn align_to(len: usize, alignment: usize) -> usize {
+    len.div_ceil(alignment) * alignment
+}
+
+macro_rules! size_measurable_for_tuple {
+    ($($t:ident: $i:tt),+) => {
+        impl<$($t),+> SizeMeasurable for ($($t,)+)
+        where
+            $($t: SizeMeasurable,)+
+        {
+            /// Archived tuple is fields' out of line data placed one after
+            /// another and followed by the tuple's root, which is fields'
+            /// roots placed with their alignment.
+            fn aligned_size(&self) -> usize {
+                let mut out_of_line = 0;
+                let mut len = 0;
+                let mut max_align = 1;
+                $(
+                    let alignment = field_align::<$t>();
+                    max_align = max_align.max(alignment);
+                    let field_out_of_line = self.$i.out_of_line_size();
+                    out_of_line += field_out_of_line;
+                    let root = self.$i.aligned_size() - align_to(field_out_of_line, alignment);
+                    len = align_to(len, alignment) + root;
+                )+
+                align_to(out_of_line, max_align) + align_to(len, max_align)
             }
-        }
-        align(self.0.aligned_size() + self.1.aligned_size())
-    }
 
-    fn align() -> Option<usize> {
-        if let Some(align) = T1::align() {
-            if align % 8 == 0 {
-                return Some(8);
+            fn align() -> Option<usize> {
+                Some(1 $(.max(field_align::<$t>()))+)
             }
-        }
-        if let Some(align) = T2::align() {
-            if align % 8 == 0 {
-                return Some(8);
+
+            fn out_of_line_size(&self) -> usize {
+                0 $(+ self.$i.out_of_line_size())+
             }
         }
-        None
-    }
+    };
 }
 
+size_measurable_for_tuple!(T1: 0, T2: 1);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2, T4: 3);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2, T4: 3, T5: 4);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2, T4: 3, T5: 4, T6: 5);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2, T4: 3, T5: 4, T6: 5, T7: 6);
+size_measurable_for_tuple!(T1: 0, T2: 1, T3: 2, T4: 3, T5: 4, T6: 5, T7: 6, T8: 7);
+
 // That was found on practice... Check unit test for proofs that works.
 impl SizeMeasurable for String {
     fn aligned_size(&self) -> usize {
@@ -142,6 +187,14 @@ impl SizeMeasurable for String {
             align(self.len() + 8)
         }
     }
+
+    fn out_of_line_size(&self) -> usize {
+        if self.len() <= 8 {
+            0
+        } else {
+            self.len()
+        }
+    }
 }
 
 impl<T> SizeMeasurable for Vec<T>
@@ -172,6 +225,14 @@ impl<T: SizeMeasurable> SizeMeasurable for Arc<T> {
     fn aligned_size(&self) -> usize {
         self.as_ref().aligned_size()
     }
+
+    fn align() -> Option<usize> {
+        T::align()
+    }
+
+    fn out_of_line_size(&self) -> usize {
+        self.as_ref().out_of_line_size()
+    }
 }
 
 impl<T: SizeMeasurable> SizeMeasurable for lockfree::set::Set<T> {
@@ -229,6 +290,27 @@ impl VariableSizeMeasurable for String {
     }
 }
 
+macro_rules! variable_size_measurable_for_tuple {
+    ($($t:ident),+) => {
+        impl<$($t),+> VariableSizeMeasurable for ($($t,)+)
+        where
+            $($t: VariableSizeMeasurable,)+
+        {
+            fn aligned_size(length: usize) -> usize {
+                align(0 $(+ $t::aligned_size(length))+)
+            }
+        }
+    };
+}
+
+variable_size_measurable_for_tuple!(T1, T2);
+variable_size_measurable_for_tuple!(T1, T2, T3);
+variable_size_measurable_for_tuple!(T1, T2, T3, T4);
+variable_size_measurable_for_tuple!(T1, T2, T3, T4, T5);
+variable_size_measurable_for_tuple!(T1, T2, T3, T4, T5, T6);
+variable_size_measurable_for_tuple!(T1, T2, T3, T4, T5, T6, T7);
+variable_size_measurable_for_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+
 impl<K, L> VariableSizeMeasurable for indexset::core::pair::Pair<K, L>
 where
     K: VariableSizeMeasurable,
@@ -319,6 +401,95 @@ mod test {
             to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
         )
     }
+    #[test]
+    fn test_tuple_composite() {
+        let t = (u64::MAX, u32::MAX, u16::MAX);
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (u8::MAX, u64::MAX, u8::MAX);
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (u32::MAX, "short".to_string(), u64::MAX, Link::default());
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (
+            "some long string".to_string(),
+            u64::MAX,
+            u32::MAX,
+            i64::MIN,
+            i32::MIN,
+            u128::MAX,
+            Link::default(),
+            Uuid::new_v4(),
+        );
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let v = IndexValue {
+            key: (1u64, 2u32, 3u64),
+            link: Link::default(),
+        };
+        assert_eq!(
+            v.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&v).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_tuple_aligned_16() {
+        let t = (u8::MAX, u128::MAX);
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (Uuid::new_v4(), u8::MAX, i128::MIN, u16::MAX);
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (u8::MAX, (u32::MAX, u128::MAX), u8::MAX);
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        let t = (u8::MAX, u8::MAX, Uuid::new_v4());
+        assert_eq!(
+            t.aligned_size(),
+            to_bytes::<rkyv::rancor::Error>(&t).unwrap().len()
+        );
+        for i in 0..40 {
+            let s = "a".repeat(i);
+            let t = (s.clone(), u128::MAX, "b".repeat(40 - i), u8::MAX);
+            assert_eq!(
+                t.aligned_size(),
+                to_bytes::<rkyv::rancor::Error>(&t).unwrap().len(),
+                "{t:?}"
+            );
+            let t = (u8::MAX, (s, u64::MAX), Link::default());
+            assert_eq!(
+                t.aligned_size(),
+                to_bytes::<rkyv::rancor::Error>(&t).unwrap().len(),
+                "{t:?}"
+            );
+            let v = IndexValue {
+                key: ("a".repeat(i), u32::MAX),
+                link: Link::default(),
+            };
+            assert_eq!(
+                v.aligned_size(),
+                to_bytes::<rkyv::rancor::Error>(&v).unwrap().len(),
+                "{v:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_option() {
         let t = Some(0.0f64);
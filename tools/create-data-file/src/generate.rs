@@ -0,0 +1,162 @@
+use data_bucket::persistence::data::{ColumnType, DataTypeValue, FieldType};
+use eyre::bail;
+use uuid::Uuid;
+
+use crate::spec::SpaceSpec;
+
+/// Small deterministic `SplitMix64` generator, so same seed always gives same
+/// file.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u128(&mut self) -> u128 {
+        ((self.next_u64() as u128) << 64) | self.next_u64() as u128
+    }
+
+    /// Returns value in `0..bound` range.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+/// Generates rows' values of the space. Values of primary key and indexed
+/// columns are unique, as indexes are built from sorted unique keys.
+pub fn generate_rows(spec: &SpaceSpec) -> eyre::Result<Vec<Vec<DataTypeValue>>> {
+    let mut rng = Rng::new(spec.seed);
+    let mut columns = Vec::with_capacity(spec.columns.len());
+    for (column, ty) in &spec.columns {
+        let ty = ty.parse::<ColumnType>()?;
+        let values = if spec.is_unique(column) {
+            let mut ids = (0..spec.rows as u64).collect::<Vec<_>>();
+            // Primary key goes in insert order, other unique columns are
+            // shuffled.
+            if !spec.primary_key.contains(column) {
+                for i in (1..ids.len()).rev() {
+                    ids.swap(i, rng.below(i as u64 + 1) as usize);
+                }
+            }
+            ids.into_iter()
+                .map(|id| unique_value(column, &ty, id))
+                .collect::<eyre::Result<Vec<_>>>()?
+        } else {
+            (0..spec.rows)
+                .map(|_| random_value(&ty, &mut rng))
+                .collect::<Vec<_>>()
+        };
+        columns.push(values)
+    }
+
+    let mut rows = vec![Vec::with_capacity(columns.len()); spec.rows];
+    for values in columns {
+        for (row, value) in rows.iter_mut().zip(values) {
+            row.push(value)
+        }
+    }
+    Ok(rows)
+}
+
+fn unique_value(column: &str, ty: &ColumnType, id: u64) -> eyre::Result<DataTypeValue> {
+    macro_rules! int {
+        ($t:ty) => {{
+            let Some(v) = <$t>::try_from(id).ok() else {
+                bail!(
+                    "Unique value {} does not fit in column `{}` of type {}",
+                    id,
+                    column,
+                    ty
+                )
+            };
+            v.into()
+        }};
+    }
+
+    Ok(match ty {
+        ColumnType::String => format!("{column} {id:08}").into(),
+        ColumnType::I128 => int!(i128),
+        ColumnType::I64 => int!(i64),
+        ColumnType::I32 => int!(i32),
+        ColumnType::I16 => int!(i16),
+        ColumnType::I8 => int!(i8),
+        ColumnType::U128 => int!(u128),
+        ColumnType::U64 => int!(u64),
+        ColumnType::U32 => int!(u32),
+        ColumnType::U16 => int!(u16),
+        ColumnType::U8 => int!(u8),
+        ColumnType::F64 => (id as f64).into(),
+        ColumnType::F32 => (id as f32).into(),
+        ColumnType::Uuid => Uuid::from_u128(id as u128).into(),
+        ty => bail!("Unique values of `{}` type can not be generated", ty),
+    })
+}
+
+fn random_value(ty: &ColumnType, rng: &mut Rng) -> DataTypeValue {
+    match ty {
+        ColumnType::String => {
+            let len = rng.below(24) as usize;
+            (0..len)
+                .map(|_| (b'a' + rng.below(26) as u8) as char)
+                .collect::<String>()
+                .into()
+        }
+        ColumnType::I128 => (rng.next_u128() as i128).into(),
+        ColumnType::I64 => (rng.next_u64() as i64).into(),
+        ColumnType::I32 => (rng.next_u64() as i32).into(),
+        ColumnType::I16 => (rng.next_u64() as i16).into(),
+        ColumnType::I8 => (rng.next_u64() as i8).into(),
+        ColumnType::U128 => rng.next_u128().into(),
+        ColumnType::U64 => rng.next_u64().into(),
+        ColumnType::U32 => (rng.next_u64() as u32).into(),
+        ColumnType::U16 => (rng.next_u64() as u16).into(),
+        ColumnType::U8 => (rng.next_u64() as u8).into(),
+        ColumnType::F64 => (rng.next_u64() as f64 / u64::MAX as f64).into(),
+        ColumnType::F32 => ((rng.next_u64() as f64 / u64::MAX as f64) as f32).into(),
+        ColumnType::Bool => (rng.below(2) == 1).into(),
+        ColumnType::Char => ((b'a' + rng.below(26) as u8) as char).into(),
+        ColumnType::Uuid => Uuid::from_u128(rng.next_u128()).into(),
+        ColumnType::OrderedF64 => {
+            DataTypeValue::OrderedF64((rng.next_u64() as f64 / u64::MAX as f64).into())
+        }
+        ColumnType::OrderedF32 => {
+            DataTypeValue::OrderedF32(((rng.next_u64() as f64 / u64::MAX as f64) as f32).into())
+        }
+        ColumnType::PackedNanoid { bytes, .. } => {
+            DataTypeValue::PackedNanoid((0..*bytes).map(|_| rng.next_u64() as u8).collect())
+        }
+        // Every fourth value is `None`.
+        ColumnType::Option(inner) => {
+            DataTypeValue::Option((rng.below(4) != 0).then(|| Box::new(random_value(inner, rng))))
+        }
+        ColumnType::Vec(inner) => {
+            let len = rng.below(4);
+            DataTypeValue::Vec((0..len).map(|_| random_value(inner, rng)).collect())
+        }
+        ColumnType::Struct(fields) => DataTypeValue::Struct(random_fields(fields, rng)),
+        ColumnType::Enum(variants) => {
+            let variant = &variants[rng.below(variants.len() as u64) as usize];
+            DataTypeValue::Enum {
+                variant: variant.name.clone(),
+                fields: random_fields(&variant.fields, rng),
+            }
+        }
+    }
+}
+
+fn random_fields(fields: &[FieldType], rng: &mut Rng) -> Vec<(String, DataTypeValue)> {
+    fields
+        .iter()
+        .map(|f| (f.name.clone(), random_value(&f.ty, rng)))
+        .collect()
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use data_bucket::persistence::import::{import_space, ImportFormat, ImportedSpace};
+use data_bucket::SpaceInfoPage;
+use eyre::WrapErr;
+use tokio::fs::File;
+
+/// Format of the imported rows.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum ImportFileFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl From<ImportFileFormat> for ImportFormat {
+    fn from(format: ImportFileFormat) -> Self {
+        match format {
+            ImportFileFormat::Csv => ImportFormat::Csv,
+            ImportFileFormat::Ndjson => ImportFormat::Ndjson,
+        }
+    }
+}
+
+/// Imports rows of the `input` file to the space's data `file`.
+pub async fn import_rows(
+    input: &Path,
+    format: ImportFileFormat,
+    space_info: &SpaceInfoPage,
+    file: &mut File,
+) -> eyre::Result<ImportedSpace> {
+    let rows = tokio::fs::read_to_string(input).await?;
+    import_space(&rows, format.into(), space_info, file)
+        .await
+        .wrap_err_with(|| format!("{} can not be imported", input.display()))
+}
@@ -1,151 +1,243 @@
+mod generate;
+mod import;
+mod spec;
+
+use std::path::PathBuf;
+
 use clap::Parser;
-use data_bucket::{persist_page, GeneralHeader, GeneralPage, PageType, DATA_VERSION};
-use data_bucket::{IndexData, IndexValue, Interval, Link, SpaceInfoData};
-use rkyv::rancor::Error;
-use rkyv::{Archive, Deserialize, Serialize};
-use std::{
-    fs::{remove_file, File},
-    str,
+use data_bucket::persistence::data::{encode_row, ColumnType, DataTypeValue};
+use data_bucket::persistence::rebuild::{extract_secondary_index_entries, persist_secondary_index};
+use data_bucket::{
+    persist_page, persist_pages_batch, ColumnSchema, DataPage, GeneralHeader, GeneralPage,
+    IndexSchema, Link, PageType, SpaceInfoPage, INNER_PAGE_SIZE,
 };
+use eyre::bail;
+use tokio::fs::File;
+
+use crate::generate::generate_rows;
+use crate::import::{import_rows, ImportFileFormat};
+use crate::spec::{parse_column, SpaceSpec};
 
+/// Generates synthetic space file and it's secondary index files. Files are
+/// used for benchmarks and as regression fixtures, so same spec and seed
+/// always give same files. Rows can be imported from CSV or newline delimited
+/// JSON file instead, then primary index file is written too.
 #[derive(Parser, Debug)]
 struct Args {
+    /// Space's data file that will be created.
+    #[arg(short, long)]
+    filename: PathBuf,
+    /// Spec file with space description.
+    #[arg(short, long)]
+    spec: Option<PathBuf>,
+    /// Column in `<name>:<type>` form. Added after spec's columns.
+    #[arg(short, long)]
+    column: Vec<String>,
+    /// Primary key column. Added after spec's primary key columns.
     #[arg(short, long)]
-    filename: String,
-    #[arg(short, long, default_value_t = 5)]
-    count: usize,
+    primary_key: Vec<String>,
+    /// Indexed column. Added after spec's indexes.
+    #[arg(short, long)]
+    index: Vec<String>,
+    /// CSV or newline delimited JSON file which rows are imported instead of
+    /// generated ones.
+    #[arg(long)]
+    import: Option<PathBuf>,
+    /// Format of the imported file.
+    #[arg(long, value_enum, default_value_t)]
+    format: ImportFileFormat,
+    /// Count of generated rows. Overrides spec's value.
+    #[arg(short, long)]
+    rows: Option<usize>,
+    /// Seed of the values generator. Overrides spec's value.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Directory where `<column>.wt` index files will be written. Data file's
+    /// directory is used if not set.
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+    /// Part of the index page's capacity that will be filled.
+    #[arg(long, default_value_t = 1.0)]
+    fill_factor: f64,
 }
 
-fn main() -> eyre::Result<()> {
-    let args = Args::parse();
-    _ = remove_file(args.filename.as_str());
-    let mut output_file = File::create(args.filename.as_str())?;
-
-    let space_info_header = GeneralHeader {
-        data_version: DATA_VERSION,
-        space_id: 1.into(),
-        page_id: 0.into(),
-        previous_id: 0.into(),
-        next_id: 1.into(),
-        page_type: PageType::SpaceInfo,
-        data_length: 0u32,
-    };
-
-    let space_info = SpaceInfoData {
-        id: 1.into(),
-        page_count: 4,
-        name: "generated space".to_owned(),
-        row_schema: vec![
-            ("val".to_string(), "i32".to_string()),
-            ("attr".to_string(), "String".to_string()),
-        ],
-        primary_key_fields: vec!["val".to_string()],
-        primary_key_intervals: vec![Interval(1, 1)],
-        secondary_index_types: vec![],
-        secondary_index_intervals: Default::default(),
-        data_intervals: vec![],
-        pk_gen_state: (),
-        empty_links_list: vec![],
-    };
-
-    let mut space_info_page = GeneralPage {
-        header: space_info_header,
-        inner: space_info,
-    };
-    persist_page(&mut space_info_page, &mut output_file).unwrap();
-
-    let index_header = GeneralHeader {
-        data_version: DATA_VERSION,
-        space_id: 1.into(),
-        page_id: 1.into(),
-        previous_id: 0.into(),
-        next_id: 2.into(),
-        page_type: PageType::Index,
-        data_length: 0,
-    };
-
-    let data_header = GeneralHeader {
-        data_version: DATA_VERSION,
-        space_id: 1.into(),
-        page_id: 2.into(),
-        previous_id: 2.into(),
-        next_id: 4.into(),
-        page_type: PageType::Data,
-        data_length: 0,
-    };
-
-    let page_size = 100;
-    let total_pages = (args.count + page_size - 1) / page_size;
-
-    for page_idx in 0..total_pages {
-        let start = page_idx * page_size;
-        let end = usize::min(start + page_size, args.count);
-
-        let (mut data_page, offsets) = generate_data_page(start as i32, end - start, data_header);
-        persist_page(&mut data_page, &mut output_file).unwrap();
-
-        let index_data = create_index_data(&data_page, &offsets);
-
-        let mut index_page = GeneralPage {
-            header: index_header,
-            inner: index_data,
+impl Args {
+    async fn spec(&self) -> eyre::Result<SpaceSpec> {
+        let mut spec = match &self.spec {
+            Some(path) => SpaceSpec::parse(&tokio::fs::read_to_string(path).await?)?,
+            None => SpaceSpec::default(),
         };
-        persist_page(&mut index_page, &mut output_file).unwrap();
+        for column in &self.column {
+            spec.columns.push(parse_column(column)?)
+        }
+        spec.primary_key.extend(self.primary_key.iter().cloned());
+        spec.indexes.extend(self.index.iter().cloned());
+        if let Some(rows) = self.rows {
+            spec.rows = rows
+        }
+        if let Some(seed) = self.seed {
+            spec.seed = seed
+        }
+        if spec.columns.is_empty() {
+            // Same space as was generated by the tool before specs were added.
+            spec.columns = vec![
+                ("val".to_string(), "i32".to_string()),
+                ("attr".to_string(), "String".to_string()),
+            ];
+            spec.primary_key = vec!["val".to_string()];
+        }
+        spec.validate()?;
+
+        Ok(spec)
     }
-
-    Ok(())
 }
 
-#[derive(Archive, Debug, Deserialize, Serialize)]
-struct TableStruct {
-    val: i32,
-    attr: String,
-}
-
-pub fn generate_data_page(
-    start_key: i32,
-    count: usize,
-    header: GeneralHeader,
-) -> (GeneralPage<Vec<u8>>, Vec<(i32, u32, u32)>) {
-    let mut buffer = Vec::new();
-    let mut offsets = Vec::new();
-    let mut current_offset = 0;
-
-    for i in 0..count {
-        let key = start_key + i as i32;
-        let data = TableStruct {
-            val: key,
-            attr: format!("string {}", key),
+type DataPages = Vec<GeneralPage<DataPage<INNER_PAGE_SIZE>>>;
+type Rows = Vec<(Link, Vec<DataTypeValue>)>;
+
+/// Packs rows into data pages one after another. Pages get sequential ids
+/// starting from `1`.
+fn build_data_pages(
+    rows: Vec<Vec<DataTypeValue>>,
+    types: &[ColumnType],
+    space_id: data_bucket::SpaceId,
+) -> eyre::Result<(DataPages, Rows)> {
+    let mut pages: DataPages = vec![];
+    let mut linked_rows = Vec::with_capacity(rows.len());
+    for row in rows {
+        let bytes = encode_row(types, &row)?;
+        if bytes.len() > INNER_PAGE_SIZE {
+            bail!("Row with length {} does not fit in page", bytes.len())
+        }
+        let fits = pages
+            .last()
+            .map(|p| p.inner.length as usize + bytes.len() <= INNER_PAGE_SIZE)
+            .unwrap_or(false);
+        if !fits {
+            let header = match pages.last_mut() {
+                Some(previous) => previous.header.follow(),
+                None => GeneralHeader::new(1.into(), PageType::Data, space_id),
+            };
+            pages.push(GeneralPage {
+                header,
+                inner: DataPage {
+                    length: 0,
+                    data: [0; INNER_PAGE_SIZE],
+                },
+            })
+        }
+        let page = pages.last_mut().expect("page is added before");
+        let offset = page.inner.length as usize;
+        page.inner.data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        page.inner.length += bytes.len() as u32;
+        let link = Link {
+            page_id: page.header.page_id,
+            offset: offset as u32,
+            length: bytes.len() as u32,
         };
-        let serialized_data = rkyv::to_bytes::<Error>(&data).unwrap();
-        let length = serialized_data.len() as u32;
-
-        buffer.extend_from_slice(&serialized_data);
-        offsets.push((key, current_offset as u32, length));
-        current_offset += length as usize;
+        linked_rows.push((link, row))
     }
 
-    (
-        GeneralPage {
-            header,
-            inner: buffer,
-        },
-        offsets,
-    )
+    Ok((pages, linked_rows))
 }
 
-fn create_index_data(page: &GeneralPage<Vec<u8>>, offsets: &[(i32, u32, u32)]) -> IndexData<i32> {
-    let index_values = offsets
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    let spec = args.spec().await?;
+    let space_id = 1.into();
+
+    let columns = ColumnSchema::from_row_schema(&spec.columns)?;
+    let types = columns.iter().map(|c| c.ty.clone()).collect::<Vec<_>>();
+    let secondary_index_schemas = spec
+        .indexes
         .iter()
-        .map(|(key, offset, length)| IndexValue::<i32> {
-            key: *key,
-            link: Link {
-                page_id: page.header.page_id,
-                offset: *offset,
-                length: *length,
-            },
+        .map(|column| {
+            let schema = columns
+                .iter()
+                .find(|c| &c.name == column)
+                .expect("indexes are checked by spec validation");
+            IndexSchema::single(column, schema.ty.clone())
         })
-        .collect();
+        .collect::<Vec<_>>();
+    let space_info = SpaceInfoPage {
+        id: space_id,
+        page_count: 0,
+        pk_gen_state: (),
+        name: spec.name.clone(),
+        version: 0,
+        columns,
+        schema_version: 0,
+        schema_history: vec![],
+        primary_key_fields: spec.primary_key.clone(),
+        secondary_index_schemas,
+        empty_links_list: vec![],
+    };
+
+    if let Some(dir) = args.filename.parent().filter(|d| !d.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = File::create(&args.filename).await?;
+    let (space_info, indexes) = match &args.import {
+        Some(input) => {
+            let imported = import_rows(input, args.format, &space_info, &mut file).await?;
+            println!(
+                "{}: {} rows in {} data pages",
+                args.filename.display(),
+                imported.report.rows,
+                imported.report.pages_written
+            );
+            let indexes = imported.index_entries()?;
+            (imported.space_info, indexes)
+        }
+        None => {
+            let rows = generate_rows(&spec)?;
+            let (pages, rows) = build_data_pages(rows, &types, space_id)?;
+            let space_info = SpaceInfoPage {
+                page_count: pages.len() as u32,
+                ..space_info
+            };
+            let mut info = GeneralPage {
+                header: GeneralHeader::new(0.into(), PageType::SpaceInfo, space_id),
+                inner: space_info.clone(),
+            };
+            persist_page(&mut info, &mut file).await?;
+            let pages_count = pages.len();
+            persist_pages_batch(pages, &mut file).await?;
+            println!(
+                "{}: {} rows in {} data pages",
+                args.filename.display(),
+                rows.len(),
+                pages_count
+            );
+            let indexes = extract_secondary_index_entries(&space_info, &rows)?;
+            (space_info, indexes)
+        }
+    };
 
-    IndexData { index_values }
+    let output_dir = match &args.output_dir {
+        Some(dir) => dir.clone(),
+        None => args
+            .filename
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default(),
+    };
+    if !indexes.is_empty() {
+        tokio::fs::create_dir_all(&output_dir).await?;
+    }
+    for index in indexes {
+        let path = output_dir.join(format!("{}.wt", index.column));
+        let mut index_file = File::create(&path).await?;
+        let report =
+            persist_secondary_index(&mut index_file, &space_info, index, args.fill_factor).await?;
+        println!(
+            "{}: {} entries in {} pages",
+            path.display(),
+            report.entries_count,
+            report.index_pages_count
+        );
+    }
+
+    Ok(())
 }
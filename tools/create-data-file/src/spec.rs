@@ -0,0 +1,100 @@
+use data_bucket::persistence::data::ColumnType;
+use eyre::{bail, eyre};
+
+/// Description of the generated space.
+///
+/// Spec file is a list of lines in `<key> <values...>` form, empty lines and
+/// lines starting with `#` are skipped:
+///
+/// ```text
+/// name generated space
+/// column id u64
+/// column name String
+/// primary_key id
+/// index name
+/// rows 1000
+/// seed 42
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpaceSpec {
+    pub name: String,
+    pub columns: Vec<(String, String)>,
+    pub primary_key: Vec<String>,
+    pub indexes: Vec<String>,
+    pub rows: usize,
+    pub seed: u64,
+}
+
+impl Default for SpaceSpec {
+    fn default() -> Self {
+        Self {
+            name: "generated space".to_string(),
+            columns: vec![],
+            primary_key: vec![],
+            indexes: vec![],
+            rows: 5,
+            seed: 0,
+        }
+    }
+}
+
+impl SpaceSpec {
+    pub fn parse(spec: &str) -> eyre::Result<Self> {
+        let mut parsed = Self::default();
+        for (number, line) in spec.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "name" => parsed.name = value.to_string(),
+                "column" => parsed.columns.push(parse_column(value)?),
+                "primary_key" => parsed.primary_key.push(value.to_string()),
+                "index" => parsed.indexes.push(value.to_string()),
+                "rows" => parsed.rows = value.parse()?,
+                "seed" => parsed.seed = value.parse()?,
+                key => bail!("Unknown key `{}` at line {}", key, number + 1),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Checks that all columns have supported types and all keys and indexes
+    /// refer existing columns.
+    pub fn validate(&self) -> eyre::Result<()> {
+        if self.columns.is_empty() {
+            bail!("Space should have at least one column")
+        }
+        for (column, ty) in &self.columns {
+            if ty.parse::<ColumnType>().is_err() {
+                bail!("Column `{}` has unsupported type `{}`", column, ty)
+            }
+        }
+        for column in self.primary_key.iter().chain(&self.indexes) {
+            if !self.columns.iter().any(|(name, _)| name == column) {
+                bail!("Column `{}` is not found in columns", column)
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if values of the column should be unique.
+    pub fn is_unique(&self, column: &str) -> bool {
+        self.primary_key
+            .iter()
+            .chain(&self.indexes)
+            .any(|c| c == column)
+    }
+}
+
+/// Parses column in `<name> <type>` or `<name>:<type>` form.
+pub fn parse_column(value: &str) -> eyre::Result<(String, String)> {
+    let (name, ty) = value
+        .split_once(':')
+        .or_else(|| value.split_once(char::is_whitespace))
+        .ok_or_else(|| eyre!("Column `{}` should have `<name>:<type>` form", value))?;
+    Ok((name.trim().to_string(), ty.trim().to_string()))
+}
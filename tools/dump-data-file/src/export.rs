@@ -0,0 +1,59 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+
+use clap::ValueEnum;
+#[cfg(feature = "arrow")]
+use data_bucket::persistence::arrow::export_space_ipc;
+use data_bucket::persistence::export::{export_space, ExportFormat, ExportOptions};
+use tokio::fs::File;
+
+/// Format of the exported rows.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum ExportFileFormat {
+    #[default]
+    Csv,
+    Ndjson,
+    /// Arrow IPC file with record batch per data page.
+    #[cfg(feature = "arrow")]
+    Arrow,
+}
+
+/// Streams live rows of the space file to `output` file or to stdout.
+pub async fn export_rows(
+    filename: &str,
+    format: ExportFileFormat,
+    output: Option<&str>,
+    options: ExportOptions,
+) -> eyre::Result<()> {
+    let mut file = File::open(filename).await?;
+    let mut writer: Box<dyn Write> = match output {
+        Some(output) => Box::new(BufWriter::new(fs::File::create(output)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let text = |format| ExportOptions {
+        format,
+        ..options.clone()
+    };
+    let report = match format {
+        ExportFileFormat::Csv => {
+            export_space(&mut file, &text(ExportFormat::Csv), &mut writer).await?
+        }
+        ExportFileFormat::Ndjson => {
+            export_space(&mut file, &text(ExportFormat::Ndjson), &mut writer).await?
+        }
+        #[cfg(feature = "arrow")]
+        ExportFileFormat::Arrow => {
+            let report = export_space_ipc(&mut file, &options, &mut writer).await?;
+            writer.flush()?;
+            report
+        }
+    };
+    if let Some(output) = output {
+        println!(
+            "{}: {} rows from {} data pages",
+            output, report.rows, report.pages_read
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+
+use data_bucket::page::{parse_space_info, PageId};
+use data_bucket::persistence::data::ColumnType;
+use data_bucket::{
+    parse_page, parse_table_of_contents_chain, GeneralHeader, IndexColumn, IndexPage, IndexValue,
+    Link, Persistable, SizeMeasurable, UnsizedIndexPage, VariableSizeMeasurable, INNER_PAGE_SIZE,
+    PAGE_SIZE,
+};
+use eyre::bail;
+use rkyv::api::high::HighDeserializer;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::ser::sharing::Share;
+use rkyv::ser::Serializer;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::fs::File;
+
+/// Options of the index file dump.
+#[derive(Clone, Debug)]
+pub struct IndexDumpOptions {
+    /// Indexed column which type is used to decode keys. First index of the
+    /// file's `secondary_index_schemas` is used if not set.
+    pub column: Option<String>,
+    /// [`PageId`] of the first table of contents page.
+    pub toc_page_id: u32,
+    /// Print every `(key, Link)` pair of the index pages.
+    pub values: bool,
+}
+
+/// Common part of both index page kinds that is printed.
+trait DumpedNode<T> {
+    fn node_id(&self) -> &IndexValue<T>;
+    fn values(&self) -> Vec<IndexValue<T>>;
+    /// Returns count of occupied slots and slots capacity if it is fixed.
+    fn slots(&self) -> (usize, Option<usize>);
+    fn fill_factor(&self) -> f64;
+}
+
+impl<T> DumpedNode<T> for IndexPage<T>
+where
+    T: Default + Clone + Ord + SizeMeasurable,
+{
+    fn node_id(&self) -> &IndexValue<T> {
+        &self.node_id
+    }
+
+    fn values(&self) -> Vec<IndexValue<T>> {
+        self.get_node().into_iter().map(Into::into).collect()
+    }
+
+    fn slots(&self) -> (usize, Option<usize>) {
+        (self.current_length as usize, Some(self.size as usize))
+    }
+
+    fn fill_factor(&self) -> f64 {
+        IndexPage::fill_factor(self)
+    }
+}
+
+impl<T, const DATA_LENGTH: u32> DumpedNode<T> for UnsizedIndexPage<T, DATA_LENGTH>
+where
+    T: Archive
+        + Default
+        + Clone
+        + Ord
+        + SizeMeasurable
+        + VariableSizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, Strategy<Pool, rkyv::rancor::Error>>,
+{
+    fn node_id(&self) -> &IndexValue<T> {
+        &self.node_id
+    }
+
+    fn values(&self) -> Vec<IndexValue<T>> {
+        self.get_node().into_iter().map(Into::into).collect()
+    }
+
+    fn slots(&self) -> (usize, Option<usize>) {
+        (self.index_values.len(), None)
+    }
+
+    fn fill_factor(&self) -> f64 {
+        UnsizedIndexPage::fill_factor(self)
+    }
+}
+
+/// Prints table of contents and index pages of the index file. Keys are
+/// decoded using type of the indexed column from the file's
+/// `secondary_index_schemas`. Composite indexes can not be dumped.
+pub async fn dump_index(filename: &str, options: IndexDumpOptions) -> eyre::Result<()> {
+    let mut file = File::open(filename).await?;
+    let space_info = parse_space_info::<PAGE_SIZE>(&mut file).await?;
+    let mut columns = space_info
+        .secondary_index_schemas
+        .iter()
+        .filter_map(|schema| schema.single_column());
+    let index = match &options.column {
+        Some(column) => columns.find(|c| &c.name == column),
+        None => columns.next(),
+    };
+    let Some(IndexColumn {
+        name: column, ty, ..
+    }) = index
+    else {
+        bail!("Secondary index is not found in `{}`", filename)
+    };
+    println!(
+        "Index `{}` on `{}` column of type {}",
+        space_info.name, column, ty
+    );
+
+    match ty {
+        ColumnType::String => {
+            dump::<UnsizedIndexPage<String, { INNER_PAGE_SIZE as u32 }>, String>(
+                &mut file, &options,
+            )
+            .await
+        }
+        ColumnType::I128 => dump::<IndexPage<i128>, i128>(&mut file, &options).await,
+        ColumnType::I64 => dump::<IndexPage<i64>, i64>(&mut file, &options).await,
+        ColumnType::I32 => dump::<IndexPage<i32>, i32>(&mut file, &options).await,
+        ColumnType::I16 => dump::<IndexPage<i16>, i16>(&mut file, &options).await,
+        ColumnType::I8 => dump::<IndexPage<i8>, i8>(&mut file, &options).await,
+        ColumnType::U128 => dump::<IndexPage<u128>, u128>(&mut file, &options).await,
+        ColumnType::U64 => dump::<IndexPage<u64>, u64>(&mut file, &options).await,
+        ColumnType::U32 => dump::<IndexPage<u32>, u32>(&mut file, &options).await,
+        ColumnType::U16 => dump::<IndexPage<u16>, u16>(&mut file, &options).await,
+        ColumnType::U8 => dump::<IndexPage<u8>, u8>(&mut file, &options).await,
+        ty => bail!("Index on `{}` type can not be dumped", ty),
+    }
+}
+
+async fn dump<Page, T>(file: &mut File, options: &IndexDumpOptions) -> eyre::Result<()>
+where
+    Page: Archive + Persistable + DumpedNode<T>,
+    <Page as Archive>::Archived: Deserialize<Page, HighDeserializer<rkyv::rancor::Error>>,
+    T: Archive
+        + Debug
+        + Ord
+        + Clone
+        + SizeMeasurable
+        + for<'a> Serialize<
+            Strategy<Serializer<AlignedVec, ArenaHandle<'a>, Share>, rkyv::rancor::Error>,
+        >,
+    <T as Archive>::Archived: Deserialize<T, HighDeserializer<rkyv::rancor::Error>> + Ord,
+{
+    let toc = parse_table_of_contents_chain::<T>(file, options.toc_page_id.into()).await?;
+    let records = toc
+        .iter()
+        .map(|(key, page_id)| (key.clone(), *page_id))
+        .collect::<Vec<_>>();
+    println!();
+    println!("Table of contents: {} records", records.len());
+    for (key, page_id) in &records {
+        println!("  {:?} -> {}", key, page_id);
+    }
+
+    for (_, page_id) in records {
+        let page = parse_page::<Page, { INNER_PAGE_SIZE as u32 }>(file, page_id.into()).await?;
+        println!();
+        print_header(page_id, &page.header);
+        let node = &page.inner;
+        let node_id = node.node_id();
+        println!(
+            "  node_id: {:?} -> {}",
+            node_id.key,
+            format_link(&node_id.link)
+        );
+        match node.slots() {
+            (count, Some(size)) => println!("  slots: {}/{}", count, size),
+            (count, None) => println!("  slots: {}", count),
+        }
+        println!("  fill factor: {:.3}", node.fill_factor());
+        if options.values {
+            for value in node.values() {
+                println!("    {:?} -> {}", value.key, format_link(&value.link));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_header(page_id: PageId, header: &GeneralHeader) {
+    println!("Page {} ({})", page_id, header.page_type);
+    println!(
+        "  previous: {}, next: {}, space: {}, data length: {}, version: {}",
+        header.previous_id,
+        header.next_id,
+        header.space_id,
+        header.data_length,
+        header.data_version
+    );
+}
+
+fn format_link(link: &Link) -> String {
+    format!(
+        "page {} offset {} length {}",
+        link.page_id, link.offset, link.length
+    )
+}
@@ -1,103 +1,107 @@
-use clap::Parser;
-use data_bucket::{
-    page::{parse_space_info, DataIterator, LinksIterator, PageIterator},
-    persistence::data::DataTypeValue,
-    read_data_pages, PAGE_SIZE,
-};
-use std::{fs::File, str};
+mod export;
+mod index;
+mod rows;
+mod table;
 
-#[derive(Parser, Debug)]
-struct Args {
-    #[arg(short, long)]
-    filename: String,
-}
+use clap::{Parser, Subcommand};
 
-fn print_horizontal_cells_delimiters(column_widths: &[usize]) {
-    print!("+");
-    for column_width in column_widths.iter() {
-        print!("-");
-        for _ in 0..*column_width {
-            print!("-");
-        }
-        print!("-+");
-    }
-    println!();
-}
+use data_bucket::persistence::export::ExportOptions;
 
-fn print_padded_string(string: &str, column_width: usize) {
-    print!("{}", string);
-    for _ in 0..column_width - string.len() {
-        print!(" ");
-    }
-}
+use crate::export::{export_rows, ExportFileFormat};
+use crate::index::{dump_index, IndexDumpOptions};
+use crate::rows::{dump_rows, RowsFormat};
 
-fn format_table(header: &Vec<String>, rows: &Vec<Vec<String>>) {
-    let mut column_widths = vec![0; header.len()];
-    for i in 0..header.len() {
-        column_widths[i] = header[i].len();
-    }
-    for row in rows.iter() {
-        for i in 0..row.len() {
-            if row[i].len() > column_widths[i] {
-                column_widths[i] = row[i].len();
-            }
-        }
-    }
+/// Prints content of the space's data and index files.
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    print_horizontal_cells_delimiters(&column_widths[..]);
-    print!("|");
-    for i in 0..header.len() {
-        print!(" ");
-        print_padded_string(header[i].as_str(), column_widths[i]);
-        print!(" |");
-    }
-    println!();
-    print_horizontal_cells_delimiters(&column_widths[..]);
-    for row in rows.iter() {
-        print!("|");
-        for i in 0..row.len() {
-            print!(" ");
-            print_padded_string(row[i].as_str(), column_widths[i]);
-            print!(" |");
-        }
-        println!();
-    }
-    print_horizontal_cells_delimiters(&column_widths[..]);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Prints rows of the space's data file.
+    Rows {
+        #[arg(short, long)]
+        filename: String,
+        /// Output format of the rows.
+        #[arg(long, value_enum, default_value_t)]
+        format: RowsFormat,
+        /// Comma separated columns to print. Other columns are not decoded.
+        /// All columns are printed if not set.
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+    },
+    /// Exports live rows of the space's data file as CSV with header line or
+    /// as newline delimited JSON. Arrow IPC file is written if tool is built
+    /// with `arrow` feature.
+    Export {
+        #[arg(short, long)]
+        filename: String,
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFileFormat,
+        /// Comma separated columns to export. All columns are exported if
+        /// not set.
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+        /// Maximum count of the exported rows.
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// File where rows are written. Rows are printed if not set.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Prints table of contents and pages of the index file.
+    Index {
+        #[arg(short, long)]
+        filename: String,
+        /// Indexed column. First index of the file is used if not set.
+        #[arg(short, long)]
+        column: Option<String>,
+        /// Page of the first table of contents page.
+        #[arg(long, default_value_t = 1)]
+        toc_page: u32,
+        /// Print every `(key, Link)` pair of the index pages.
+        #[arg(long)]
+        values: bool,
+    },
 }
 
-fn main() -> eyre::Result<()> {
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
     let args = Args::parse();
-    let mut file = File::open(args.filename)?;
-
-    let space_info = parse_space_info::<PAGE_SIZE>(&mut file)?;
-    let row_schema = space_info.row_schema.clone();
-
-    let mut rows: Vec<Vec<DataTypeValue>> = vec![];
-
-    let pages = PageIterator::new(space_info.primary_key_intervals.clone());
-    for page in pages {
-        let links = LinksIterator::new(&mut file, page, &space_info).collect::<Vec<_>>();
-        for row in DataIterator::new(&mut file, row_schema.clone(), links) {
-            rows.push(row);
+    match args.command {
+        Command::Rows {
+            filename,
+            format,
+            columns,
+        } => dump_rows(&filename, format, &columns).await,
+        Command::Export {
+            filename,
+            format,
+            columns,
+            limit,
+            output,
+        } => {
+            let options = ExportOptions {
+                columns,
+                limit,
+                ..ExportOptions::default()
+            };
+            export_rows(&filename, format, output.as_deref(), options).await
+        }
+        Command::Index {
+            filename,
+            column,
+            toc_page,
+            values,
+        } => {
+            let options = IndexDumpOptions {
+                column,
+                toc_page_id: toc_page,
+                values,
+            };
+            dump_index(&filename, options).await
         }
     }
-
-    let rows: Vec<Vec<DataTypeValue>> = read_data_pages::<PAGE_SIZE>(&mut file)?;
-
-    let header: Vec<String> = row_schema
-        .iter()
-        .map(|(column, _data_type)| column.to_owned())
-        .collect();
-    let rows: Vec<Vec<String>> = rows
-        .iter()
-        .map(|row| {
-            row.iter()
-                .map(|column| column.to_string())
-                .collect::<Vec<String>>()
-        })
-        .collect();
-
-    format_table(&header, &rows);
-
-    Ok(())
 }
@@ -0,0 +1,98 @@
+use clap::ValueEnum;
+use data_bucket::page::parse_space_info;
+use data_bucket::persistence::data::{
+    data_page_row_links, parse_data_page_ids, DataTypeValue, RowReader,
+};
+use data_bucket::persistence::export::{csv_field, csv_value, json_object};
+use data_bucket::{parse_data_pages_batch, INNER_PAGE_SIZE, PAGE_SIZE};
+use eyre::eyre;
+use tokio::fs::File;
+
+use crate::table::format_table;
+
+/// Format of the printed rows.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum RowsFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+/// Prints all live rows of the space file. Every data page is parsed and rows
+/// listed in page's empty links are skipped. Only `columns` are decoded and
+/// printed if they are not empty.
+pub async fn dump_rows(filename: &str, format: RowsFormat, columns: &[String]) -> eyre::Result<()> {
+    let mut file = File::open(filename).await?;
+    let space_info = parse_space_info::<PAGE_SIZE>(&mut file).await?;
+    let reader = RowReader::new(&space_info)?;
+    let positions = if columns.is_empty() {
+        (0..space_info.columns.len()).collect::<Vec<_>>()
+    } else {
+        columns
+            .iter()
+            .map(|name| {
+                space_info
+                    .columns
+                    .iter()
+                    .position(|c| &c.name == name)
+                    .ok_or_else(|| eyre!("Column `{}` is not found", name))
+            })
+            .collect::<eyre::Result<_>>()?
+    };
+
+    let ids = parse_data_page_ids(&mut file).await?;
+    let pages =
+        parse_data_pages_batch::<{ PAGE_SIZE as u32 }, INNER_PAGE_SIZE>(&mut file, ids).await?;
+    let mut rows = vec![];
+    for page in pages {
+        let page_id = page.header.page_id;
+        let links = data_page_row_links(
+            &page,
+            reader.layout(page_id),
+            space_info.page_empty_links(page_id),
+        )?;
+        for link in links {
+            let bytes = page.inner.get_at(link)?;
+            rows.push(reader.read_columns(page_id, bytes, &positions)?)
+        }
+    }
+
+    let header: Vec<String> = positions
+        .iter()
+        .map(|pos| space_info.columns[*pos].name.clone())
+        .collect();
+    match format {
+        RowsFormat::Table => {
+            let rows: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| row.iter().map(|column| column.to_string()).collect())
+                .collect();
+            format_table(&header, &rows);
+        }
+        RowsFormat::Json => print_json(&header, &rows),
+        RowsFormat::Csv => print_csv(&header, &rows),
+    }
+
+    Ok(())
+}
+
+/// Prints rows as JSON array of objects, one row per line.
+fn print_json(header: &[String], rows: &[Vec<DataTypeValue>]) {
+    println!("[");
+    for (i, row) in rows.iter().enumerate() {
+        let delimiter = if i + 1 == rows.len() { "" } else { "," };
+        println!("  {}{}", json_object(header, row), delimiter);
+    }
+    println!("]");
+}
+
+/// Prints rows as CSV with header line.
+fn print_csv(header: &[String], rows: &[Vec<DataTypeValue>]) {
+    let header = header.iter().map(|c| csv_field(c)).collect::<Vec<_>>();
+    println!("{}", header.join(","));
+    for row in rows {
+        let fields = row.iter().map(csv_value).collect::<Vec<_>>();
+        println!("{}", fields.join(","));
+    }
+}
@@ -0,0 +1,49 @@
+fn print_horizontal_cells_delimiters(column_widths: &[usize]) {
+    print!("+");
+    for column_width in column_widths.iter() {
+        print!("-");
+        for _ in 0..*column_width {
+            print!("-");
+        }
+        print!("-+");
+    }
+    println!();
+}
+
+fn print_padded_string(string: &str, column_width: usize) {
+    print!("{}", string);
+    for _ in 0..column_width - string.len() {
+        print!(" ");
+    }
+}
+
+pub fn format_table(header: &[String], rows: &[Vec<String>]) {
+    let mut column_widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows.iter() {
+        for (width, cell) in column_widths.iter_mut().zip(row) {
+            if cell.len() > *width {
+                *width = cell.len();
+            }
+        }
+    }
+
+    print_horizontal_cells_delimiters(&column_widths[..]);
+    print!("|");
+    for (cell, width) in header.iter().zip(&column_widths) {
+        print!(" ");
+        print_padded_string(cell.as_str(), *width);
+        print!(" |");
+    }
+    println!();
+    print_horizontal_cells_delimiters(&column_widths[..]);
+    for row in rows.iter() {
+        print!("|");
+        for (cell, width) in row.iter().zip(&column_widths) {
+            print!(" ");
+            print_padded_string(cell.as_str(), *width);
+            print!(" |");
+        }
+        println!();
+    }
+    print_horizontal_cells_delimiters(&column_widths[..]);
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use data_bucket::page::parse_space_info;
+use data_bucket::persistence::data::read_data_rows;
+use data_bucket::persistence::rebuild::{extract_secondary_index_entries, persist_secondary_index};
+use data_bucket::PAGE_SIZE;
+use eyre::bail;
+use tokio::fs::File;
+
+/// Rebuilds secondary indexes of the space from it's data pages.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Space's data file.
+    #[arg(short, long)]
+    filename: String,
+    /// Directory where `<column>.wt` index files will be written.
+    #[arg(short, long)]
+    output_dir: PathBuf,
+    /// Indexed column to rebuild. All secondary indexes are rebuilt if not set.
+    #[arg(short, long)]
+    index: Option<String>,
+    /// Part of the index page's capacity that will be filled.
+    #[arg(long, default_value_t = 1.0)]
+    fill_factor: f64,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    let mut file = File::open(args.filename.as_str()).await?;
+
+    let space_info = parse_space_info::<PAGE_SIZE>(&mut file).await?;
+    if let Some(index) = &args.index {
+        if !space_info
+            .secondary_index_schemas
+            .iter()
+            .filter_map(|schema| schema.single_column())
+            .any(|column| &column.name == index)
+        {
+            bail!("Space has no secondary index for `{}` column", index)
+        }
+    }
+
+    let (space_info, rows) = read_data_rows(&mut file).await?;
+    let indexes = extract_secondary_index_entries(&space_info, &rows)?;
+    tokio::fs::create_dir_all(&args.output_dir).await?;
+    for index in indexes {
+        if args.index.as_ref().is_some_and(|i| i != &index.column) {
+            continue;
+        }
+        let path = args.output_dir.join(format!("{}.wt", index.column));
+        let mut output_file = File::create(&path).await?;
+        let report =
+            persist_secondary_index(&mut output_file, &space_info, index, args.fill_factor).await?;
+        println!(
+            "{}: {} entries in {} pages written to {}",
+            report.column,
+            report.entries_count,
+            report.index_pages_count,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
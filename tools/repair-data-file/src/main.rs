@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use data_bucket::page::parse_space_info;
+use data_bucket::persistence::export::json_string;
+use data_bucket::persistence::rebuild::{
+    extract_secondary_index_entries, persist_secondary_index, RebuildReport,
+};
+use data_bucket::persistence::salvage::{persist_salvaged_space, salvage_rows, SalvagedSpace};
+use data_bucket::PAGE_SIZE;
+use eyre::bail;
+use tokio::fs::File;
+
+/// Salvages rows of the damaged space's data file and writes them to a fresh
+/// data file with rebuilt secondary indexes. Damaged file is not changed.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Damaged space's data file.
+    #[arg(short, long)]
+    filename: PathBuf,
+    /// Repaired space's data file that will be created.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory where `<column>.wt` index files will be written. Repaired
+    /// file's directory is used if not set.
+    #[arg(long)]
+    index_dir: Option<PathBuf>,
+    /// File to read space info from if damaged file's one is broken, for
+    /// example, older copy of the same space.
+    #[arg(long)]
+    space_info_from: Option<PathBuf>,
+    /// Part of the index page's capacity that will be filled.
+    #[arg(long, default_value_t = 1.0)]
+    fill_factor: f64,
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t)]
+    format: ReportFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ReportFormat {
+    /// JSON document with salvage summary and everything that was dropped.
+    #[default]
+    Json,
+    /// Human readable summary.
+    Text,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    if args.filename == args.output {
+        bail!("Repaired file should differ from the damaged one")
+    }
+    let space_info = match &args.space_info_from {
+        Some(path) => {
+            let mut file = File::open(path).await?;
+            Some(parse_space_info::<PAGE_SIZE>(&mut file).await?)
+        }
+        None => None,
+    };
+    let mut file = File::open(&args.filename).await?;
+    let salvaged = salvage_rows(&mut file, space_info).await?;
+
+    if let Some(dir) = args.output.parent().filter(|d| !d.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut output = File::create(&args.output).await?;
+    let (space_info, rows) = persist_salvaged_space(&mut output, &salvaged).await?;
+
+    let index_dir = match &args.index_dir {
+        Some(dir) => dir.clone(),
+        None => args
+            .output
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default(),
+    };
+    let indexes = extract_secondary_index_entries(&space_info, &rows)?;
+    if !indexes.is_empty() {
+        tokio::fs::create_dir_all(&index_dir).await?;
+    }
+    let mut rebuilt = vec![];
+    for index in indexes {
+        let path = index_dir.join(format!("{}.wt", index.column));
+        let mut index_file = File::create(&path).await?;
+        let report =
+            persist_secondary_index(&mut index_file, &space_info, index, args.fill_factor).await?;
+        rebuilt.push((path, report));
+    }
+
+    match args.format {
+        ReportFormat::Json => print_json(&args, &salvaged, &rebuilt),
+        ReportFormat::Text => print_text(&args, &salvaged, &rebuilt),
+    }
+
+    Ok(())
+}
+
+fn print_json(args: &Args, salvaged: &SalvagedSpace, rebuilt: &[(PathBuf, RebuildReport)]) {
+    let report = &salvaged.report;
+    println!("{{");
+    println!(
+        "  \"file\": {},",
+        json_string(&args.filename.display().to_string())
+    );
+    println!(
+        "  \"output\": {},",
+        json_string(&args.output.display().to_string())
+    );
+    println!("  \"complete\": {},", report.is_complete());
+    println!("  \"pages_read\": {},", report.pages_read);
+    println!("  \"pages_recovered\": {},", report.pages_recovered);
+    println!("  \"pages_scanned\": {},", report.pages_scanned);
+    println!("  \"rows_recovered\": {},", report.rows_recovered);
+    println!("  \"rows_from_scan\": {},", report.rows_from_scan);
+
+    let dropped_pages = report
+        .dropped_pages
+        .iter()
+        .map(|p| {
+            format!(
+                "{{\"page_id\": {}, \"reason\": {}}}",
+                p.page_id,
+                json_string(&p.reason)
+            )
+        })
+        .collect::<Vec<_>>();
+    print_json_array("dropped_pages", &dropped_pages, true);
+    let dropped_ranges = report
+        .dropped_ranges
+        .iter()
+        .map(|r| {
+            format!(
+                "{{\"page_id\": {}, \"start\": {}, \"end\": {}}}",
+                r.page_id, r.range.start, r.range.end
+            )
+        })
+        .collect::<Vec<_>>();
+    print_json_array("dropped_ranges", &dropped_ranges, true);
+    let scanned_rows = salvaged
+        .rows
+        .iter()
+        .filter(|r| r.from_scan)
+        .map(|r| {
+            format!(
+                "{{\"page_id\": {}, \"offset\": {}, \"length\": {}}}",
+                r.link.page_id, r.link.offset, r.link.length
+            )
+        })
+        .collect::<Vec<_>>();
+    print_json_array("scanned_rows", &scanned_rows, true);
+    let indexes = rebuilt
+        .iter()
+        .map(|(path, r)| {
+            format!(
+                "{{\"column\": {}, \"file\": {}, \"entries\": {}, \"pages\": {}}}",
+                json_string(&r.column),
+                json_string(&path.display().to_string()),
+                r.entries_count,
+                r.index_pages_count
+            )
+        })
+        .collect::<Vec<_>>();
+    print_json_array("indexes", &indexes, false);
+    println!("}}");
+}
+
+fn print_json_array(name: &str, values: &[String], trailing_comma: bool) {
+    let comma = if trailing_comma { "," } else { "" };
+    if values.is_empty() {
+        println!("  \"{name}\": []{comma}");
+        return;
+    }
+    println!("  \"{name}\": [");
+    for (i, value) in values.iter().enumerate() {
+        let separator = if i + 1 == values.len() { "" } else { "," };
+        println!("    {value}{separator}");
+    }
+    println!("  ]{comma}");
+}
+
+fn print_text(args: &Args, salvaged: &SalvagedSpace, rebuilt: &[(PathBuf, RebuildReport)]) {
+    let report = &salvaged.report;
+    println!(
+        "{}: {} rows recovered from {} pages ({} split completely, {} scanned)",
+        args.filename.display(),
+        report.rows_recovered,
+        report.pages_read,
+        report.pages_recovered,
+        report.pages_scanned
+    );
+    for page in &report.dropped_pages {
+        println!("  page {} dropped: {}", page.page_id, page.reason);
+    }
+    for range in &report.dropped_ranges {
+        println!(
+            "  page {} bytes {}..{} dropped",
+            range.page_id, range.range.start, range.range.end
+        );
+    }
+    if report.rows_from_scan != 0 {
+        println!(
+            "  {} rows were found by scan of damaged pages and should be reviewed",
+            report.rows_from_scan
+        );
+    }
+    println!("{}: written", args.output.display());
+    for (path, r) in rebuilt {
+        println!(
+            "{}: {} entries in {} pages",
+            path.display(),
+            r.entries_count,
+            r.index_pages_count
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use data_bucket::persistence::rebuild::{extract_secondary_index_entries, persist_secondary_index};
+use data_bucket::persistence::rewrite::rewrite_space;
+use eyre::bail;
+use tokio::fs::File;
+
+/// Rewrites space's data file so all rows are stored under the current schema
+/// version and rebuilds secondary indexes. Source file is not changed.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Space's data file.
+    #[arg(short, long)]
+    filename: PathBuf,
+    /// Rewritten space's data file that will be created.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory where `<column>.wt` index files will be written. Rewritten
+    /// file's directory is used if not set.
+    #[arg(long)]
+    index_dir: Option<PathBuf>,
+    /// Part of the index page's capacity that will be filled.
+    #[arg(long, default_value_t = 1.0)]
+    fill_factor: f64,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    if args.filename == args.output {
+        bail!("Rewritten file should differ from the source one")
+    }
+    let mut file = File::open(&args.filename).await?;
+
+    if let Some(dir) = args.output.parent().filter(|d| !d.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut output = File::create(&args.output).await?;
+    let rewritten = rewrite_space(&mut file, &mut output).await?;
+    let report = &rewritten.report;
+    println!(
+        "{}: {} rows read from {} pages, {} rows upgraded to schema version {}",
+        args.filename.display(),
+        report.rows,
+        report.pages_read,
+        report.rows_upgraded,
+        rewritten.space_info.schema_version
+    );
+    println!(
+        "{}: {} pages written",
+        args.output.display(),
+        report.pages_written
+    );
+
+    let index_dir = match &args.index_dir {
+        Some(dir) => dir.clone(),
+        None => args
+            .output
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default(),
+    };
+    let indexes = extract_secondary_index_entries(&rewritten.space_info, &rewritten.rows)?;
+    if !indexes.is_empty() {
+        tokio::fs::create_dir_all(&index_dir).await?;
+    }
+    for index in indexes {
+        let path = index_dir.join(format!("{}.wt", index.column));
+        let mut index_file = File::create(&path).await?;
+        let r = persist_secondary_index(
+            &mut index_file,
+            &rewritten.space_info,
+            index,
+            args.fill_factor,
+        )
+        .await?;
+        println!(
+            "{}: {} entries in {} pages",
+            path.display(),
+            r.entries_count,
+            r.index_pages_count
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use data_bucket::persistence::export::json_string;
+use data_bucket::persistence::fsck::{check_file, FileKind, FileReport};
+use tokio::fs::File;
+
+/// Checks space's data and index files page by page. Exits with non-zero code
+/// if any problem is found.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Checked file. Can be set multiple times.
+    #[arg(short, long, required = true)]
+    filename: Vec<String>,
+    /// Output format of the report.
+    #[arg(long, value_enum, default_value_t)]
+    format: ReportFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ReportFormat {
+    /// One JSON document with reports of all files.
+    #[default]
+    Json,
+    /// Human readable list of the found problems.
+    Text,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<ExitCode> {
+    let args = Args::parse();
+
+    let mut reports = Vec::with_capacity(args.filename.len());
+    for filename in &args.filename {
+        let mut file = File::open(filename).await?;
+        reports.push((filename.as_str(), check_file(&mut file).await?));
+    }
+    match args.format {
+        ReportFormat::Json => print_json(&reports),
+        ReportFormat::Text => print_text(&reports),
+    }
+
+    if reports.iter().all(|(_, report)| report.is_consistent()) {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn kind_name(kind: Option<FileKind>) -> &'static str {
+    match kind {
+        Some(FileKind::Data) => "data",
+        Some(FileKind::Index) => "index",
+        None => "unknown",
+    }
+}
+
+fn print_json(reports: &[(&str, FileReport)]) {
+    let consistent = reports.iter().all(|(_, report)| report.is_consistent());
+    println!("{{");
+    println!("  \"consistent\": {consistent},");
+    println!("  \"files\": [");
+    for (i, (filename, report)) in reports.iter().enumerate() {
+        println!("    {{");
+        println!("      \"file\": {},", json_string(filename));
+        println!("      \"kind\": \"{}\",", kind_name(report.kind));
+        println!("      \"file_length\": {},", report.file_length);
+        println!("      \"pages_checked\": {},", report.pages_checked);
+        println!("      \"consistent\": {},", report.is_consistent());
+        if report.violations.is_empty() {
+            println!("      \"violations\": []");
+        } else {
+            println!("      \"violations\": [");
+            for (j, violation) in report.violations.iter().enumerate() {
+                let page_id = violation
+                    .page_id()
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "null".to_string());
+                let separator = if j + 1 == report.violations.len() {
+                    ""
+                } else {
+                    ","
+                };
+                println!(
+                    "        {{\"kind\": \"{}\", \"page_id\": {}, \"message\": {}}}{}",
+                    violation.kind(),
+                    page_id,
+                    json_string(&violation.message()),
+                    separator
+                );
+            }
+            println!("      ]");
+        }
+        let separator = if i + 1 == reports.len() { "" } else { "," };
+        println!("    }}{separator}");
+    }
+    println!("  ]");
+    println!("}}");
+}
+
+fn print_text(reports: &[(&str, FileReport)]) {
+    for (filename, report) in reports {
+        let status = if report.is_consistent() {
+            "OK"
+        } else {
+            "FAILED"
+        };
+        println!(
+            "{filename}: {status} ({} file, {} pages checked, {} problems)",
+            kind_name(report.kind),
+            report.pages_checked,
+            report.violations.len()
+        );
+        for violation in &report.violations {
+            match violation.page_id() {
+                Some(page_id) => println!(
+                    "  page {page_id}: {}: {}",
+                    violation.kind(),
+                    violation.message()
+                ),
+                None => println!("  {}: {}", violation.kind(), violation.message()),
+            }
+        }
+    }
+}